@@ -14,16 +14,18 @@ use solana_exchange_api::id;
 use solana_metrics::datapoint_info;
 use solana_sdk::client::Client;
 use solana_sdk::client::SyncClient;
+use solana_sdk::message::Message;
 use solana_sdk::pubkey::Pubkey;
 use solana_sdk::signature::{Keypair, KeypairUtil};
 use solana_sdk::system_instruction;
 use solana_sdk::timing::{duration_as_ms, duration_as_s};
 use solana_sdk::transaction::Transaction;
+use solana_token_api::token_instruction;
+use solana_token_api::token_state::{TokenInfo, TransferApproval};
 use std::cmp;
 use std::collections::{HashMap, VecDeque};
 use std::fs::File;
 use std::io::prelude::*;
-use std::mem;
 use std::net::SocketAddr;
 use std::path::Path;
 use std::process::exit;
@@ -40,6 +42,12 @@ const FUND_CHUNK_LEN: usize = 4 * 1024 * 1024 / 512;
 // Maximum system transfers per transaction
 const MAX_TRANSFERS_PER_TX: u64 = 4;
 
+// Tokens of each asset deposited into every source token account
+const DEPOSIT_TOKENS: u64 = 100_000;
+
+// Space for any token program account
+const TOKEN_PROGRAM_ACCOUNT_SIZE: u64 = 256;
+
 pub type SharedTransactions = Arc<RwLock<VecDeque<Vec<Transaction>>>>;
 
 pub struct Config {
@@ -182,6 +190,22 @@ where
     info!("Create {:?} profit token accounts", profit_pubkeys.len());
     create_token_accounts(client, &swapper_signers, &profit_pubkeys);
 
+    info!("Create the traded assets");
+    let base = create_asset(client, &identity);
+    let quote = create_asset(client, &identity);
+    let pair = AssetPair {
+        Base: base.token,
+        Quote: quote.token,
+    };
+    info!("Deposit into {:?} source token accounts", src_pubkeys.len());
+    deposit_tokens(
+        client,
+        &identity,
+        &[&base, &quote],
+        &src_pubkeys,
+        DEPOSIT_TOKENS,
+    );
+
     // Collect the max transaction rate and total tx count seen (single node only)
     let sample_stats = Arc::new(RwLock::new(Vec::new()));
     let sample_period = 1; // in seconds
@@ -248,6 +272,7 @@ where
                     &shared_txs,
                     &trader_signers,
                     &src_pubkeys,
+                    pair,
                     transfer_delay,
                     batch_size,
                     chunk_size,
@@ -500,6 +525,7 @@ fn trader<T>(
     shared_txs: &SharedTransactions,
     signers: &[Arc<Keypair>],
     srcs: &[Pubkey],
+    pair: AssetPair,
     transfer_delay: u64,
     batch_size: usize,
     chunk_size: usize,
@@ -509,7 +535,6 @@ fn trader<T>(
     T: Client,
 {
     // TODO Hard coded for now
-    let pair = AssetPair::default();
     let tokens = 1;
     let price = 1000;
     let mut account_group: usize = 0;
@@ -546,6 +571,7 @@ fn trader<T>(
                 tokens,
                 price,
                 tokens_settled: 0,
+                order_book: None,
             };
             trade_infos.push(TradeInfo {
                 trade_account: trade.pubkey(),
@@ -565,7 +591,7 @@ fn trader<T>(
                 .map(|(signer, trade, side, src)| {
                     let s: &Keypair = &signer;
                     let owner = &signer.pubkey();
-                    let space = mem::size_of::<ExchangeState>() as u64;
+                    let space = TokenAccountInfo::max_account_size();
                     Transaction::new_signed_instructions(
                         &[s],
                         vec![
@@ -787,7 +813,7 @@ pub fn create_token_accounts(client: &dyn Client, signers: &[Arc<Keypair>], acco
                 .par_iter()
                 .map(|(signer, new)| {
                     let owner_pubkey = &signer.pubkey();
-                    let space = mem::size_of::<ExchangeState>() as u64;
+                    let space = TokenAccountInfo::max_account_size();
                     let create_ix =
                         system_instruction::create_account(owner_pubkey, new, 1, space, &id());
                     let request_ix = exchange_instruction::account_request(owner_pubkey, new);
@@ -864,6 +890,183 @@ pub fn create_token_accounts(client: &dyn Client, signers: &[Arc<Keypair>], acco
     }
 }
 
+/// A token program token whose supply the identity holds, along with the token's exchange vault
+pub struct Asset {
+    pub token: Pubkey,
+    pub account: Pubkey,
+    pub vault: Pubkey,
+    pub vault_account: Pubkey,
+}
+
+fn create_token_program_account(
+    client: &dyn Client,
+    payer: &Keypair,
+    owner: &Pubkey,
+    token: &Pubkey,
+) -> Pubkey {
+    let new = Keypair::new();
+    let instructions = vec![
+        system_instruction::create_account(
+            &payer.pubkey(),
+            &new.pubkey(),
+            1,
+            TOKEN_PROGRAM_ACCOUNT_SIZE,
+            &solana_token_api::id(),
+        ),
+        token_instruction::new_token_account(&new.pubkey(), owner, token),
+    ];
+    client
+        .send_message(&[payer, &new], Message::new(instructions))
+        .expect("Failed to create token program account");
+    new.pubkey()
+}
+
+pub fn create_asset(client: &dyn Client, identity: &Keypair) -> Asset {
+    let token = Keypair::new();
+    let account =
+        create_token_program_account(client, identity, &identity.pubkey(), &token.pubkey());
+    let instructions = vec![
+        system_instruction::create_account(
+            &identity.pubkey(),
+            &token.pubkey(),
+            1,
+            TOKEN_PROGRAM_ACCOUNT_SIZE,
+            &solana_token_api::id(),
+        ),
+        token_instruction::new_token(
+            &token.pubkey(),
+            &account,
+            TokenInfo::new(u64::max_value(), 0, "Bench token", "BENCH"),
+        ),
+    ];
+    client
+        .send_message(&[identity, &token], Message::new(instructions))
+        .expect("Failed to create token");
+
+    let vault = vault_address(&token.pubkey());
+    let authority = vault_authority_address(&token.pubkey());
+    let vault_account = create_token_program_account(client, identity, &authority, &token.pubkey());
+    let instructions = vec![
+        system_instruction::create_account_with_seed(
+            &identity.pubkey(),
+            &vault,
+            &token.pubkey(),
+            VAULT_SEED,
+            1,
+            TokenAccountInfo::max_account_size(),
+            &id(),
+        ),
+        system_instruction::create_account_with_seed(
+            &identity.pubkey(),
+            &authority,
+            &token.pubkey(),
+            VAULT_AUTHORITY_SEED,
+            1,
+            TransferApproval::max_account_size(),
+            &id(),
+        ),
+        exchange_instruction::vault_request(&identity.pubkey(), &vault, &vault_account, &authority),
+    ];
+    client
+        .send_message(&[identity, &token], Message::new(instructions))
+        .expect("Failed to create vault");
+
+    Asset {
+        token: token.pubkey(),
+        account,
+        vault,
+        vault_account,
+    }
+}
+
+pub fn deposit_tokens(
+    client: &dyn Client,
+    identity: &Keypair,
+    assets: &[&Asset],
+    accounts: &[Pubkey],
+    tokens: u64,
+) {
+    accounts.chunks(FUND_CHUNK_LEN).for_each(|chunk| {
+        let mut to_deposit_txs: Vec<_> = chunk
+            .par_iter()
+            .map(|account| {
+                let owner = &identity.pubkey();
+                let instructions = assets
+                    .iter()
+                    .flat_map(|asset| {
+                        vec![
+                            exchange_instruction::prepare_deposit(
+                                owner,
+                                &asset.vault,
+                                &asset.vault_account,
+                                &asset.account,
+                            ),
+                            token_instruction::transfer(
+                                owner,
+                                &asset.account,
+                                &asset.vault_account,
+                                tokens,
+                            ),
+                            exchange_instruction::deposit_request(
+                                owner,
+                                account,
+                                &asset.vault,
+                                &asset.vault_account,
+                                &asset.account,
+                                tokens,
+                            ),
+                        ]
+                    })
+                    .collect();
+                Transaction::new_unsigned_instructions(instructions)
+            })
+            .collect();
+
+        debug!("  Depositing in {} txs", to_deposit_txs.len());
+
+        // Every deposit locks the vaults, so expect some to be retried
+        let mut retries = 0;
+        while !to_deposit_txs.is_empty() {
+            let (blockhash, _fee_calculator) = client
+                .get_recent_blockhash()
+                .expect("Failed to get blockhash");
+            to_deposit_txs.par_iter_mut().for_each(|tx| {
+                tx.sign(&[identity], blockhash);
+            });
+            to_deposit_txs.iter().for_each(|tx| {
+                client.async_send_transaction(tx.clone()).expect("deposit");
+            });
+
+            let mut waits = 0;
+            while !to_deposit_txs.is_empty() {
+                sleep(Duration::from_millis(200));
+                to_deposit_txs.retain(|tx| !verify_transfer(client, &tx));
+                if to_deposit_txs.is_empty() {
+                    break;
+                }
+                debug!(
+                    "    {} transactions outstanding, waits {:?}",
+                    to_deposit_txs.len(),
+                    waits
+                );
+                waits += 1;
+                if waits >= 5 {
+                    break;
+                }
+            }
+
+            if !to_deposit_txs.is_empty() {
+                retries += 1;
+                debug!("  Retry {:?}", retries);
+                if retries >= 20 {
+                    error!("deposit_tokens: Too many retries ({}), give up", retries);
+                    exit(1);
+                }
+            }
+        }
+    });
+}
+
 fn compute_and_report_stats(maxes: &Arc<RwLock<Vec<(String, SampleStats)>>>, total_txs_sent: u64) {
     let mut max_txs = 0;
     let mut max_elapsed = Duration::new(0, 0);
@@ -1003,7 +1206,8 @@ mod tests {
             node_stakes: vec![100_000; NUM_NODES],
             cluster_lamports: 100_000_000_000_000,
             validator_configs: vec![ValidatorConfig::default(); NUM_NODES],
-            native_instruction_processors: [solana_exchange_program!()].to_vec(),
+            native_instruction_processors: [solana_exchange_program!(), solana_token_program!()]
+                .to_vec(),
             ..ClusterConfig::default()
         });
 
@@ -1047,6 +1251,10 @@ mod tests {
         let (genesis_block, identity) = create_genesis_block(100_000_000_000_000);
         let mut bank = Bank::new(&genesis_block);
         bank.add_instruction_processor(id(), process_instruction);
+        bank.add_instruction_processor(
+            solana_token_api::id(),
+            solana_token_api::token_processor::process_instruction,
+        );
         let clients = vec![BankClient::new(bank)];
 
         let mut config = Config::default();
@@ -5,6 +5,9 @@ pub mod order_book;
 #[cfg(test)]
 #[macro_use]
 extern crate solana_exchange_program;
+#[cfg(test)]
+#[macro_use]
+extern crate solana_token_program;
 
 use crate::bench::{airdrop_lamports, create_client_accounts_file, do_bench_exchange, Config};
 use log::*;
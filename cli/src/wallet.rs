@@ -723,8 +723,11 @@ fn process_deactivate_stake_account(
     vote_account_pubkey: &Pubkey,
 ) -> ProcessResult {
     let (recent_blockhash, fee_calculator) = rpc_client.get_recent_blockhash()?;
-    let ixs =
-        stake_instruction::deactivate_stake(&stake_account_keypair.pubkey(), vote_account_pubkey);
+    let ixs = stake_instruction::deactivate_stake(
+        &stake_account_keypair.pubkey(),
+        &stake_account_keypair.pubkey(),
+        vote_account_pubkey,
+    );
     let mut tx = Transaction::new_signed_with_payer(
         vec![ixs],
         Some(&config.keypair.pubkey()),
@@ -823,6 +826,7 @@ fn process_withdraw_stake(
 ) -> ProcessResult {
     let (recent_blockhash, fee_calculator) = rpc_client.get_recent_blockhash()?;
     let ixs = vec![stake_instruction::withdraw(
+        &stake_account_keypair.pubkey(),
         &stake_account_keypair.pubkey(),
         destination_account_pubkey,
         lamports,
@@ -868,7 +872,15 @@ fn process_show_stake_account(
     _config: &WalletConfig,
     stake_account_pubkey: &Pubkey,
 ) -> ProcessResult {
-    use solana_stake_api::stake_state::StakeState;
+    use solana_stake_api::stake_state::{Authorized, Lockup, StakeState};
+    fn show_authorized(authorized: &Authorized) {
+        println!("authorized staker: {}", authorized.staker);
+        println!("authorized withdrawer: {}", authorized.withdrawer);
+    }
+    fn show_lockup(lockup: &Lockup) {
+        println!("lockup slot: {}", lockup.slot);
+        println!("lockup custodian: {}", lockup.custodian);
+    }
     let stake_account = rpc_client.get_account(stake_account_pubkey)?;
     if stake_account.owner != solana_stake_api::id() {
         Err(WalletError::RpcRequestError(
@@ -876,7 +888,7 @@ fn process_show_stake_account(
         ))?;
     }
     match stake_account.state() {
-        Ok(StakeState::Stake(stake)) => {
+        Ok(StakeState::Stake(authorized, lockup, stake)) => {
             println!("total stake: {}", stake_account.lamports);
             println!("credits observed: {}", stake.credits_observed);
             println!("delegated stake: {}", stake.stake);
@@ -893,12 +905,18 @@ fn process_show_stake_account(
                     stake.deactivation_epoch
                 );
             }
+            show_authorized(&authorized);
+            show_lockup(&lockup);
             Ok("".to_string())
         }
         Ok(StakeState::RewardsPool) => Ok("Stake account is a rewards pool".to_string()),
-        Ok(StakeState::Uninitialized) | Ok(StakeState::Lockup(_)) => {
-            Ok("Stake account is uninitialized".to_string())
+        Ok(StakeState::Initialized(authorized, lockup)) => {
+            println!("total stake: {}", stake_account.lamports);
+            show_authorized(&authorized);
+            show_lockup(&lockup);
+            Ok("Stake account is undelegated".to_string())
         }
+        Ok(StakeState::Uninitialized) => Ok("Stake account is uninitialized".to_string()),
         Err(err) => Err(WalletError::RpcRequestError(format!(
             "Account data could not be deserialized to stake state: {:?}",
             err
@@ -4,5 +4,6 @@ pub mod mock_rpc_client_request;
 pub mod perf_utils;
 pub mod rpc_client;
 pub mod rpc_client_request;
+pub mod rpc_filter;
 pub mod rpc_request;
 pub mod thin_client;
@@ -45,11 +45,20 @@ impl GenericRpcClientRequest for MockRpcClientRequest {
                 let n = if self.url == "airdrop" { 0 } else { 50 };
                 Value::Number(Number::from(n))
             }
+            RpcRequest::GetBlockhashLastValidSlot => {
+                // A signature that's never found or never processed must belong to an expired
+                // transaction
+                if self.url == "sig_not_found" || self.url == "account_in_use" {
+                    Value::Null
+                } else {
+                    Value::Number(Number::from(100))
+                }
+            }
             RpcRequest::GetRecentBlockhash => Value::Array(vec![
                 Value::String(PUBKEY.to_string()),
                 serde_json::to_value(FeeCalculator::default()).unwrap(),
             ]),
-            RpcRequest::GetSignatureStatus => {
+            RpcRequest::GetRootedSignatureStatus | RpcRequest::GetSignatureStatus => {
                 let response: Option<transaction::Result<()>> = if self.url == "account_in_use" {
                     Some(Err(TransactionError::AccountInUse))
                 } else if self.url == "sig_not_found" {
@@ -2,12 +2,12 @@ use crate::client_error::ClientError;
 use crate::generic_rpc_client_request::GenericRpcClientRequest;
 use crate::mock_rpc_client_request::MockRpcClientRequest;
 use crate::rpc_client_request::RpcClientRequest;
-use crate::rpc_request::RpcRequest;
+use crate::rpc_request::{RpcProgramAccountsConfig, RpcRequest};
 use bincode::serialize;
 use log::*;
 use serde_json::{json, Value};
 use solana_sdk::account::Account;
-use solana_sdk::clock::{DEFAULT_TICKS_PER_SECOND, DEFAULT_TICKS_PER_SLOT};
+use solana_sdk::clock::{Slot, DEFAULT_TICKS_PER_SECOND, DEFAULT_TICKS_PER_SLOT};
 use solana_sdk::fee_calculator::FeeCalculator;
 use solana_sdk::hash::Hash;
 use solana_sdk::inflation::Inflation;
@@ -17,9 +17,20 @@ use solana_sdk::transaction::{self, Transaction, TransactionError};
 use std::error;
 use std::io;
 use std::net::SocketAddr;
+use std::slice;
 use std::thread::sleep;
 use std::time::{Duration, Instant};
 
+/// What became of a transaction resent by `RpcClient::send_transactions_until_expired`
+#[derive(Debug, Clone, PartialEq)]
+pub enum SendTransactionStatus {
+    /// The transaction was processed, successfully or not
+    Processed(transaction::Result<()>),
+
+    /// The transaction's blockhash expired before it was processed
+    Expired,
+}
+
 pub struct RpcClient {
     client: Box<dyn GenericRpcClientRequest + Send + Sync>,
 }
@@ -76,6 +87,21 @@ impl RpcClient {
         Ok(result)
     }
 
+    /// Like `get_signature_status`, but only reports transactions processed in a rooted slot,
+    /// whose status can't be undone by a fork switch
+    pub fn get_rooted_signature_status(
+        &self,
+        signature: &str,
+    ) -> Result<Option<transaction::Result<()>>, ClientError> {
+        let params = json!([signature.to_string()]);
+        let signature_status =
+            self.client
+                .send(&RpcRequest::GetRootedSignatureStatus, Some(params), 5)?;
+        let result: Option<transaction::Result<()>> =
+            serde_json::from_value(signature_status).unwrap();
+        Ok(result)
+    }
+
     pub fn get_slot(&self) -> io::Result<u64> {
         let response = self
             .client
@@ -133,6 +159,116 @@ impl RpcClient {
         })
     }
 
+    pub fn get_blockhash_last_valid_slot(&self, blockhash: &Hash) -> io::Result<Option<Slot>> {
+        let params = json!([blockhash.to_string()]);
+        let response = self
+            .client
+            .send(&RpcRequest::GetBlockhashLastValidSlot, Some(params), 0)
+            .map_err(|err| {
+                io::Error::new(
+                    io::ErrorKind::Other,
+                    format!("GetBlockhashLastValidSlot request failure: {:?}", err),
+                )
+            })?;
+
+        serde_json::from_value(response).map_err(|err| {
+            io::Error::new(
+                io::ErrorKind::Other,
+                format!("GetBlockhashLastValidSlot parse failure: {}", err),
+            )
+        })
+    }
+
+    /// Send the transactions, then keep resending the same signed transactions until each is
+    /// either processed or the last slot its blockhash is valid for has passed.  The
+    /// transactions are never re-signed here, and expiry is only reported once the blockhash
+    /// has expired in the last rooted slot without the transaction having been processed, so
+    /// one reported as `Expired` can no longer be processed on any fork and it's safe for the
+    /// caller to re-sign it.  A transaction rejected with
+    /// `AccountInUse` was never executed, so it's resent like one that wasn't seen at all
+    pub fn send_transactions_until_expired(
+        &self,
+        transactions: &[Transaction],
+    ) -> Result<Vec<SendTransactionStatus>, ClientError> {
+        let mut statuses = vec![None; transactions.len()];
+        let mut pending_transactions = vec![];
+        for (i, transaction) in transactions.iter().enumerate() {
+            let signature = transaction.signatures[0].to_string();
+            match self.get_blockhash_last_valid_slot(&transaction.message().recent_blockhash)? {
+                Some(last_valid_slot) => pending_transactions.push((i, signature, last_valid_slot)),
+                None => statuses[i] = Some(self.get_expired_transaction_status(&signature)?),
+            }
+        }
+
+        while !pending_transactions.is_empty() {
+            for (i, signature, _last_valid_slot) in &pending_transactions {
+                if cfg!(not(test)) {
+                    // Delay ~1 tick between write transactions in an attempt to reduce AccountInUse errors
+                    // when all the write transactions modify the same program account (eg, deploying a
+                    // new program)
+                    sleep(Duration::from_millis(1000 / DEFAULT_TICKS_PER_SECOND));
+                }
+                if let Err(err) = self.send_transaction(&transactions[*i]) {
+                    debug!("Failed to send transaction {}: {:?}", signature, err);
+                }
+            }
+
+            if cfg!(not(test)) {
+                // Retry ~twice during a slot
+                sleep(Duration::from_millis(
+                    500 * DEFAULT_TICKS_PER_SLOT / DEFAULT_TICKS_PER_SECOND,
+                ));
+            }
+
+            let slot = self.get_slot()?;
+            let mut still_pending_transactions = vec![];
+            for (i, signature, last_valid_slot) in pending_transactions {
+                if let Some(result) = self.get_processed_signature_status(&signature)? {
+                    statuses[i] = Some(SendTransactionStatus::Processed(result));
+                } else if slot <= last_valid_slot {
+                    still_pending_transactions.push((i, signature, last_valid_slot));
+                } else {
+                    // Skipped slots don't age the blockhash, so check it really has expired
+                    let blockhash = &transactions[i].message().recent_blockhash;
+                    match self.get_blockhash_last_valid_slot(blockhash)? {
+                        Some(last_valid_slot) => {
+                            still_pending_transactions.push((i, signature, last_valid_slot))
+                        }
+                        None => {
+                            statuses[i] = Some(self.get_expired_transaction_status(&signature)?)
+                        }
+                    }
+                }
+            }
+            pending_transactions = still_pending_transactions;
+        }
+        Ok(statuses.into_iter().map(Option::unwrap).collect())
+    }
+
+    // Like `get_signature_status`, but `AccountInUse` is reported as not processed since the
+    // same signed transaction may still be processed once the account is free
+    fn get_processed_signature_status(
+        &self,
+        signature: &str,
+    ) -> Result<Option<transaction::Result<()>>, ClientError> {
+        Ok(match self.get_signature_status(signature)? {
+            Some(Err(TransactionError::AccountInUse)) => None,
+            status => status,
+        })
+    }
+
+    // The transaction's blockhash has expired as of the last rooted slot, so no fork can still
+    // process it and its rooted status can no longer change
+    fn get_expired_transaction_status(
+        &self,
+        signature: &str,
+    ) -> Result<SendTransactionStatus, ClientError> {
+        Ok(match self.get_rooted_signature_status(signature)? {
+            Some(Err(TransactionError::AccountInUse)) | None => SendTransactionStatus::Expired,
+            Some(result) => SendTransactionStatus::Processed(result),
+        })
+    }
+
     pub fn send_and_confirm_transaction<T: KeypairUtil>(
         &self,
         transaction: &mut Transaction,
@@ -140,48 +276,25 @@ impl RpcClient {
     ) -> Result<String, ClientError> {
         let mut send_retries = 5;
         loop {
-            let mut status_retries = 4;
-            let signature_str = self.send_transaction(transaction)?;
-            let status = loop {
-                let status = self.get_signature_status(&signature_str)?;
-                if status.is_none() {
-                    status_retries -= 1;
-                    if status_retries == 0 {
-                        break status;
-                    }
-                } else {
-                    break status;
-                }
-                if cfg!(not(test)) {
-                    // Retry ~twice during a slot
-                    sleep(Duration::from_millis(
-                        500 * DEFAULT_TICKS_PER_SLOT / DEFAULT_TICKS_PER_SECOND,
-                    ));
-                }
-            };
-            send_retries = if let Some(result) = status.clone() {
-                match result {
-                    Ok(_) => return Ok(signature_str),
-                    Err(TransactionError::AccountInUse) => {
-                        // Fetch a new blockhash and re-sign the transaction before sending it again
-                        self.resign_transaction(transaction, signer_keys)?;
-                        send_retries - 1
-                    }
-                    Err(_) => 0,
-                }
-            } else {
-                send_retries - 1
-            };
-            if send_retries == 0 {
-                if status.is_some() {
-                    status.unwrap()?
-                } else {
-                    Err(io::Error::new(
+            let signature_str = transaction.signatures[0].to_string();
+            let status = self
+                .send_transactions_until_expired(slice::from_ref(transaction))?
+                .remove(0);
+            send_retries -= 1;
+            match status {
+                SendTransactionStatus::Processed(Ok(())) => return Ok(signature_str),
+                SendTransactionStatus::Processed(Err(err)) => return Err(err.into()),
+                SendTransactionStatus::Expired if send_retries > 0 => {}
+                SendTransactionStatus::Expired => {
+                    return Err(io::Error::new(
                         io::ErrorKind::Other,
-                        format!("Transaction {:?} failed: {:?}", signature_str, status),
-                    ))?;
+                        format!("Transaction {:?} expired", signature_str),
+                    )
+                    .into())
                 }
             }
+            // Fetch a new blockhash and re-sign the transaction before sending it again
+            self.resign_transaction(transaction, signer_keys)?;
         }
     }
 
@@ -192,51 +305,22 @@ impl RpcClient {
     ) -> Result<(), Box<dyn error::Error>> {
         let mut send_retries = 5;
         loop {
-            let mut status_retries = 4;
-
-            // Send all transactions
-            let mut transactions_signatures = vec![];
-            for transaction in transactions {
-                if cfg!(not(test)) {
-                    // Delay ~1 tick between write transactions in an attempt to reduce AccountInUse errors
-                    // when all the write transactions modify the same program account (eg, deploying a
-                    // new program)
-                    sleep(Duration::from_millis(1000 / DEFAULT_TICKS_PER_SECOND));
+            let statuses = self.send_transactions_until_expired(&transactions)?;
+
+            // Drop the confirmed transactions and give up on any that failed, those left expired
+            // so they can no longer be processed as signed
+            let mut expired_transactions = vec![];
+            for (transaction, status) in transactions.into_iter().zip(statuses) {
+                match status {
+                    SendTransactionStatus::Processed(Ok(())) => {}
+                    SendTransactionStatus::Processed(Err(err)) => Err(ClientError::from(err))?,
+                    SendTransactionStatus::Expired => expired_transactions.push(transaction),
                 }
-
-                let signature = self.send_transaction(&transaction).ok();
-                transactions_signatures.push((transaction, signature))
             }
+            transactions = expired_transactions;
 
-            // Collect statuses for all the transactions, drop those that are confirmed
-            while status_retries > 0 {
-                status_retries -= 1;
-
-                if cfg!(not(test)) {
-                    // Retry ~twice during a slot
-                    sleep(Duration::from_millis(
-                        500 * DEFAULT_TICKS_PER_SLOT / DEFAULT_TICKS_PER_SECOND,
-                    ));
-                }
-
-                transactions_signatures = transactions_signatures
-                    .into_iter()
-                    .filter(|(_transaction, signature)| {
-                        if let Some(signature) = signature {
-                            if let Ok(status) = self.get_signature_status(&signature) {
-                                if status.is_none() {
-                                    return false;
-                                }
-                                return status.unwrap().is_err();
-                            }
-                        }
-                        true
-                    })
-                    .collect();
-
-                if transactions_signatures.is_empty() {
-                    return Ok(());
-                }
+            if transactions.is_empty() {
+                return Ok(());
             }
 
             if send_retries == 0 {
@@ -244,16 +328,12 @@ impl RpcClient {
             }
             send_retries -= 1;
 
-            // Re-sign any failed transactions with a new blockhash and retry
+            // Re-sign the expired transactions with a new blockhash and retry
             let (blockhash, _fee_calculator) =
-                self.get_new_blockhash(&transactions_signatures[0].0.message().recent_blockhash)?;
-            transactions = transactions_signatures
-                .into_iter()
-                .map(|(mut transaction, _)| {
-                    transaction.sign(signer_keys, blockhash);
-                    transaction
-                })
-                .collect();
+                self.get_new_blockhash(&transactions[0].message().recent_blockhash)?;
+            for transaction in &mut transactions {
+                transaction.sign(signer_keys, blockhash);
+            }
         }
     }
 
@@ -314,7 +394,20 @@ impl RpcClient {
     }
 
     pub fn get_program_accounts(&self, pubkey: &Pubkey) -> io::Result<Vec<(Pubkey, Account)>> {
-        let params = json!([format!("{}", pubkey)]);
+        self.get_program_accounts_with_config(pubkey, None)
+    }
+
+    /// Like `get_program_accounts`, but only the accounts passing `config`'s filters are
+    /// returned, with their data sliced as it asks
+    pub fn get_program_accounts_with_config(
+        &self,
+        pubkey: &Pubkey,
+        config: Option<RpcProgramAccountsConfig>,
+    ) -> io::Result<Vec<(Pubkey, Account)>> {
+        let params = match config {
+            Some(config) => json!([format!("{}", pubkey), config]),
+            None => json!([format!("{}", pubkey)]),
+        };
         let response = self
             .client
             .send(&RpcRequest::GetProgramAccounts, Some(params), 0)
@@ -815,6 +908,45 @@ mod tests {
         assert_eq!(status, Some(Err(TransactionError::AccountInUse)));
     }
 
+    #[test]
+    fn test_get_blockhash_last_valid_slot() {
+        let rpc_client = RpcClient::new_mock("succeeds".to_string());
+        let last_valid_slot = rpc_client
+            .get_blockhash_last_valid_slot(&Hash::default())
+            .unwrap();
+        assert_eq!(last_valid_slot, Some(100));
+
+        let rpc_client = RpcClient::new_mock("sig_not_found".to_string());
+        let last_valid_slot = rpc_client
+            .get_blockhash_last_valid_slot(&Hash::default())
+            .unwrap();
+        assert_eq!(last_valid_slot, None);
+    }
+
+    #[test]
+    fn test_send_transactions_until_expired() {
+        let key = Keypair::new();
+        let to = Pubkey::new_rand();
+        let blockhash = Hash::default();
+        let txs = vec![
+            system_transaction::create_user_account(&key, &to, 50, blockhash),
+            system_transaction::create_user_account(&key, &to, 60, blockhash),
+        ];
+
+        let rpc_client = RpcClient::new_mock("succeeds".to_string());
+        let statuses = rpc_client.send_transactions_until_expired(&txs).unwrap();
+        assert_eq!(statuses, vec![SendTransactionStatus::Processed(Ok(())); 2]);
+
+        // Transactions rejected for an account in use are only given up on once they expire
+        let rpc_client = RpcClient::new_mock("account_in_use".to_string());
+        let statuses = rpc_client.send_transactions_until_expired(&txs).unwrap();
+        assert_eq!(statuses, vec![SendTransactionStatus::Expired; 2]);
+
+        let rpc_client = RpcClient::new_mock("sig_not_found".to_string());
+        let statuses = rpc_client.send_transactions_until_expired(&txs).unwrap();
+        assert_eq!(statuses, vec![SendTransactionStatus::Expired; 2]);
+    }
+
     #[test]
     fn test_send_and_confirm_transaction() {
         let rpc_client = RpcClient::new_mock("succeeds".to_string());
@@ -0,0 +1,167 @@
+use serde_derive::{Deserialize, Serialize};
+use solana_sdk::account::Account;
+use std::cmp;
+
+/// The largest comparison a memcmp filter may ask for, in bytes
+pub const MAX_MEMCMP_BYTES: usize = 128;
+/// The longest base-58 encoding of `MAX_MEMCMP_BYTES` bytes
+const MAX_MEMCMP_ENCODED_LEN: usize = 175;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RpcFilterType {
+    DataSize(u64),
+    Memcmp(Memcmp),
+}
+
+impl RpcFilterType {
+    /// Check the filter can be applied, decoding the bytes a memcmp filter compares
+    pub fn verify(&mut self) -> Result<(), RpcFilterError> {
+        match self {
+            RpcFilterType::DataSize(_) => Ok(()),
+            RpcFilterType::Memcmp(compare) => {
+                // Don't decode what can't fit once decoded
+                if compare.bytes.len() > MAX_MEMCMP_ENCODED_LEN {
+                    return Err(RpcFilterError::DataTooLarge);
+                }
+                let bytes = compare.bytes().ok_or(RpcFilterError::Base58DecodeError)?;
+                if bytes.len() > MAX_MEMCMP_BYTES {
+                    Err(RpcFilterError::DataTooLarge)
+                } else {
+                    compare.decoded_bytes = Some(bytes);
+                    Ok(())
+                }
+            }
+        }
+    }
+
+    pub fn allows(&self, account: &Account) -> bool {
+        match self {
+            RpcFilterType::DataSize(size) => account.data.len() as u64 == *size,
+            RpcFilterType::Memcmp(compare) => compare.bytes_match(&account.data),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RpcFilterError {
+    DataTooLarge,
+    Base58DecodeError,
+}
+
+/// Matches accounts whose data holds `bytes`, base-58 encoded, starting at `offset`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Memcmp {
+    pub offset: usize,
+    pub bytes: String,
+    /// `bytes` decoded by `RpcFilterType::verify`, so that accounts aren't compared
+    ///  against a filter that hasn't been verified
+    #[serde(skip)]
+    pub decoded_bytes: Option<Vec<u8>>,
+}
+
+impl Memcmp {
+    pub fn new(offset: usize, bytes: &[u8]) -> Self {
+        Self {
+            offset,
+            bytes: bs58::encode(bytes).into_string(),
+            decoded_bytes: None,
+        }
+    }
+
+    pub fn bytes(&self) -> Option<Vec<u8>> {
+        bs58::decode(&self.bytes).into_vec().ok()
+    }
+
+    /// Whether `data` holds the decoded bytes at `offset`, never true before `verify`
+    pub fn bytes_match(&self, data: &[u8]) -> bool {
+        match &self.decoded_bytes {
+            Some(bytes) => {
+                self.offset <= data.len()
+                    && data.len() - self.offset >= bytes.len()
+                    && data[self.offset..self.offset + bytes.len()] == bytes[..]
+            }
+            None => false,
+        }
+    }
+}
+
+/// Trims the data of each returned account to `length` bytes starting at `offset`
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RpcDataSlice {
+    pub offset: usize,
+    pub length: usize,
+}
+
+impl RpcDataSlice {
+    pub fn slice(&self, data: &[u8]) -> Vec<u8> {
+        let start = cmp::min(self.offset, data.len());
+        let end = cmp::min(start.saturating_add(self.length), data.len());
+        data[start..end].to_vec()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::pubkey::Pubkey;
+
+    #[test]
+    fn test_data_size_filter() {
+        let account = Account::new(1, 16, &Pubkey::default());
+        assert!(RpcFilterType::DataSize(16).allows(&account));
+        assert!(!RpcFilterType::DataSize(15).allows(&account));
+    }
+
+    #[test]
+    fn test_memcmp_filter() {
+        let mut account = Account::new(1, 8, &Pubkey::default());
+        account.data = vec![1, 2, 3, 4, 5, 6, 7, 8];
+        let memcmp = |offset, bytes: &[u8]| RpcFilterType::Memcmp(Memcmp::new(offset, bytes));
+        let verified = |offset, bytes: &[u8]| {
+            let mut filter = memcmp(offset, bytes);
+            assert_eq!(filter.verify(), Ok(()));
+            filter
+        };
+
+        assert!(verified(0, &[1, 2, 3]).allows(&account));
+        assert!(verified(5, &[6, 7, 8]).allows(&account));
+        assert!(!verified(5, &[6, 7, 9]).allows(&account));
+        // runs off the end of the data
+        assert!(!verified(6, &[7, 8, 9]).allows(&account));
+        assert!(!verified(9, &[]).allows(&account));
+        // the bytes are only decoded by verify
+        assert!(!memcmp(0, &[1, 2, 3]).allows(&account));
+
+        assert_eq!(memcmp(0, &[0; MAX_MEMCMP_BYTES]).verify(), Ok(()));
+        assert_eq!(memcmp(0, &[0xff; MAX_MEMCMP_BYTES]).verify(), Ok(()));
+        let mut too_long = RpcFilterType::Memcmp(Memcmp {
+            offset: 0,
+            bytes: "1".repeat(MAX_MEMCMP_ENCODED_LEN + 1),
+            decoded_bytes: None,
+        });
+        assert_eq!(too_long.verify(), Err(RpcFilterError::DataTooLarge));
+        assert_eq!(
+            memcmp(0, &[0; MAX_MEMCMP_BYTES + 1]).verify(),
+            Err(RpcFilterError::DataTooLarge)
+        );
+        let mut bad_bytes = RpcFilterType::Memcmp(Memcmp {
+            offset: 0,
+            bytes: "0OIl".to_string(),
+            decoded_bytes: None,
+        });
+        assert_eq!(bad_bytes.verify(), Err(RpcFilterError::Base58DecodeError));
+        assert!(!bad_bytes.allows(&account));
+    }
+
+    #[test]
+    fn test_data_slice() {
+        let data = [1, 2, 3, 4, 5];
+        let slice = |offset, length| RpcDataSlice { offset, length }.slice(&data);
+        assert_eq!(slice(0, 5), vec![1, 2, 3, 4, 5]);
+        assert_eq!(slice(1, 2), vec![2, 3]);
+        assert_eq!(slice(3, 10), vec![4, 5]);
+        assert_eq!(slice(10, 1), Vec::<u8>::new());
+        assert_eq!(slice(2, std::usize::MAX), vec![3, 4, 5]);
+    }
+}
@@ -1,6 +1,81 @@
+use crate::rpc_filter::{RpcDataSlice, RpcFilterType};
+use serde_derive::{Deserialize, Serialize};
 use serde_json::{json, Value};
+use solana_sdk::account::Account;
+use solana_sdk::clock::Slot;
+use solana_sdk::hash::Hash;
+use solana_sdk::transaction::{self, Transaction};
 use std::{error, fmt};
 
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcConfirmedBlock {
+    pub previous_blockhash: Hash,
+    pub blockhash: Hash,
+    pub parent_slot: Slot,
+    pub transactions: Vec<(Transaction, Option<RpcTransactionStatus>)>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcTransactionStatus {
+    pub status: transaction::Result<()>,
+    pub fee: u64,
+    pub pre_balances: Vec<u64>,
+    pub post_balances: Vec<u64>,
+    pub log_messages: Vec<String>,
+    pub compute_units_consumed: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcSimulatedTransaction {
+    pub result: transaction::Result<()>,
+    pub fee: u64,
+    pub accounts: Vec<(String, Account)>,
+    pub log_messages: Vec<String>,
+    pub compute_units_consumed: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcTransactionLogs {
+    pub signature: String,
+    pub result: transaction::Result<()>,
+    pub log_messages: Vec<String>,
+}
+
+/// Optional parameters to getProgramAccounts and programSubscribe
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcProgramAccountsConfig {
+    /// Only accounts passing every filter are returned
+    pub filters: Option<Vec<RpcFilterType>>,
+    pub data_slice: Option<RpcDataSlice>,
+}
+
+impl RpcProgramAccountsConfig {
+    /// The account as it should be returned, or None if it doesn't pass the filters
+    pub fn apply(&self, account: &Account) -> Option<Account> {
+        if let Some(filters) = &self.filters {
+            if !filters.iter().all(|filter| filter.allows(account)) {
+                return None;
+            }
+        }
+        let data = match &self.data_slice {
+            Some(data_slice) => data_slice.slice(&account.data),
+            None => account.data.clone(),
+        };
+        Some(Account {
+            lamports: account.lamports,
+            data,
+            owner: account.owner,
+            executable: account.executable,
+            rent_epoch: account.rent_epoch,
+        })
+    }
+}
+
 #[derive(Debug, PartialEq)]
 pub enum RpcRequest {
     ConfirmTransaction,
@@ -8,12 +83,15 @@ pub enum RpcRequest {
     FullnodeExit,
     GetAccountInfo,
     GetBalance,
+    GetBlockhashLastValidSlot,
     GetClusterNodes,
+    GetConfirmedBlock,
     GetGenesisBlockhash,
     GetInflation,
     GetNumBlocksSinceSignatureConfirmation,
     GetProgramAccounts,
     GetRecentBlockhash,
+    GetRootedSignatureStatus,
     GetSignatureStatus,
     GetSlot,
     GetSlotLeader,
@@ -28,6 +106,7 @@ pub enum RpcRequest {
     RequestAirdrop,
     SendTransaction,
     SignVote,
+    SimulateTransaction,
 }
 
 impl RpcRequest {
@@ -39,7 +118,9 @@ impl RpcRequest {
             RpcRequest::FullnodeExit => "fullnodeExit",
             RpcRequest::GetAccountInfo => "getAccountInfo",
             RpcRequest::GetBalance => "getBalance",
+            RpcRequest::GetBlockhashLastValidSlot => "getBlockhashLastValidSlot",
             RpcRequest::GetClusterNodes => "getClusterNodes",
+            RpcRequest::GetConfirmedBlock => "getConfirmedBlock",
             RpcRequest::GetGenesisBlockhash => "getGenesisBlockhash",
             RpcRequest::GetInflation => "getInflation",
             RpcRequest::GetNumBlocksSinceSignatureConfirmation => {
@@ -47,6 +128,7 @@ impl RpcRequest {
             }
             RpcRequest::GetProgramAccounts => "getProgramAccounts",
             RpcRequest::GetRecentBlockhash => "getRecentBlockhash",
+            RpcRequest::GetRootedSignatureStatus => "getRootedSignatureStatus",
             RpcRequest::GetSignatureStatus => "getSignatureStatus",
             RpcRequest::GetSlot => "getSlot",
             RpcRequest::GetSlotLeader => "getSlotLeader",
@@ -61,6 +143,7 @@ impl RpcRequest {
             RpcRequest::RequestAirdrop => "requestAirdrop",
             RpcRequest::SendTransaction => "sendTransaction",
             RpcRequest::SignVote => "signVote",
+            RpcRequest::SimulateTransaction => "simulateTransaction",
         };
         let mut request = json!({
            "jsonrpc": jsonrpc,
@@ -112,6 +195,10 @@ mod tests {
         let request = test_request.build_request_json(1, Some(addr));
         assert_eq!(request["method"], "getBalance");
 
+        let test_request = RpcRequest::GetConfirmedBlock;
+        let request = test_request.build_request_json(1, Some(json!([1])));
+        assert_eq!(request["method"], "getConfirmedBlock");
+
         let test_request = RpcRequest::GetInflation;
         let request = test_request.build_request_json(1, None);
         assert_eq!(request["method"], "getInflation");
@@ -135,5 +222,9 @@ mod tests {
         let test_request = RpcRequest::SendTransaction;
         let request = test_request.build_request_json(1, None);
         assert_eq!(request["method"], "sendTransaction");
+
+        let test_request = RpcRequest::SimulateTransaction;
+        let request = test_request.build_request_json(1, None);
+        assert_eq!(request["method"], "simulateTransaction");
     }
 }
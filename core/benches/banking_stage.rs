@@ -88,6 +88,7 @@ fn bench_consume_buffered(bencher: &mut Bencher) {
                 &poh_recorder,
                 &mut packets,
                 10_000,
+                None,
             );
         });
 
@@ -209,6 +210,7 @@ fn bench_banking(bencher: &mut Bencher, tx_type: TransactionType) {
             &poh_recorder,
             verified_receiver,
             vote_receiver,
+            None,
         );
         poh_recorder.lock().unwrap().set_bank(&bank);
 
@@ -300,7 +302,7 @@ fn simulate_process_entries(
         hash: next_hash(&bank.last_blockhash(), 1, &tx_vector),
         transactions: tx_vector,
     };
-    process_entries(&bank, &vec![entry], randomize_txs).unwrap();
+    process_entries(&bank, &vec![entry], randomize_txs, None).unwrap();
 }
 
 fn bench_process_entries(randomize_txs: bool, bencher: &mut Bencher) {
@@ -0,0 +1,66 @@
+//! The `accounts_background_service` periodically reclaims the disk held by dead accounts
+//! in rooted slots
+
+use crate::bank_forks::BankForks;
+use crate::service::Service;
+use solana_measure::measure::Measure;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+use std::thread::{self, sleep, Builder, JoinHandle};
+use std::time::{Duration, Instant};
+
+// How often the rooted account storage is cleaned and shrunk
+pub const DEFAULT_ACCOUNTS_CLEAN_INTERVAL: Duration = Duration::from_secs(60);
+
+pub struct AccountsBackgroundService {
+    t_background: JoinHandle<()>,
+}
+
+impl AccountsBackgroundService {
+    pub fn new(bank_forks: Arc<RwLock<BankForks>>, exit: &Arc<AtomicBool>) -> Self {
+        info!("AccountsBackgroundService active");
+        let exit = exit.clone();
+        let t_background = Builder::new()
+            .name("solana-accounts-background".to_string())
+            .spawn(move || {
+                let mut last_clean = Instant::now();
+                loop {
+                    if exit.load(Ordering::Relaxed) {
+                        break;
+                    }
+                    if last_clean.elapsed() >= DEFAULT_ACCOUNTS_CLEAN_INTERVAL {
+                        Self::clean_and_shrink(&bank_forks);
+                        last_clean = Instant::now();
+                    }
+                    sleep(Duration::from_millis(100));
+                }
+            })
+            .unwrap();
+        Self { t_background }
+    }
+
+    fn clean_and_shrink(bank_forks: &RwLock<BankForks>) {
+        let (root_bank, max_clean_root) = {
+            let bank_forks = bank_forks.read().unwrap();
+            (
+                bank_forks[bank_forks.root()].clone(),
+                bank_forks.last_snapshot_root(),
+            )
+        };
+        let mut clean_time = Measure::start("accounts-clean-and-shrink");
+        root_bank.clean_and_shrink_accounts(max_clean_root);
+        clean_time.stop();
+        datapoint_info!(
+            "accounts_background_service",
+            ("clean_and_shrink_ms", clean_time.as_ms(), i64),
+        );
+    }
+}
+
+impl Service for AccountsBackgroundService {
+    type JoinReturnType = ();
+
+    fn join(self) -> thread::Result<()> {
+        self.t_background.join()
+    }
+}
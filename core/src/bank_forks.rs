@@ -2,11 +2,12 @@
 
 use crate::result::Result;
 use crate::snapshot_package::SnapshotPackageSender;
-use crate::snapshot_utils;
+use crate::snapshot_utils::{self, ArchiveFormat, FullSnapshotInfo};
 use solana_measure::measure::Measure;
 use solana_metrics::inc_new_counter_info;
 use solana_runtime::bank::Bank;
 use solana_runtime::status_cache::MAX_CACHE_ENTRIES;
+use solana_sdk::hash::Hash;
 use solana_sdk::timing;
 use std::collections::{HashMap, HashSet};
 use std::ops::Index;
@@ -19,11 +20,22 @@ pub struct SnapshotConfig {
     // Generate a new snapshot every this many slots
     pub snapshot_interval_slots: usize,
 
+    // Between full snapshots, generate a snapshot of just what changed since the last full
+    // snapshot every this many slots
+    pub incremental_snapshot_interval_slots: usize,
+
     // Where to store the latest packaged snapshot
     pub snapshot_package_output_path: PathBuf,
 
     // Where to place the snapshots for recent slots
     pub snapshot_path: PathBuf,
+
+    // How to compress packaged snapshots
+    pub archive_format: ArchiveFormat,
+
+    // The accounts hash a snapshot must have to be loaded, from a trusted source. The hash
+    // recorded in the snapshot itself only catches corruption, not tampering
+    pub expected_accounts_hash: Option<Hash>,
 }
 
 pub struct BankForks {
@@ -32,6 +44,7 @@ pub struct BankForks {
     root: u64,
     snapshot_config: Option<SnapshotConfig>,
     slots_since_snapshot: Vec<u64>,
+    last_full_snapshot: Option<FullSnapshotInfo>,
 }
 
 impl Index<u64> for BankForks {
@@ -52,6 +65,7 @@ impl BankForks {
             root: 0,
             snapshot_config: None,
             slots_since_snapshot: vec![bank_slot],
+            last_full_snapshot: None,
         }
     }
 
@@ -127,6 +141,7 @@ impl BankForks {
             working_bank,
             snapshot_config: None,
             slots_since_snapshot: rooted_path,
+            last_full_snapshot: None,
         }
     }
 
@@ -180,18 +195,54 @@ impl BankForks {
         if self.snapshot_config.is_some() && snapshot_package_sender.is_some() {
             let config = self.snapshot_config.as_ref().unwrap();
             info!("setting snapshot root: {}", root);
-            if root - self.slots_since_snapshot[0] >= config.snapshot_interval_slots as u64 {
+            let last_full_snapshot_slot = self
+                .last_full_snapshot
+                .as_ref()
+                .map(|info| info.slot)
+                .unwrap_or(self.slots_since_snapshot[0]);
+            let full = root - last_full_snapshot_slot >= config.snapshot_interval_slots as u64;
+            let incremental = !full
+                && self.last_full_snapshot.is_some()
+                && root - self.slots_since_snapshot[0]
+                    >= config.incremental_snapshot_interval_slots as u64;
+            if full || incremental {
                 let mut snapshot_time = Measure::start("total-snapshot-ms");
-                let r = self.generate_snapshot(
-                    root,
-                    &self.slots_since_snapshot[1..],
-                    snapshot_package_sender.as_ref().unwrap(),
-                    snapshot_utils::get_snapshot_tar_path(&config.snapshot_package_output_path),
-                );
-                if r.is_err() {
-                    warn!("Error generating snapshot for bank: {}, err: {:?}", root, r);
+                let r = if full {
+                    self.generate_snapshot(
+                        root,
+                        &self.slots_since_snapshot[1..],
+                        snapshot_package_sender.as_ref().unwrap(),
+                        snapshot_utils::get_snapshot_tar_path(
+                            &config.snapshot_package_output_path,
+                            config.archive_format,
+                        ),
+                    )
+                    .map(Some)
                 } else {
-                    self.slots_since_snapshot = vec![root];
+                    self.generate_incremental_snapshot(
+                        root,
+                        &self.slots_since_snapshot[1..],
+                        snapshot_package_sender.as_ref().unwrap(),
+                        snapshot_utils::get_incremental_snapshot_tar_path(
+                            &config.snapshot_package_output_path,
+                            config.archive_format,
+                        ),
+                    )
+                    .map(|_| None)
+                };
+                match r {
+                    Ok(full_snapshot) => {
+                        if full_snapshot.is_some() {
+                            self.last_full_snapshot = full_snapshot;
+                        }
+                        self.slots_since_snapshot = vec![root];
+                    }
+                    Err(err) => {
+                        warn!(
+                            "Error generating snapshot for bank: {}, err: {:?}",
+                            root, err
+                        )
+                    }
                 }
 
                 // Cleanup outdated snapshots
@@ -221,6 +272,13 @@ impl BankForks {
         &self.slots_since_snapshot
     }
 
+    /// The root of the latest snapshot, or None if snapshots aren't being taken
+    pub fn last_snapshot_root(&self) -> Option<u64> {
+        self.snapshot_config
+            .as_ref()
+            .map(|_| self.slots_since_snapshot[0])
+    }
+
     fn purge_old_snapshots(&self) {
         // Remove outdated snapshots
         let config = self.snapshot_config.as_ref().unwrap();
@@ -240,10 +298,58 @@ impl BankForks {
         slots_since_snapshot: &[u64],
         snapshot_package_sender: &SnapshotPackageSender,
         tar_output_file: P,
+    ) -> Result<FullSnapshotInfo> {
+        let (bank, slot_snapshot_paths) = self.add_root_snapshot(root, slots_since_snapshot)?;
+        let config = self.snapshot_config.as_ref().unwrap();
+        let package = snapshot_utils::package_snapshot(
+            &bank,
+            &slot_snapshot_paths,
+            tar_output_file,
+            &config.snapshot_path,
+            config.archive_format,
+        )?;
+        let info = FullSnapshotInfo::new(root, package.storage_entries());
+
+        // Send the package to the packaging thread
+        snapshot_package_sender.send(package)?;
+
+        Ok(info)
+    }
+
+    fn generate_incremental_snapshot<P: AsRef<Path>>(
+        &self,
+        root: u64,
+        slots_since_snapshot: &[u64],
+        snapshot_package_sender: &SnapshotPackageSender,
+        tar_output_file: P,
     ) -> Result<()> {
+        let (bank, slot_snapshot_paths) = self.add_root_snapshot(root, slots_since_snapshot)?;
+        let config = self.snapshot_config.as_ref().unwrap();
+        let package = snapshot_utils::package_incremental_snapshot(
+            &bank,
+            self.last_full_snapshot
+                .as_ref()
+                .expect("incremental snapshots need a full snapshot"),
+            &slot_snapshot_paths,
+            tar_output_file,
+            &config.snapshot_path,
+            config.archive_format,
+        )?;
+
+        // Send the package to the packaging thread
+        snapshot_package_sender.send(package)?;
+
+        Ok(())
+    }
+
+    // Add a snapshot for the new root and return it along with the snapshots to package
+    fn add_root_snapshot(
+        &self,
+        root: u64,
+        slots_since_snapshot: &[u64],
+    ) -> Result<(Arc<Bank>, Vec<snapshot_utils::SlotSnapshotPaths>)> {
         let config = self.snapshot_config.as_ref().unwrap();
 
-        // Add a snapshot for the new root
         let bank = self
             .get(root)
             .cloned()
@@ -251,23 +357,13 @@ impl BankForks {
         snapshot_utils::add_snapshot(&config.snapshot_path, &bank, slots_since_snapshot)?;
 
         // Package the relevant snapshots
-        let slot_snapshot_paths = snapshot_utils::get_snapshot_paths(&config.snapshot_path);
+        let mut slot_snapshot_paths = snapshot_utils::get_snapshot_paths(&config.snapshot_path);
 
         // We only care about the last MAX_CACHE_ENTRIES snapshots of roots because
         // the status cache of anything older is thrown away by the bank in
         // status_cache.prune_roots()
         let start = slot_snapshot_paths.len().saturating_sub(MAX_CACHE_ENTRIES);
-        let package = snapshot_utils::package_snapshot(
-            &bank,
-            &slot_snapshot_paths[start..],
-            tar_output_file,
-            &config.snapshot_path,
-        )?;
-
-        // Send the package to the packaging thread
-        snapshot_package_sender.send(package)?;
-
-        Ok(())
+        Ok((bank, slot_snapshot_paths.split_off(start)))
     }
 
     fn prune_non_root(&mut self, root: u64) {
@@ -371,16 +467,35 @@ mod tests {
     }
 
     fn restore_from_snapshot(old_bank_forks: &BankForks, account_paths: String) {
-        let (snapshot_path, snapshot_package_output_path) = old_bank_forks
+        let (snapshot_path, snapshot_package_output_path, archive_format) = old_bank_forks
             .snapshot_config
             .as_ref()
-            .map(|c| (&c.snapshot_path, &c.snapshot_package_output_path))
+            .map(|c| {
+                (
+                    &c.snapshot_path,
+                    &c.snapshot_package_output_path,
+                    c.archive_format,
+                )
+            })
             .unwrap();
 
+        let snapshot_tar =
+            snapshot_utils::get_snapshot_tar_path(snapshot_package_output_path, archive_format);
+
+        // a snapshot without the accounts hash it's expected to have isn't loaded
+        let mut snapshot_config = old_bank_forks.snapshot_config.clone().unwrap();
+        snapshot_config.expected_accounts_hash = Some(Hash::default());
+        assert!(snapshot_utils::bank_from_archive(
+            account_paths.clone(),
+            &snapshot_config,
+            &snapshot_tar
+        )
+        .is_err());
+
         let deserialized_bank = snapshot_utils::bank_from_archive(
             account_paths,
             old_bank_forks.snapshot_config.as_ref().unwrap(),
-            snapshot_utils::get_snapshot_tar_path(snapshot_package_output_path),
+            &snapshot_tar,
         )
         .unwrap();
 
@@ -435,8 +550,12 @@ mod tests {
         let snapshot_package = snapshot_utils::package_snapshot(
             last_bank,
             &slot_snapshot_paths,
-            snapshot_utils::get_snapshot_tar_path(&snapshot_config.snapshot_package_output_path),
+            snapshot_utils::get_snapshot_tar_path(
+                &snapshot_config.snapshot_package_output_path,
+                snapshot_config.archive_format,
+            ),
             &snapshot_config.snapshot_path,
+            snapshot_config.archive_format,
         )
         .unwrap();
         SnapshotPackagerService::package_snapshots(&snapshot_package).unwrap();
@@ -545,9 +664,11 @@ mod tests {
         let saved_snapshots_dir = TempDir::new().unwrap();
         let saved_accounts_dir = TempDir::new().unwrap();
         let saved_slot = 4;
-        let saved_tar = snapshot_config
-            .snapshot_package_output_path
-            .join(saved_slot.to_string());
+        let saved_tar = snapshot_config.snapshot_package_output_path.join(format!(
+            "{}.{}",
+            saved_slot,
+            snapshot_config.archive_format.extension()
+        ));
         for forks in 0..MAX_CACHE_ENTRIES + 2 {
             let bank = Bank::new_from_parent(
                 &bank_forks[forks as u64],
@@ -582,9 +703,11 @@ mod tests {
                     slot,
                     &vec![],
                     &package_sender,
-                    snapshot_config
-                        .snapshot_package_output_path
-                        .join(slot.to_string()),
+                    snapshot_config.snapshot_package_output_path.join(format!(
+                        "{}.{}",
+                        slot,
+                        snapshot_config.archive_format.extension()
+                    )),
                 )
                 .unwrap();
 
@@ -648,6 +771,81 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_bank_forks_incremental_snapshot() {
+        solana_logger::setup();
+        let mut snapshot_test_config = setup_snapshot_test(4);
+        let snapshot_config = SnapshotConfig {
+            incremental_snapshot_interval_slots: 1,
+            archive_format: ArchiveFormat::TarZstd,
+            ..snapshot_test_config.snapshot_config.clone()
+        };
+        let bank_forks = &mut snapshot_test_config.bank_forks;
+        bank_forks.set_snapshot_config(snapshot_config.clone());
+        let mint_keypair = &snapshot_test_config.genesis_block_info.mint_keypair;
+
+        let (sender, receiver) = channel();
+        let sender = Some(sender);
+        for slot in 0..6 {
+            let bank = Bank::new_from_parent(&bank_forks[slot], &Pubkey::default(), slot + 1);
+            let tx = system_transaction::create_user_account(
+                &mint_keypair,
+                &Keypair::new().pubkey(),
+                1,
+                bank.last_blockhash(),
+            );
+            assert_eq!(bank.process_transaction(&tx), Ok(()));
+            let bank = bank_forks.insert(bank);
+            bank_forks.set_root(bank.slot(), &sender);
+        }
+
+        // a full snapshot of slot 4, then incrementals of slots 5 and 6 relative to it
+        let packages: Vec<_> = receiver.try_iter().collect();
+        assert_eq!(
+            packages.iter().map(|p| p.base_slot()).collect_vec(),
+            vec![None, Some(4), Some(4)]
+        );
+        assert!(packages[2]
+            .storage_entries()
+            .iter()
+            .all(|entry| entry.fork_id() > 4));
+        for package in &packages {
+            SnapshotPackagerService::package_snapshots(package).unwrap();
+        }
+
+        let output_path = &snapshot_config.snapshot_package_output_path;
+        let incremental_tar =
+            snapshot_utils::find_incremental_snapshot_tar_path(output_path).unwrap();
+        assert_eq!(
+            ArchiveFormat::from_path(&incremental_tar),
+            Some(ArchiveFormat::TarZstd)
+        );
+        assert_eq!(
+            snapshot_utils::base_slot_from_incremental_archive(&incremental_tar).unwrap(),
+            4
+        );
+        let snapshot_tar = snapshot_utils::find_snapshot_tar_path(output_path).unwrap();
+        assert_eq!(snapshot_utils::slot_from_archive(&snapshot_tar).unwrap(), 4);
+        assert_eq!(
+            snapshot_utils::find_matching_incremental_snapshot_tar_path(output_path, &snapshot_tar),
+            Some(incremental_tar.clone())
+        );
+        let deserialized_bank = snapshot_utils::bank_from_archives(
+            snapshot_test_config
+                .accounts_dir
+                .path()
+                .to_str()
+                .unwrap()
+                .to_string(),
+            &snapshot_config,
+            snapshot_tar,
+            Some(incremental_tar),
+        )
+        .unwrap();
+        assert_eq!(deserialized_bank.slot(), 6);
+        bank_forks[6].compare_bank(&deserialized_bank);
+    }
+
     #[test]
     fn test_slots_since_snapshot() {
         solana_logger::setup();
@@ -714,8 +912,11 @@ mod tests {
 
         let snapshot_config = SnapshotConfig {
             snapshot_interval_slots,
+            incremental_snapshot_interval_slots: std::usize::MAX,
             snapshot_package_output_path: PathBuf::from(snapshot_output_path.path()),
             snapshot_path: PathBuf::from(snapshot_dir.path()),
+            archive_format: ArchiveFormat::TarBzip2,
+            expected_accounts_hash: None,
         };
         bank_forks.set_snapshot_config(snapshot_config.clone());
         SnapshotTestConfig {
@@ -2,6 +2,7 @@
 //! to contruct a software pipeline. The stage uses all available CPU cores and
 //! can do its processing in parallel with signature verification on the GPU.
 use crate::blocktree::Blocktree;
+use crate::blocktree_processor::{self, TransactionStatusBatch, TransactionStatusSender};
 use crate::cluster_info::ClusterInfo;
 use crate::entry;
 use crate::entry::{hash_transactions, Entry};
@@ -20,7 +21,7 @@ use itertools::Itertools;
 use solana_measure::measure::Measure;
 use solana_metrics::{inc_new_counter_debug, inc_new_counter_info, inc_new_counter_warn};
 use solana_runtime::accounts_db::ErrorCounters;
-use solana_runtime::bank::Bank;
+use solana_runtime::bank::{Bank, TransactionBalancesSet};
 use solana_runtime::locked_accounts_results::LockedAccountsResults;
 use solana_sdk::clock::{
     DEFAULT_TICKS_PER_SECOND, DEFAULT_TICKS_PER_SLOT, MAX_PROCESSING_AGE,
@@ -71,6 +72,7 @@ impl BankingStage {
         poh_recorder: &Arc<Mutex<PohRecorder>>,
         verified_receiver: CrossbeamReceiver<VerifiedPackets>,
         verified_vote_receiver: CrossbeamReceiver<VerifiedPackets>,
+        transaction_status_sender: Option<TransactionStatusSender>,
     ) -> Self {
         Self::new_num_threads(
             cluster_info,
@@ -78,6 +80,7 @@ impl BankingStage {
             verified_receiver,
             verified_vote_receiver,
             Self::num_threads(),
+            transaction_status_sender,
         )
     }
 
@@ -87,6 +90,7 @@ impl BankingStage {
         verified_receiver: CrossbeamReceiver<VerifiedPackets>,
         verified_vote_receiver: CrossbeamReceiver<VerifiedPackets>,
         num_threads: u32,
+        transaction_status_sender: Option<TransactionStatusSender>,
     ) -> Self {
         let batch_limit = TOTAL_BUFFERED_PACKETS / ((num_threads - 1) as usize * PACKETS_PER_BATCH);
         // Single thread to generate entries from many banks.
@@ -105,6 +109,7 @@ impl BankingStage {
 
                 let poh_recorder = poh_recorder.clone();
                 let cluster_info = cluster_info.clone();
+                let transaction_status_sender = transaction_status_sender.clone();
                 let mut recv_start = Instant::now();
                 Builder::new()
                     .name("solana-banking-stage-tx".to_string())
@@ -118,6 +123,7 @@ impl BankingStage {
                             enable_forwarding,
                             i,
                             batch_limit,
+                            transaction_status_sender.as_ref(),
                         );
                     })
                     .unwrap()
@@ -152,6 +158,7 @@ impl BankingStage {
         poh_recorder: &Arc<Mutex<PohRecorder>>,
         buffered_packets: &mut Vec<PacketsAndOffsets>,
         batch_limit: usize,
+        transaction_status_sender: Option<&TransactionStatusSender>,
     ) -> Result<UnprocessedPackets> {
         let mut unprocessed_packets = vec![];
         let mut rebuffered_packets = 0;
@@ -182,6 +189,7 @@ impl BankingStage {
                     &poh_recorder,
                     &msgs,
                     unprocessed_indexes.to_owned(),
+                    transaction_status_sender,
                 );
 
             new_tx_count += processed;
@@ -274,6 +282,7 @@ impl BankingStage {
         buffered_packets: &mut Vec<PacketsAndOffsets>,
         enable_forwarding: bool,
         batch_limit: usize,
+        transaction_status_sender: Option<&TransactionStatusSender>,
     ) -> Result<()> {
         let (poh_next_slot_leader, poh_has_bank, would_be_leader) = {
             let poh = poh_recorder.lock().unwrap();
@@ -300,6 +309,7 @@ impl BankingStage {
                     poh_recorder,
                     buffered_packets,
                     batch_limit,
+                    transaction_status_sender,
                 )?;
                 buffered_packets.append(&mut unprocessed);
                 Ok(())
@@ -347,6 +357,7 @@ impl BankingStage {
         enable_forwarding: bool,
         id: u32,
         batch_limit: usize,
+        transaction_status_sender: Option<&TransactionStatusSender>,
     ) {
         let socket = UdpSocket::bind("0.0.0.0:0").unwrap();
         let mut buffered_packets = vec![];
@@ -360,6 +371,7 @@ impl BankingStage {
                     &mut buffered_packets,
                     enable_forwarding,
                     batch_limit,
+                    transaction_status_sender,
                 )
                 .unwrap_or_else(|_| buffered_packets.clear());
             }
@@ -382,6 +394,7 @@ impl BankingStage {
                 recv_timeout,
                 id,
                 batch_limit,
+                transaction_status_sender,
             ) {
                 Err(Error::CrossbeamRecvTimeoutError(RecvTimeoutError::Timeout)) => (),
                 Err(Error::CrossbeamRecvTimeoutError(RecvTimeoutError::Disconnected)) => break,
@@ -483,14 +496,28 @@ impl BankingStage {
         txs: &[Transaction],
         poh: &Arc<Mutex<PohRecorder>>,
         lock_results: &LockedAccountsResults,
+        transaction_status_sender: Option<&TransactionStatusSender>,
     ) -> (Result<usize>, Vec<usize>) {
+        let pre_balances = if transaction_status_sender.is_some() {
+            bank.collect_balances(txs)
+        } else {
+            vec![]
+        };
+
         let mut load_execute_time = Measure::start("load_execute_time");
         // Use a shorter maximum age when adding transactions into the pipeline.  This will reduce
         // the likelihood of any single thread getting starved and processing old ids.
         // TODO: Banking stage threads should be prioritized to complete faster then this queue
         // expires.
-        let (mut loaded_accounts, results, mut retryable_txs, tx_count, signature_count) =
-            bank.load_and_execute_transactions(txs, None, lock_results, MAX_PROCESSING_AGE);
+        let (
+            mut loaded_accounts,
+            results,
+            transaction_logs,
+            compute_units_consumed,
+            mut retryable_txs,
+            tx_count,
+            signature_count,
+        ) = bank.load_and_execute_transactions(txs, None, lock_results, MAX_PROCESSING_AGE);
         load_execute_time.stop();
 
         let freeze_lock = bank.freeze_lock();
@@ -514,9 +541,36 @@ impl BankingStage {
                 None,
                 &mut loaded_accounts,
                 &results,
+                &transaction_logs,
                 tx_count,
                 signature_count,
             );
+
+            if let Some(transaction_status_sender) = transaction_status_sender {
+                let post_balances = bank.collect_post_balances(
+                    txs,
+                    None,
+                    &loaded_accounts,
+                    &results,
+                    &pre_balances,
+                );
+                let statuses = blocktree_processor::collect_transaction_statuses(
+                    bank,
+                    txs,
+                    None,
+                    &results,
+                    transaction_logs,
+                    &compute_units_consumed,
+                    &TransactionBalancesSet {
+                        pre_balances,
+                        post_balances,
+                    },
+                );
+                let _ = transaction_status_sender.send(TransactionStatusBatch {
+                    slot: bank.slot(),
+                    statuses,
+                });
+            }
         }
         commit_time.stop();
 
@@ -539,6 +593,7 @@ impl BankingStage {
         txs: &[Transaction],
         poh: &Arc<Mutex<PohRecorder>>,
         chunk_offset: usize,
+        transaction_status_sender: Option<&TransactionStatusSender>,
     ) -> (Result<usize>, Vec<usize>) {
         let mut lock_time = Measure::start("lock_time");
         // Once accounts are locked, other threads cannot encode transactions that will modify the
@@ -546,8 +601,13 @@ impl BankingStage {
         let lock_results = bank.lock_accounts(txs, None);
         lock_time.stop();
 
-        let (result, mut retryable_txs) =
-            Self::process_and_record_transactions_locked(bank, txs, poh, &lock_results);
+        let (result, mut retryable_txs) = Self::process_and_record_transactions_locked(
+            bank,
+            txs,
+            poh,
+            &lock_results,
+            transaction_status_sender,
+        );
         retryable_txs.iter_mut().for_each(|x| *x += chunk_offset);
 
         let mut unlock_time = Measure::start("unlock_time");
@@ -574,6 +634,7 @@ impl BankingStage {
         bank: &Bank,
         transactions: &[Transaction],
         poh: &Arc<Mutex<PohRecorder>>,
+        transaction_status_sender: Option<&TransactionStatusSender>,
     ) -> (usize, Vec<usize>) {
         let mut chunk_start = 0;
         let mut unprocessed_txs = vec![];
@@ -590,6 +651,7 @@ impl BankingStage {
                 &transactions[chunk_start..chunk_end],
                 poh,
                 chunk_start,
+                transaction_status_sender,
             );
             trace!("process_transactions result: {:?}", result);
 
@@ -717,6 +779,7 @@ impl BankingStage {
         poh: &Arc<Mutex<PohRecorder>>,
         msgs: &Packets,
         packet_indexes: Vec<usize>,
+        transaction_status_sender: Option<&TransactionStatusSender>,
     ) -> (usize, usize, Vec<usize>) {
         let (transactions, transaction_to_packet_indexes) =
             Self::transactions_from_packets(msgs, &packet_indexes);
@@ -729,7 +792,7 @@ impl BankingStage {
         let tx_len = transactions.len();
 
         let (processed, unprocessed_tx_indexes) =
-            Self::process_transactions(bank, &transactions, poh);
+            Self::process_transactions(bank, &transactions, poh, transaction_status_sender);
 
         let unprocessed_tx_count = unprocessed_tx_indexes.len();
 
@@ -800,6 +863,7 @@ impl BankingStage {
         recv_timeout: Duration,
         id: u32,
         batch_limit: usize,
+        transaction_status_sender: Option<&TransactionStatusSender>,
     ) -> Result<UnprocessedPackets> {
         let mut recv_time = Measure::start("process_packets_recv");
         let mms = verified_receiver.recv_timeout(recv_timeout)?;
@@ -836,8 +900,13 @@ impl BankingStage {
             }
             let bank = bank.unwrap();
 
-            let (processed, verified_txs_len, unprocessed_indexes) =
-                Self::process_received_packets(&bank, &poh, &msgs, packet_indexes);
+            let (processed, verified_txs_len, unprocessed_indexes) = Self::process_received_packets(
+                &bank,
+                &poh,
+                &msgs,
+                packet_indexes,
+                transaction_status_sender,
+            );
 
             new_tx_count += processed;
 
@@ -993,6 +1062,7 @@ mod tests {
                 &poh_recorder,
                 verified_receiver,
                 vote_receiver,
+                None,
             );
             drop(verified_sender);
             drop(vote_sender);
@@ -1028,6 +1098,7 @@ mod tests {
                 &poh_recorder,
                 verified_receiver,
                 vote_receiver,
+                None,
             );
             trace!("sending bank");
             sleep(Duration::from_millis(600));
@@ -1077,6 +1148,7 @@ mod tests {
                 &poh_recorder,
                 verified_receiver,
                 vote_receiver,
+                None,
             );
 
             // fund another account so we can send 2 good transactions in a single batch.
@@ -1224,6 +1296,7 @@ mod tests {
                     verified_receiver,
                     vote_receiver,
                     2,
+                    None,
                 );
 
                 // wait for banking_stage to eat the packets
@@ -1624,9 +1697,15 @@ mod tests {
 
             poh_recorder.lock().unwrap().set_working_bank(working_bank);
 
-            BankingStage::process_and_record_transactions(&bank, &transactions, &poh_recorder, 0)
-                .0
-                .unwrap();
+            BankingStage::process_and_record_transactions(
+                &bank,
+                &transactions,
+                &poh_recorder,
+                0,
+                None,
+            )
+            .0
+            .unwrap();
             poh_recorder.lock().unwrap().tick();
 
             let mut done = false;
@@ -1660,7 +1739,8 @@ mod tests {
                     &bank,
                     &transactions,
                     &poh_recorder,
-                    0
+                    0,
+                    None,
                 )
                 .0,
                 Err(Error::PohRecorderError(PohRecorderError::MaxHeightReached))
@@ -1717,6 +1797,7 @@ mod tests {
                 &transactions,
                 &poh_recorder,
                 0,
+                None,
             );
 
             assert!(result.is_ok());
@@ -1814,7 +1895,7 @@ mod tests {
             let poh_recorder = Arc::new(Mutex::new(poh_recorder));
 
             let (processed_transactions_count, mut retryable_txs) =
-                BankingStage::process_transactions(&bank, &transactions, &poh_recorder);
+                BankingStage::process_transactions(&bank, &transactions, &poh_recorder, None);
 
             assert_eq!(processed_transactions_count, 0,);
 
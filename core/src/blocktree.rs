@@ -16,11 +16,12 @@ use std::collections::HashMap;
 #[cfg(not(feature = "kvstore"))]
 use rocksdb;
 
+use solana_client::rpc_request::{RpcConfirmedBlock, RpcTransactionStatus};
 use solana_metrics::{datapoint_error, datapoint_info};
 
 use solana_sdk::genesis_block::GenesisBlock;
 use solana_sdk::hash::Hash;
-use solana_sdk::signature::{Keypair, KeypairUtil};
+use solana_sdk::signature::{Keypair, KeypairUtil, Signature};
 
 use std::borrow::{Borrow, Cow};
 use std::cell::RefCell;
@@ -79,6 +80,7 @@ pub enum BlocktreeError {
     #[cfg(feature = "kvstore")]
     KvsDb(kvstore::Error),
     SlotNotRooted,
+    SlotUnavailable,
 }
 
 // ledger window
@@ -91,6 +93,7 @@ pub struct Blocktree {
     index_cf: LedgerColumn<cf::Index>,
     data_shred_cf: LedgerColumn<cf::ShredData>,
     code_shred_cf: LedgerColumn<cf::ShredCode>,
+    transaction_status_cf: LedgerColumn<cf::TransactionStatus>,
     batch_processor: Arc<RwLock<BatchProcessor>>,
     last_root: Arc<RwLock<u64>>,
     pub new_shreds_signals: Vec<SyncSender<bool>>,
@@ -112,6 +115,8 @@ pub const INDEX_CF: &str = "index";
 pub const DATA_SHRED_CF: &str = "data_shred";
 /// Column family for Code Shreds
 pub const CODE_SHRED_CF: &str = "code_shred";
+/// Column family for Transaction Status
+pub const TRANSACTION_STATUS_CF: &str = "transaction_status";
 
 impl Blocktree {
     /// Opens a Ledger in directory, provides "infinite" window of shreds
@@ -140,6 +145,7 @@ impl Blocktree {
 
         let data_shred_cf = db.column();
         let code_shred_cf = db.column();
+        let transaction_status_cf = db.column();
 
         let db = Arc::new(db);
 
@@ -160,6 +166,7 @@ impl Blocktree {
             index_cf,
             data_shred_cf,
             code_shred_cf,
+            transaction_status_cf,
             new_shreds_signals: vec![],
             batch_processor,
             completed_slots_senders: vec![],
@@ -264,6 +271,10 @@ impl Blocktree {
                     .dead_slots_cf
                     .delete_slot(&mut write_batch, from_slot, batch_end)
                     .unwrap_or(false)
+                && self
+                    .transaction_status_cf
+                    .delete_slot(&mut write_batch, from_slot, batch_end)
+                    .unwrap_or(false)
                 && self
                     .db
                     .column::<cf::Root>()
@@ -1031,6 +1042,58 @@ impl Blocktree {
             .map(|x| x.0)
     }
 
+    /// Returns the transactions of a rooted slot, along with its blockhash and the blockhash
+    /// of its parent. Transaction statuses are left empty
+    pub fn get_confirmed_block(&self, slot: u64) -> Result<RpcConfirmedBlock> {
+        if !self.is_root(slot) {
+            return Err(Error::BlocktreeError(BlocktreeError::SlotNotRooted));
+        }
+        let slot_meta = self
+            .meta_cf
+            .get(slot)?
+            .ok_or(Error::BlocktreeError(BlocktreeError::SlotUnavailable))?;
+        let slot_entries = self.get_slot_entries(slot, 0, None)?;
+        let blockhash = get_last_hash(slot_entries.iter())
+            .ok_or(Error::BlocktreeError(BlocktreeError::SlotUnavailable))?;
+        let previous_blockhash = if slot == 0 {
+            Hash::default()
+        } else {
+            let parent_slot_entries = self.get_slot_entries(slot_meta.parent_slot, 0, None)?;
+            get_last_hash(parent_slot_entries.iter()).unwrap_or_default()
+        };
+        let transactions = slot_entries
+            .into_iter()
+            .flat_map(|entry| entry.transactions)
+            .map(|transaction| {
+                let status = self
+                    .transaction_status_cf
+                    .get((slot, transaction.signatures[0]))?;
+                Ok((transaction, status))
+            })
+            .collect::<Result<_>>()?;
+        Ok(RpcConfirmedBlock {
+            previous_blockhash,
+            blockhash,
+            parent_slot: slot_meta.parent_slot,
+            transactions,
+        })
+    }
+
+    pub fn transaction_status(
+        &self,
+        index: (Slot, Signature),
+    ) -> Result<Option<RpcTransactionStatus>> {
+        self.transaction_status_cf.get(index)
+    }
+
+    pub fn write_transaction_status(
+        &self,
+        index: (Slot, Signature),
+        status: &RpcTransactionStatus,
+    ) -> Result<()> {
+        self.transaction_status_cf.put(index, status)
+    }
+
     pub fn get_slot_entries_with_shred_count(
         &self,
         slot: u64,
@@ -1220,6 +1283,10 @@ impl Blocktree {
     }
 }
 
+fn get_last_hash<'a>(iterator: impl Iterator<Item = &'a Entry> + 'a) -> Option<Hash> {
+    iterator.last().map(|entry| entry.hash)
+}
+
 fn update_slot_meta(
     is_last_in_slot: bool,
     slot_meta: &mut SlotMeta,
@@ -2751,6 +2818,47 @@ pub mod tests {
             Blocktree::destroy(&blocktree_path).expect("Expected successful database destruction");
         }
     */
+    #[test]
+    fn test_get_confirmed_block() {
+        let blocktree_path = get_tmp_ledger_path!();
+        {
+            let blocktree = Blocktree::open(&blocktree_path).unwrap();
+            let (shreds, slot0_entries) = make_slot_entries(0, 0, 4);
+            blocktree.insert_shreds(shreds, None).unwrap();
+            let (shreds, slot1_entries) = make_slot_entries(1, 0, 4);
+            blocktree.insert_shreds(shreds, None).unwrap();
+
+            // Slot 1 isn't a root yet
+            assert!(blocktree.get_confirmed_block(1).is_err());
+
+            blocktree.set_roots(&[0, 1]).unwrap();
+            let confirmed_block = blocktree.get_confirmed_block(1).unwrap();
+            assert_eq!(confirmed_block.parent_slot, 0);
+            assert_eq!(
+                confirmed_block.previous_blockhash,
+                slot0_entries.last().unwrap().hash
+            );
+            assert_eq!(
+                confirmed_block.blockhash,
+                slot1_entries.last().unwrap().hash
+            );
+            let expected_transactions: Vec<_> = slot1_entries
+                .into_iter()
+                .flat_map(|entry| entry.transactions)
+                .map(|transaction| (transaction, None))
+                .collect();
+            assert_eq!(confirmed_block.transactions, expected_transactions);
+
+            // A rooted slot this ledger has no data for is an error, not a panic
+            blocktree.set_roots(&[2]).unwrap();
+            match blocktree.get_confirmed_block(2) {
+                Err(Error::BlocktreeError(BlocktreeError::SlotUnavailable)) => (),
+                result => panic!("unexpected result: {:?}", result.map(|_| ())),
+            }
+        }
+        Blocktree::destroy(&blocktree_path).expect("Expected successful database destruction");
+    }
+
     #[test]
     pub fn test_get_slots_since() {
         let blocktree_path = get_tmp_ledger_path("test_get_slots_since");
@@ -55,6 +55,10 @@ pub mod columns {
     #[derive(Debug)]
     /// The shred erasure code column
     pub struct ShredCode;
+
+    #[derive(Debug)]
+    /// The transaction status column
+    pub struct TransactionStatus;
 }
 
 pub trait Backend: Sized + Send + Sync {
@@ -4,7 +4,9 @@ use crate::blocktree::db::{
 };
 use crate::blocktree::BlocktreeError;
 use crate::result::{Error, Result};
+use solana_client::rpc_request::RpcTransactionStatus;
 use solana_sdk::clock::Slot;
+use solana_sdk::signature::Signature;
 
 use byteorder::{BigEndian, ByteOrder};
 
@@ -36,6 +38,7 @@ impl Backend for Rocks {
     fn open(path: &Path) -> Result<Rocks> {
         use crate::blocktree::db::columns::{
             DeadSlots, ErasureMeta, Index, Orphans, Root, ShredCode, ShredData, SlotMeta,
+            TransactionStatus,
         };
 
         fs::create_dir_all(&path)?;
@@ -60,6 +63,10 @@ impl Backend for Rocks {
             ColumnFamilyDescriptor::new(ShredData::NAME, get_cf_options(ShredData::NAME));
         let shred_code_cf_descriptor =
             ColumnFamilyDescriptor::new(ShredCode::NAME, get_cf_options(ShredCode::NAME));
+        let transaction_status_cf_descriptor = ColumnFamilyDescriptor::new(
+            TransactionStatus::NAME,
+            get_cf_options(TransactionStatus::NAME),
+        );
 
         let cfs = vec![
             meta_cf_descriptor,
@@ -70,6 +77,7 @@ impl Backend for Rocks {
             index_cf_descriptor,
             shred_data_cf_descriptor,
             shred_code_cf_descriptor,
+            transaction_status_cf_descriptor,
         ];
 
         // Open the database
@@ -81,6 +89,7 @@ impl Backend for Rocks {
     fn columns(&self) -> Vec<&'static str> {
         use crate::blocktree::db::columns::{
             DeadSlots, ErasureMeta, Index, Orphans, Root, ShredCode, ShredData, SlotMeta,
+            TransactionStatus,
         };
 
         vec![
@@ -92,6 +101,7 @@ impl Backend for Rocks {
             SlotMeta::NAME,
             ShredData::NAME,
             ShredCode::NAME,
+            TransactionStatus::NAME,
         ]
     }
 
@@ -374,6 +384,36 @@ impl TypedColumn<Rocks> for cf::ErasureMeta {
     type Type = super::ErasureMeta;
 }
 
+impl Column<Rocks> for cf::TransactionStatus {
+    const NAME: &'static str = super::TRANSACTION_STATUS_CF;
+    type Index = (Slot, Signature);
+
+    fn key((slot, index): (Slot, Signature)) -> Vec<u8> {
+        let mut key = vec![0; 8 + 64];
+        BigEndian::write_u64(&mut key[..8], slot);
+        key[8..72].clone_from_slice(&index.as_ref()[0..64]);
+        key
+    }
+
+    fn index(key: &[u8]) -> (Slot, Signature) {
+        let slot = BigEndian::read_u64(&key[..8]);
+        let index = Signature::new(&key[8..72]);
+        (slot, index)
+    }
+
+    fn slot(index: Self::Index) -> Slot {
+        index.0
+    }
+
+    fn as_index(slot: Slot) -> Self::Index {
+        (slot, Signature::default())
+    }
+}
+
+impl TypedColumn<Rocks> for cf::TransactionStatus {
+    type Type = RpcTransactionStatus;
+}
+
 impl DbCursor<Rocks> for DBRawIterator {
     fn valid(&self) -> bool {
         DBRawIterator::valid(self)
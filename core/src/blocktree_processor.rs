@@ -4,15 +4,19 @@ use crate::entry::{Entry, EntrySlice};
 use crate::leader_schedule_cache::LeaderScheduleCache;
 use rayon::prelude::*;
 use rayon::ThreadPool;
+use solana_client::rpc_request::RpcTransactionStatus;
 use solana_metrics::{datapoint, datapoint_error, inc_new_counter_debug};
-use solana_runtime::bank::Bank;
+use solana_runtime::bank::{Bank, TransactionBalancesSet};
 use solana_runtime::locked_accounts_results::LockedAccountsResults;
+use solana_runtime::log_collector::TransactionLogMessages;
 use solana_sdk::clock::{Slot, MAX_RECENT_BLOCKHASHES};
 use solana_sdk::genesis_block::GenesisBlock;
 use solana_sdk::hash::Hash;
+use solana_sdk::signature::Signature;
 use solana_sdk::timing::duration_as_ms;
-use solana_sdk::transaction::Result;
+use solana_sdk::transaction::{Result, Transaction};
 use std::result;
+use std::sync::mpsc::Sender;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
@@ -36,12 +40,63 @@ fn first_err(results: &[Result<()>]) -> Result<()> {
     Ok(())
 }
 
+pub struct TransactionStatusBatch {
+    pub slot: Slot,
+    pub statuses: Vec<(Signature, RpcTransactionStatus)>,
+}
+
+pub type TransactionStatusSender = Sender<TransactionStatusBatch>;
+
+// Builds the status of every committed transaction in `transactions`. `results`,
+// `transaction_logs` and `compute_units_consumed` are in execution order, which may differ from
+// the order of `transactions` and the balances
+pub fn collect_transaction_statuses(
+    bank: &Bank,
+    transactions: &[Transaction],
+    txs_execution_order: Option<&[usize]>,
+    results: &[Result<()>],
+    transaction_logs: Vec<TransactionLogMessages>,
+    compute_units_consumed: &[u64],
+    balances: &TransactionBalancesSet,
+) -> Vec<(Signature, RpcTransactionStatus)> {
+    (0..transactions.len())
+        .map(|i| txs_execution_order.map_or(i, |order| order[i]))
+        .zip(results.iter().zip(transaction_logs.into_iter()))
+        .zip(compute_units_consumed.iter())
+        .filter(|((_, (result, _)), _)| Bank::can_commit(result))
+        .map(|((index, (_, log_messages)), compute_units_consumed)| {
+            let transaction = &transactions[index];
+            let signature = transaction.signatures[0];
+            // The fee filter masks instruction errors, so recover the actual outcome from
+            // the status cache
+            let status = bank.get_signature_status(&signature).unwrap_or(Ok(()));
+            let fee = bank
+                .get_fee_calculator(&transaction.message().recent_blockhash)
+                .map(|fee_calculator| fee_calculator.calculate_fee(transaction.message()))
+                .unwrap_or(0);
+            (
+                signature,
+                RpcTransactionStatus {
+                    status,
+                    fee,
+                    pre_balances: balances.pre_balances[index].clone(),
+                    post_balances: balances.post_balances[index].clone(),
+                    log_messages,
+                    compute_units_consumed: *compute_units_consumed,
+                },
+            )
+        })
+        .collect()
+}
+
 fn par_execute_entries(
     bank: &Bank,
     entries: &[(&Entry, LockedAccountsResults, bool, Vec<usize>)],
+    transaction_status_sender: Option<&TransactionStatusSender>,
 ) -> Result<()> {
     inc_new_counter_debug!("bank-par_execute_entries-count", entries.len());
-    let results: Vec<Result<()>> = PAR_THREAD_POOL.with(|thread_pool| {
+    let collect_statuses = transaction_status_sender.is_some();
+    let results: Vec<_> = PAR_THREAD_POOL.with(|thread_pool| {
         thread_pool.borrow().install(|| {
             entries
                 .into_par_iter()
@@ -52,12 +107,27 @@ fn par_execute_entries(
                         } else {
                             None
                         };
-                        let results = bank.load_execute_and_commit_transactions(
-                            &e.transactions,
-                            tx_execution_order,
-                            locked_accounts,
-                            MAX_RECENT_BLOCKHASHES,
-                        );
+                        let (results, transaction_logs, compute_units_consumed, balances) = bank
+                            .load_execute_and_commit_transactions(
+                                &e.transactions,
+                                tx_execution_order,
+                                locked_accounts,
+                                MAX_RECENT_BLOCKHASHES,
+                                collect_statuses,
+                            );
+                        let statuses = if collect_statuses {
+                            collect_transaction_statuses(
+                                bank,
+                                &e.transactions,
+                                tx_execution_order,
+                                &results,
+                                transaction_logs,
+                                &compute_units_consumed,
+                                &balances,
+                            )
+                        } else {
+                            vec![]
+                        };
                         let mut first_err = None;
                         for (r, tx) in results.iter().zip(e.transactions.iter()) {
                             if let Err(ref e) = r {
@@ -73,13 +143,24 @@ fn par_execute_entries(
                                 }
                             }
                         }
-                        first_err.unwrap_or(Ok(()))
+                        (first_err.unwrap_or(Ok(())), statuses)
                     },
                 )
                 .collect()
         })
     });
 
+    let (results, statuses): (Vec<_>, Vec<_>) = results.into_iter().unzip();
+    if let Some(transaction_status_sender) = transaction_status_sender {
+        let statuses: Vec<_> = statuses.into_iter().flatten().collect();
+        if !statuses.is_empty() {
+            let _ = transaction_status_sender.send(TransactionStatusBatch {
+                slot: bank.slot(),
+                statuses,
+            });
+        }
+    }
+
     first_err(&results)
 }
 
@@ -92,13 +173,14 @@ pub fn process_entries(
     bank: &Bank,
     entries: &[Entry],
     randomize_tx_execution_order: bool,
+    transaction_status_sender: Option<&TransactionStatusSender>,
 ) -> Result<()> {
     // accumulator for entries that can be processed in parallel
     let mut mt_group = vec![];
     for entry in entries {
         if entry.is_tick() {
             // if its a tick, execute the group and register the tick
-            par_execute_entries(bank, &mt_group)?;
+            par_execute_entries(bank, &mt_group, transaction_status_sender)?;
             mt_group = vec![];
             bank.register_tick(&entry.hash);
             continue;
@@ -156,12 +238,12 @@ pub fn process_entries(
             } else {
                 // else we have an entry that conflicts with a prior entry
                 // execute the current queue and try to process this entry again
-                par_execute_entries(bank, &mt_group)?;
+                par_execute_entries(bank, &mt_group, transaction_status_sender)?;
                 mt_group = vec![];
             }
         }
     }
-    par_execute_entries(bank, &mt_group)?;
+    par_execute_entries(bank, &mt_group, transaction_status_sender)?;
     Ok(())
 }
 
@@ -181,13 +263,20 @@ pub fn process_blocktree(
     account_paths: Option<String>,
     verify_ledger: bool,
     dev_halt_at_slot: Option<Slot>,
+    transaction_status_sender: Option<&TransactionStatusSender>,
 ) -> result::Result<(BankForks, Vec<BankForksInfo>, LeaderScheduleCache), BlocktreeProcessorError> {
     info!("processing ledger from bank 0...");
 
     // Setup bank for slot 0
     let bank0 = Arc::new(Bank::new_with_paths(&genesis_block, account_paths));
-    process_bank_0(&bank0, blocktree, verify_ledger)?;
-    process_blocktree_from_root(blocktree, bank0, verify_ledger, dev_halt_at_slot)
+    process_bank_0(&bank0, blocktree, verify_ledger, transaction_status_sender)?;
+    process_blocktree_from_root(
+        blocktree,
+        bank0,
+        verify_ledger,
+        dev_halt_at_slot,
+        transaction_status_sender,
+    )
 }
 
 // Process blocktree from a known root bank
@@ -196,6 +285,7 @@ pub fn process_blocktree_from_root(
     bank: Arc<Bank>,
     verify_ledger: bool,
     dev_halt_at_slot: Option<Slot>,
+    transaction_status_sender: Option<&TransactionStatusSender>,
 ) -> result::Result<(BankForks, Vec<BankForksInfo>, LeaderScheduleCache), BlocktreeProcessorError> {
     info!("processing ledger from root: {}...", bank.slot());
     // Starting slot must be a root, and thus has no parents
@@ -224,6 +314,7 @@ pub fn process_blocktree_from_root(
                 &mut rooted_path,
                 verify_ledger,
                 dev_halt_at_slot,
+                transaction_status_sender,
             )?;
             let (banks, bank_forks_info): (Vec<_>, Vec<_>) = fork_info.into_iter().unzip();
             let bank_forks = BankForks::new_from_banks(&banks, rooted_path);
@@ -255,6 +346,7 @@ fn verify_and_process_entries(
     entries: &[Entry],
     verify_ledger: bool,
     last_entry_hash: Hash,
+    transaction_status_sender: Option<&TransactionStatusSender>,
 ) -> result::Result<Hash, BlocktreeProcessorError> {
     assert!(!entries.is_empty());
 
@@ -263,7 +355,7 @@ fn verify_and_process_entries(
         return Err(BlocktreeProcessorError::LedgerVerificationFailed);
     }
 
-    process_entries(&bank, &entries, true).map_err(|err| {
+    process_entries(&bank, &entries, true, transaction_status_sender).map_err(|err| {
         warn!(
             "Failed to process entries for slot {}: {:?}",
             bank.slot(),
@@ -280,6 +372,7 @@ fn process_bank_0(
     bank0: &Bank,
     blocktree: &Blocktree,
     verify_ledger: bool,
+    transaction_status_sender: Option<&TransactionStatusSender>,
 ) -> result::Result<(), BlocktreeProcessorError> {
     assert_eq!(bank0.slot(), 0);
 
@@ -303,7 +396,13 @@ fn process_bank_0(
     }
 
     if !entries.is_empty() {
-        verify_and_process_entries(bank0, &entries, verify_ledger, entry0.hash)?;
+        verify_and_process_entries(
+            bank0,
+            &entries,
+            verify_ledger,
+            entry0.hash,
+            transaction_status_sender,
+        )?;
     } else {
         bank0.register_tick(&entry0.hash);
     }
@@ -378,6 +477,7 @@ fn process_pending_slots(
     rooted_path: &mut Vec<u64>,
     verify_ledger: bool,
     dev_halt_at_slot: Slot,
+    transaction_status_sender: Option<&TransactionStatusSender>,
 ) -> result::Result<Vec<(Arc<Bank>, BankForksInfo)>, BlocktreeProcessorError> {
     let mut fork_info = vec![];
     let mut last_status_report = Instant::now();
@@ -405,7 +505,13 @@ fn process_pending_slots(
             BlocktreeProcessorError::LedgerVerificationFailed
         })?;
 
-        verify_and_process_entries(&bank, &entries, verify_ledger, last_entry_hash)?;
+        verify_and_process_entries(
+            &bank,
+            &entries,
+            verify_ledger,
+            last_entry_hash,
+            transaction_status_sender,
+        )?;
 
         bank.freeze(); // all banks handled by this routine are created from complete slots
 
@@ -456,6 +562,8 @@ pub mod tests {
     use solana_sdk::system_transaction;
     use solana_sdk::transaction::Transaction;
     use solana_sdk::transaction::TransactionError;
+    use std::collections::HashMap;
+    use std::sync::mpsc::channel;
 
     pub fn fill_blocktree_slot_with_ticks(
         blocktree: &Blocktree,
@@ -538,7 +646,7 @@ pub mod tests {
         fill_blocktree_slot_with_ticks(&blocktree, ticks_per_slot, 2, 1, blockhash);
 
         let (mut _bank_forks, bank_forks_info, _) =
-            process_blocktree(&genesis_block, &blocktree, None, true, None).unwrap();
+            process_blocktree(&genesis_block, &blocktree, None, true, None, None).unwrap();
 
         assert_eq!(bank_forks_info.len(), 1);
         assert_eq!(
@@ -596,7 +704,7 @@ pub mod tests {
         blocktree.set_roots(&[0, 1, 4]).unwrap();
 
         let (bank_forks, bank_forks_info, _) =
-            process_blocktree(&genesis_block, &blocktree, None, true, None).unwrap();
+            process_blocktree(&genesis_block, &blocktree, None, true, None, None).unwrap();
 
         assert_eq!(bank_forks_info.len(), 1); // One fork, other one is ignored b/c not a descendant of the root
 
@@ -666,7 +774,7 @@ pub mod tests {
         blocktree.set_roots(&[0, 1]).unwrap();
 
         let (bank_forks, bank_forks_info, _) =
-            process_blocktree(&genesis_block, &blocktree, None, true, None).unwrap();
+            process_blocktree(&genesis_block, &blocktree, None, true, None, None).unwrap();
 
         assert_eq!(bank_forks_info.len(), 2); // There are two forks
         assert_eq!(
@@ -742,7 +850,7 @@ pub mod tests {
 
         // Check that we can properly restart the ledger / leader scheduler doesn't fail
         let (bank_forks, bank_forks_info, _) =
-            process_blocktree(&genesis_block, &blocktree, None, true, None).unwrap();
+            process_blocktree(&genesis_block, &blocktree, None, true, None, None).unwrap();
 
         assert_eq!(bank_forks_info.len(), 1); // There is one fork
         assert_eq!(
@@ -820,7 +928,7 @@ pub mod tests {
         );
 
         // Now ensure the TX is accepted despite pointing to the ID of an empty entry.
-        process_entries(&bank, &slot_entries, true).unwrap();
+        process_entries(&bank, &slot_entries, true, None).unwrap();
         assert_eq!(bank.process_transaction(&tx), Ok(()));
     }
 
@@ -885,7 +993,7 @@ pub mod tests {
             )
             .unwrap();
         let (bank_forks, bank_forks_info, _) =
-            process_blocktree(&genesis_block, &blocktree, None, true, None).unwrap();
+            process_blocktree(&genesis_block, &blocktree, None, true, None, None).unwrap();
 
         assert_eq!(bank_forks_info.len(), 1);
         assert_eq!(bank_forks.root(), 0);
@@ -910,7 +1018,7 @@ pub mod tests {
 
         let blocktree = Blocktree::open(&ledger_path).unwrap();
         let (bank_forks, bank_forks_info, _) =
-            process_blocktree(&genesis_block, &blocktree, None, true, None).unwrap();
+            process_blocktree(&genesis_block, &blocktree, None, true, None, None).unwrap();
 
         assert_eq!(bank_forks_info.len(), 1);
         assert_eq!(bank_forks_info[0], BankForksInfo { bank_slot: 0 });
@@ -926,7 +1034,7 @@ pub mod tests {
         // ensure bank can process a tick
         assert_eq!(bank.tick_height(), 0);
         let tick = next_entry(&genesis_block.hash(), 1, vec![]);
-        assert_eq!(process_entries(&bank, &[tick.clone()], true), Ok(()));
+        assert_eq!(process_entries(&bank, &[tick.clone()], true, None), Ok(()));
         assert_eq!(bank.tick_height(), 1);
     }
 
@@ -958,7 +1066,10 @@ pub mod tests {
             bank.last_blockhash(),
         );
         let entry_2 = next_entry(&entry_1.hash, 1, vec![tx]);
-        assert_eq!(process_entries(&bank, &[entry_1, entry_2], true), Ok(()));
+        assert_eq!(
+            process_entries(&bank, &[entry_1, entry_2], true, None),
+            Ok(())
+        );
         assert_eq!(bank.get_balance(&keypair1.pubkey()), 2);
         assert_eq!(bank.get_balance(&keypair2.pubkey()), 2);
         assert_eq!(bank.last_blockhash(), blockhash);
@@ -1012,7 +1123,12 @@ pub mod tests {
         );
 
         assert_eq!(
-            process_entries(&bank, &[entry_1_to_mint, entry_2_to_3_mint_to_1], false),
+            process_entries(
+                &bank,
+                &[entry_1_to_mint, entry_2_to_3_mint_to_1],
+                false,
+                None
+            ),
             Ok(())
         );
 
@@ -1081,7 +1197,8 @@ pub mod tests {
         assert!(process_entries(
             &bank,
             &[entry_1_to_mint.clone(), entry_2_to_3_mint_to_1.clone()],
-            false
+            false,
+            None
         )
         .is_err());
 
@@ -1191,7 +1308,8 @@ pub mod tests {
                 entry_2_to_3_and_1_to_mint.clone(),
                 entry_conflict_itself.clone()
             ],
-            false
+            false,
+            None
         )
         .is_err());
 
@@ -1246,12 +1364,80 @@ pub mod tests {
             bank.last_blockhash(),
         );
         let entry_2 = next_entry(&entry_1.hash, 1, vec![tx]);
-        assert_eq!(process_entries(&bank, &[entry_1, entry_2], true), Ok(()));
+        assert_eq!(
+            process_entries(&bank, &[entry_1, entry_2], true, None),
+            Ok(())
+        );
         assert_eq!(bank.get_balance(&keypair3.pubkey()), 1);
         assert_eq!(bank.get_balance(&keypair4.pubkey()), 1);
         assert_eq!(bank.last_blockhash(), blockhash);
     }
 
+    #[test]
+    fn test_process_entries_transaction_status() {
+        let GenesisBlockInfo {
+            genesis_block,
+            mint_keypair,
+            ..
+        } = create_genesis_block(1000);
+        let bank = Bank::new(&genesis_block);
+        let keypair1 = Keypair::new();
+        let keypair2 = Keypair::new();
+        let keypair3 = Keypair::new();
+        let keypair4 = Keypair::new();
+
+        let tx = system_transaction::create_user_account(
+            &mint_keypair,
+            &keypair2.pubkey(),
+            5,
+            bank.last_blockhash(),
+        );
+        assert_eq!(bank.process_transaction(&tx), Ok(()));
+
+        let blockhash = bank.last_blockhash();
+        let success_tx =
+            system_transaction::transfer(&mint_keypair, &keypair1.pubkey(), 2, blockhash);
+        let failed_tx = system_transaction::transfer(&keypair2, &keypair2.pubkey(), 1, blockhash);
+        let uncommitted_tx =
+            system_transaction::transfer(&keypair3, &keypair4.pubkey(), 1, blockhash);
+        let entry = next_entry(
+            &blockhash,
+            1,
+            vec![
+                success_tx.clone(),
+                failed_tx.clone(),
+                uncommitted_tx.clone(),
+            ],
+        );
+
+        let (transaction_status_sender, transaction_status_receiver) = channel();
+        assert_eq!(
+            process_entries(&bank, &[entry], true, Some(&transaction_status_sender)),
+            Err(TransactionError::AccountNotFound)
+        );
+
+        let batch = transaction_status_receiver.try_recv().unwrap();
+        assert_eq!(batch.slot, bank.slot());
+        assert_eq!(batch.statuses.len(), 2);
+        let statuses: HashMap<_, _> = batch.statuses.into_iter().collect();
+        assert!(!statuses.contains_key(&uncommitted_tx.signatures[0]));
+
+        let status = &statuses[&success_tx.signatures[0]];
+        assert_eq!(status.status, Ok(()));
+        assert_eq!(status.pre_balances[..2], [995, 0]);
+        assert_eq!(status.post_balances[..2], [993, 2]);
+
+        let status = &statuses[&failed_tx.signatures[0]];
+        assert_eq!(
+            status.status,
+            Err(TransactionError::InstructionError(
+                0,
+                InstructionError::DuplicateAccountIndex
+            ))
+        );
+        assert_eq!(status.pre_balances, status.post_balances);
+    }
+
     #[test]
     fn test_process_entry_tx_random_execution_no_error() {
         // entropy multiplier should be big enough to provide sufficient entropy
@@ -1309,7 +1495,7 @@ pub mod tests {
 
         // Transfer lamports to each other
         let entry = next_entry(&bank.last_blockhash(), 1, tx_vector);
-        assert_eq!(process_entries(&bank, &vec![entry], true), Ok(()));
+        assert_eq!(process_entries(&bank, &vec![entry], true, None), Ok(()));
         bank.squash();
 
         // Even number keypair should have balance of 2 * initial_lamports and
@@ -1378,7 +1564,8 @@ pub mod tests {
             process_entries(
                 &bank,
                 &[entry_1.clone(), tick.clone(), entry_2.clone()],
-                true
+                true,
+                None
             ),
             Ok(())
         );
@@ -1394,7 +1581,7 @@ pub mod tests {
         );
         let entry_3 = next_entry(&entry_2.hash, 1, vec![tx]);
         assert_eq!(
-            process_entries(&bank, &[entry_3], true),
+            process_entries(&bank, &[entry_3], true, None),
             Err(TransactionError::AccountNotFound)
         );
     }
@@ -1475,7 +1662,7 @@ pub mod tests {
         );
 
         assert_eq!(
-            process_entries(&bank, &[entry_1_to_mint], false),
+            process_entries(&bank, &[entry_1_to_mint], false, None),
             Err(TransactionError::AccountInUse)
         );
 
@@ -1521,15 +1708,16 @@ pub mod tests {
 
         // Set up bank1
         let bank0 = Arc::new(Bank::new(&genesis_block));
-        process_bank_0(&bank0, &blocktree, true).unwrap();
+        process_bank_0(&bank0, &blocktree, true, None).unwrap();
         let bank1 = Arc::new(Bank::new_from_parent(&bank0, &Pubkey::default(), 1));
         bank1.squash();
         let slot1_entries = blocktree.get_slot_entries(1, 0, None).unwrap();
-        verify_and_process_entries(&bank1, &slot1_entries, true, bank0.last_blockhash()).unwrap();
+        verify_and_process_entries(&bank1, &slot1_entries, true, bank0.last_blockhash(), None)
+            .unwrap();
 
         // Test process_blocktree_from_root() from slot 1 onwards
         let (bank_forks, bank_forks_info, _) =
-            process_blocktree_from_root(&blocktree, bank1, true, None).unwrap();
+            process_blocktree_from_root(&blocktree, bank1, true, None, None).unwrap();
 
         assert_eq!(bank_forks_info.len(), 1); // One fork
         assert_eq!(
@@ -1601,7 +1789,7 @@ pub mod tests {
                 })
                 .collect();
             info!("paying iteration {}", i);
-            process_entries(&bank, &entries, true).expect("paying failed");
+            process_entries(&bank, &entries, true, None).expect("paying failed");
 
             let entries: Vec<_> = (0..NUM_TRANSFERS)
                 .map(|i| {
@@ -1619,7 +1807,7 @@ pub mod tests {
                 .collect();
 
             info!("refunding iteration {}", i);
-            process_entries(&bank, &entries, true).expect("refunding failed");
+            process_entries(&bank, &entries, true, None).expect("refunding failed");
 
             // advance to next block
             process_entries(
@@ -1628,6 +1816,7 @@ pub mod tests {
                     .map(|_| next_entry_mut(&mut hash, 1, vec![]))
                     .collect::<Vec<_>>(),
                 true,
+                None,
             )
             .expect("process ticks failed");
 
@@ -5,6 +5,7 @@
 //! command-line tools to spin up fullnodes and a Rust library
 //!
 
+pub mod accounts_background_service;
 pub mod bank_forks;
 pub mod banking_stage;
 pub mod blob_fetch_stage;
@@ -70,6 +71,7 @@ pub mod storage_stage;
 pub mod streamer;
 pub mod test_tx;
 pub mod tpu;
+pub mod transaction_status_service;
 pub mod tvu;
 pub mod validator;
 pub(crate) mod version;
@@ -2,7 +2,7 @@
 
 use crate::bank_forks::BankForks;
 use crate::blocktree::{Blocktree, BlocktreeError};
-use crate::blocktree_processor;
+use crate::blocktree_processor::{self, TransactionStatusSender};
 use crate::cluster_info::ClusterInfo;
 use crate::confidence::ForkConfidenceCache;
 use crate::consensus::{StakeLockout, Tower};
@@ -11,7 +11,7 @@ use crate::leader_schedule_cache::LeaderScheduleCache;
 use crate::packet::BlobError;
 use crate::poh_recorder::PohRecorder;
 use crate::result::{Error, Result};
-use crate::rpc_subscriptions::RpcSubscriptions;
+use crate::rpc_subscriptions::{RpcSubscriptions, SlotInfo};
 use crate::service::Service;
 use crate::snapshot_package::SnapshotPackageSender;
 use solana_metrics::{datapoint_warn, inc_new_counter_info};
@@ -96,6 +96,7 @@ impl ReplayStage {
         slot_full_senders: Vec<Sender<(u64, Pubkey)>>,
         snapshot_package_sender: Option<SnapshotPackageSender>,
         fork_confidence_cache: Arc<RwLock<ForkConfidenceCache>>,
+        transaction_status_sender: Option<TransactionStatusSender>,
     ) -> (Self, Receiver<Vec<Arc<Bank>>>)
     where
         T: 'static + KeypairUtil + Send + Sync,
@@ -129,11 +130,14 @@ impl ReplayStage {
                         break;
                     }
 
-                    Self::generate_new_bank_forks(
+                    let new_slots = Self::generate_new_bank_forks(
                         &blocktree,
                         &mut bank_forks.write().unwrap(),
                         &leader_schedule_cache,
                     );
+                    for SlotInfo { slot, parent, root } in new_slots {
+                        subscriptions.notify_slot(slot, parent, root);
+                    }
 
                     let mut tpu_has_bank = poh_recorder.lock().unwrap().has_bank();
 
@@ -143,6 +147,7 @@ impl ReplayStage {
                         &my_pubkey,
                         &mut progress,
                         &slot_full_senders,
+                        transaction_status_sender.as_ref(),
                     );
 
                     let ancestors = Arc::new(bank_forks.read().unwrap().ancestors());
@@ -194,6 +199,7 @@ impl ReplayStage {
                             total_staked,
                             &lockouts_sender,
                             &snapshot_package_sender,
+                            &subscriptions,
                         )?;
 
                         Self::reset_poh_recorder(
@@ -212,6 +218,7 @@ impl ReplayStage {
                             &bank_forks,
                             &poh_recorder,
                             &leader_schedule_cache,
+                            &subscriptions,
                         );
 
                         if let Some(bank) = poh_recorder.lock().unwrap().bank() {
@@ -281,6 +288,7 @@ impl ReplayStage {
         bank_forks: &Arc<RwLock<BankForks>>,
         poh_recorder: &Arc<Mutex<PohRecorder>>,
         leader_schedule_cache: &Arc<LeaderScheduleCache>,
+        subscriptions: &Arc<RpcSubscriptions>,
     ) {
         // all the individual calls to poh_recorder.lock() are designed to
         // increase granularity, decrease contention
@@ -335,6 +343,8 @@ impl ReplayStage {
                 ("leader", next_leader.to_string(), String),
             );
 
+            let root_slot = bank_forks.read().unwrap().root();
+            subscriptions.notify_slot(poh_slot, parent_slot, root_slot);
             let tpu_bank = bank_forks
                 .write()
                 .unwrap()
@@ -366,13 +376,20 @@ impl ReplayStage {
         bank: &Bank,
         blocktree: &Blocktree,
         progress: &mut HashMap<u64, ForkProgress>,
+        transaction_status_sender: Option<&TransactionStatusSender>,
     ) -> (Result<()>, usize) {
         let mut tx_count = 0;
         let result =
             Self::load_blocktree_entries(bank, blocktree, progress).and_then(|(entries, num)| {
                 debug!("Replaying {:?} entries, num {:?}", entries.len(), num);
                 tx_count += entries.iter().map(|e| e.transactions.len()).sum::<usize>();
-                Self::replay_entries_into_bank(bank, entries, progress, num)
+                Self::replay_entries_into_bank(
+                    bank,
+                    entries,
+                    progress,
+                    num,
+                    transaction_status_sender,
+                )
             });
 
         if Self::is_replay_result_fatal(&result) {
@@ -415,6 +432,7 @@ impl ReplayStage {
         total_staked: u64,
         lockouts_sender: &Sender<LockoutAggregationData>,
         snapshot_package_sender: &Option<SnapshotPackageSender>,
+        subscriptions: &Arc<RpcSubscriptions>,
     ) -> Result<()>
     where
         T: 'static + KeypairUtil + Send + Sync,
@@ -445,6 +463,7 @@ impl ReplayStage {
                 .unwrap()
                 .set_root(new_root, snapshot_package_sender);
             Self::handle_new_root(&bank_forks, progress);
+            subscriptions.notify_roots(rooted_slots);
             trace!("new root {}", new_root);
             if let Err(e) = root_bank_sender.send(rooted_banks) {
                 trace!("root_bank_sender failed: {:?}", e);
@@ -522,6 +541,7 @@ impl ReplayStage {
         my_pubkey: &Pubkey,
         progress: &mut HashMap<u64, ForkProgress>,
         slot_full_senders: &[Sender<(u64, Pubkey)>],
+        transaction_status_sender: Option<&TransactionStatusSender>,
     ) -> bool {
         let mut did_complete_bank = false;
         let mut tx_count = 0;
@@ -537,8 +557,12 @@ impl ReplayStage {
 
             let bank = bank_forks.read().unwrap().get(*bank_slot).unwrap().clone();
             if bank.collector_id() != my_pubkey {
-                let (replay_result, replay_tx_count) =
-                    Self::replay_blocktree_into_bank(&bank, &blocktree, progress);
+                let (replay_result, replay_tx_count) = Self::replay_blocktree_into_bank(
+                    &bank,
+                    &blocktree,
+                    progress,
+                    transaction_status_sender,
+                );
                 tx_count += replay_tx_count;
                 if Self::is_replay_result_fatal(&replay_result) {
                     trace!("replay_result_fatal slot {}", bank_slot);
@@ -686,11 +710,17 @@ impl ReplayStage {
         entries: Vec<Entry>,
         progress: &mut HashMap<u64, ForkProgress>,
         num: usize,
+        transaction_status_sender: Option<&TransactionStatusSender>,
     ) -> Result<()> {
         let bank_progress = &mut progress
             .entry(bank.slot())
             .or_insert_with(|| ForkProgress::new(bank.last_blockhash()));
-        let result = Self::verify_and_process_entries(&bank, &entries, &bank_progress.last_entry);
+        let result = Self::verify_and_process_entries(
+            &bank,
+            &entries,
+            &bank_progress.last_entry,
+            transaction_status_sender,
+        );
         bank_progress.num_blobs += num;
         if let Some(last_entry) = entries.last() {
             bank_progress.last_entry = last_entry.hash;
@@ -703,6 +733,7 @@ impl ReplayStage {
         bank: &Bank,
         entries: &[Entry],
         last_entry: &Hash,
+        transaction_status_sender: Option<&TransactionStatusSender>,
     ) -> Result<()> {
         if !entries.verify(last_entry) {
             trace!(
@@ -720,7 +751,7 @@ impl ReplayStage {
             );
             return Err(Error::BlobError(BlobError::VerificationFailed));
         }
-        blocktree_processor::process_entries(bank, entries, true)?;
+        blocktree_processor::process_entries(bank, entries, true, transaction_status_sender)?;
 
         Ok(())
     }
@@ -751,7 +782,7 @@ impl ReplayStage {
         blocktree: &Blocktree,
         forks: &mut BankForks,
         leader_schedule_cache: &Arc<LeaderScheduleCache>,
-    ) {
+    ) -> Vec<SlotInfo> {
         // Find the next slot that chains to the old slot
         let frozen_banks = forks.frozen_banks();
         let frozen_bank_slots: Vec<u64> = frozen_banks.keys().cloned().collect();
@@ -764,6 +795,7 @@ impl ReplayStage {
             next_slots.sort();
             next_slots
         });
+        let mut new_slots = vec![];
         for (parent_id, children) in next_slots {
             let parent_bank = frozen_banks
                 .get(&parent_id)
@@ -778,9 +810,15 @@ impl ReplayStage {
                     .slot_leader_at(child_id, Some(&parent_bank))
                     .unwrap();
                 info!("new fork:{} parent:{}", child_id, parent_id);
+                new_slots.push(SlotInfo {
+                    slot: child_id,
+                    parent: parent_id,
+                    root: forks.root(),
+                });
                 forks.insert(Bank::new_from_parent(&parent_bank, &leader, child_id));
             }
         }
+        new_slots
     }
 }
 
@@ -899,24 +937,28 @@ mod test {
             let (shreds, _) = make_slot_entries(1, 0, 8);
             blocktree.insert_shreds(shreds, None).unwrap();
             assert!(bank_forks.get(1).is_none());
-            ReplayStage::generate_new_bank_forks(
+            let new_slots = ReplayStage::generate_new_bank_forks(
                 &blocktree,
                 &mut bank_forks,
                 &leader_schedule_cache,
             );
             assert!(bank_forks.get(1).is_some());
+            assert_eq!(new_slots.len(), 1);
+            assert_eq!((new_slots[0].slot, new_slots[0].parent), (1, 0));
 
             // Insert blob for slot 3, generate new forks, check result
             let (shreds, _) = make_slot_entries(2, 0, 8);
             blocktree.insert_shreds(shreds, None).unwrap();
             assert!(bank_forks.get(2).is_none());
-            ReplayStage::generate_new_bank_forks(
+            let new_slots = ReplayStage::generate_new_bank_forks(
                 &blocktree,
                 &mut bank_forks,
                 &leader_schedule_cache,
             );
             assert!(bank_forks.get(1).is_some());
             assert!(bank_forks.get(2).is_some());
+            assert_eq!(new_slots.len(), 1);
+            assert_eq!((new_slots[0].slot, new_slots[0].parent), (2, 0));
         }
 
         let _ignored = remove_dir_all(&ledger_path);
@@ -1036,7 +1078,7 @@ mod test {
             let shreds = shred_to_insert(&last_blockhash, bank0.slot());
             blocktree.insert_shreds(shreds, None).unwrap();
             let (res, _tx_count) =
-                ReplayStage::replay_blocktree_into_bank(&bank0, &blocktree, &mut progress);
+                ReplayStage::replay_blocktree_into_bank(&bank0, &blocktree, &mut progress, None);
 
             // Check that the erroring bank was marked as dead in the progress map
             assert!(progress
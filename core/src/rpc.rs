@@ -1,18 +1,24 @@
 //! The `rpc` module implements the Solana RPC interface.
 
 use crate::bank_forks::BankForks;
+use crate::blocktree::{Blocktree, BlocktreeError};
 use crate::cluster_info::ClusterInfo;
 use crate::contact_info::ContactInfo;
 use crate::packet::PACKET_DATA_SIZE;
+use crate::result;
 use crate::storage_stage::StorageState;
 use crate::validator::ValidatorExit;
 use crate::version::VERSION;
 use bincode::{deserialize, serialize};
 use jsonrpc_core::{Error, Metadata, Result};
 use jsonrpc_derive::rpc;
+use solana_client::rpc_request::{
+    RpcConfirmedBlock, RpcProgramAccountsConfig, RpcSimulatedTransaction, RpcTransactionStatus,
+};
 use solana_drone::drone::request_airdrop_transaction;
 use solana_runtime::bank::Bank;
 use solana_sdk::account::Account;
+use solana_sdk::clock::Slot;
 use solana_sdk::fee_calculator::FeeCalculator;
 use solana_sdk::hash::Hash;
 use solana_sdk::inflation::Inflation;
@@ -43,6 +49,7 @@ impl Default for JsonRpcConfig {
 #[derive(Clone)]
 pub struct JsonRpcRequestProcessor {
     bank_forks: Arc<RwLock<BankForks>>,
+    blocktree: Arc<Blocktree>,
     storage_state: StorageState,
     config: JsonRpcConfig,
     validator_exit: Arc<RwLock<Option<ValidatorExit>>>,
@@ -53,14 +60,22 @@ impl JsonRpcRequestProcessor {
         self.bank_forks.read().unwrap().working_bank()
     }
 
+    // The bank of the last rooted slot, whose view can no longer be undone by a fork switch
+    fn root_bank(&self) -> Arc<Bank> {
+        let bank_forks = self.bank_forks.read().unwrap();
+        bank_forks[bank_forks.root()].clone()
+    }
+
     pub fn new(
         storage_state: StorageState,
         config: JsonRpcConfig,
         bank_forks: Arc<RwLock<BankForks>>,
+        blocktree: Arc<Blocktree>,
         validator_exit: &Arc<RwLock<Option<ValidatorExit>>>,
     ) -> Self {
         JsonRpcRequestProcessor {
             bank_forks,
+            blocktree,
             storage_state,
             config,
             validator_exit: validator_exit.clone(),
@@ -73,12 +88,22 @@ impl JsonRpcRequestProcessor {
             .ok_or_else(Error::invalid_request)
     }
 
-    pub fn get_program_accounts(&self, program_id: &Pubkey) -> Result<Vec<(String, Account)>> {
+    pub fn get_program_accounts(
+        &self,
+        program_id: &Pubkey,
+        config: Option<RpcProgramAccountsConfig>,
+    ) -> Result<Vec<(String, Account)>> {
+        let mut config = config.unwrap_or_default();
+        verify_program_accounts_config(&mut config)?;
         Ok(self
             .bank()
             .get_program_accounts(&program_id)
             .into_iter()
-            .map(|(pubkey, account)| (pubkey.to_string(), account))
+            .filter_map(|(pubkey, account)| {
+                config
+                    .apply(&account)
+                    .map(|account| (pubkey.to_string(), account))
+            })
             .collect())
     }
 
@@ -95,11 +120,24 @@ impl JsonRpcRequestProcessor {
         (blockhash.to_string(), fee_calculator)
     }
 
+    // Answered by the root bank, since a blockhash expired there can't be used by any of the
+    // forks still able to become rooted
+    fn get_blockhash_last_valid_slot(&self, blockhash: &Hash) -> Option<Slot> {
+        self.root_bank().get_blockhash_last_valid_slot(blockhash)
+    }
+
     pub fn get_signature_status(&self, signature: Signature) -> Option<transaction::Result<()>> {
         self.get_signature_confirmation_status(signature)
             .map(|x| x.1)
     }
 
+    pub fn get_rooted_signature_status(
+        &self,
+        signature: Signature,
+    ) -> Option<transaction::Result<()>> {
+        self.root_bank().get_signature_status(&signature)
+    }
+
     pub fn get_signature_confirmations(&self, signature: Signature) -> Option<usize> {
         self.get_signature_confirmation_status(signature)
             .map(|x| x.0)
@@ -188,6 +226,74 @@ impl JsonRpcRequestProcessor {
             .get_pubkeys_for_slot(slot, &self.bank_forks))
     }
 
+    pub fn get_confirmed_block(&self, slot: Slot) -> Result<Option<RpcConfirmedBlock>> {
+        match self.blocktree.get_confirmed_block(slot) {
+            Ok(mut block) => {
+                let bank = self.bank();
+                for (transaction, status) in block.transactions.iter_mut() {
+                    if status.is_none() {
+                        // Fall back to the status cache for signatures that haven't aged out yet
+                        *status = bank
+                            .get_signature_status(&transaction.signatures[0])
+                            .and_then(|result| {
+                                bank.get_fee_calculator(&transaction.message().recent_blockhash)
+                                    .map(|fee_calculator| RpcTransactionStatus {
+                                        status: result,
+                                        fee: fee_calculator.calculate_fee(transaction.message()),
+                                        pre_balances: vec![],
+                                        post_balances: vec![],
+                                        log_messages: vec![],
+                                        compute_units_consumed: 0,
+                                    })
+                            });
+                    }
+                }
+                Ok(Some(block))
+            }
+            Err(result::Error::BlocktreeError(BlocktreeError::SlotNotRooted)) => Ok(None),
+            Err(err) => {
+                warn!("get_confirmed_block failed for slot {}: {:?}", slot, err);
+                Err(Error::internal_error())
+            }
+        }
+    }
+
+    pub fn simulate_transaction(
+        &self,
+        transaction: &Transaction,
+    ) -> Result<RpcSimulatedTransaction> {
+        // Simulate against the newest frozen bank; the working bank belongs to the banking stage
+        let bank = self.bank();
+        let bank = if bank.is_frozen() {
+            bank
+        } else {
+            bank.parent().ok_or_else(|| {
+                info!("simulate_transaction: no frozen bank available");
+                Error::internal_error()
+            })?
+        };
+        let fee_calculator = bank
+            .get_fee_calculator(&transaction.message().recent_blockhash)
+            .ok_or_else(|| {
+                Error::invalid_params(format!(
+                    "Blockhash not found: {}",
+                    transaction.message().recent_blockhash
+                ))
+            })?;
+        let (result, accounts, log_messages, compute_units_consumed) =
+            bank.simulate_transaction(transaction);
+        Ok(RpcSimulatedTransaction {
+            result,
+            fee: fee_calculator.calculate_fee(transaction.message()),
+            accounts: accounts
+                .into_iter()
+                .map(|(pubkey, account)| (pubkey.to_string(), account))
+                .collect(),
+            log_messages,
+            compute_units_consumed,
+        })
+    }
+
     pub fn fullnode_exit(&self) -> Result<bool> {
         if self.config.enable_fullnode_exit {
             warn!("fullnode_exit request...");
@@ -211,10 +317,26 @@ fn verify_pubkey(input: String) -> Result<Pubkey> {
     input.parse().map_err(|_e| Error::invalid_request())
 }
 
+fn verify_hash(input: &str) -> Result<Hash> {
+    input.parse().map_err(|_e| Error::invalid_request())
+}
+
 fn verify_signature(input: &str) -> Result<Signature> {
     input.parse().map_err(|_e| Error::invalid_request())
 }
 
+pub(crate) fn verify_program_accounts_config(config: &mut RpcProgramAccountsConfig) -> Result<()> {
+    for filter in config.filters.iter_mut().flatten() {
+        if let Err(e) = filter.verify() {
+            return Err(Error::invalid_params(format!(
+                "Invalid filter {:?}: {:?}",
+                filter, e
+            )));
+        }
+    }
+    Ok(())
+}
+
 #[derive(Clone)]
 pub struct Meta {
     pub request_processor: Arc<RwLock<JsonRpcRequestProcessor>>,
@@ -297,7 +419,12 @@ pub trait RpcSol {
     fn get_account_info(&self, _: Self::Metadata, _: String) -> Result<Account>;
 
     #[rpc(meta, name = "getProgramAccounts")]
-    fn get_program_accounts(&self, _: Self::Metadata, _: String) -> Result<Vec<(String, Account)>>;
+    fn get_program_accounts(
+        &self,
+        _: Self::Metadata,
+        _: String,
+        _: Option<RpcProgramAccountsConfig>,
+    ) -> Result<Vec<(String, Account)>>;
 
     #[rpc(meta, name = "getInflation")]
     fn get_inflation(&self, _: Self::Metadata) -> Result<Inflation>;
@@ -320,6 +447,9 @@ pub trait RpcSol {
     #[rpc(meta, name = "getRecentBlockhash")]
     fn get_recent_blockhash(&self, _: Self::Metadata) -> Result<(String, FeeCalculator)>;
 
+    #[rpc(meta, name = "getBlockhashLastValidSlot")]
+    fn get_blockhash_last_valid_slot(&self, _: Self::Metadata, _: String) -> Result<Option<Slot>>;
+
     #[rpc(meta, name = "getSignatureStatus")]
     fn get_signature_status(
         &self,
@@ -327,6 +457,13 @@ pub trait RpcSol {
         _: String,
     ) -> Result<Option<transaction::Result<()>>>;
 
+    #[rpc(meta, name = "getRootedSignatureStatus")]
+    fn get_rooted_signature_status(
+        &self,
+        _: Self::Metadata,
+        _: String,
+    ) -> Result<Option<transaction::Result<()>>>;
+
     #[rpc(meta, name = "getSlot")]
     fn get_slot(&self, _: Self::Metadata) -> Result<u64>;
 
@@ -382,6 +519,16 @@ pub trait RpcSol {
 
     #[rpc(meta, name = "setLogFilter")]
     fn set_log_filter(&self, _: Self::Metadata, _: String) -> Result<()>;
+
+    #[rpc(meta, name = "getConfirmedBlock")]
+    fn get_confirmed_block(&self, _: Self::Metadata, _: Slot) -> Result<Option<RpcConfirmedBlock>>;
+
+    #[rpc(meta, name = "simulateTransaction")]
+    fn simulate_transaction(
+        &self,
+        _: Self::Metadata,
+        _: Vec<u8>,
+    ) -> Result<RpcSimulatedTransaction>;
 }
 
 pub struct RpcSolImpl;
@@ -411,13 +558,14 @@ impl RpcSol for RpcSolImpl {
         &self,
         meta: Self::Metadata,
         id: String,
+        config: Option<RpcProgramAccountsConfig>,
     ) -> Result<Vec<(String, Account)>> {
         debug!("get_program_accounts rpc request received: {:?}", id);
         let program_id = verify_pubkey(id)?;
         meta.request_processor
             .read()
             .unwrap()
-            .get_program_accounts(&program_id)
+            .get_program_accounts(&program_id, config)
     }
 
     fn get_inflation(&self, meta: Self::Metadata) -> Result<Inflation> {
@@ -505,6 +653,23 @@ impl RpcSol for RpcSolImpl {
             .get_recent_blockhash())
     }
 
+    fn get_blockhash_last_valid_slot(
+        &self,
+        meta: Self::Metadata,
+        blockhash: String,
+    ) -> Result<Option<Slot>> {
+        debug!(
+            "get_blockhash_last_valid_slot rpc request received: {:?}",
+            blockhash
+        );
+        let blockhash = verify_hash(&blockhash)?;
+        Ok(meta
+            .request_processor
+            .read()
+            .unwrap()
+            .get_blockhash_last_valid_slot(&blockhash))
+    }
+
     fn get_signature_status(
         &self,
         meta: Self::Metadata,
@@ -514,6 +679,20 @@ impl RpcSol for RpcSolImpl {
             .map(|res| res.map(|x| x.1))
     }
 
+    fn get_rooted_signature_status(
+        &self,
+        meta: Self::Metadata,
+        id: String,
+    ) -> Result<Option<transaction::Result<()>>> {
+        debug!("get_rooted_signature_status rpc request received: {:?}", id);
+        let signature = verify_signature(&id)?;
+        Ok(meta
+            .request_processor
+            .read()
+            .unwrap()
+            .get_rooted_signature_status(signature))
+    }
+
     fn get_slot(&self, meta: Self::Metadata) -> Result<u64> {
         meta.request_processor.read().unwrap().get_slot()
     }
@@ -692,20 +871,59 @@ impl RpcSol for RpcSolImpl {
         solana_logger::setup_with_filter(&filter);
         Ok(())
     }
+
+    fn get_confirmed_block(
+        &self,
+        meta: Self::Metadata,
+        slot: Slot,
+    ) -> Result<Option<RpcConfirmedBlock>> {
+        debug!("get_confirmed_block rpc request received: {:?}", slot);
+        meta.request_processor
+            .read()
+            .unwrap()
+            .get_confirmed_block(slot)
+    }
+
+    fn simulate_transaction(
+        &self,
+        meta: Self::Metadata,
+        data: Vec<u8>,
+    ) -> Result<RpcSimulatedTransaction> {
+        if data.len() >= PACKET_DATA_SIZE {
+            info!(
+                "simulate_transaction: transaction too large: {} bytes (max: {} bytes)",
+                data.len(),
+                PACKET_DATA_SIZE
+            );
+            return Err(Error::invalid_request());
+        }
+        let tx: Transaction = deserialize(&data).map_err(|err| {
+            info!("simulate_transaction: deserialize error: {:?}", err);
+            Error::invalid_request()
+        })?;
+        debug!("simulate_transaction rpc request received: {:?}", tx);
+        meta.request_processor
+            .read()
+            .unwrap()
+            .simulate_transaction(&tx)
+    }
 }
 
 #[cfg(test)]
 pub mod tests {
     use super::*;
+    use crate::blocktree::{entries_to_test_shreds, get_tmp_ledger_path};
     use crate::contact_info::ContactInfo;
+    use crate::entry::next_entry_mut;
     use crate::genesis_utils::{create_genesis_block, GenesisBlockInfo};
-    use jsonrpc_core::{MetaIoHandler, Output, Response, Value};
+    use jsonrpc_core::{ErrorCode, MetaIoHandler, Output, Response, Value};
     use solana_sdk::fee_calculator::DEFAULT_BURN_PERCENT;
     use solana_sdk::hash::{hash, Hash};
     use solana_sdk::instruction::InstructionError;
     use solana_sdk::signature::{Keypair, KeypairUtil};
     use solana_sdk::system_transaction;
     use solana_sdk::transaction::TransactionError;
+    use std::path::PathBuf;
     use std::sync::atomic::{AtomicBool, Ordering};
     use std::thread;
 
@@ -713,7 +931,15 @@ pub mod tests {
 
     fn start_rpc_handler_with_tx(
         pubkey: &Pubkey,
-    ) -> (MetaIoHandler<Meta>, Meta, Arc<Bank>, Hash, Keypair, Pubkey) {
+    ) -> (
+        TmpLedger,
+        MetaIoHandler<Meta>,
+        Meta,
+        Arc<Bank>,
+        Hash,
+        Keypair,
+        Pubkey,
+    ) {
         let (bank_forks, alice) = new_bank_forks();
         let bank = bank_forks.read().unwrap().working_bank();
         let leader_pubkey = *bank.collector_id();
@@ -721,16 +947,55 @@ pub mod tests {
         let validator_exit = create_validator_exit(&exit);
 
         let blockhash = bank.confirmed_last_blockhash().0;
-        let tx = system_transaction::transfer(&alice, pubkey, 20, blockhash);
-        bank.process_transaction(&tx).expect("process transaction");
-
-        let tx = system_transaction::transfer(&alice, &alice.pubkey(), 20, blockhash);
-        let _ = bank.process_transaction(&tx);
+        let alice_balance = bank.get_balance(&alice.pubkey());
+        let tx0 = system_transaction::transfer(&alice, pubkey, 20, blockhash);
+        bank.process_transaction(&tx0).expect("process transaction");
+
+        let tx1 = system_transaction::transfer(&alice, &alice.pubkey(), 20, blockhash);
+        let _ = bank.process_transaction(&tx1);
+
+        let (blocktree, ledger) = new_blocktree();
+        blocktree
+            .write_transaction_status(
+                (0, tx0.signatures[0]),
+                &RpcTransactionStatus {
+                    status: Ok(()),
+                    fee: 0,
+                    pre_balances: vec![alice_balance, 0, 1],
+                    post_balances: vec![alice_balance - 20, 20, 1],
+                    log_messages: vec![],
+                    compute_units_consumed: 0,
+                },
+            )
+            .unwrap();
+        blocktree
+            .write_transaction_status(
+                (0, tx1.signatures[0]),
+                &RpcTransactionStatus {
+                    status: bank.get_signature_status(&tx1.signatures[0]).unwrap(),
+                    fee: 0,
+                    pre_balances: vec![alice_balance - 20, alice_balance - 20, 1],
+                    post_balances: vec![alice_balance - 20, alice_balance - 20, 1],
+                    log_messages: vec![],
+                    compute_units_consumed: 0,
+                },
+            )
+            .unwrap();
+        let mut last_hash = blockhash;
+        let entries = vec![
+            next_entry_mut(&mut last_hash, 1, vec![tx0]),
+            next_entry_mut(&mut last_hash, 1, vec![tx1]),
+        ];
+        blocktree
+            .insert_shreds(entries_to_test_shreds(entries, 0, 0, true), None)
+            .unwrap();
+        blocktree.set_roots(&[0]).unwrap();
 
         let request_processor = Arc::new(RwLock::new(JsonRpcRequestProcessor::new(
             StorageState::default(),
             JsonRpcConfig::default(),
             bank_forks,
+            blocktree,
             &validator_exit,
         )));
         let cluster_info = Arc::new(RwLock::new(ClusterInfo::new_with_invalid_keypair(
@@ -753,7 +1018,7 @@ pub mod tests {
             cluster_info,
             genesis_blockhash: Hash::default(),
         };
-        (io, meta, bank, blockhash, alice, leader_pubkey)
+        (ledger, io, meta, bank, blockhash, alice, leader_pubkey)
     }
 
     #[test]
@@ -763,10 +1028,12 @@ pub mod tests {
         let validator_exit = create_validator_exit(&exit);
         let (bank_forks, alice) = new_bank_forks();
         let bank = bank_forks.read().unwrap().working_bank();
+        let (blocktree, _ledger) = new_blocktree();
         let request_processor = JsonRpcRequestProcessor::new(
             StorageState::default(),
             JsonRpcConfig::default(),
             bank_forks,
+            blocktree,
             &validator_exit,
         );
         thread::spawn(move || {
@@ -779,10 +1046,49 @@ pub mod tests {
         assert_eq!(request_processor.get_transaction_count().unwrap(), 1);
     }
 
+    #[test]
+    fn test_rpc_request_processor_unrooted_slot() {
+        let bob_pubkey = Pubkey::new_rand();
+        let exit = Arc::new(AtomicBool::new(false));
+        let validator_exit = create_validator_exit(&exit);
+        let (bank_forks, alice) = new_bank_forks();
+        let bank0 = bank_forks.read().unwrap().working_bank();
+        let blockhash = bank0.confirmed_last_blockhash().0;
+        let bank1 = bank_forks.write().unwrap().insert(Bank::new_from_parent(
+            &bank0,
+            &Pubkey::default(),
+            1,
+        ));
+        let (blocktree, _ledger) = new_blocktree();
+        let request_processor = JsonRpcRequestProcessor::new(
+            StorageState::default(),
+            JsonRpcConfig::default(),
+            bank_forks,
+            blocktree,
+            &validator_exit,
+        );
+        let tx = system_transaction::transfer(&alice, &bob_pubkey, 20, blockhash);
+        bank1.process_transaction(&tx).expect("process transaction");
+
+        // Slot 1 isn't rooted, so the transaction could still be undone
+        assert_eq!(
+            request_processor.get_signature_status(tx.signatures[0]),
+            Some(Ok(()))
+        );
+        assert_eq!(
+            request_processor.get_rooted_signature_status(tx.signatures[0]),
+            None
+        );
+        assert_eq!(
+            request_processor.get_blockhash_last_valid_slot(&blockhash),
+            bank0.get_blockhash_last_valid_slot(&blockhash)
+        );
+    }
+
     #[test]
     fn test_rpc_get_balance() {
         let bob_pubkey = Pubkey::new_rand();
-        let (io, meta, _bank, _blockhash, _alice, _leader_pubkey) =
+        let (_ledger, io, meta, _bank, _blockhash, _alice, _leader_pubkey) =
             start_rpc_handler_with_tx(&bob_pubkey);
 
         let req = format!(
@@ -801,7 +1107,7 @@ pub mod tests {
     #[test]
     fn test_rpc_get_cluster_nodes() {
         let bob_pubkey = Pubkey::new_rand();
-        let (io, meta, _bank, _blockhash, _alice, leader_pubkey) =
+        let (_ledger, io, meta, _bank, _blockhash, _alice, leader_pubkey) =
             start_rpc_handler_with_tx(&bob_pubkey);
 
         let req = format!(r#"{{"jsonrpc":"2.0","id":1,"method":"getClusterNodes"}}"#);
@@ -822,7 +1128,7 @@ pub mod tests {
     #[test]
     fn test_rpc_get_slot_leader() {
         let bob_pubkey = Pubkey::new_rand();
-        let (io, meta, _bank, _blockhash, _alice, leader_pubkey) =
+        let (_ledger, io, meta, _bank, _blockhash, _alice, leader_pubkey) =
             start_rpc_handler_with_tx(&bob_pubkey);
 
         let req = format!(r#"{{"jsonrpc":"2.0","id":1,"method":"getSlotLeader"}}"#);
@@ -838,7 +1144,7 @@ pub mod tests {
     #[test]
     fn test_rpc_get_tx_count() {
         let bob_pubkey = Pubkey::new_rand();
-        let (io, meta, _bank, _blockhash, _alice, _leader_pubkey) =
+        let (_ledger, io, meta, _bank, _blockhash, _alice, _leader_pubkey) =
             start_rpc_handler_with_tx(&bob_pubkey);
 
         let req = format!(r#"{{"jsonrpc":"2.0","id":1,"method":"getTransactionCount"}}"#);
@@ -854,7 +1160,7 @@ pub mod tests {
     #[test]
     fn test_rpc_get_total_supply() {
         let bob_pubkey = Pubkey::new_rand();
-        let (io, meta, _bank, _blockhash, _alice, _leader_pubkey) =
+        let (_ledger, io, meta, _bank, _blockhash, _alice, _leader_pubkey) =
             start_rpc_handler_with_tx(&bob_pubkey);
 
         let req = format!(r#"{{"jsonrpc":"2.0","id":1,"method":"getTotalSupply"}}"#);
@@ -880,7 +1186,7 @@ pub mod tests {
     #[test]
     fn test_rpc_get_inflation() {
         let bob_pubkey = Pubkey::new_rand();
-        let (io, meta, bank, _blockhash, _alice, _leader_pubkey) =
+        let (_ledger, io, meta, bank, _blockhash, _alice, _leader_pubkey) =
             start_rpc_handler_with_tx(&bob_pubkey);
 
         let req = format!(r#"{{"jsonrpc":"2.0","id":1,"method":"getInflation"}}"#);
@@ -902,7 +1208,7 @@ pub mod tests {
     #[test]
     fn test_rpc_get_account_info() {
         let bob_pubkey = Pubkey::new_rand();
-        let (io, meta, _bank, _blockhash, _alice, _leader_pubkey) =
+        let (_ledger, io, meta, _bank, _blockhash, _alice, _leader_pubkey) =
             start_rpc_handler_with_tx(&bob_pubkey);
 
         let req = format!(
@@ -931,7 +1237,7 @@ pub mod tests {
     #[test]
     fn test_rpc_get_program_accounts() {
         let bob = Keypair::new();
-        let (io, meta, bank, blockhash, _alice, _leader_pubkey) =
+        let (_ledger, io, meta, bank, blockhash, _alice, _leader_pubkey) =
             start_rpc_handler_with_tx(&bob.pubkey());
 
         let new_program_id = Pubkey::new_rand();
@@ -964,10 +1270,82 @@ pub mod tests {
         assert_eq!(expected, result);
     }
 
+    #[test]
+    fn test_rpc_get_program_accounts_with_config() {
+        let bob = Keypair::new();
+        let (_ledger, io, meta, bank, blockhash, alice, _leader_pubkey) =
+            start_rpc_handler_with_tx(&bob.pubkey());
+
+        let new_program_id = Pubkey::new_rand();
+        let carol = Keypair::new();
+        let tx = system_transaction::create_account(
+            &alice,
+            &carol.pubkey(),
+            blockhash,
+            1,
+            4,
+            &new_program_id,
+        );
+        bank.process_transaction(&tx).unwrap();
+        let tx = system_transaction::assign(&bob, blockhash, &new_program_id);
+        bank.process_transaction(&tx).unwrap();
+
+        let get_program_accounts = |config: &str| {
+            let req = format!(
+                r#"{{"jsonrpc":"2.0","id":1,"method":"getProgramAccounts","params":["{}",{}]}}"#,
+                new_program_id, config,
+            );
+            let res = io.handle_request_sync(&req, meta.clone());
+            let result: Response = serde_json::from_str(&res.expect("actual response"))
+                .expect("actual response deserialization");
+            result
+        };
+        let accounts = |result: Response| -> Vec<(String, Account)> {
+            if let Response::Single(res) = result {
+                if let Output::Success(res) = res {
+                    return serde_json::from_value(res.result).unwrap();
+                }
+            }
+            panic!("Expected success");
+        };
+
+        let result = accounts(get_program_accounts(r#"{"filters":[{"dataSize":4}]}"#));
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].0, carol.pubkey().to_string());
+        assert_eq!(result[0].1.data, vec![0; 4]);
+
+        let config = format!(
+            r#"{{"filters":[{{"memcmp":{{"offset":1,"bytes":"{}"}}}}],"dataSlice":{{"offset":2,"length":1}}}}"#,
+            bs58::encode(vec![0u8; 3]).into_string()
+        );
+        let result = accounts(get_program_accounts(&config));
+        assert_eq!(result.len(), 1);
+        assert_eq!(result[0].0, carol.pubkey().to_string());
+        assert_eq!(result[0].1.data, vec![0]);
+
+        let result = accounts(get_program_accounts(
+            r#"{"dataSlice":{"offset":0,"length":0}}"#,
+        ));
+        assert_eq!(result.len(), 2);
+        assert!(result.iter().all(|(_, account)| account.data.is_empty()));
+
+        let config = r#"{"filters":[{"memcmp":{"offset":0,"bytes":"0OIl"}}]}"#;
+        let result = get_program_accounts(config);
+        if let Response::Single(res) = result {
+            if let Output::Failure(res) = res {
+                assert_eq!(res.error.code, ErrorCode::InvalidParams);
+            } else {
+                panic!("Expected failure");
+            }
+        } else {
+            panic!("Expected single response");
+        }
+    }
+
     #[test]
     fn test_rpc_confirm_tx() {
         let bob_pubkey = Pubkey::new_rand();
-        let (io, meta, _bank, blockhash, alice, _leader_pubkey) =
+        let (_ledger, io, meta, _bank, blockhash, alice, _leader_pubkey) =
             start_rpc_handler_with_tx(&bob_pubkey);
         let tx = system_transaction::transfer(&alice, &bob_pubkey, 20, blockhash);
 
@@ -987,7 +1365,7 @@ pub mod tests {
     #[test]
     fn test_rpc_get_signature_status() {
         let bob_pubkey = Pubkey::new_rand();
-        let (io, meta, _bank, blockhash, alice, _leader_pubkey) =
+        let (_ledger, io, meta, _bank, blockhash, alice, _leader_pubkey) =
             start_rpc_handler_with_tx(&bob_pubkey);
         let tx = system_transaction::transfer(&alice, &bob_pubkey, 20, blockhash);
 
@@ -1052,7 +1430,7 @@ pub mod tests {
     #[test]
     fn test_rpc_get_recent_blockhash() {
         let bob_pubkey = Pubkey::new_rand();
-        let (io, meta, _bank, blockhash, _alice, _leader_pubkey) =
+        let (_ledger, io, meta, _bank, blockhash, _alice, _leader_pubkey) =
             start_rpc_handler_with_tx(&bob_pubkey);
 
         let req = format!(r#"{{"jsonrpc":"2.0","id":1,"method":"getRecentBlockhash"}}"#);
@@ -1076,10 +1454,49 @@ pub mod tests {
         assert_eq!(expected, result);
     }
 
+    #[test]
+    fn test_rpc_get_blockhash_last_valid_slot() {
+        let bob_pubkey = Pubkey::new_rand();
+        let (_ledger, io, meta, bank, blockhash, _alice, _leader_pubkey) =
+            start_rpc_handler_with_tx(&bob_pubkey);
+
+        let req = format!(
+            r#"{{"jsonrpc":"2.0","id":1,"method":"getBlockhashLastValidSlot","params":["{}"]}}"#,
+            blockhash
+        );
+        let res = io.handle_request_sync(&req, meta.clone());
+        let expected = json!({
+            "jsonrpc": "2.0",
+            "result": bank.get_blockhash_last_valid_slot(&blockhash),
+            "id": 1
+        });
+        let expected: Response =
+            serde_json::from_value(expected).expect("expected response deserialization");
+        let result: Response = serde_json::from_str(&res.expect("actual response"))
+            .expect("actual response deserialization");
+        assert_eq!(expected, result);
+
+        let req = format!(
+            r#"{{"jsonrpc":"2.0","id":1,"method":"getBlockhashLastValidSlot","params":["{}"]}}"#,
+            Hash::new(&[1; 32])
+        );
+        let res = io.handle_request_sync(&req, meta);
+        let expected = json!({
+            "jsonrpc": "2.0",
+            "result": null,
+            "id": 1
+        });
+        let expected: Response =
+            serde_json::from_value(expected).expect("expected response deserialization");
+        let result: Response = serde_json::from_str(&res.expect("actual response"))
+            .expect("actual response deserialization");
+        assert_eq!(expected, result);
+    }
+
     #[test]
     fn test_rpc_fail_request_airdrop() {
         let bob_pubkey = Pubkey::new_rand();
-        let (io, meta, _bank, _blockhash, _alice, _leader_pubkey) =
+        let (_ledger, io, meta, _bank, _blockhash, _alice, _leader_pubkey) =
             start_rpc_handler_with_tx(&bob_pubkey);
 
         // Expect internal error because no drone is available
@@ -1101,6 +1518,7 @@ pub mod tests {
     fn test_rpc_send_bad_tx() {
         let exit = Arc::new(AtomicBool::new(false));
         let validator_exit = create_validator_exit(&exit);
+        let (blocktree, _ledger) = new_blocktree();
 
         let mut io = MetaIoHandler::default();
         let rpc = RpcSolImpl;
@@ -1111,6 +1529,7 @@ pub mod tests {
                     StorageState::default(),
                     JsonRpcConfig::default(),
                     new_bank_forks().0,
+                    blocktree,
                     &validator_exit,
                 );
                 Arc::new(RwLock::new(request_processor))
@@ -1182,6 +1601,23 @@ pub mod tests {
         )
     }
 
+    /// A temporary ledger, destroyed when the test that opened it finishes.
+    struct TmpLedger(PathBuf);
+
+    impl Drop for TmpLedger {
+        fn drop(&mut self) {
+            let _ = Blocktree::destroy(&self.0);
+        }
+    }
+
+    /// Open a Blocktree in a temporary ledger. Keep the returned `TmpLedger`
+    /// alive for as long as the Blocktree is in use.
+    fn new_blocktree() -> (Arc<Blocktree>, TmpLedger) {
+        let ledger_path = get_tmp_ledger_path!();
+        let blocktree = Arc::new(Blocktree::open(&ledger_path).unwrap());
+        (blocktree, TmpLedger(ledger_path))
+    }
+
     pub fn create_validator_exit(exit: &Arc<AtomicBool>) -> Arc<RwLock<Option<ValidatorExit>>> {
         let mut validator_exit = ValidatorExit::default();
         let exit_ = exit.clone();
@@ -1193,10 +1629,12 @@ pub mod tests {
     fn test_rpc_request_processor_config_default_trait_fullnode_exit_fails() {
         let exit = Arc::new(AtomicBool::new(false));
         let validator_exit = create_validator_exit(&exit);
+        let (blocktree, _ledger) = new_blocktree();
         let request_processor = JsonRpcRequestProcessor::new(
             StorageState::default(),
             JsonRpcConfig::default(),
             new_bank_forks().0,
+            blocktree,
             &validator_exit,
         );
         assert_eq!(request_processor.fullnode_exit(), Ok(false));
@@ -1209,20 +1647,130 @@ pub mod tests {
         let validator_exit = create_validator_exit(&exit);
         let mut config = JsonRpcConfig::default();
         config.enable_fullnode_exit = true;
+        let (blocktree, _ledger) = new_blocktree();
         let request_processor = JsonRpcRequestProcessor::new(
             StorageState::default(),
             config,
             new_bank_forks().0,
+            blocktree,
             &validator_exit,
         );
         assert_eq!(request_processor.fullnode_exit(), Ok(true));
         assert_eq!(exit.load(Ordering::Relaxed), true);
     }
 
+    #[test]
+    fn test_rpc_get_confirmed_block() {
+        let bob_pubkey = Pubkey::new_rand();
+        let (_ledger, io, meta, _bank, blockhash, alice, _leader_pubkey) =
+            start_rpc_handler_with_tx(&bob_pubkey);
+
+        let req =
+            format!(r#"{{"jsonrpc":"2.0","id":1,"method":"getConfirmedBlock","params":[0]}}"#);
+        let res = io.handle_request_sync(&req, meta.clone());
+        let result: Value = serde_json::from_str(&res.expect("actual response"))
+            .expect("actual response deserialization");
+        let confirmed_block: Option<RpcConfirmedBlock> =
+            serde_json::from_value(result["result"].clone()).unwrap();
+        let confirmed_block = confirmed_block.unwrap();
+        assert_eq!(confirmed_block.parent_slot, 0);
+        assert_eq!(confirmed_block.transactions.len(), 2);
+
+        let (transaction, status) = &confirmed_block.transactions[0];
+        assert_eq!(
+            *transaction,
+            system_transaction::transfer(&alice, &bob_pubkey, 20, blockhash)
+        );
+        let status = status.clone().unwrap();
+        assert_eq!(status.status, Ok(()));
+        assert_eq!(status.fee, 0);
+        assert_eq!(status.pre_balances[0] - status.post_balances[0], 20);
+        assert_eq!(status.pre_balances[1], 0);
+        assert_eq!(status.post_balances[1], 20);
+
+        let (_, status) = &confirmed_block.transactions[1];
+        let status = status.clone().unwrap();
+        assert_eq!(
+            status.status,
+            Err(TransactionError::InstructionError(
+                0,
+                InstructionError::DuplicateAccountIndex
+            ))
+        );
+        assert_eq!(status.fee, 0);
+        assert_eq!(status.pre_balances, status.post_balances);
+
+        // Unrooted slots aren't available
+        let req =
+            format!(r#"{{"jsonrpc":"2.0","id":1,"method":"getConfirmedBlock","params":[1]}}"#);
+        let res = io.handle_request_sync(&req, meta);
+        let result: Value = serde_json::from_str(&res.expect("actual response"))
+            .expect("actual response deserialization");
+        assert_eq!(result["result"], Value::Null);
+    }
+
+    #[test]
+    fn test_rpc_simulate_transaction() {
+        let bob_pubkey = Pubkey::new_rand();
+        let (_ledger, io, meta, bank, blockhash, alice, _leader_pubkey) =
+            start_rpc_handler_with_tx(&bob_pubkey);
+        bank.freeze();
+        let alice_balance = bank.get_balance(&alice.pubkey());
+
+        let tx = system_transaction::transfer(&alice, &bob_pubkey, 10, blockhash);
+        let req = format!(
+            r#"{{"jsonrpc":"2.0","id":1,"method":"simulateTransaction","params":[{}]}}"#,
+            json!(serialize(&tx).unwrap())
+        );
+        let res = io.handle_request_sync(&req, meta.clone());
+        let result: Value = serde_json::from_str(&res.expect("actual response"))
+            .expect("actual response deserialization");
+        let simulated: RpcSimulatedTransaction =
+            serde_json::from_value(result["result"].clone()).unwrap();
+        assert_eq!(simulated.result, Ok(()));
+        assert_eq!(simulated.fee, 0);
+        assert_eq!(simulated.accounts.len(), 2);
+        assert_eq!(simulated.accounts[0].0, alice.pubkey().to_string());
+        assert_eq!(simulated.accounts[0].1.lamports, alice_balance - 10);
+        assert_eq!(simulated.accounts[1].0, bob_pubkey.to_string());
+        assert_eq!(simulated.accounts[1].1.lamports, 30);
+        assert!(simulated.log_messages.is_empty());
+        assert_eq!(simulated.compute_units_consumed, 0);
+
+        // The simulation doesn't touch the bank
+        assert_eq!(bank.get_balance(&alice.pubkey()), alice_balance);
+        assert_eq!(bank.get_balance(&bob_pubkey), 20);
+        assert_eq!(bank.get_signature_status(&tx.signatures[0]), None);
+
+        let tx = system_transaction::transfer(&alice, &bob_pubkey, 10, Hash::default());
+        let req = format!(
+            r#"{{"jsonrpc":"2.0","id":1,"method":"simulateTransaction","params":[{}]}}"#,
+            json!(serialize(&tx).unwrap())
+        );
+        let res = io.handle_request_sync(&req, meta.clone());
+        let result: Response = serde_json::from_str(&res.expect("actual response"))
+            .expect("actual response deserialization");
+        if let Response::Single(Output::Failure(res)) = result {
+            assert_eq!(res.error.code, ErrorCode::InvalidParams);
+        } else {
+            panic!("Expected a failure for an unknown blockhash");
+        }
+
+        let req = r#"{"jsonrpc":"2.0","id":1,"method":"simulateTransaction","params":[[0,0,0,0,0,0,0,0]]}"#;
+        let res = io.handle_request_sync(req, meta);
+        let expected =
+            r#"{"jsonrpc":"2.0","error":{"code":-32600,"message":"Invalid request"},"id":1}"#;
+        let expected: Response =
+            serde_json::from_str(expected).expect("expected response deserialization");
+        let result: Response = serde_json::from_str(&res.expect("actual response"))
+            .expect("actual response deserialization");
+        assert_eq!(expected, result);
+    }
+
     #[test]
     fn test_rpc_get_version() {
         let bob_pubkey = Pubkey::new_rand();
-        let (io, meta, ..) = start_rpc_handler_with_tx(&bob_pubkey);
+        let (_ledger, io, meta, ..) = start_rpc_handler_with_tx(&bob_pubkey);
 
         let req = format!(r#"{{"jsonrpc":"2.0","id":1,"method":"getVersion"}}"#);
         let res = io.handle_request_sync(&req, meta);
@@ -1,11 +1,14 @@
 //! The `pubsub` module implements a threaded subscription service on client RPC request
 
-use crate::rpc_subscriptions::{Confirmations, RpcSubscriptions};
+use crate::rpc::verify_program_accounts_config;
+use crate::rpc_subscriptions::{Confirmations, RpcSubscriptions, SlotInfo};
 use jsonrpc_core::{Error, ErrorCode, Result};
 use jsonrpc_derive::rpc;
 use jsonrpc_pubsub::typed::Subscriber;
 use jsonrpc_pubsub::{Session, SubscriptionId};
+use solana_client::rpc_request::{RpcProgramAccountsConfig, RpcTransactionLogs};
 use solana_sdk::account::Account;
+use solana_sdk::clock::Slot;
 use solana_sdk::pubkey::Pubkey;
 use solana_sdk::signature::Signature;
 use solana_sdk::transaction;
@@ -40,7 +43,8 @@ pub trait RpcSolPubSub {
     fn account_unsubscribe(&self, _: Option<Self::Metadata>, _: SubscriptionId) -> Result<bool>;
 
     // Get notification every time account data owned by a particular program is changed
-    // Accepts pubkey parameter as base-58 encoded string
+    // Accepts pubkey parameter as base-58 encoded string, and optionally the filters and
+    // data slice to apply to each notification
     #[pubsub(
         subscription = "programNotification",
         subscribe,
@@ -52,6 +56,7 @@ pub trait RpcSolPubSub {
         _: Subscriber<(String, Account)>,
         _: String,
         _: Option<Confirmations>,
+        _: Option<RpcProgramAccountsConfig>,
     );
 
     // Unsubscribe from account notification subscription.
@@ -84,6 +89,50 @@ pub trait RpcSolPubSub {
         name = "signatureUnsubscribe"
     )]
     fn signature_unsubscribe(&self, _: Option<Self::Metadata>, _: SubscriptionId) -> Result<bool>;
+
+    // Get notification with the messages logged by each transaction
+    // Accepts "all", or a pubkey parameter as base-58 encoded string to only receive the logs of
+    // transactions that reference that address
+    #[pubsub(subscription = "logsNotification", subscribe, name = "logsSubscribe")]
+    fn logs_subscribe(
+        &self,
+        _: Self::Metadata,
+        _: Subscriber<RpcTransactionLogs>,
+        _: String,
+        _: Option<Confirmations>,
+    );
+
+    // Unsubscribe from logs notification subscription.
+    #[pubsub(
+        subscription = "logsNotification",
+        unsubscribe,
+        name = "logsUnsubscribe"
+    )]
+    fn logs_unsubscribe(&self, _: Option<Self::Metadata>, _: SubscriptionId) -> Result<bool>;
+
+    // Get notification when a bank is created for a new slot
+    #[pubsub(subscription = "slotNotification", subscribe, name = "slotSubscribe")]
+    fn slot_subscribe(&self, _: Self::Metadata, _: Subscriber<SlotInfo>);
+
+    // Unsubscribe from slot notification subscription.
+    #[pubsub(
+        subscription = "slotNotification",
+        unsubscribe,
+        name = "slotUnsubscribe"
+    )]
+    fn slot_unsubscribe(&self, _: Option<Self::Metadata>, _: SubscriptionId) -> Result<bool>;
+
+    // Get notification when a slot is rooted
+    #[pubsub(subscription = "rootNotification", subscribe, name = "rootSubscribe")]
+    fn root_subscribe(&self, _: Self::Metadata, _: Subscriber<Slot>);
+
+    // Unsubscribe from root notification subscription.
+    #[pubsub(
+        subscription = "rootNotification",
+        unsubscribe,
+        name = "rootUnsubscribe"
+    )]
+    fn root_unsubscribe(&self, _: Option<Self::Metadata>, _: SubscriptionId) -> Result<bool>;
 }
 
 #[derive(Default)]
@@ -156,16 +205,28 @@ impl RpcSolPubSub for RpcSolPubSubImpl {
         subscriber: Subscriber<(String, Account)>,
         pubkey_str: String,
         confirmations: Option<Confirmations>,
+        mut config: Option<RpcProgramAccountsConfig>,
     ) {
-        match param::<Pubkey>(&pubkey_str, "pubkey") {
+        let pubkey = param::<Pubkey>(&pubkey_str, "pubkey").and_then(|pubkey| {
+            if let Some(config) = &mut config {
+                verify_program_accounts_config(config)?;
+            }
+            Ok(pubkey)
+        });
+        match pubkey {
             Ok(pubkey) => {
                 let id = self.uid.fetch_add(1, atomic::Ordering::Relaxed);
                 let sub_id = SubscriptionId::Number(id as u64);
                 info!("program_subscribe: account={:?} id={:?}", pubkey, sub_id);
                 let sink = subscriber.assign_id(sub_id.clone()).unwrap();
 
-                self.subscriptions
-                    .add_program_subscription(&pubkey, confirmations, &sub_id, &sink)
+                self.subscriptions.add_program_subscription(
+                    &pubkey,
+                    confirmations,
+                    config,
+                    &sub_id,
+                    &sink,
+                )
             }
             Err(e) => subscriber.reject(e).unwrap(),
         }
@@ -233,6 +294,87 @@ impl RpcSolPubSub for RpcSolPubSubImpl {
             })
         }
     }
+
+    fn logs_subscribe(
+        &self,
+        _meta: Self::Metadata,
+        subscriber: Subscriber<RpcTransactionLogs>,
+        filter_str: String,
+        confirmations: Option<Confirmations>,
+    ) {
+        let address = if filter_str == "all" {
+            Ok(None)
+        } else {
+            param::<Pubkey>(&filter_str, "pubkey").map(Some)
+        };
+        match address {
+            Ok(address) => {
+                let id = self.uid.fetch_add(1, atomic::Ordering::Relaxed);
+                let sub_id = SubscriptionId::Number(id as u64);
+                info!("logs_subscribe: address={:?} id={:?}", address, sub_id);
+                let sink = subscriber.assign_id(sub_id.clone()).unwrap();
+
+                self.subscriptions
+                    .add_logs_subscription(&address, confirmations, &sub_id, &sink)
+            }
+            Err(e) => subscriber.reject(e).unwrap(),
+        }
+    }
+
+    fn logs_unsubscribe(&self, _meta: Option<Self::Metadata>, id: SubscriptionId) -> Result<bool> {
+        info!("logs_unsubscribe: id={:?}", id);
+        if self.subscriptions.remove_logs_subscription(&id) {
+            Ok(true)
+        } else {
+            Err(Error {
+                code: ErrorCode::InvalidParams,
+                message: "Invalid Request: Subscription id does not exist".into(),
+                data: None,
+            })
+        }
+    }
+
+    fn slot_subscribe(&self, _meta: Self::Metadata, subscriber: Subscriber<SlotInfo>) {
+        let id = self.uid.fetch_add(1, atomic::Ordering::Relaxed);
+        let sub_id = SubscriptionId::Number(id as u64);
+        info!("slot_subscribe: id={:?}", sub_id);
+        let sink = subscriber.assign_id(sub_id.clone()).unwrap();
+        self.subscriptions.add_slot_subscription(&sub_id, &sink);
+    }
+
+    fn slot_unsubscribe(&self, _meta: Option<Self::Metadata>, id: SubscriptionId) -> Result<bool> {
+        info!("slot_unsubscribe: id={:?}", id);
+        if self.subscriptions.remove_slot_subscription(&id) {
+            Ok(true)
+        } else {
+            Err(Error {
+                code: ErrorCode::InvalidParams,
+                message: "Invalid Request: Subscription id does not exist".into(),
+                data: None,
+            })
+        }
+    }
+
+    fn root_subscribe(&self, _meta: Self::Metadata, subscriber: Subscriber<Slot>) {
+        let id = self.uid.fetch_add(1, atomic::Ordering::Relaxed);
+        let sub_id = SubscriptionId::Number(id as u64);
+        info!("root_subscribe: id={:?}", sub_id);
+        let sink = subscriber.assign_id(sub_id.clone()).unwrap();
+        self.subscriptions.add_root_subscription(&sub_id, &sink);
+    }
+
+    fn root_unsubscribe(&self, _meta: Option<Self::Metadata>, id: SubscriptionId) -> Result<bool> {
+        info!("root_unsubscribe: id={:?}", id);
+        if self.subscriptions.remove_root_subscription(&id) {
+            Ok(true)
+        } else {
+            Err(Error {
+                code: ErrorCode::InvalidParams,
+                message: "Invalid Request: Subscription id does not exist".into(),
+                data: None,
+            })
+        }
+    }
 }
 
 #[cfg(test)]
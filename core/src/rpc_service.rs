@@ -1,9 +1,11 @@
 //! The `rpc_service` module implements the Solana JSON RPC service.
 
 use crate::bank_forks::BankForks;
+use crate::blocktree::Blocktree;
 use crate::cluster_info::ClusterInfo;
 use crate::rpc::*;
 use crate::service::Service;
+use crate::snapshot_utils;
 use crate::storage_stage::StorageState;
 use crate::validator::ValidatorExit;
 use jsonrpc_core::MetaIoHandler;
@@ -74,8 +76,10 @@ impl RequestMiddleware for RpcRequestMiddleware {
     fn on_request(&self, request: hyper::Request<hyper::Body>) -> RequestMiddlewareAction {
         trace!("request uri: {}", request.uri());
         match request.uri().path() {
-            "/snapshot.tar.bz2" => self.get("snapshot.tar.bz2"),
             "/genesis.tar.bz2" => self.get("genesis.tar.bz2"),
+            path if snapshot_utils::is_snapshot_tar_file_name(path.trim_start_matches('/')) => {
+                self.get(path.trim_start_matches('/'))
+            }
             _ => RequestMiddlewareAction::Proceed {
                 should_continue_on_invalid_cors: false,
                 request,
@@ -91,6 +95,7 @@ impl JsonRpcService {
         storage_state: StorageState,
         config: JsonRpcConfig,
         bank_forks: Arc<RwLock<BankForks>>,
+        blocktree: Arc<Blocktree>,
         ledger_path: &Path,
         genesis_blockhash: Hash,
         validator_exit: &Arc<RwLock<Option<ValidatorExit>>>,
@@ -101,6 +106,7 @@ impl JsonRpcService {
             storage_state,
             config,
             bank_forks,
+            blocktree,
             validator_exit,
         )));
         let request_processor_ = request_processor.clone();
@@ -171,6 +177,7 @@ impl Service for JsonRpcService {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::blocktree::get_tmp_ledger_path;
     use crate::contact_info::ContactInfo;
     use crate::genesis_utils::{create_genesis_block, GenesisBlockInfo};
     use crate::rpc::tests::create_validator_exit;
@@ -197,12 +204,15 @@ mod tests {
             solana_netutil::find_available_port_in_range((10000, 65535)).unwrap(),
         );
         let bank_forks = Arc::new(RwLock::new(BankForks::new(bank.slot(), bank)));
+        let ledger_path = get_tmp_ledger_path!();
+        let blocktree = Arc::new(Blocktree::open(&ledger_path).unwrap());
         let mut rpc_service = JsonRpcService::new(
             &cluster_info,
             rpc_addr,
             StorageState::default(),
             JsonRpcConfig::default(),
             bank_forks,
+            blocktree,
             &PathBuf::from("farf"),
             Hash::default(),
             &validator_exit,
@@ -220,5 +230,6 @@ mod tests {
         );
         rpc_service.exit();
         rpc_service.join().unwrap();
+        Blocktree::destroy(&ledger_path).unwrap();
     }
 }
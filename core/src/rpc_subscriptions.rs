@@ -6,31 +6,54 @@ use jsonrpc_core::futures::Future;
 use jsonrpc_pubsub::typed::Sink;
 use jsonrpc_pubsub::SubscriptionId;
 use serde::Serialize;
+use solana_client::rpc_request::{RpcProgramAccountsConfig, RpcTransactionLogs};
 use solana_runtime::bank::Bank;
 use solana_sdk::account::Account;
+use solana_sdk::clock::Slot;
 use solana_sdk::pubkey::Pubkey;
 use solana_sdk::signature::Signature;
 use solana_sdk::transaction;
 use solana_vote_api::vote_state::MAX_LOCKOUT_HISTORY;
 use std::collections::HashMap;
-use std::sync::{Arc, RwLock};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::{Arc, Mutex, RwLock};
+use std::thread::Builder;
 
 pub type Confirmations = usize;
 
-type RpcAccountSubscriptions =
-    RwLock<HashMap<Pubkey, HashMap<SubscriptionId, (Sink<Account>, Confirmations)>>>;
+#[derive(Serialize, Clone, Copy, Debug)]
+pub struct SlotInfo {
+    pub slot: Slot,
+    pub parent: Slot,
+    pub root: Slot,
+}
+
+// Each subscription is kept with its sink, its confirmations, and any parameters particular to it
+type Subscriptions<K, S, P> = HashMap<K, HashMap<SubscriptionId, (Sink<S>, Confirmations, P)>>;
+
+type RpcAccountSubscriptions = RwLock<Subscriptions<Pubkey, Account, ()>>;
 type RpcProgramSubscriptions =
-    RwLock<HashMap<Pubkey, HashMap<SubscriptionId, (Sink<(String, Account)>, Confirmations)>>>;
-type RpcSignatureSubscriptions = RwLock<
-    HashMap<Signature, HashMap<SubscriptionId, (Sink<transaction::Result<()>>, Confirmations)>>,
->;
+    RwLock<Subscriptions<Pubkey, (String, Account), RpcProgramAccountsConfig>>;
+type RpcSignatureSubscriptions = RwLock<Subscriptions<Signature, transaction::Result<()>, ()>>;
+// Keyed by the address the logged transactions must reference, or None for all transactions
+type RpcLogsSubscriptions = RwLock<Subscriptions<Option<Pubkey>, RpcTransactionLogs, ()>>;
+type RpcSlotSubscriptions = RwLock<HashMap<SubscriptionId, Sink<SlotInfo>>>;
+type RpcRootSubscriptions = RwLock<HashMap<SubscriptionId, Sink<Slot>>>;
+
+// Slot and root notifications are sent from the replay stage, so they are queued and delivered
+// by a separate thread rather than waiting on slow subscribers
+enum NotificationEntry {
+    Slot(SlotInfo),
+    Roots(Vec<Slot>),
+}
 
-fn add_subscription<K, S>(
-    subscriptions: &mut HashMap<K, HashMap<SubscriptionId, (Sink<S>, Confirmations)>>,
+fn add_subscription<K, S, P>(
+    subscriptions: &mut Subscriptions<K, S, P>,
     hashmap_key: &K,
     confirmations: Option<Confirmations>,
     sub_id: &SubscriptionId,
     sink: &Sink<S>,
+    params: P,
 ) where
     K: Eq + Hash + Clone + Copy,
     S: Clone,
@@ -42,16 +65,16 @@ fn add_subscription<K, S>(
         confirmations
     };
     if let Some(current_hashmap) = subscriptions.get_mut(hashmap_key) {
-        current_hashmap.insert(sub_id.clone(), (sink.clone(), confirmations));
+        current_hashmap.insert(sub_id.clone(), (sink.clone(), confirmations, params));
         return;
     }
     let mut hashmap = HashMap::new();
-    hashmap.insert(sub_id.clone(), (sink.clone(), confirmations));
+    hashmap.insert(sub_id.clone(), (sink.clone(), confirmations, params));
     subscriptions.insert(*hashmap_key, hashmap);
 }
 
-fn remove_subscription<K, S>(
-    subscriptions: &mut HashMap<K, HashMap<SubscriptionId, (Sink<S>, Confirmations)>>,
+fn remove_subscription<K, S, P>(
+    subscriptions: &mut Subscriptions<K, S, P>,
     sub_id: &SubscriptionId,
 ) -> bool
 where
@@ -71,8 +94,8 @@ where
     found
 }
 
-fn check_confirmations_and_notify<K, S, F, N, X>(
-    subscriptions: &HashMap<K, HashMap<SubscriptionId, (Sink<S>, Confirmations)>>,
+fn check_confirmations_and_notify<K, S, P, F, N, X>(
+    subscriptions: &Subscriptions<K, S, P>,
     hashmap_key: &K,
     current_slot: u64,
     bank_forks: &Arc<RwLock<BankForks>>,
@@ -82,7 +105,7 @@ fn check_confirmations_and_notify<K, S, F, N, X>(
     K: Eq + Hash + Clone + Copy,
     S: Clone + Serialize,
     F: Fn(&Bank, &K) -> X,
-    N: Fn(X, &Sink<S>, u64),
+    N: Fn(X, &Sink<S>, &P, u64),
     X: Clone + Serialize,
 {
     let current_ancestors = bank_forks
@@ -93,7 +116,7 @@ fn check_confirmations_and_notify<K, S, F, N, X>(
         .ancestors
         .clone();
     if let Some(hashmap) = subscriptions.get(hashmap_key) {
-        for (_bank_sub_id, (sink, confirmations)) in hashmap.iter() {
+        for (_bank_sub_id, (sink, confirmations, params)) in hashmap.iter() {
             let desired_slot: Vec<u64> = current_ancestors
                 .iter()
                 .filter(|(_, &v)| v == *confirmations)
@@ -115,13 +138,13 @@ fn check_confirmations_and_notify<K, S, F, N, X>(
                     .unwrap()
                     .clone();
                 let result = bank_method(&desired_bank, hashmap_key);
-                notify(result, &sink, root);
+                notify(result, &sink, params, root);
             }
         }
     }
 }
 
-fn notify_account<S>(result: Option<(S, u64)>, sink: &Sink<S>, root: u64)
+fn notify_account<S>(result: Option<(S, u64)>, sink: &Sink<S>, _params: &(), root: u64)
 where
     S: Clone + Serialize,
 {
@@ -132,7 +155,7 @@ where
     }
 }
 
-fn notify_signature<S>(result: Option<S>, sink: &Sink<S>, _root: u64)
+fn notify_signature<S>(result: Option<S>, sink: &Sink<S>, _params: &(), _root: u64)
 where
     S: Clone + Serialize,
 {
@@ -141,31 +164,108 @@ where
     }
 }
 
-fn notify_program(accounts: Vec<(Pubkey, Account)>, sink: &Sink<(String, Account)>, _root: u64) {
+fn notify_program(
+    accounts: Vec<(Pubkey, Account)>,
+    sink: &Sink<(String, Account)>,
+    config: &RpcProgramAccountsConfig,
+    _root: u64,
+) {
     for (pubkey, account) in accounts.iter() {
-        sink.notify(Ok((pubkey.to_string(), account.clone())))
-            .wait()
-            .unwrap();
+        if let Some(account) = config.apply(account) {
+            sink.notify(Ok((pubkey.to_string(), account)))
+                .wait()
+                .unwrap();
+        }
     }
 }
 
+fn notify_logs(
+    logs: Vec<RpcTransactionLogs>,
+    sink: &Sink<RpcTransactionLogs>,
+    _params: &(),
+    _root: u64,
+) {
+    for transaction_logs in logs {
+        sink.notify(Ok(transaction_logs)).wait().unwrap();
+    }
+}
+
+fn get_transaction_logs(bank: &Bank, address: &Option<Pubkey>) -> Vec<RpcTransactionLogs> {
+    bank.get_transaction_logs(address.as_ref())
+        .into_iter()
+        .map(|info| RpcTransactionLogs {
+            signature: info.signature.to_string(),
+            result: info.result,
+            log_messages: info.log_messages,
+        })
+        .collect()
+}
+
 pub struct RpcSubscriptions {
     account_subscriptions: RpcAccountSubscriptions,
     program_subscriptions: RpcProgramSubscriptions,
     signature_subscriptions: RpcSignatureSubscriptions,
+    logs_subscriptions: RpcLogsSubscriptions,
+    slot_subscriptions: Arc<RpcSlotSubscriptions>,
+    root_subscriptions: Arc<RpcRootSubscriptions>,
+    notification_sender: Mutex<Sender<NotificationEntry>>,
 }
 
 impl Default for RpcSubscriptions {
     fn default() -> Self {
+        let slot_subscriptions = Arc::new(RpcSlotSubscriptions::default());
+        let root_subscriptions = Arc::new(RpcRootSubscriptions::default());
+        let (notification_sender, notification_receiver) = channel();
+        Self::spawn_notifier(
+            notification_receiver,
+            slot_subscriptions.clone(),
+            root_subscriptions.clone(),
+        );
         RpcSubscriptions {
             account_subscriptions: RpcAccountSubscriptions::default(),
             program_subscriptions: RpcProgramSubscriptions::default(),
             signature_subscriptions: RpcSignatureSubscriptions::default(),
+            logs_subscriptions: RpcLogsSubscriptions::default(),
+            slot_subscriptions,
+            root_subscriptions,
+            notification_sender: Mutex::new(notification_sender),
         }
     }
 }
 
 impl RpcSubscriptions {
+    // Deliver queued notifications until the `RpcSubscriptions` is dropped. A subscriber that
+    // has gone away only misses its own notifications
+    fn spawn_notifier(
+        notification_receiver: Receiver<NotificationEntry>,
+        slot_subscriptions: Arc<RpcSlotSubscriptions>,
+        root_subscriptions: Arc<RpcRootSubscriptions>,
+    ) {
+        Builder::new()
+            .name("solana-rpc-notifier".to_string())
+            .spawn(move || {
+                while let Ok(notification) = notification_receiver.recv() {
+                    match notification {
+                        NotificationEntry::Slot(slot_info) => {
+                            let subscriptions = slot_subscriptions.read().unwrap();
+                            for (_, sink) in subscriptions.iter() {
+                                let _ = sink.notify(Ok(slot_info)).wait();
+                            }
+                        }
+                        NotificationEntry::Roots(rooted_slots) => {
+                            let subscriptions = root_subscriptions.read().unwrap();
+                            for root in rooted_slots {
+                                for (_, sink) in subscriptions.iter() {
+                                    let _ = sink.notify(Ok(root)).wait();
+                                }
+                            }
+                        }
+                    }
+                }
+            })
+            .unwrap();
+    }
+
     pub fn check_account(
         &self,
         pubkey: &Pubkey,
@@ -218,6 +318,23 @@ impl RpcSubscriptions {
         subscriptions.remove(&signature);
     }
 
+    pub fn check_logs(
+        &self,
+        address: &Option<Pubkey>,
+        current_slot: u64,
+        bank_forks: &Arc<RwLock<BankForks>>,
+    ) {
+        let subscriptions = self.logs_subscriptions.read().unwrap();
+        check_confirmations_and_notify(
+            &subscriptions,
+            address,
+            current_slot,
+            bank_forks,
+            get_transaction_logs,
+            notify_logs,
+        );
+    }
+
     pub fn add_account_subscription(
         &self,
         pubkey: &Pubkey,
@@ -226,7 +343,7 @@ impl RpcSubscriptions {
         sink: &Sink<Account>,
     ) {
         let mut subscriptions = self.account_subscriptions.write().unwrap();
-        add_subscription(&mut subscriptions, pubkey, confirmations, sub_id, sink);
+        add_subscription(&mut subscriptions, pubkey, confirmations, sub_id, sink, ());
     }
 
     pub fn remove_account_subscription(&self, id: &SubscriptionId) -> bool {
@@ -238,11 +355,19 @@ impl RpcSubscriptions {
         &self,
         program_id: &Pubkey,
         confirmations: Option<Confirmations>,
+        config: Option<RpcProgramAccountsConfig>,
         sub_id: &SubscriptionId,
         sink: &Sink<(String, Account)>,
     ) {
         let mut subscriptions = self.program_subscriptions.write().unwrap();
-        add_subscription(&mut subscriptions, program_id, confirmations, sub_id, sink);
+        add_subscription(
+            &mut subscriptions,
+            program_id,
+            confirmations,
+            sub_id,
+            sink,
+            config.unwrap_or_default(),
+        );
     }
 
     pub fn remove_program_subscription(&self, id: &SubscriptionId) -> bool {
@@ -258,7 +383,14 @@ impl RpcSubscriptions {
         sink: &Sink<transaction::Result<()>>,
     ) {
         let mut subscriptions = self.signature_subscriptions.write().unwrap();
-        add_subscription(&mut subscriptions, signature, confirmations, sub_id, sink);
+        add_subscription(
+            &mut subscriptions,
+            signature,
+            confirmations,
+            sub_id,
+            sink,
+            (),
+        );
     }
 
     pub fn remove_signature_subscription(&self, id: &SubscriptionId) -> bool {
@@ -266,8 +398,65 @@ impl RpcSubscriptions {
         remove_subscription(&mut subscriptions, id)
     }
 
-    /// Notify subscribers of changes to any accounts or new signatures since
-    /// the bank's last checkpoint.
+    pub fn add_logs_subscription(
+        &self,
+        address: &Option<Pubkey>,
+        confirmations: Option<Confirmations>,
+        sub_id: &SubscriptionId,
+        sink: &Sink<RpcTransactionLogs>,
+    ) {
+        let mut subscriptions = self.logs_subscriptions.write().unwrap();
+        add_subscription(&mut subscriptions, address, confirmations, sub_id, sink, ());
+    }
+
+    pub fn remove_logs_subscription(&self, id: &SubscriptionId) -> bool {
+        let mut subscriptions = self.logs_subscriptions.write().unwrap();
+        remove_subscription(&mut subscriptions, id)
+    }
+
+    pub fn add_slot_subscription(&self, sub_id: &SubscriptionId, sink: &Sink<SlotInfo>) {
+        let mut subscriptions = self.slot_subscriptions.write().unwrap();
+        subscriptions.insert(sub_id.clone(), sink.clone());
+    }
+
+    pub fn remove_slot_subscription(&self, id: &SubscriptionId) -> bool {
+        let mut subscriptions = self.slot_subscriptions.write().unwrap();
+        subscriptions.remove(id).is_some()
+    }
+
+    /// Notify slot subscribers that a bank has been created for `slot`, without waiting for
+    /// the notifications to be delivered
+    pub fn notify_slot(&self, slot: Slot, parent: Slot, root: Slot) {
+        let _ = self
+            .notification_sender
+            .lock()
+            .unwrap()
+            .send(NotificationEntry::Slot(SlotInfo { slot, parent, root }));
+    }
+
+    pub fn add_root_subscription(&self, sub_id: &SubscriptionId, sink: &Sink<Slot>) {
+        let mut subscriptions = self.root_subscriptions.write().unwrap();
+        subscriptions.insert(sub_id.clone(), sink.clone());
+    }
+
+    pub fn remove_root_subscription(&self, id: &SubscriptionId) -> bool {
+        let mut subscriptions = self.root_subscriptions.write().unwrap();
+        subscriptions.remove(id).is_some()
+    }
+
+    /// Notify root subscribers of each newly rooted slot, in ascending order, without waiting
+    /// for the notifications to be delivered
+    pub fn notify_roots(&self, mut rooted_slots: Vec<Slot>) {
+        rooted_slots.sort();
+        let _ = self
+            .notification_sender
+            .lock()
+            .unwrap()
+            .send(NotificationEntry::Roots(rooted_slots));
+    }
+
+    /// Notify subscribers of changes to any accounts, new signatures or logged
+    /// messages since the bank's last checkpoint.
     pub fn notify_subscribers(&self, current_slot: u64, bank_forks: &Arc<RwLock<BankForks>>) {
         let pubkeys: Vec<_> = {
             let subs = self.account_subscriptions.read().unwrap();
@@ -292,6 +481,14 @@ impl RpcSubscriptions {
         for signature in &signatures {
             self.check_signature(signature, current_slot, bank_forks);
         }
+
+        let addresses: Vec<_> = {
+            let subs = self.logs_subscriptions.read().unwrap();
+            subs.keys().cloned().collect()
+        };
+        for address in &addresses {
+            self.check_logs(address, current_slot, bank_forks);
+        }
     }
 }
 
@@ -301,6 +498,8 @@ mod tests {
     use crate::genesis_utils::{create_genesis_block, GenesisBlockInfo};
     use jsonrpc_pubsub::typed::Subscriber;
     use solana_budget_api;
+    use solana_client::rpc_filter::{RpcDataSlice, RpcFilterType};
+    use solana_sdk::clock::MAX_RECENT_BLOCKHASHES;
     use solana_sdk::signature::{Keypair, KeypairUtil};
     use solana_sdk::system_transaction;
     use tokio::prelude::{Async, Stream};
@@ -392,7 +591,13 @@ mod tests {
         let sub_id = SubscriptionId::Number(0 as u64);
         let sink = subscriber.assign_id(sub_id.clone()).unwrap();
         let subscriptions = RpcSubscriptions::default();
-        subscriptions.add_program_subscription(&solana_budget_api::id(), None, &sub_id, &sink);
+        subscriptions.add_program_subscription(
+            &solana_budget_api::id(),
+            None,
+            None,
+            &sub_id,
+            &sink,
+        );
 
         assert!(subscriptions
             .program_subscriptions
@@ -414,6 +619,84 @@ mod tests {
             .unwrap()
             .contains_key(&solana_budget_api::id()));
     }
+
+    #[test]
+    fn test_check_program_subscribe_with_config() {
+        let GenesisBlockInfo {
+            genesis_block,
+            mint_keypair,
+            ..
+        } = create_genesis_block(100);
+        let bank = Bank::new(&genesis_block);
+        let blockhash = bank.last_blockhash();
+        let bank_forks = Arc::new(RwLock::new(BankForks::new(0, bank)));
+        let alice = Keypair::new();
+        let tx = system_transaction::create_account(
+            &mint_keypair,
+            &alice.pubkey(),
+            blockhash,
+            1,
+            16,
+            &solana_budget_api::id(),
+        );
+        bank_forks
+            .write()
+            .unwrap()
+            .get(0)
+            .unwrap()
+            .process_transaction(&tx)
+            .unwrap();
+
+        let subscriptions = RpcSubscriptions::default();
+
+        // filtered out
+        let (subscriber, _id_receiver, mut filtered_receiver) =
+            Subscriber::new_test("programNotification");
+        let sub_id = SubscriptionId::Number(0 as u64);
+        let sink = subscriber.assign_id(sub_id.clone()).unwrap();
+        let config = RpcProgramAccountsConfig {
+            filters: Some(vec![RpcFilterType::DataSize(15)]),
+            data_slice: None,
+        };
+        subscriptions.add_program_subscription(
+            &solana_budget_api::id(),
+            None,
+            Some(config),
+            &sub_id,
+            &sink,
+        );
+
+        // passes the filter, with its data sliced
+        let (subscriber, _id_receiver, mut transport_receiver) =
+            Subscriber::new_test("programNotification");
+        let sub_id = SubscriptionId::Number(1 as u64);
+        let sink = subscriber.assign_id(sub_id.clone()).unwrap();
+        let config = RpcProgramAccountsConfig {
+            filters: Some(vec![RpcFilterType::DataSize(16)]),
+            data_slice: Some(RpcDataSlice {
+                offset: 0,
+                length: 2,
+            }),
+        };
+        subscriptions.add_program_subscription(
+            &solana_budget_api::id(),
+            None,
+            Some(config),
+            &sub_id,
+            &sink,
+        );
+
+        subscriptions.check_program(&solana_budget_api::id(), 0, &bank_forks);
+        assert_eq!(filtered_receiver.poll(), Ok(Async::NotReady));
+        let string = transport_receiver.poll();
+        if let Async::Ready(Some(response)) = string.unwrap() {
+            let expected = format!(r#"{{"jsonrpc":"2.0","method":"programNotification","params":{{"result":["{:?}",{{"data":[0,0],"executable":false,"lamports":1,"owner":[2,203,81,223,225,24,34,35,203,214,138,130,144,208,35,77,63,16,87,51,47,198,115,123,98,188,19,160,0,0,0,0],"rent_epoch":0}}],"subscription":1}}}}"#, alice.pubkey());
+            assert_eq!(expected, response);
+        } else {
+            panic!("Expected a notification");
+        }
+    }
+
     #[test]
     fn test_check_signature_subscribe() {
         let GenesisBlockInfo {
@@ -465,4 +748,124 @@ mod tests {
             .unwrap()
             .contains_key(&signature));
     }
+
+    #[test]
+    fn test_check_logs_subscribe() {
+        let GenesisBlockInfo {
+            genesis_block,
+            mint_keypair,
+            ..
+        } = create_genesis_block(100);
+        let bank = Bank::new(&genesis_block);
+        let blockhash = bank.last_blockhash();
+        let alice = Keypair::new();
+        let tx = system_transaction::transfer(&mint_keypair, &alice.pubkey(), 20, blockhash);
+        let signature = tx.signatures[0];
+
+        // The system program doesn't log, so commit the transaction with stand-in logs
+        let txs = vec![tx];
+        let lock_results = bank.lock_accounts(&txs, None);
+        let (mut loaded_accounts, executed, _, _, _, tx_count, signature_count) =
+            bank.load_and_execute_transactions(&txs, None, &lock_results, MAX_RECENT_BLOCKHASHES);
+        bank.commit_transactions(
+            &txs,
+            None,
+            &mut loaded_accounts,
+            &executed,
+            &[vec!["hello".to_string()]],
+            tx_count,
+            signature_count,
+        );
+        drop(lock_results);
+        let bank_forks = Arc::new(RwLock::new(BankForks::new(0, bank)));
+
+        let (subscriber, _id_receiver, mut transport_receiver) =
+            Subscriber::new_test("logsNotification");
+        let sub_id = SubscriptionId::Number(0 as u64);
+        let sink = subscriber.assign_id(sub_id.clone()).unwrap();
+        let subscriptions = RpcSubscriptions::default();
+        let address = Some(alice.pubkey());
+        subscriptions.add_logs_subscription(&address, None, &sub_id, &sink);
+
+        assert!(subscriptions
+            .logs_subscriptions
+            .read()
+            .unwrap()
+            .contains_key(&address));
+
+        subscriptions.check_logs(&address, 0, &bank_forks);
+        let string = transport_receiver.poll();
+        if let Async::Ready(Some(response)) = string.unwrap() {
+            let expected = format!(r#"{{"jsonrpc":"2.0","method":"logsNotification","params":{{"result":{{"logMessages":["hello"],"result":{{"Ok":null}},"signature":"{}"}},"subscription":0}}}}"#, signature);
+            assert_eq!(expected, response);
+        } else {
+            panic!("expected a logs notification");
+        }
+
+        subscriptions.remove_logs_subscription(&sub_id);
+        assert!(!subscriptions
+            .logs_subscriptions
+            .read()
+            .unwrap()
+            .contains_key(&address));
+    }
+
+    #[test]
+    fn test_check_slot_subscribe() {
+        let (subscriber, _id_receiver, transport_receiver) =
+            Subscriber::new_test("slotNotification");
+        let sub_id = SubscriptionId::Number(0 as u64);
+        let sink = subscriber.assign_id(sub_id.clone()).unwrap();
+        let subscriptions = RpcSubscriptions::default();
+        subscriptions.add_slot_subscription(&sub_id, &sink);
+
+        assert!(subscriptions
+            .slot_subscriptions
+            .read()
+            .unwrap()
+            .contains_key(&sub_id));
+
+        subscriptions.notify_slot(0, 0, 0);
+        let response = transport_receiver.wait().next().unwrap().unwrap();
+        let expected = format!(r#"{{"jsonrpc":"2.0","method":"slotNotification","params":{{"result":{{"parent":0,"root":0,"slot":0}},"subscription":0}}}}"#);
+        assert_eq!(expected, response);
+
+        subscriptions.remove_slot_subscription(&sub_id);
+        assert!(!subscriptions
+            .slot_subscriptions
+            .read()
+            .unwrap()
+            .contains_key(&sub_id));
+    }
+
+    #[test]
+    fn test_check_root_subscribe() {
+        let (subscriber, _id_receiver, transport_receiver) =
+            Subscriber::new_test("rootNotification");
+        let sub_id = SubscriptionId::Number(0 as u64);
+        let sink = subscriber.assign_id(sub_id.clone()).unwrap();
+        let subscriptions = RpcSubscriptions::default();
+        subscriptions.add_root_subscription(&sub_id, &sink);
+
+        assert!(subscriptions
+            .root_subscriptions
+            .read()
+            .unwrap()
+            .contains_key(&sub_id));
+
+        subscriptions.notify_roots(vec![2, 1]);
+        let mut notifications = transport_receiver.wait();
+        for root in 1..3 {
+            let response = notifications.next().unwrap().unwrap();
+            let expected = format!(r#"{{"jsonrpc":"2.0","method":"rootNotification","params":{{"result":{},"subscription":0}}}}"#, root);
+            assert_eq!(expected, response);
+        }
+
+        subscriptions.remove_root_subscription(&sub_id);
+        assert!(!subscriptions
+            .root_subscriptions
+            .read()
+            .unwrap()
+            .contains_key(&sub_id));
+    }
 }
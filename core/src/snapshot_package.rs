@@ -1,11 +1,15 @@
 use crate::result::{Error, Result};
 use crate::service::Service;
+use crate::snapshot_utils::{self, ArchiveFormat};
+use bzip2::write::BzEncoder;
+use flate2::write::GzEncoder;
 use solana_measure::measure::Measure;
 use solana_metrics::datapoint_info;
 use solana_runtime::accounts_db::AccountStorageEntry;
 use std::fs;
-use std::io::{Error as IOError, ErrorKind};
+use std::io::{self, Error as IOError, ErrorKind};
 use std::path::PathBuf;
+use std::process::Stdio;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc::{Receiver, RecvTimeoutError, Sender};
 use std::sync::Arc;
@@ -19,12 +23,17 @@ pub type SnapshotPackageReceiver = Receiver<SnapshotPackage>;
 
 pub const TAR_SNAPSHOTS_DIR: &str = "snapshots";
 pub const TAR_ACCOUNTS_DIR: &str = "accounts";
+// Names the full snapshot an incremental snapshot archive was taken relative to
+pub const TAR_BASE_SLOT_FILE: &str = "base_slot";
 
 pub struct SnapshotPackage {
     root: u64,
     snapshot_links: TempDir,
     storage_entries: Vec<Arc<AccountStorageEntry>>,
     tar_output_file: PathBuf,
+    /// The root of the full snapshot this package is relative to, None for a full snapshot
+    base_slot: Option<u64>,
+    archive_format: ArchiveFormat,
 }
 
 impl SnapshotPackage {
@@ -33,14 +42,26 @@ impl SnapshotPackage {
         snapshot_links: TempDir,
         storage_entries: Vec<Arc<AccountStorageEntry>>,
         tar_output_file: PathBuf,
+        base_slot: Option<u64>,
+        archive_format: ArchiveFormat,
     ) -> Self {
         Self {
             root,
             snapshot_links,
             storage_entries,
             tar_output_file,
+            base_slot,
+            archive_format,
         }
     }
+
+    pub fn storage_entries(&self) -> &[Arc<AccountStorageEntry>] {
+        &self.storage_entries
+    }
+
+    pub fn base_slot(&self) -> Option<u64> {
+        self.base_slot
+    }
 }
 
 pub struct SnapshotPackagerService {
@@ -72,8 +93,8 @@ impl SnapshotPackagerService {
 
     pub fn package_snapshots(snapshot_package: &SnapshotPackage) -> Result<()> {
         info!(
-            "Generating snapshot tarball for root {}",
-            snapshot_package.root
+            "Generating snapshot tarball for root {}, base: {:?}",
+            snapshot_package.root, snapshot_package.base_slot
         );
         let mut timer = Measure::start("snapshot_package-package_snapshots");
         let tar_dir = snapshot_package
@@ -109,20 +130,36 @@ impl SnapshotPackagerService {
             symlink::symlink_dir(storage_path, output_path)?;
         }
 
-        // Tar the staging directory into the archive `temp_tar_gz`
-        let temp_tar_gz = tempfile::Builder::new()
+        // Tar the staging directory into the archive `temp_tar`
+        let archive_format = snapshot_package.archive_format;
+        let mut temp_tar = tempfile::Builder::new()
             .prefix("new_state")
-            .suffix(".tar.bz2")
+            .suffix(&format!(".{}", archive_format.extension()))
             .tempfile_in(tar_dir)?;
-        let temp_tar_path = temp_tar_gz.path();
-        let mut args = vec!["jcfhS"];
-        args.push(temp_tar_path.to_str().unwrap());
-        args.push("-C");
+        let mut args = vec!["cfhS", "-", "-C"];
         args.push(staging_dir.path().to_str().unwrap());
+        if let Some(base_slot) = snapshot_package.base_slot {
+            // Goes first so the base can be read without unpacking the whole archive
+            fs::write(
+                staging_dir.path().join(TAR_BASE_SLOT_FILE),
+                base_slot.to_string(),
+            )?;
+            args.push(TAR_BASE_SLOT_FILE);
+        }
         args.push(TAR_ACCOUNTS_DIR);
         args.push(TAR_SNAPSHOTS_DIR);
 
-        let status = std::process::Command::new("tar").args(&args).status()?;
+        let mut tar = std::process::Command::new("tar")
+            .args(&args)
+            .stdout(Stdio::piped())
+            .spawn()?;
+        let compress_result = {
+            let mut tar_output = tar.stdout.take().unwrap();
+            Self::compress_archive(&mut tar_output, temp_tar.as_file_mut(), archive_format)
+        };
+        // Reap tar even if compressing failed; its stdout is closed by now so it can't block
+        let status = tar.wait()?;
+        compress_result?;
 
         if !status.success() {
             return Err(Self::get_io_error(&format!(
@@ -133,10 +170,27 @@ impl SnapshotPackagerService {
 
         // Once everything is successful, overwrite the previous tarball so that other validators
         // can fetch this newly packaged snapshot
+        let temp_tar_path = temp_tar.path();
         let _ = fs::remove_file(&snapshot_package.tar_output_file);
         let metadata = fs::metadata(&temp_tar_path)?;
         fs::hard_link(&temp_tar_path, &snapshot_package.tar_output_file)?;
 
+        // Remove the archives this one replaces: those written in other formats and, for a full
+        // snapshot, any incremental snapshot taken relative to an older full snapshot
+        for format in ArchiveFormat::all().iter() {
+            let mut stale_archives = vec![snapshot_utils::get_incremental_snapshot_tar_path(
+                tar_dir, *format,
+            )];
+            if snapshot_package.base_slot.is_none() {
+                stale_archives.push(snapshot_utils::get_snapshot_tar_path(tar_dir, *format));
+            }
+            for stale_archive in stale_archives {
+                if stale_archive != snapshot_package.tar_output_file {
+                    let _ = fs::remove_file(stale_archive);
+                }
+            }
+        }
+
         timer.stop();
         info!(
             "Successfully created tarball. slot: {}, elapsed ms: {}, size={}",
@@ -147,6 +201,7 @@ impl SnapshotPackagerService {
         datapoint_info!(
             "snapshot-package",
             ("slot", snapshot_package.root, i64),
+            ("incremental", snapshot_package.base_slot.is_some(), bool),
             ("duration_ms", timer.as_ms(), i64),
             ("size", metadata.len(), i64)
         );
@@ -154,12 +209,55 @@ impl SnapshotPackagerService {
     }
 
     fn run(snapshot_receiver: &SnapshotPackageReceiver) -> Result<()> {
-        let mut snapshot_package = snapshot_receiver.recv_timeout(Duration::from_secs(1))?;
-        // Only package the latest
-        while let Ok(new_snapshot_package) = snapshot_receiver.try_recv() {
-            snapshot_package = new_snapshot_package;
+        let snapshot_package = snapshot_receiver.recv_timeout(Duration::from_secs(1))?;
+        // Only package the latest full snapshot and the latest incremental snapshot taken
+        // after it, an incremental taken before a newer full snapshot is of no use
+        let mut full_package = None;
+        let mut incremental_package = None;
+        for snapshot_package in
+            std::iter::once(snapshot_package).chain(snapshot_receiver.try_iter())
+        {
+            if snapshot_package.base_slot.is_some() {
+                incremental_package = Some(snapshot_package);
+            } else {
+                full_package = Some(snapshot_package);
+                incremental_package = None;
+            }
+        }
+        if let Some(snapshot_package) = full_package {
+            Self::package_snapshots(&snapshot_package)?;
+        }
+        if let Some(snapshot_package) = incremental_package {
+            Self::package_snapshots(&snapshot_package)?;
+        }
+        Ok(())
+    }
+
+    fn compress_archive(
+        tar_output: &mut impl io::Read,
+        archive_file: &mut fs::File,
+        archive_format: ArchiveFormat,
+    ) -> io::Result<()> {
+        match archive_format {
+            ArchiveFormat::TarBzip2 => {
+                let mut encoder = BzEncoder::new(archive_file, bzip2::Compression::Best);
+                io::copy(tar_output, &mut encoder)?;
+                encoder.finish()?;
+            }
+            ArchiveFormat::TarGzip => {
+                let mut encoder = GzEncoder::new(archive_file, flate2::Compression::default());
+                io::copy(tar_output, &mut encoder)?;
+                encoder.finish()?;
+            }
+            ArchiveFormat::TarZstd => {
+                let mut encoder = zstd::stream::write::Encoder::new(archive_file, 0)?;
+                io::copy(tar_output, &mut encoder)?;
+                encoder.finish()?;
+            }
+            ArchiveFormat::Tar => {
+                io::copy(tar_output, archive_file)?;
+            }
         }
-        Self::package_snapshots(&snapshot_package)?;
         Ok(())
     }
 
@@ -180,7 +278,6 @@ impl Service for SnapshotPackagerService {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::snapshot_utils;
     use std::fs::OpenOptions;
     use std::io::Write;
     use tempfile::TempDir;
@@ -216,27 +313,48 @@ mod tests {
             })
             .collect();
 
-        // Create directory of hard links for snapshots
-        let link_snapshots_dir = tempfile::tempdir_in(temp_dir.path()).unwrap();
-        for snapshots_path in snapshots_paths {
-            let snapshot_file_name = snapshots_path.file_name().unwrap();
-            let link_path = link_snapshots_dir.path().join(snapshot_file_name);
-            fs::hard_link(&snapshots_path, &link_path).unwrap();
-        }
+        for archive_format in ArchiveFormat::all().iter() {
+            // Create directory of hard links for snapshots
+            let link_snapshots_dir = tempfile::tempdir_in(temp_dir.path()).unwrap();
+            for snapshots_path in &snapshots_paths {
+                let snapshot_file_name = snapshots_path.file_name().unwrap();
+                let link_path = link_snapshots_dir.path().join(snapshot_file_name);
+                fs::hard_link(&snapshots_path, &link_path).unwrap();
+            }
 
-        // Create a packageable snapshot
-        let output_tar_path = snapshot_utils::get_snapshot_tar_path(&snapshot_package_output_path);
-        let snapshot_package = SnapshotPackage::new(
-            5,
-            link_snapshots_dir,
-            storage_entries.clone(),
-            output_tar_path.clone(),
-        );
+            // Create a packageable snapshot
+            let output_tar_path = snapshot_utils::get_snapshot_tar_path(
+                &snapshot_package_output_path,
+                *archive_format,
+            );
+            let snapshot_package = SnapshotPackage::new(
+                5,
+                link_snapshots_dir,
+                storage_entries.clone(),
+                output_tar_path.clone(),
+                None,
+                *archive_format,
+            );
 
-        // Make tarball from packageable snapshot
-        SnapshotPackagerService::package_snapshots(&snapshot_package).unwrap();
+            // Make tarball from packageable snapshot
+            SnapshotPackagerService::package_snapshots(&snapshot_package).unwrap();
 
-        // Check tarball is correct
-        snapshot_utils::tests::verify_snapshot_tar(output_tar_path, snapshots_dir, accounts_dir);
+            // The archive in the previous format was replaced
+            assert_eq!(
+                snapshot_utils::find_snapshot_tar_path(&snapshot_package_output_path),
+                Some(output_tar_path.clone())
+            );
+            assert_eq!(
+                fs::read_dir(&snapshot_package_output_path).unwrap().count(),
+                1
+            );
+
+            // Check tarball is correct
+            snapshot_utils::tests::verify_snapshot_tar(
+                output_tar_path,
+                &snapshots_dir,
+                &accounts_dir,
+            );
+        }
     }
 }
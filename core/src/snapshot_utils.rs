@@ -1,18 +1,23 @@
 use crate::bank_forks::SnapshotConfig;
 use crate::result::{Error, Result};
 use crate::snapshot_package::SnapshotPackage;
-use crate::snapshot_package::{TAR_ACCOUNTS_DIR, TAR_SNAPSHOTS_DIR};
+use crate::snapshot_package::{TAR_ACCOUNTS_DIR, TAR_BASE_SLOT_FILE, TAR_SNAPSHOTS_DIR};
 use bincode::{deserialize_from, serialize_into};
 use bzip2::bufread::BzDecoder;
+use flate2::read::GzDecoder;
 use fs_extra::dir::CopyOptions;
+use solana_runtime::accounts_db::{AccountStorageEntry, AppendVecId};
 use solana_runtime::bank::Bank;
 use solana_runtime::status_cache::SlotDelta;
 use solana_sdk::transaction;
 use std::cmp::Ordering;
+use std::collections::HashMap;
 use std::fs;
 use std::fs::File;
-use std::io::{BufReader, BufWriter, Error as IOError, ErrorKind};
+use std::io::{BufReader, BufWriter, Error as IOError, ErrorKind, Read};
 use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Arc;
 use tar::Archive;
 
 const SNAPSHOT_STATUS_CACHE_FILE_NAME: &str = "status_cache";
@@ -51,28 +56,103 @@ impl SlotSnapshotPaths {
     }
 }
 
+/// The storage a full snapshot archived, which later incremental snapshots are taken relative to
+#[derive(Debug, Clone, PartialEq)]
+pub struct FullSnapshotInfo {
+    pub slot: u64,
+    // Length of each archived AppendVec, a store that has since been reset and written again
+    //  has to go into the incremental snapshot
+    storage_lens: HashMap<AppendVecId, usize>,
+}
+
+impl FullSnapshotInfo {
+    pub fn new(slot: u64, storage_entries: &[Arc<AccountStorageEntry>]) -> Self {
+        let storage_lens = storage_entries
+            .iter()
+            .map(|entry| (entry.append_vec_id(), entry.written_len()))
+            .collect();
+        Self { slot, storage_lens }
+    }
+
+    fn contains(&self, entry: &AccountStorageEntry) -> bool {
+        self.storage_lens.get(&entry.append_vec_id()) == Some(&entry.written_len())
+    }
+}
+
 pub fn package_snapshot<P: AsRef<Path>, Q: AsRef<Path>>(
     bank: &Bank,
     snapshot_files: &[SlotSnapshotPaths],
     snapshot_package_output_file: P,
     snapshot_path: Q,
+    archive_format: ArchiveFormat,
 ) -> Result<SnapshotPackage> {
-    // Hard link all the snapshots we need for this package
-    let snapshot_hard_links_dir = tempfile::tempdir_in(snapshot_path)?;
+    package_snapshot_storages(
+        bank,
+        get_snapshot_storages(bank),
+        None,
+        snapshot_files,
+        snapshot_package_output_file,
+        snapshot_path,
+        archive_format,
+    )
+}
+
+/// Package only the storage entries and status cache deltas that aren't already in the full
+///  snapshot described by `base`
+pub fn package_incremental_snapshot<P: AsRef<Path>, Q: AsRef<Path>>(
+    bank: &Bank,
+    base: &FullSnapshotInfo,
+    snapshot_files: &[SlotSnapshotPaths],
+    snapshot_package_output_file: P,
+    snapshot_path: Q,
+    archive_format: ArchiveFormat,
+) -> Result<SnapshotPackage> {
+    let account_storage_entries = get_snapshot_storages(bank)
+        .into_iter()
+        .filter(|entry| !base.contains(entry))
+        .collect();
+    let snapshot_files: Vec<_> = snapshot_files
+        .iter()
+        .filter(|files| files.slot > base.slot)
+        .collect();
+
+    package_snapshot_storages(
+        bank,
+        account_storage_entries,
+        Some(base.slot),
+        snapshot_files,
+        snapshot_package_output_file,
+        snapshot_path,
+        archive_format,
+    )
+}
 
-    // Get a reference to all the relevant AccountStorageEntries
-    let account_storage_entries: Vec<_> = bank
-        .rc
+fn get_snapshot_storages(bank: &Bank) -> Vec<Arc<AccountStorageEntry>> {
+    bank.rc
         .get_storage_entries()
         .into_iter()
         .filter(|x| x.fork_id() <= bank.slot())
-        .collect();
+        .collect()
+}
+
+fn package_snapshot_storages<'a, P: AsRef<Path>, Q: AsRef<Path>>(
+    bank: &Bank,
+    account_storage_entries: Vec<Arc<AccountStorageEntry>>,
+    base_slot: Option<u64>,
+    snapshot_files: impl IntoIterator<Item = &'a SlotSnapshotPaths>,
+    snapshot_package_output_file: P,
+    snapshot_path: Q,
+    archive_format: ArchiveFormat,
+) -> Result<SnapshotPackage> {
+    // Hard link all the snapshots we need for this package
+    let snapshot_hard_links_dir = tempfile::tempdir_in(snapshot_path)?;
 
     // Create a snapshot package
     info!(
-        "Snapshot for bank: {} has {} account storage entries",
+        "Snapshot for bank: {} has {} account storage entries, base: {:?}",
         bank.slot(),
-        account_storage_entries.len()
+        account_storage_entries.len(),
+        base_slot
     );
 
     // Any errors from this point on will cause the above SnapshotPackage to drop, clearing
@@ -86,6 +166,8 @@ pub fn package_snapshot<P: AsRef<Path>, Q: AsRef<Path>>(
         snapshot_hard_links_dir,
         account_storage_entries,
         snapshot_package_output_file.as_ref().to_path_buf(),
+        base_slot,
+        archive_format,
     );
 
     Ok(package)
@@ -157,7 +239,8 @@ pub fn add_snapshot<P: AsRef<Path>>(
     // status cache writer
     let mut status_cache_stream = BufWriter::new(status_cache);
 
-    // Create the snapshot
+    // Create the snapshot, with the hash of the accounts it holds
+    bank.update_accounts_hash();
     serialize_into(&mut snapshot_stream, &*bank).map_err(|e| get_io_error(&e.to_string()))?;
     serialize_into(&mut snapshot_stream, &bank.rc).map_err(|e| get_io_error(&e.to_string()))?;
     // write the status cache
@@ -202,6 +285,17 @@ pub fn bank_from_archive<P: AsRef<Path>>(
     account_paths: String,
     snapshot_config: &SnapshotConfig,
     snapshot_tar: P,
+) -> Result<Bank> {
+    bank_from_archives(account_paths, snapshot_config, snapshot_tar, None)
+}
+
+/// Restore a bank from a full snapshot archive, brought forward by an incremental snapshot
+///  archive taken relative to it if one is given
+pub fn bank_from_archives<P: AsRef<Path>>(
+    account_paths: String,
+    snapshot_config: &SnapshotConfig,
+    snapshot_tar: P,
+    incremental_snapshot_tar: Option<P>,
 ) -> Result<Bank> {
     // Untar the snapshot into a temp directory under `snapshot_config.snapshot_path()`
     let unpack_dir = tempfile::tempdir_in(&snapshot_config.snapshot_path)?;
@@ -209,8 +303,35 @@ pub fn bank_from_archive<P: AsRef<Path>>(
 
     let unpacked_accounts_dir = unpack_dir.as_ref().join(TAR_ACCOUNTS_DIR);
     let unpacked_snapshots_dir = unpack_dir.as_ref().join(TAR_SNAPSHOTS_DIR);
+
+    if let Some(incremental_snapshot_tar) = incremental_snapshot_tar {
+        let full_slot = get_snapshot_paths(&unpacked_snapshots_dir)
+            .last()
+            .map(|paths| paths.slot)
+            .ok_or_else(|| get_io_error("No snapshots found in snapshots directory"))?;
+        let base_slot = base_slot_from_incremental_archive(&incremental_snapshot_tar)?;
+        if base_slot != full_slot {
+            return Err(get_io_error(&format!(
+                "Incremental snapshot is relative to slot {}, but the full snapshot is of slot {}",
+                base_slot, full_slot
+            )));
+        }
+        // The incremental archive's storage entries and snapshots land next to the full ones
+        untar_snapshot_in(&incremental_snapshot_tar, &unpack_dir)?;
+    }
+
     let snapshot_paths = get_snapshot_paths(&unpacked_snapshots_dir);
     let bank = rebuild_bank_from_snapshots(account_paths, &snapshot_paths, unpacked_accounts_dir)?;
+    if let Some(expected_accounts_hash) = snapshot_config.expected_accounts_hash {
+        if bank.accounts_hash() != expected_accounts_hash {
+            return Err(get_io_error(&format!(
+                "Snapshot accounts hash mismatch for bank {}: expected {} but the snapshot has {}",
+                bank.slot(),
+                expected_accounts_hash,
+                bank.accounts_hash()
+            )));
+        }
+    }
 
     // Move the unpacked snapshots into `snapshot_config.snapshot_path`
     let dir_files = fs::read_dir(&unpacked_snapshots_dir).unwrap_or_else(|err| {
@@ -229,21 +350,202 @@ pub fn bank_from_archive<P: AsRef<Path>>(
     Ok(bank)
 }
 
-pub fn get_snapshot_tar_path<P: AsRef<Path>>(snapshot_output_dir: P) -> PathBuf {
-    snapshot_output_dir.as_ref().join("snapshot.tar.bz2")
+/// The root of the full snapshot an incremental snapshot archive was taken relative to
+pub fn base_slot_from_incremental_archive<P: AsRef<Path>>(
+    incremental_snapshot_tar: P,
+) -> Result<u64> {
+    let mut archive = open_snapshot_archive(incremental_snapshot_tar)?;
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        if entry.path()? == Path::new(TAR_BASE_SLOT_FILE) {
+            let mut base_slot = String::new();
+            entry.read_to_string(&mut base_slot)?;
+            return base_slot
+                .trim()
+                .parse()
+                .map_err(|_| get_io_error("Invalid incremental snapshot base slot"));
+        }
+    }
+    Err(get_io_error("Incremental snapshot base slot not found"))
+}
+
+/// The slot of the last root in a full snapshot archive, read from the archive's listing
+///  without unpacking it
+pub fn slot_from_archive<P: AsRef<Path>>(snapshot_tar: P) -> Result<u64> {
+    let mut archive = open_snapshot_archive(snapshot_tar)?;
+    let mut slot = None;
+    for entry in archive.entries()? {
+        let entry = entry?;
+        let path = entry.path()?;
+        let entry_slot = path
+            .strip_prefix(TAR_SNAPSHOTS_DIR)
+            .ok()
+            .and_then(|path| path.iter().next())
+            .and_then(|slot_dir| slot_dir.to_str())
+            .and_then(|slot_dir| slot_dir.parse::<u64>().ok());
+        if entry_slot > slot {
+            slot = entry_slot;
+        }
+    }
+    slot.ok_or_else(|| get_io_error("No snapshots found in snapshot archive"))
+}
+
+/// The incremental snapshot archive in `snapshot_output_dir`, if there is one and it was
+///  taken relative to the full snapshot archive `snapshot_tar`
+pub fn find_matching_incremental_snapshot_tar_path<P: AsRef<Path>, Q: AsRef<Path>>(
+    snapshot_output_dir: P,
+    snapshot_tar: Q,
+) -> Option<PathBuf> {
+    let incremental_snapshot_tar = find_incremental_snapshot_tar_path(snapshot_output_dir)?;
+    match (
+        slot_from_archive(&snapshot_tar),
+        base_slot_from_incremental_archive(&incremental_snapshot_tar),
+    ) {
+        (Ok(slot), Ok(base_slot)) if slot == base_slot => Some(incremental_snapshot_tar),
+        (Ok(slot), Ok(base_slot)) => {
+            warn!(
+                "Ignoring incremental snapshot {:?}: it's relative to slot {}, but the full snapshot is of slot {}",
+                incremental_snapshot_tar, base_slot, slot
+            );
+            None
+        }
+        (Err(err), _) | (_, Err(err)) => {
+            warn!(
+                "Ignoring incremental snapshot {:?}: {:?}",
+                incremental_snapshot_tar, err
+            );
+            None
+        }
+    }
+}
+
+/// How a snapshot archive is compressed, told apart by the archive's file extension
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArchiveFormat {
+    TarBzip2,
+    TarGzip,
+    TarZstd,
+    Tar,
+}
+
+impl ArchiveFormat {
+    pub fn all() -> [ArchiveFormat; 4] {
+        [
+            ArchiveFormat::TarBzip2,
+            ArchiveFormat::TarGzip,
+            ArchiveFormat::TarZstd,
+            ArchiveFormat::Tar,
+        ]
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            ArchiveFormat::TarBzip2 => "tar.bz2",
+            ArchiveFormat::TarGzip => "tar.gz",
+            ArchiveFormat::TarZstd => "tar.zst",
+            ArchiveFormat::Tar => "tar",
+        }
+    }
+
+    pub fn from_path<P: AsRef<Path>>(archive_path: P) -> Option<Self> {
+        let file_name = archive_path.as_ref().file_name()?.to_str()?;
+        Self::all()
+            .iter()
+            .find(|format| file_name.ends_with(&format!(".{}", format.extension())))
+            .cloned()
+    }
+}
+
+impl Default for ArchiveFormat {
+    fn default() -> Self {
+        ArchiveFormat::TarBzip2
+    }
+}
+
+impl FromStr for ArchiveFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "bz2" => Ok(ArchiveFormat::TarBzip2),
+            "gzip" => Ok(ArchiveFormat::TarGzip),
+            "zstd" => Ok(ArchiveFormat::TarZstd),
+            "none" => Ok(ArchiveFormat::Tar),
+            _ => Err(format!("Unknown snapshot archive format: {}", s)),
+        }
+    }
+}
+
+pub fn get_snapshot_tar_path<P: AsRef<Path>>(
+    snapshot_output_dir: P,
+    archive_format: ArchiveFormat,
+) -> PathBuf {
+    snapshot_output_dir
+        .as_ref()
+        .join(format!("snapshot.{}", archive_format.extension()))
+}
+
+pub fn get_incremental_snapshot_tar_path<P: AsRef<Path>>(
+    snapshot_output_dir: P,
+    archive_format: ArchiveFormat,
+) -> PathBuf {
+    snapshot_output_dir.as_ref().join(format!(
+        "incremental-snapshot.{}",
+        archive_format.extension()
+    ))
+}
+
+/// The full snapshot archive in `snapshot_output_dir`, whichever format it was written in
+pub fn find_snapshot_tar_path<P: AsRef<Path>>(snapshot_output_dir: P) -> Option<PathBuf> {
+    ArchiveFormat::all()
+        .iter()
+        .map(|format| get_snapshot_tar_path(&snapshot_output_dir, *format))
+        .find(|path| path.is_file())
+}
+
+pub fn find_incremental_snapshot_tar_path<P: AsRef<Path>>(
+    snapshot_output_dir: P,
+) -> Option<PathBuf> {
+    ArchiveFormat::all()
+        .iter()
+        .map(|format| get_incremental_snapshot_tar_path(&snapshot_output_dir, *format))
+        .find(|path| path.is_file())
+}
+
+/// Whether `file_name` names a full or incremental snapshot archive in any format
+pub fn is_snapshot_tar_file_name(file_name: &str) -> bool {
+    ArchiveFormat::all().iter().any(|format| {
+        Path::new(file_name) == get_snapshot_tar_path("", *format)
+            || Path::new(file_name) == get_incremental_snapshot_tar_path("", *format)
+    })
 }
 
 pub fn untar_snapshot_in<P: AsRef<Path>, Q: AsRef<Path>>(
     snapshot_tar: P,
     unpack_dir: Q,
 ) -> Result<()> {
-    let tar_bz2 = File::open(snapshot_tar)?;
-    let tar = BzDecoder::new(BufReader::new(tar_bz2));
-    let mut archive = Archive::new(tar);
+    let mut archive = open_snapshot_archive(snapshot_tar)?;
     archive.unpack(&unpack_dir)?;
     Ok(())
 }
 
+fn open_snapshot_archive<P: AsRef<Path>>(snapshot_tar: P) -> Result<Archive<Box<dyn Read>>> {
+    let archive_format = ArchiveFormat::from_path(&snapshot_tar).ok_or_else(|| {
+        get_io_error(&format!(
+            "Unknown snapshot archive format: {:?}",
+            snapshot_tar.as_ref()
+        ))
+    })?;
+    let file = BufReader::new(File::open(snapshot_tar)?);
+    let tar: Box<dyn Read> = match archive_format {
+        ArchiveFormat::TarBzip2 => Box::new(BzDecoder::new(file)),
+        ArchiveFormat::TarGzip => Box::new(GzDecoder::new(file)),
+        ArchiveFormat::TarZstd => Box::new(zstd::stream::read::Decoder::new(file)?),
+        ArchiveFormat::Tar => Box::new(file),
+    };
+    Ok(Archive::new(tar))
+}
+
 fn rebuild_bank_from_snapshots<P>(
     local_account_paths: String,
     snapshot_paths: &[SlotSnapshotPaths],
@@ -264,6 +566,13 @@ where
     // Rebuild accounts
     bank.rc
         .accounts_from_stream(&mut stream, local_account_paths, append_vecs_path)?;
+    if !bank.verify_accounts_hash() {
+        return Err(get_io_error(&format!(
+            "Snapshot accounts hash mismatch for bank {}, expected {}",
+            bank.slot(),
+            bank.accounts_hash()
+        )));
+    }
 
     // merge the status caches from all previous banks
     for slot_paths in snapshot_paths.iter().rev() {
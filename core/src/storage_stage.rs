@@ -744,6 +744,7 @@ mod tests {
                     &bank,
                     &entry::create_ticks(64, bank.last_blockhash()),
                     true,
+                    None,
                 )
                 .expect("failed process entries");
                 last_bank = Arc::new(bank);
@@ -865,6 +866,7 @@ mod tests {
                 bank.last_blockhash(),
             ),
             true,
+            None,
         )
         .unwrap();
         let message = Message::new_with_payer(vec![mining_proof_ix], Some(&mint_keypair.pubkey()));
@@ -3,6 +3,7 @@
 
 use crate::banking_stage::BankingStage;
 use crate::blocktree::Blocktree;
+use crate::blocktree_processor::TransactionStatusSender;
 use crate::broadcast_stage::{BroadcastStage, BroadcastStageType};
 use crate::cluster_info::ClusterInfo;
 use crate::cluster_info_vote_listener::ClusterInfoVoteListener;
@@ -37,6 +38,7 @@ impl Tpu {
         sigverify_disabled: bool,
         blocktree: &Arc<Blocktree>,
         broadcast_type: &BroadcastStageType,
+        transaction_status_sender: Option<TransactionStatusSender>,
         exit: &Arc<AtomicBool>,
     ) -> Self {
         let (packet_sender, packet_receiver) = channel();
@@ -66,6 +68,7 @@ impl Tpu {
             poh_recorder,
             verified_receiver,
             verified_vote_receiver,
+            transaction_status_sender,
         );
 
         let broadcast_stage = broadcast_type.new_broadcast_stage(
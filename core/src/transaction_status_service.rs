@@ -0,0 +1,113 @@
+//! The `transaction_status_service` persists the statuses of replayed transactions to the blocktree
+
+use crate::blocktree::Blocktree;
+use crate::blocktree_processor::TransactionStatusBatch;
+use crate::result::{Error, Result};
+use crate::service::Service;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::sync::Arc;
+use std::thread::{self, Builder, JoinHandle};
+use std::time::Duration;
+
+pub struct TransactionStatusService {
+    t_transaction_status: JoinHandle<()>,
+}
+
+impl TransactionStatusService {
+    pub fn new(
+        transaction_status_receiver: Receiver<TransactionStatusBatch>,
+        blocktree: Arc<Blocktree>,
+        exit: &Arc<AtomicBool>,
+    ) -> Self {
+        let exit = exit.clone();
+        let t_transaction_status = Builder::new()
+            .name("solana-transaction-status-writer".to_string())
+            .spawn(move || loop {
+                if exit.load(Ordering::Relaxed) {
+                    break;
+                }
+                if let Err(e) =
+                    Self::write_transaction_status_batch(&transaction_status_receiver, &blocktree)
+                {
+                    match e {
+                        Error::RecvTimeoutError(RecvTimeoutError::Disconnected) => break,
+                        Error::RecvTimeoutError(RecvTimeoutError::Timeout) => (),
+                        _ => info!("Error from write_transaction_status_batch: {:?}", e),
+                    }
+                }
+            })
+            .unwrap();
+        Self {
+            t_transaction_status,
+        }
+    }
+
+    fn write_transaction_status_batch(
+        transaction_status_receiver: &Receiver<TransactionStatusBatch>,
+        blocktree: &Arc<Blocktree>,
+    ) -> Result<()> {
+        let TransactionStatusBatch { slot, statuses } =
+            transaction_status_receiver.recv_timeout(Duration::from_secs(1))?;
+        for (signature, status) in statuses {
+            blocktree.write_transaction_status((slot, signature), &status)?;
+        }
+        Ok(())
+    }
+}
+
+impl Service for TransactionStatusService {
+    type JoinReturnType = ();
+
+    fn join(self) -> thread::Result<()> {
+        self.t_transaction_status.join()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blocktree::get_tmp_ledger_path;
+    use solana_client::rpc_request::RpcTransactionStatus;
+    use solana_sdk::signature::Signature;
+    use solana_sdk::transaction::TransactionError;
+    use std::sync::mpsc::channel;
+
+    #[test]
+    fn test_write_transaction_status_batch() {
+        let blocktree_path = get_tmp_ledger_path!();
+        let blocktree = Arc::new(Blocktree::open(&blocktree_path).unwrap());
+        let (sender, receiver) = channel();
+
+        let signature = Signature::new(&[1; 64]);
+        let status = RpcTransactionStatus {
+            status: Err(TransactionError::AccountInUse),
+            fee: 42,
+            pre_balances: vec![1, 2, 3],
+            post_balances: vec![2, 3, 4],
+            log_messages: vec!["Program log".to_string()],
+            compute_units_consumed: 1_000,
+        };
+        sender
+            .send(TransactionStatusBatch {
+                slot: 5,
+                statuses: vec![(signature, status.clone())],
+            })
+            .unwrap();
+        TransactionStatusService::write_transaction_status_batch(&receiver, &blocktree).unwrap();
+
+        assert_eq!(
+            blocktree.transaction_status((5, signature)).unwrap(),
+            Some(status)
+        );
+        assert_eq!(
+            blocktree
+                .transaction_status((5, Signature::default()))
+                .unwrap(),
+            None
+        );
+
+        drop(blocktree);
+        Blocktree::destroy(&blocktree_path).expect("Expected successful database destruction");
+    }
+}
@@ -12,10 +12,12 @@
 //! 4. StorageStage
 //! - Generating the keys used to encrypt the ledger and sample it for storage mining.
 
+use crate::accounts_background_service::AccountsBackgroundService;
 use crate::bank_forks::BankForks;
 use crate::blob_fetch_stage::BlobFetchStage;
 use crate::blockstream_service::BlockstreamService;
 use crate::blocktree::{Blocktree, CompletedSlotsReceiver};
+use crate::blocktree_processor::TransactionStatusSender;
 use crate::cluster_info::ClusterInfo;
 use crate::confidence::ForkConfidenceCache;
 use crate::leader_schedule_cache::LeaderScheduleCache;
@@ -44,6 +46,7 @@ pub struct Tvu {
     ledger_cleanup_service: Option<LedgerCleanupService>,
     storage_stage: StorageStage,
     snapshot_packager_service: Option<SnapshotPackagerService>,
+    accounts_background_service: AccountsBackgroundService,
 }
 
 pub struct Sockets {
@@ -79,6 +82,7 @@ impl Tvu {
         exit: &Arc<AtomicBool>,
         completed_slots_receiver: CompletedSlotsReceiver,
         fork_confidence_cache: Arc<RwLock<ForkConfidenceCache>>,
+        transaction_status_sender: Option<TransactionStatusSender>,
     ) -> Self
     where
         T: 'static + KeypairUtil + Sync + Send,
@@ -156,6 +160,7 @@ impl Tvu {
             vec![blockstream_slot_sender, ledger_cleanup_slot_sender],
             snapshot_package_sender,
             fork_confidence_cache,
+            transaction_status_sender,
         );
 
         let blockstream_service = if blockstream_unix_socket.is_some() {
@@ -179,6 +184,8 @@ impl Tvu {
             )
         });
 
+        let accounts_background_service = AccountsBackgroundService::new(bank_forks.clone(), &exit);
+
         let storage_stage = StorageStage::new(
             storage_state,
             root_bank_receiver,
@@ -198,6 +205,7 @@ impl Tvu {
             ledger_cleanup_service,
             storage_stage,
             snapshot_packager_service,
+            accounts_background_service,
         }
     }
 }
@@ -219,6 +227,7 @@ impl Service for Tvu {
         if let Some(s) = self.snapshot_packager_service {
             s.join()?;
         }
+        self.accounts_background_service.join()?;
         Ok(())
     }
 }
@@ -287,6 +296,7 @@ pub mod tests {
             &exit,
             completed_slots_receiver,
             fork_confidence_cache,
+            None,
         );
         exit.store(true, Ordering::Relaxed);
         tvu.join().unwrap();
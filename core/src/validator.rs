@@ -2,7 +2,7 @@
 
 use crate::bank_forks::{BankForks, SnapshotConfig};
 use crate::blocktree::{Blocktree, CompletedSlotsReceiver};
-use crate::blocktree_processor::{self, BankForksInfo};
+use crate::blocktree_processor::{self, BankForksInfo, TransactionStatusSender};
 use crate::broadcast_stage::BroadcastStageType;
 use crate::cluster_info::{ClusterInfo, Node};
 use crate::confidence::ForkConfidenceCache;
@@ -19,6 +19,7 @@ use crate::service::Service;
 use crate::snapshot_utils;
 use crate::storage_stage::StorageState;
 use crate::tpu::Tpu;
+use crate::transaction_status_service::TransactionStatusService;
 use crate::tvu::{Sockets, Tvu};
 use solana_metrics::datapoint_info;
 use solana_sdk::clock::{Slot, DEFAULT_SLOTS_PER_TURN};
@@ -33,7 +34,7 @@ use std::fs;
 use std::net::{IpAddr, Ipv4Addr, SocketAddr};
 use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::mpsc::Receiver;
+use std::sync::mpsc::{channel, Receiver};
 use std::sync::{Arc, Mutex, RwLock};
 use std::thread::Result;
 
@@ -92,6 +93,7 @@ pub struct Validator {
     validator_exit: Arc<RwLock<Option<ValidatorExit>>>,
     rpc_service: Option<JsonRpcService>,
     rpc_pubsub_service: Option<PubSubService>,
+    transaction_status_service: Option<TransactionStatusService>,
     gossip_service: GossipService,
     poh_recorder: Arc<Mutex<PohRecorder>>,
     poh_service: PohService,
@@ -137,6 +139,18 @@ impl Validator {
             node.sockets.retransmit.local_addr().unwrap()
         );
 
+        // Statuses are collected for RPC, starting with the slots replayed from the ledger below
+        let (transaction_status_sender, transaction_status_receiver) = if node.info.rpc.port() == 0
+        {
+            (None, None)
+        } else {
+            let (transaction_status_sender, transaction_status_receiver) = channel();
+            (
+                Some(transaction_status_sender),
+                Some(transaction_status_receiver),
+            )
+        };
+
         info!("creating bank...");
         let (
             genesis_blockhash,
@@ -154,6 +168,7 @@ impl Validator {
             config.snapshot_config.clone(),
             verify_ledger,
             config.dev_halt_at_slot,
+            transaction_status_sender.as_ref(),
         );
 
         let leader_schedule_cache = Arc::new(leader_schedule_cache);
@@ -179,19 +194,31 @@ impl Validator {
             bank.slots_per_segment(),
         );
 
-        let rpc_service = if node.info.rpc.port() == 0 {
-            None
+        let blocktree = Arc::new(blocktree);
+
+        let (rpc_service, transaction_status_service) = if let Some(transaction_status_receiver) =
+            transaction_status_receiver
+        {
+            (
+                Some(JsonRpcService::new(
+                    &cluster_info,
+                    SocketAddr::new(IpAddr::V4(Ipv4Addr::new(0, 0, 0, 0)), node.info.rpc.port()),
+                    storage_state.clone(),
+                    config.rpc_config.clone(),
+                    bank_forks.clone(),
+                    blocktree.clone(),
+                    ledger_path,
+                    genesis_blockhash,
+                    &validator_exit,
+                )),
+                Some(TransactionStatusService::new(
+                    transaction_status_receiver,
+                    blocktree.clone(),
+                    &exit,
+                )),
+            )
         } else {
-            Some(JsonRpcService::new(
-                &cluster_info,
-                SocketAddr::new(IpAddr::V4(Ipv4Addr::new(0, 0, 0, 0)), node.info.rpc.port()),
-                storage_state.clone(),
-                config.rpc_config.clone(),
-                bank_forks.clone(),
-                ledger_path,
-                genesis_blockhash,
-                &validator_exit,
-            ))
+            (None, None)
         };
 
         let subscriptions = Arc::new(RpcSubscriptions::default());
@@ -223,8 +250,6 @@ impl Validator {
             std::thread::park();
         }
 
-        let blocktree = Arc::new(blocktree);
-
         let poh_config = Arc::new(poh_config);
         let (mut poh_recorder, entry_receiver) = PohRecorder::new_with_clear_signal(
             bank.tick_height(),
@@ -320,6 +345,7 @@ impl Validator {
             &exit,
             completed_slots_receiver,
             fork_confidence_cache,
+            transaction_status_sender.clone(),
         );
 
         if config.dev_sigverify_disabled {
@@ -336,6 +362,7 @@ impl Validator {
             config.dev_sigverify_disabled,
             &blocktree,
             &config.broadcast_stage_type,
+            transaction_status_sender,
             &exit,
         );
 
@@ -345,6 +372,7 @@ impl Validator {
             gossip_service,
             rpc_service,
             rpc_pubsub_service,
+            transaction_status_service,
             tpu,
             tvu,
             poh_service,
@@ -374,6 +402,7 @@ fn get_bank_forks(
     snapshot_config: Option<&SnapshotConfig>,
     verify_ledger: bool,
     dev_halt_at_slot: Option<Slot>,
+    transaction_status_sender: Option<&TransactionStatusSender>,
 ) -> (BankForks, Vec<BankForksInfo>, LeaderScheduleCache) {
     if let Some(snapshot_config) = snapshot_config.as_ref() {
         info!(
@@ -384,17 +413,30 @@ fn get_bank_forks(
         fs::create_dir_all(&snapshot_config.snapshot_path)
             .expect("Couldn't create snapshot directory");
 
-        let tar =
-            snapshot_utils::get_snapshot_tar_path(&snapshot_config.snapshot_package_output_path);
-        if tar.exists() {
+        if let Some(tar) =
+            snapshot_utils::find_snapshot_tar_path(&snapshot_config.snapshot_package_output_path)
+        {
             info!("Loading snapshot package: {:?}", tar);
+            // An incremental snapshot taken relative to another full snapshot is ignored, the
+            // full snapshot is loaded on its own
+            let incremental_tar = snapshot_utils::find_matching_incremental_snapshot_tar_path(
+                &snapshot_config.snapshot_package_output_path,
+                &tar,
+            );
+            if let Some(incremental_tar) = &incremental_tar {
+                info!(
+                    "Loading incremental snapshot package: {:?}",
+                    incremental_tar
+                );
+            }
             // Fail hard here if snapshot fails to load, don't silently continue
-            let deserialized_bank = snapshot_utils::bank_from_archive(
+            let deserialized_bank = snapshot_utils::bank_from_archives(
                 account_paths
                     .clone()
                     .expect("Account paths not present when booting from snapshot"),
                 snapshot_config,
-                &tar,
+                tar,
+                incremental_tar,
             )
             .expect("Load from snapshot failed");
 
@@ -403,10 +445,14 @@ fn get_bank_forks(
                 Arc::new(deserialized_bank),
                 verify_ledger,
                 dev_halt_at_slot,
+                transaction_status_sender,
             )
             .expect("processing blocktree after loading snapshot failed");
         } else {
-            info!("Snapshot package does not exist: {:?}", tar);
+            info!(
+                "Snapshot package does not exist in {:?}",
+                snapshot_config.snapshot_package_output_path
+            );
         }
     } else {
         info!("Snapshots disabled");
@@ -419,6 +465,7 @@ fn get_bank_forks(
         account_paths,
         verify_ledger,
         dev_halt_at_slot,
+        transaction_status_sender,
     )
     .expect("process_blocktree failed")
 }
@@ -469,6 +516,7 @@ pub fn new_banks_from_blocktree(
     snapshot_config: Option<SnapshotConfig>,
     verify_ledger: bool,
     dev_halt_at_slot: Option<Slot>,
+    transaction_status_sender: Option<&TransactionStatusSender>,
 ) -> (
     Hash,
     BankForks,
@@ -509,6 +557,7 @@ pub fn new_banks_from_blocktree(
         snapshot_config.as_ref(),
         verify_ledger,
         dev_halt_at_slot,
+        transaction_status_sender,
     );
 
     if snapshot_config.is_some() {
@@ -539,6 +588,9 @@ impl Service for Validator {
         if let Some(rpc_pubsub_service) = self.rpc_pubsub_service {
             rpc_pubsub_service.join()?;
         }
+        if let Some(transaction_status_service) = self.transaction_status_service {
+            transaction_status_service.join()?;
+        }
 
         self.gossip_service.join()?;
         self.tpu.join()?;
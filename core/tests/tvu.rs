@@ -100,7 +100,7 @@ fn test_replay() {
         completed_slots_receiver,
         leader_schedule_cache,
         _,
-    ) = validator::new_banks_from_blocktree(None, &blocktree_path, None, None, true, None);
+    ) = validator::new_banks_from_blocktree(None, &blocktree_path, None, None, true, None, None);
     let working_bank = bank_forks.working_bank();
     assert_eq!(
         working_bank.get_balance(&mint_keypair.pubkey()),
@@ -147,6 +147,7 @@ fn test_replay() {
             &exit,
             completed_slots_receiver,
             fork_confidence_cache,
+            None,
         );
 
         let mut mint_ref_balance = mint_balance;
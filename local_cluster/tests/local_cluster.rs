@@ -3,8 +3,13 @@ extern crate solana_core;
 use log::*;
 use serial_test_derive::serial;
 use solana_core::{
-    bank_forks::SnapshotConfig, blocktree::Blocktree, broadcast_stage::BroadcastStageType,
-    cluster::Cluster, gossip_service::discover_cluster, snapshot_utils, validator::ValidatorConfig,
+    bank_forks::SnapshotConfig,
+    blocktree::Blocktree,
+    broadcast_stage::BroadcastStageType,
+    cluster::Cluster,
+    gossip_service::discover_cluster,
+    snapshot_utils::{self, ArchiveFormat},
+    validator::ValidatorConfig,
 };
 use solana_local_cluster::{
     cluster_tests,
@@ -332,7 +337,10 @@ fn test_snapshots_blocktree_floor() {
 
     trace!("Waiting for snapshot tar to be generated with slot",);
 
-    let tar = snapshot_utils::get_snapshot_tar_path(&snapshot_package_output_path);
+    let tar = snapshot_utils::get_snapshot_tar_path(
+        &snapshot_package_output_path,
+        ArchiveFormat::TarBzip2,
+    );
     loop {
         if tar.exists() {
             trace!("snapshot tar exists");
@@ -342,8 +350,10 @@ fn test_snapshots_blocktree_floor() {
     }
 
     // Copy tar to validator's snapshot output directory
-    let validator_tar_path =
-        snapshot_utils::get_snapshot_tar_path(&validator_snapshot_test_config.snapshot_output_path);
+    let validator_tar_path = snapshot_utils::get_snapshot_tar_path(
+        &validator_snapshot_test_config.snapshot_output_path,
+        ArchiveFormat::TarBzip2,
+    );
     fs::hard_link(tar, &validator_tar_path).unwrap();
     let slot_floor = snapshot_utils::bank_slot_from_archive(&validator_tar_path).unwrap();
 
@@ -440,7 +450,10 @@ fn test_snapshots_restart_validity() {
 
         // Wait for a snapshot for a bank >= last_slot to be made so we know that the snapshot
         // must include the transactions just pushed
-        let tar = snapshot_utils::get_snapshot_tar_path(&snapshot_package_output_path);
+        let tar = snapshot_utils::get_snapshot_tar_path(
+            &snapshot_package_output_path,
+            ArchiveFormat::TarBzip2,
+        );
         trace!(
             "Waiting for snapshot tar to be generated with slot > {}",
             last_slot
@@ -676,8 +689,11 @@ fn setup_snapshot_validator_config(
     let snapshot_output_path = TempDir::new().unwrap();
     let snapshot_config = SnapshotConfig {
         snapshot_interval_slots,
+        incremental_snapshot_interval_slots: std::usize::MAX,
         snapshot_package_output_path: PathBuf::from(snapshot_output_path.path()),
         snapshot_path: PathBuf::from(snapshot_dir.path()),
+        archive_format: ArchiveFormat::TarBzip2,
+        expected_accounts_hash: None,
     };
 
     // Create the account paths
@@ -3,13 +3,17 @@
 extern crate test;
 
 use byteorder::{ByteOrder, LittleEndian, WriteBytesExt};
+use solana_bpf_loader_api::helpers::LogMessages;
 use solana_rbpf::EbpfVm;
+use solana_sdk::instruction_processor_utils::{ComputeBudget, ComputeMeter};
+use std::cell::RefCell;
 use std::env;
 use std::fs::File;
 use std::io::Error;
 use std::io::Read;
 use std::mem;
 use std::path::PathBuf;
+use std::rc::Rc;
 use test::Bencher;
 
 /// BPF program file extension
@@ -76,7 +80,14 @@ fn bench_program_alu(bencher: &mut Bencher) {
     inner_iter.write_u64::<LittleEndian>(0).unwrap();
 
     let elf = load_elf().unwrap();
-    let (mut vm, _) = solana_bpf_loader_api::create_vm(&elf).unwrap();
+    let compute_meter = Rc::new(RefCell::new(ComputeMeter::new(std::u64::MAX)));
+    let (mut vm, _) = solana_bpf_loader_api::create_vm(
+        &elf,
+        &LogMessages::default(),
+        &compute_meter,
+        &ComputeBudget::default(),
+    )
+    .unwrap();
 
     println!("Interpreted:");
     assert_eq!(
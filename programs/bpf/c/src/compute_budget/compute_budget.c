@@ -0,0 +1,15 @@
+/**
+ * @brief test program that spends compute units, by logging and then
+ * looping, and fails with the number of accounts passed to it, less one
+ */
+
+#include <solana_sdk.h>
+
+extern uint32_t entrypoint(const uint8_t *input) {
+  uint64_t ka_num = *(uint64_t *) input;
+
+  sol_log_64(1, 2, 3, 4, 5);
+  for (volatile uint64_t i = 0; i < 50; i++) {
+  }
+  return ka_num - 1;
+}
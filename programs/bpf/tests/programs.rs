@@ -29,8 +29,13 @@ mod bpf {
         use solana_runtime::loader_utils::create_invoke_instruction;
         use solana_sdk::bpf_loader;
         use solana_sdk::client::SyncClient;
-        use solana_sdk::signature::KeypairUtil;
+        use solana_sdk::instruction::{AccountMeta, Instruction, InstructionError};
+        use solana_sdk::instruction_processor_utils::ComputeBudget;
+        use solana_sdk::message::Message;
+        use solana_sdk::signature::{Keypair, KeypairUtil};
+        use solana_sdk::transaction::{Transaction, TransactionError};
         use std::io::Read;
+        use std::sync::Arc;
 
         #[test]
         fn test_program_bpf_c() {
@@ -72,6 +77,87 @@ mod bpf {
                 }
             }
         }
+
+        #[test]
+        fn test_program_bpf_c_compute_budget() {
+            solana_logger::setup();
+
+            let mut file = File::open(create_bpf_path("compute_budget")).expect("file open failed");
+            let mut elf = Vec::new();
+            file.read_to_end(&mut elf).unwrap();
+
+            // Simulate the program under `compute_budget`, passing it `num_accounts` accounts,
+            // which it fails with, less one
+            let simulate = |compute_budget: ComputeBudget, num_accounts: usize| {
+                let GenesisBlockInfo {
+                    genesis_block,
+                    mint_keypair,
+                    ..
+                } = create_genesis_block(50);
+                let mut bank = Bank::new(&genesis_block);
+                bank.set_compute_budget(compute_budget);
+                let bank = Arc::new(bank);
+                let bank_client = BankClient::new_shared(&bank);
+                let program_id =
+                    load_program(&bank_client, &mint_keypair, &bpf_loader::id(), elf.clone());
+                bank.freeze();
+
+                let account_metas = (0..num_accounts)
+                    .map(|_| AccountMeta::new(Keypair::new().pubkey(), false))
+                    .collect();
+                let message = Message::new_with_payer(
+                    vec![Instruction::new(program_id, &1u8, account_metas)],
+                    Some(&mint_keypair.pubkey()),
+                );
+                let tx = Transaction::new(&[&mint_keypair], message, bank.last_blockhash());
+                let (result, _, _, compute_units_consumed) = bank.simulate_transaction(&tx);
+                (result, compute_units_consumed)
+            };
+            let exceeded = Err(TransactionError::InstructionError(
+                0,
+                InstructionError::ComputationalBudgetExceeded,
+            ));
+
+            let compute_budget = ComputeBudget::default();
+            let (result, consumed) = simulate(compute_budget, 1);
+            assert_eq!(result, Ok(()));
+            // The program is charged for its logging as well as its instructions
+            assert!(consumed > compute_budget.log_64_units);
+
+            let (result, failed_consumed) = simulate(compute_budget, 2);
+            assert_eq!(
+                result,
+                Err(TransactionError::InstructionError(
+                    0,
+                    InstructionError::CustomError(1)
+                ))
+            );
+            assert_eq!(failed_consumed, consumed);
+
+            // Enough for the program's instructions, but not its logging as well
+            let compute_budget = ComputeBudget {
+                max_units: consumed - 1,
+                ..ComputeBudget::default()
+            };
+            assert_eq!(
+                simulate(compute_budget, 1),
+                (exceeded.clone(), consumed - 1)
+            );
+            // Running out is reported in place of the error the program returned
+            assert_eq!(
+                simulate(compute_budget, 2),
+                (exceeded.clone(), consumed - 1)
+            );
+
+            // Not enough for the program's instructions, which halts the program once they run
+            // out
+            let compute_budget = ComputeBudget {
+                max_units: 10,
+                log_64_units: 0,
+                ..ComputeBudget::default()
+            };
+            assert_eq!(simulate(compute_budget, 1), (exceeded, 10));
+        }
     }
 
     #[cfg(feature = "bpf_rust")]
@@ -1,17 +1,24 @@
 use crate::alloc;
 use alloc::Alloc;
+use byteorder::{ByteOrder, LittleEndian};
 use libc::c_char;
 use log::*;
 use solana_rbpf::{
-    ebpf::{HelperContext, MM_HEAP_START},
+    ebpf::{HelperContext, MM_HEAP_START, MM_INPUT_START},
     memory_region::{translate_addr, MemoryRegion},
     EbpfVm,
 };
+use solana_sdk::account::{Account, KeyedAccount};
+use solana_sdk::instruction::{Instruction, InstructionError};
+use solana_sdk::instruction_processor_utils::{ComputeBudget, ComputeMeter, InvokeContext};
+use solana_sdk::pubkey::Pubkey;
 use std::alloc::Layout;
+use std::cell::RefCell;
 use std::ffi::CStr;
 use std::io::{Error, ErrorKind};
 use std::mem;
-use std::slice::from_raw_parts;
+use std::rc::Rc;
+use std::slice::{from_raw_parts, from_raw_parts_mut};
 use std::str::from_utf8;
 
 /// Program heap allocators are intended to allocate/free from a given
@@ -26,23 +33,95 @@ use crate::allocator_bump::BPFAllocator;
 /// are expected to enforce this
 const DEFAULT_HEAP_SIZE: usize = 32 * 1024;
 
-pub fn register_helpers(vm: &mut EbpfVm) -> Result<(MemoryRegion), Error> {
+/// Messages logged by a program via `sol_log` and `sol_log_64`, shared between the
+/// logging helpers and the loader that forwards them once the program has run
+pub type LogMessages = Rc<RefCell<Vec<String>>>;
+
+/// The error a program invoked via `sol_invoke` failed with, which the loader reports in place
+/// of the VM error once the calling program has been halted
+pub type InvokeError = Rc<RefCell<Option<InstructionError>>>;
+
+/// Context of the logging helpers
+struct LogContext {
+    log_messages: LogMessages,
+    compute_meter: Rc<RefCell<ComputeMeter>>,
+    units: u64,
+    instruction_units: u64,
+}
+
+/// Context of the `sol_alloc_free` helper
+struct AllocContext {
+    allocator: BPFAllocator,
+    compute_meter: Rc<RefCell<ComputeMeter>>,
+    units: u64,
+    instruction_units: u64,
+}
+
+/// Charge a helper call against the transaction's compute budget.  The program's own
+/// instructions are only charged once it exits, so it is halted as soon as the budget can no
+/// longer pay for the instruction following the call, rather than running on to its
+/// instruction limit
+fn consume_compute_units(
+    compute_meter: &RefCell<ComputeMeter>,
+    units: u64,
+    instruction_units: u64,
+) -> Result<(), Error> {
+    let mut compute_meter = compute_meter.borrow_mut();
+    match compute_meter.consume(units) {
+        Ok(()) if compute_meter.get_remaining() >= instruction_units => Ok(()),
+        _ => Err(Error::new(
+            ErrorKind::Other,
+            "Error: Exceeded the transaction's compute budget",
+        )),
+    }
+}
+
+pub fn register_helpers(
+    vm: &mut EbpfVm,
+    log_messages: &LogMessages,
+    compute_meter: &Rc<RefCell<ComputeMeter>>,
+    compute_budget: &ComputeBudget,
+) -> Result<(MemoryRegion), Error> {
     vm.register_helper_ex("abort", helper_abort, None)?;
     vm.register_helper_ex("sol_panic", helper_sol_panic, None)?;
     vm.register_helper_ex("sol_panic_", helper_sol_panic, None)?;
-    vm.register_helper_ex("sol_log", helper_sol_log, None)?;
-    vm.register_helper_ex("sol_log_", helper_sol_log, None)?;
-    vm.register_helper_ex("sol_log_64", helper_sol_log_u64, None)?;
-    vm.register_helper_ex("sol_log_64_", helper_sol_log_u64, None)?;
+    for name in &["sol_log", "sol_log_"] {
+        let context = Box::new(LogContext {
+            log_messages: log_messages.clone(),
+            compute_meter: compute_meter.clone(),
+            units: compute_budget.log_units,
+            instruction_units: compute_budget.instruction_units,
+        });
+        vm.register_helper_ex(name, helper_sol_log, Some(context))?;
+    }
+    for name in &["sol_log_64", "sol_log_64_"] {
+        let context = Box::new(LogContext {
+            log_messages: log_messages.clone(),
+            compute_meter: compute_meter.clone(),
+            units: compute_budget.log_64_units,
+            instruction_units: compute_budget.instruction_units,
+        });
+        vm.register_helper_ex(name, helper_sol_log_u64, Some(context))?;
+    }
 
     let heap = vec![0_u8; DEFAULT_HEAP_SIZE];
     let heap_region = MemoryRegion::new_from_slice(&heap, MM_HEAP_START);
-    let context = Box::new(BPFAllocator::new(heap, MM_HEAP_START));
+    let context = Box::new(AllocContext {
+        allocator: BPFAllocator::new(heap, MM_HEAP_START),
+        compute_meter: compute_meter.clone(),
+        units: compute_budget.alloc_free_units,
+        instruction_units: compute_budget.instruction_units,
+    });
     vm.register_helper_ex("sol_alloc_free_", helper_sol_alloc_free, Some(context))?;
 
     Ok(heap_region)
 }
 
+/// Register `sol_invoke`, which lets the program being run invoke other programs
+pub fn register_invoke_helper(vm: &mut EbpfVm, invoker: Invoker) -> Result<(), Error> {
+    vm.register_helper_ex("sol_invoke_", helper_sol_invoke, Some(Box::new(invoker)))
+}
+
 /// Abort helper functions, called when the BPF program calls `abort()`
 /// The verify function returns an error which will cause the BPF program
 /// to be halted immediately
@@ -91,16 +170,35 @@ pub fn helper_sol_panic(
     Err(Error::new(ErrorKind::Other, "Error: BPF program Panicked"))
 }
 
+/// Get the logging helpers' `LogContext`, in which the program's messages are recorded
+fn get_log_context(context: &mut HelperContext) -> Result<&mut LogContext, Error> {
+    if let Some(context) = context {
+        if let Some(log_context) = context.downcast_mut::<LogContext>() {
+            return Ok(log_context);
+        }
+    }
+    Err(Error::new(
+        ErrorKind::Other,
+        "Error: Failed to get log context",
+    ))
+}
+
 pub fn helper_sol_log(
     addr: u64,
     len: u64,
     _arg3: u64,
     _arg4: u64,
     _arg5: u64,
-    _context: &mut HelperContext,
+    context: &mut HelperContext,
     ro_regions: &[MemoryRegion],
     _rw_regions: &[MemoryRegion],
 ) -> Result<(u64), Error> {
+    let log_context = get_log_context(context)?;
+    consume_compute_units(
+        &log_context.compute_meter,
+        log_context.units,
+        log_context.instruction_units,
+    )?;
     let host_addr = translate_addr(addr, len as usize, "Load", 0, ro_regions)?;
     let c_buf: *const c_char = host_addr as *const c_char;
     unsafe {
@@ -110,6 +208,10 @@ pub fn helper_sol_log(
                 let message =
                     from_utf8(from_raw_parts(host_addr as *const u8, len as usize)).unwrap();
                 println!("info!: {}", message);
+                log_context
+                    .log_messages
+                    .borrow_mut()
+                    .push(message.to_string());
                 return Ok(0);
             }
         }
@@ -126,14 +228,22 @@ pub fn helper_sol_log_u64(
     arg3: u64,
     arg4: u64,
     arg5: u64,
-    _context: &mut HelperContext,
+    context: &mut HelperContext,
     _ro_regions: &[MemoryRegion],
     _rw_regions: &[MemoryRegion],
 ) -> Result<(u64), Error> {
-    info!(
-        "info!: {:#x}, {:#x}, {:#x}, {:#x}, {:#x}",
+    let log_context = get_log_context(context)?;
+    consume_compute_units(
+        &log_context.compute_meter,
+        log_context.units,
+        log_context.instruction_units,
+    )?;
+    let message = format!(
+        "{:#x}, {:#x}, {:#x}, {:#x}, {:#x}",
         arg1, arg2, arg3, arg4, arg5
     );
+    info!("info!: {}", message);
+    log_context.log_messages.borrow_mut().push(message);
     Ok(0)
 }
 
@@ -154,7 +264,13 @@ pub fn helper_sol_alloc_free(
     _rw_regions: &[MemoryRegion],
 ) -> Result<(u64), Error> {
     if let Some(context) = context {
-        if let Some(allocator) = context.downcast_mut::<BPFAllocator>() {
+        if let Some(alloc_context) = context.downcast_mut::<AllocContext>() {
+            consume_compute_units(
+                &alloc_context.compute_meter,
+                alloc_context.units,
+                alloc_context.instruction_units,
+            )?;
+            let allocator = &mut alloc_context.allocator;
             return {
                 let layout = Layout::from_size_align(size as usize, mem::align_of::<u8>()).unwrap();
                 if free_addr == 0 {
@@ -169,5 +285,404 @@ pub fn helper_sol_alloc_free(
             };
         };
     }
-    panic!("Failed to get alloc_free context");
+    Err(Error::new(
+        ErrorKind::Other,
+        "Error: Failed to get alloc_free context",
+    ))
+}
+
+/// What `sol_invoke` needs to know about each of the calling program's accounts that the
+/// program cannot change in its input buffer
+struct CallerAccount {
+    key: Pubkey,
+    is_signer: bool,
+    is_debitable: bool,
+    executable: bool,
+    rent_epoch: u64,
+    data_len: usize,
+}
+
+/// Context of the `sol_invoke` helper, through which the running program invokes other programs
+pub struct Invoker {
+    /// Only valid while the program runs, see `Invoker::new`
+    invoke_context: *const dyn InvokeContext,
+    program_id: Pubkey,
+    accounts: Vec<CallerAccount>,
+    input_len: usize,
+    log_messages: LogMessages,
+    invoke_error: InvokeError,
+}
+
+impl Invoker {
+    /// Create the context for a program whose input buffer of `input_len` bytes holds
+    /// `keyed_accounts`
+    ///
+    /// # Safety
+    /// `invoke_context` is kept beyond its lifetime, so the VM `sol_invoke` is registered
+    /// with must be dropped before `invoke_context` is
+    pub unsafe fn new(
+        invoke_context: &dyn InvokeContext,
+        program_id: &Pubkey,
+        keyed_accounts: &[KeyedAccount],
+        input_len: usize,
+        log_messages: &LogMessages,
+        invoke_error: &InvokeError,
+    ) -> Self {
+        let invoke_context: &'static dyn InvokeContext = mem::transmute(invoke_context);
+        let accounts = keyed_accounts
+            .iter()
+            .map(|keyed_account| CallerAccount {
+                key: *keyed_account.unsigned_key(),
+                is_signer: keyed_account.signer_key().is_some(),
+                is_debitable: keyed_account.is_debitable(),
+                executable: keyed_account.account.executable,
+                rent_epoch: keyed_account.account.rent_epoch,
+                data_len: keyed_account.account.data.len(),
+            })
+            .collect();
+        Self {
+            invoke_context,
+            program_id: *program_id,
+            accounts,
+            input_len,
+            log_messages: log_messages.clone(),
+            invoke_error: invoke_error.clone(),
+        }
+    }
+
+    /// Invoke `instruction` with the calling program's accounts named in `account_keys`, as
+    /// they currently are in its `input` buffer, and write the invoked program's changes back
+    /// into `input`
+    fn invoke(
+        &self,
+        instruction: &Instruction,
+        account_keys: &[Pubkey],
+        input: &mut [u8],
+    ) -> Result<(), InstructionError> {
+        // Offset of each account's lamports in the input buffer, as laid out by
+        // `serialize_parameters`
+        let mut offsets = Vec::with_capacity(self.accounts.len());
+        let mut start = mem::size_of::<u64>(); // skip number of accounts
+        for caller_account in &self.accounts {
+            start += mem::size_of::<u64>() // skip signer_key boolean
+                + mem::size_of::<Pubkey>(); // skip pubkey
+            offsets.push(start);
+            start += mem::size_of::<u64>() // skip lamports
+                + mem::size_of::<u64>() // skip length tag
+                + caller_account.data_len // skip data
+                + mem::size_of::<Pubkey>(); // skip owner
+        }
+
+        let mut indexes = vec![];
+        let mut accounts = vec![];
+        for account_key in account_keys {
+            let index = self
+                .accounts
+                .iter()
+                .position(|caller_account| caller_account.key == *account_key)
+                .ok_or(InstructionError::InvalidArgument)?;
+            if indexes.contains(&index) {
+                return Err(InstructionError::DuplicateAccountIndex);
+            }
+            let caller_account = &self.accounts[index];
+            let start = offsets[index] + 2 * mem::size_of::<u64>();
+            let end = start + caller_account.data_len;
+            indexes.push(index);
+            accounts.push(Account {
+                lamports: LittleEndian::read_u64(&input[offsets[index]..]),
+                data: input[start..end].to_vec(),
+                owner: Pubkey::new(&input[end..end + mem::size_of::<Pubkey>()]),
+                executable: caller_account.executable,
+                rent_epoch: caller_account.rent_epoch,
+            });
+        }
+
+        let mut keyed_accounts: Vec<_> = indexes
+            .iter()
+            .zip(accounts.iter_mut())
+            .map(|(index, account)| {
+                let caller_account = &self.accounts[*index];
+                if caller_account.is_debitable {
+                    KeyedAccount::new(&caller_account.key, caller_account.is_signer, account)
+                } else {
+                    KeyedAccount::new_credit_only(
+                        &caller_account.key,
+                        caller_account.is_signer,
+                        account,
+                    )
+                }
+            })
+            .collect();
+        unsafe { &*self.invoke_context }.invoke(
+            &self.program_id,
+            instruction,
+            &mut keyed_accounts,
+        )?;
+
+        for (index, account) in indexes.iter().zip(accounts.iter()) {
+            let caller_account = &self.accounts[*index];
+            if account.data.len() != caller_account.data_len
+                || account.executable != caller_account.executable
+            {
+                warn!("sol_invoke: invoked program resized account data or changed executable");
+                return Err(InstructionError::InvalidAccountData);
+            }
+            let start = offsets[*index] + 2 * mem::size_of::<u64>();
+            let end = start + caller_account.data_len;
+            LittleEndian::write_u64(&mut input[offsets[*index]..], account.lamports);
+            input[start..end].copy_from_slice(&account.data);
+            input[end..end + mem::size_of::<Pubkey>()].copy_from_slice(account.owner.as_ref());
+        }
+        Ok(())
+    }
+}
+
+/// Invoke helper function, called when the BPF program calls `sol_invoke_()` with a
+/// serialized `Instruction` and the keys of the accounts to pass the invoked program.
+/// Failure of the invoked program halts the BPF program immediately.  The invoked program is
+/// charged against the same compute budget as the BPF program, which is halted as soon as the
+/// invoked program leaves it too little to continue
+pub fn helper_sol_invoke(
+    instruction_addr: u64,
+    instruction_len: u64,
+    account_keys_addr: u64,
+    account_keys_len: u64,
+    _arg5: u64,
+    context: &mut HelperContext,
+    ro_regions: &[MemoryRegion],
+    rw_regions: &[MemoryRegion],
+) -> Result<(u64), Error> {
+    if let Some(context) = context {
+        if let Some(invoker) = context.downcast_mut::<Invoker>() {
+            let invoke_context = unsafe { &*invoker.invoke_context };
+            let compute_meter = invoke_context.get_compute_meter();
+            let compute_budget = invoke_context.get_compute_budget();
+            consume_compute_units(
+                &compute_meter,
+                compute_budget.invoke_units,
+                compute_budget.instruction_units,
+            )?;
+            let host_addr = translate_addr(
+                instruction_addr,
+                instruction_len as usize,
+                "Load",
+                0,
+                ro_regions,
+            )?;
+            let instruction: Instruction = bincode::deserialize(unsafe {
+                from_raw_parts(host_addr as *const u8, instruction_len as usize)
+            })
+            .map_err(|e| {
+                Error::new(
+                    ErrorKind::Other,
+                    format!("Error: Invalid instruction passed to sol_invoke: {}", e),
+                )
+            })?;
+            let account_keys_size = (account_keys_len as usize)
+                .checked_mul(mem::size_of::<Pubkey>())
+                .ok_or_else(|| {
+                    Error::new(
+                        ErrorKind::Other,
+                        "Error: Too many account keys passed to sol_invoke",
+                    )
+                })?;
+            let host_addr =
+                translate_addr(account_keys_addr, account_keys_size, "Load", 0, ro_regions)?;
+            let account_keys =
+                unsafe { from_raw_parts(host_addr as *const Pubkey, account_keys_len as usize) };
+            let host_addr =
+                translate_addr(MM_INPUT_START, invoker.input_len, "Store", 0, rw_regions)?;
+            let input = unsafe { from_raw_parts_mut(host_addr as *mut u8, invoker.input_len) };
+
+            // Keep what the program logged so far ahead of the invoked program's messages
+            for message in invoker.log_messages.borrow_mut().drain(..) {
+                invoke_context.log(&message);
+            }
+            return match invoker.invoke(&instruction, account_keys, input) {
+                Ok(()) => {
+                    consume_compute_units(&compute_meter, 0, compute_budget.instruction_units)?;
+                    Ok(0)
+                }
+                Err(err) => {
+                    let message = format!("Error: Invoked program failed: {:?}", err);
+                    *invoker.invoke_error.borrow_mut() = Some(err);
+                    Err(Error::new(ErrorKind::Other, message))
+                }
+            };
+        }
+    }
+    Err(Error::new(
+        ErrorKind::Other,
+        "Error: Failed to get invoke context",
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{deserialize_parameters, serialize_parameters};
+    use solana_sdk::instruction_processor_utils::Logger;
+
+    struct MockInvokeContext {
+        compute_meter: Rc<RefCell<ComputeMeter>>,
+    }
+
+    impl Logger for MockInvokeContext {
+        fn log(&self, _message: &str) {}
+    }
+
+    impl InvokeContext for MockInvokeContext {
+        fn invoke(
+            &self,
+            _caller_program_id: &Pubkey,
+            instruction: &Instruction,
+            keyed_accounts: &mut [KeyedAccount],
+        ) -> Result<(), InstructionError> {
+            keyed_accounts[0].account.lamports -= 1;
+            keyed_accounts[1].account.lamports += 1;
+            keyed_accounts[1].account.data[0] = 42;
+            keyed_accounts[1].account.owner = instruction.program_id;
+            Ok(())
+        }
+
+        fn get_compute_budget(&self) -> ComputeBudget {
+            ComputeBudget::default()
+        }
+
+        fn get_compute_meter(&self) -> Rc<RefCell<ComputeMeter>> {
+            self.compute_meter.clone()
+        }
+    }
+
+    #[test]
+    fn test_helpers_consume_compute_units() {
+        let log_messages = LogMessages::default();
+        let compute_meter = Rc::new(RefCell::new(ComputeMeter::new(150)));
+        let mut context: HelperContext = Some(Box::new(LogContext {
+            log_messages: log_messages.clone(),
+            compute_meter: compute_meter.clone(),
+            units: 100,
+            instruction_units: 10,
+        }));
+
+        assert!(helper_sol_log_u64(1, 2, 3, 4, 5, &mut context, &[], &[]).is_ok());
+        assert_eq!(compute_meter.borrow().get_remaining(), 50);
+        assert_eq!(log_messages.borrow().len(), 1);
+
+        // once the budget is exhausted the helper halts the program without doing its work
+        assert!(helper_sol_log_u64(1, 2, 3, 4, 5, &mut context, &[], &[]).is_err());
+        assert_eq!(compute_meter.borrow().get_remaining(), 0);
+        assert_eq!(log_messages.borrow().len(), 1);
+
+        // as it does once the budget can't pay for the program's next instruction
+        let compute_meter = Rc::new(RefCell::new(ComputeMeter::new(105)));
+        let mut context: HelperContext = Some(Box::new(LogContext {
+            log_messages: log_messages.clone(),
+            compute_meter: compute_meter.clone(),
+            units: 100,
+            instruction_units: 10,
+        }));
+        assert!(helper_sol_log_u64(1, 2, 3, 4, 5, &mut context, &[], &[]).is_err());
+        assert_eq!(compute_meter.borrow().get_remaining(), 5);
+    }
+
+    #[test]
+    fn test_invoker_writes_back_accounts() {
+        let program_id = Pubkey::new_rand();
+        let callee_program_id = Pubkey::new_rand();
+        let from_pubkey = Pubkey::new_rand();
+        let to_pubkey = Pubkey::new_rand();
+        let mut from_account = Account::new(10, 1, &program_id);
+        let mut to_account = Account::new(0, 1, &program_id);
+        let mut keyed_accounts = vec![
+            KeyedAccount::new(&from_pubkey, true, &mut from_account),
+            KeyedAccount::new(&to_pubkey, false, &mut to_account),
+        ];
+        let mut input = serialize_parameters(&program_id, &mut keyed_accounts, &[]);
+
+        let log_messages = LogMessages::default();
+        let invoke_error = InvokeError::default();
+        let invoke_context = MockInvokeContext {
+            compute_meter: Rc::new(RefCell::new(ComputeMeter::new(0))),
+        };
+        let invoker = unsafe {
+            Invoker::new(
+                &invoke_context,
+                &program_id,
+                &keyed_accounts,
+                input.len(),
+                &log_messages,
+                &invoke_error,
+            )
+        };
+        let instruction = Instruction::new(callee_program_id, &(), vec![]);
+
+        assert_eq!(
+            invoker.invoke(&instruction, &[from_pubkey, Pubkey::new_rand()], &mut input),
+            Err(InstructionError::InvalidArgument)
+        );
+        assert_eq!(
+            invoker.invoke(&instruction, &[from_pubkey, from_pubkey], &mut input),
+            Err(InstructionError::DuplicateAccountIndex)
+        );
+        assert_eq!(
+            invoker.invoke(&instruction, &[from_pubkey, to_pubkey], &mut input),
+            Ok(())
+        );
+
+        deserialize_parameters(&mut keyed_accounts, &input);
+        assert_eq!(keyed_accounts[0].account.lamports, 9);
+        assert_eq!(keyed_accounts[1].account.lamports, 1);
+        assert_eq!(keyed_accounts[1].account.data, vec![42]);
+        assert_eq!(keyed_accounts[1].account.owner, callee_program_id);
+    }
+
+    #[test]
+    fn test_helper_sol_invoke_bad_arguments() {
+        let program_id = Pubkey::new_rand();
+        let instruction = Instruction::new(Pubkey::new_rand(), &(), vec![]);
+        let instruction_data = bincode::serialize(&instruction).unwrap();
+        let ro_regions = [MemoryRegion::new_from_slice(&instruction_data, 0x1000)];
+
+        let mut context: HelperContext = None;
+        assert!(helper_sol_invoke(
+            0x1000,
+            instruction_data.len() as u64,
+            0,
+            0,
+            0,
+            &mut context,
+            &ro_regions,
+            &[],
+        )
+        .is_err());
+
+        let log_messages = LogMessages::default();
+        let invoke_error = InvokeError::default();
+        let invoke_context = MockInvokeContext {
+            compute_meter: Rc::new(RefCell::new(ComputeMeter::new(10_000))),
+        };
+        let invoker = unsafe {
+            Invoker::new(
+                &invoke_context,
+                &program_id,
+                &[],
+                0,
+                &log_messages,
+                &invoke_error,
+            )
+        };
+        let mut context: HelperContext = Some(Box::new(invoker));
+        assert!(helper_sol_invoke(
+            0x1000,
+            instruction_data.len() as u64,
+            0x2000,
+            u64::max_value(),
+            0,
+            &mut context,
+            &ro_regions,
+            &[],
+        )
+        .is_err());
+    }
 }
@@ -18,20 +18,42 @@ use log::*;
 use solana_rbpf::{memory_region::MemoryRegion, EbpfVm};
 use solana_sdk::account::KeyedAccount;
 use solana_sdk::instruction::InstructionError;
+use solana_sdk::instruction_processor_utils::{ComputeBudget, ComputeMeter, InvokeContext};
 use solana_sdk::loader_instruction::LoaderInstruction;
 use solana_sdk::pubkey::Pubkey;
+use std::cell::RefCell;
 use std::convert::TryFrom;
 use std::io::prelude::*;
-use std::io::Error;
+use std::io::{Error, ErrorKind};
 use std::mem;
+use std::rc::Rc;
 
-pub fn create_vm(prog: &[u8]) -> Result<(EbpfVm, MemoryRegion), Error> {
+/// Create a VM to run `prog`, limited to as many instructions as `compute_meter` has units for
+pub fn create_vm<'a>(
+    prog: &'a [u8],
+    log_messages: &helpers::LogMessages,
+    compute_meter: &Rc<RefCell<ComputeMeter>>,
+    compute_budget: &ComputeBudget,
+) -> Result<(EbpfVm<'a>, MemoryRegion), Error> {
     let mut vm = EbpfVm::new(None)?;
     vm.set_verifier(bpf_verifier::check)?;
-    vm.set_max_instruction_count(36000)?;
+    // A count of 0 would lift the limit, the program is charged for its instructions once it
+    // has run anyway
+    let max_instruction_count = compute_meter
+        .borrow()
+        .get_remaining()
+        .checked_div(compute_budget.instruction_units)
+        .ok_or_else(|| {
+            Error::new(
+                ErrorKind::InvalidInput,
+                "Compute budget charges no units per instruction",
+            )
+        })?;
+    vm.set_max_instruction_count(max_instruction_count.max(1))?;
     vm.set_elf(&prog)?;
 
-    let heap_region = helpers::register_helpers(&mut vm)?;
+    let heap_region =
+        helpers::register_helpers(&mut vm, log_messages, compute_meter, compute_budget)?;
 
     Ok((vm, heap_region))
 }
@@ -76,8 +98,11 @@ fn deserialize_parameters(keyed_accounts: &mut [KeyedAccount], buffer: &[u8]) {
         let end = start + info.account.data.len();
         info.account.data.clone_from_slice(&buffer[start..end]);
 
-        start += info.account.data.len() // skip data
-                  + mem::size_of::<Pubkey>(); // skip owner
+        start += info.account.data.len(); // skip data
+
+        // programs invoked via `sol_invoke` may have assigned the account
+        info.account.owner = Pubkey::new(&buffer[start..start + mem::size_of::<Pubkey>()]);
+        start += mem::size_of::<Pubkey>(); // skip owner
     }
 }
 
@@ -85,6 +110,7 @@ pub fn process_instruction(
     program_id: &Pubkey,
     keyed_accounts: &mut [KeyedAccount],
     ix_data: &[u8],
+    invoke_context: &dyn InvokeContext,
 ) -> Result<(), InstructionError> {
     solana_logger::setup();
 
@@ -127,30 +153,84 @@ pub fn process_instruction(
                 let (progs, params) = keyed_accounts.split_at_mut(1);
                 let prog = &progs[0].account.data;
                 info!("Call BPF program");
-                let (mut vm, heap_region) = match create_vm(prog) {
-                    Ok(info) => info,
-                    Err(e) => {
-                        warn!("Failed to create BPF VM: {}", e);
-                        return Err(InstructionError::GenericError);
-                    }
-                };
+                let log_messages = helpers::LogMessages::default();
+                let compute_budget = invoke_context.get_compute_budget();
+                let compute_meter = invoke_context.get_compute_meter();
+                let available_units = compute_meter.borrow().get_remaining();
+                let (mut vm, heap_region) =
+                    match create_vm(prog, &log_messages, &compute_meter, &compute_budget) {
+                        Ok(info) => info,
+                        Err(e) => {
+                            warn!("Failed to create BPF VM: {}", e);
+                            return Err(InstructionError::GenericError);
+                        }
+                    };
                 let mut v = serialize_parameters(program_id, params, &data);
 
-                match vm.execute_program(v.as_mut_slice(), &[], &[heap_region]) {
-                    Ok(status) => match u32::try_from(status) {
-                        Ok(status) => {
-                            if status > 0 {
-                                warn!("BPF program failed: {}", status);
-                                return Err(InstructionError::CustomError(status));
+                let invoke_error = helpers::InvokeError::default();
+                // The VM is dropped before `invoke_context` goes out of scope
+                let invoker = unsafe {
+                    helpers::Invoker::new(
+                        invoke_context,
+                        program_id,
+                        params,
+                        v.len(),
+                        &log_messages,
+                        &invoke_error,
+                    )
+                };
+                if let Err(e) = helpers::register_invoke_helper(&mut vm, invoker) {
+                    warn!("Failed to register sol_invoke: {}", e);
+                    return Err(InstructionError::GenericError);
+                }
+
+                let result = vm.execute_program(v.as_mut_slice(), &[], &[heap_region]);
+                // Charge for the instructions executed and forward whatever the program
+                // logged, whether or not it succeeded
+                let charged = compute_meter.borrow_mut().consume(
+                    vm.get_last_instruction_count()
+                        .saturating_mul(compute_budget.instruction_units),
+                );
+                let remaining_units = compute_meter.borrow().get_remaining();
+                for message in log_messages.borrow().iter() {
+                    invoke_context.log(message);
+                }
+                invoke_context.log(&format!(
+                    "Program {} consumed {} of {} compute units",
+                    program_id,
+                    available_units - remaining_units,
+                    available_units
+                ));
+                // Halted by a helper out of units, or by the instruction limit, which leaves
+                // less than an instruction's worth
+                let exceeded =
+                    charged.is_err() || remaining_units < compute_budget.instruction_units;
+                match result {
+                    Ok(status) => {
+                        // Whatever the program returned, it didn't do so within its budget
+                        charged?;
+                        match u32::try_from(status) {
+                            Ok(status) => {
+                                if status > 0 {
+                                    warn!("BPF program failed: {}", status);
+                                    return Err(InstructionError::CustomError(status));
+                                }
+                            }
+                            Err(e) => {
+                                warn!("BPF VM encountered invalid status: {}", e);
+                                return Err(InstructionError::GenericError);
                             }
                         }
-                        Err(e) => {
-                            warn!("BPF VM encountered invalid status: {}", e);
-                            return Err(InstructionError::GenericError);
-                        }
-                    },
+                    }
                     Err(e) => {
                         warn!("BPF VM failed to run program: {}", e);
+                        if exceeded {
+                            return Err(InstructionError::ComputationalBudgetExceeded);
+                        }
+                        // Report why a program the BPF program invoked failed
+                        if let Some(err) = invoke_error.borrow_mut().take() {
+                            return Err(err);
+                        }
                         return Err(InstructionError::GenericError);
                     }
                 }
@@ -189,4 +269,20 @@ mod tests {
         vm.set_program(prog).unwrap();
         vm.execute_program(input, &[], &[]).unwrap();
     }
+
+    #[test]
+    fn test_create_vm_zero_instruction_units() {
+        let compute_meter = Rc::new(RefCell::new(ComputeMeter::new(100)));
+        let compute_budget = ComputeBudget {
+            instruction_units: 0,
+            ..ComputeBudget::default()
+        };
+        let result = create_vm(
+            &[],
+            &helpers::LogMessages::default(),
+            &compute_meter,
+            &compute_budget,
+        );
+        assert_eq!(result.err().unwrap().kind(), ErrorKind::InvalidInput);
+    }
 }
@@ -9,4 +9,4 @@ macro_rules! solana_bpf_loader_program {
 }
 
 use solana_bpf_loader_api::process_instruction;
-solana_sdk::solana_entrypoint!(process_instruction);
+solana_sdk::solana_loader_entrypoint!(process_instruction);
@@ -5,6 +5,7 @@
 
 use chrono::prelude::*;
 use serde_derive::{Deserialize, Serialize};
+use solana_sdk::clock::Slot;
 use solana_sdk::hash::Hash;
 use solana_sdk::pubkey::Pubkey;
 use std::mem;
@@ -20,6 +21,9 @@ pub enum Witness {
 
     /// Account snapshot.
     AccountData(Hash, Pubkey),
+
+    /// The current slot, as reported by the `Clock` sysvar.
+    Slot(Slot),
 }
 
 /// Some amount of lamports that should be sent to the `to` `Pubkey`.
@@ -32,6 +36,41 @@ pub struct Payment {
     pub to: Pubkey,
 }
 
+/// A fixed amount of lamports that should be sent to the `to` `Pubkey` every
+/// `interval` slots, until the contract's funds run out.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct RecurringPayment {
+    /// Amount to be paid each interval.
+    pub lamports: u64,
+
+    /// The `Pubkey` that `lamports` should be paid to.
+    pub to: Pubkey,
+
+    /// The number of slots between payments.
+    pub interval: Slot,
+
+    /// The first slot at which the next payment may be released.
+    pub next_slot: Slot,
+}
+
+impl RecurringPayment {
+    /// Return the payment due at `slot`, if any, and advance `next_slot` past
+    /// every interval that payment covers.
+    pub fn take_due_payment(&mut self, slot: Slot) -> Option<Payment> {
+        if self.interval == 0 || slot < self.next_slot {
+            return None;
+        }
+        let intervals = (slot - self.next_slot) / self.interval + 1;
+        self.next_slot = self
+            .next_slot
+            .saturating_add(intervals.saturating_mul(self.interval));
+        Some(Payment {
+            lamports: self.lamports.saturating_mul(intervals),
+            to: self.to,
+        })
+    }
+}
+
 /// The account constraints a Condition would wait on.
 /// Note: ideally this would be function that accepts an Account and returns
 /// a bool, but we don't have a way to pass functions over the wire. To simulate
@@ -60,6 +99,13 @@ pub enum Condition {
 
     /// Wait for the account with the given constraints.
     AccountData(AccountConstraints),
+
+    /// Wait for a `Slot` `Witness` at or after the given slot.
+    Slot(Slot),
+
+    /// Wait for `Signature` `Witness`es from the given number of distinct
+    /// `Pubkey`s in the list.
+    Threshold(u8, Vec<Pubkey>),
 }
 
 impl Condition {
@@ -78,9 +124,37 @@ impl Condition {
                     && constraints.key == *from
                     && constraints.data_hash == *actual_hash
             }
+            (Condition::Slot(slot), Witness::Slot(current_slot)) => slot <= current_slot,
+            (Condition::Threshold(m, signers), Witness::Signature) => {
+                *m <= 1 && signers.contains(from)
+            }
             _ => false,
         }
     }
+
+    /// Return true if enough Witnesses could satisfy this Condition.
+    pub fn is_valid(&self) -> bool {
+        match self {
+            Condition::Threshold(m, signers) => {
+                let mut distinct_signers = signers.clone();
+                distinct_signers.sort();
+                distinct_signers.dedup();
+                *m > 0 && *m as usize <= distinct_signers.len()
+            }
+            _ => true,
+        }
+    }
+
+    /// Record a Witness that brings this Condition closer to being satisfied
+    /// without satisfying it, such as one of several required signatures.
+    pub fn apply_witness(&mut self, witness: &Witness, from: &Pubkey) {
+        if let (Condition::Threshold(m, signers), Witness::Signature) = (self, witness) {
+            if *m > 1 && signers.contains(from) {
+                signers.retain(|signer| signer != from);
+                *m -= 1;
+            }
+        }
+    }
 }
 
 /// A data type representing a payment plan.
@@ -99,6 +173,9 @@ pub enum BudgetExpr {
 
     /// Make a payment after both of two conditions are satisfied
     And(Condition, Condition, Box<BudgetExpr>),
+
+    /// Make a payment every interval until the contract's funds run out.
+    Recurring(RecurringPayment),
 }
 
 impl BudgetExpr {
@@ -210,6 +287,43 @@ impl BudgetExpr {
         )
     }
 
+    /// Create a budget that pays `lamports` to `to` once the given slot is reached.
+    pub fn new_payment_at_slot(slot: Slot, lamports: u64, to: &Pubkey) -> Self {
+        BudgetExpr::After(
+            Condition::Slot(slot),
+            Box::new(Self::new_payment(lamports, to)),
+        )
+    }
+
+    /// Create a budget that pays `lamports` to `to` after being witnessed by
+    /// `m` of the given `signers`. The budget only verifies if `m` is at least 1
+    /// and at most the number of distinct `signers`.
+    pub fn new_threshold_payment(m: u8, signers: &[Pubkey], lamports: u64, to: &Pubkey) -> Self {
+        let mut signers = signers.to_vec();
+        signers.sort();
+        signers.dedup();
+        BudgetExpr::After(
+            Condition::Threshold(m, signers),
+            Box::new(Self::new_payment(lamports, to)),
+        )
+    }
+
+    /// Create a budget that pays `lamports` to `to` every `interval` slots,
+    /// starting at `first_slot`, until the contract's funds run out.
+    pub fn new_recurring_payment(
+        lamports: u64,
+        to: &Pubkey,
+        interval: Slot,
+        first_slot: Slot,
+    ) -> Self {
+        BudgetExpr::Recurring(RecurringPayment {
+            lamports,
+            to: *to,
+            interval,
+            next_slot: first_slot,
+        })
+    }
+
     /// Return Payment if the budget requires no additional Witnesses.
     pub fn final_payment(&self) -> Option<Payment> {
         match self {
@@ -218,15 +332,25 @@ impl BudgetExpr {
         }
     }
 
-    /// Return true if the budget spends exactly `spendable_lamports`.
+    /// Return true if the budget spends exactly `spendable_lamports` and all of
+    /// its conditions can be satisfied.
     pub fn verify(&self, spendable_lamports: u64) -> bool {
         match self {
             BudgetExpr::Pay(payment) => payment.lamports == spendable_lamports,
-            BudgetExpr::After(_, sub_expr) | BudgetExpr::And(_, _, sub_expr) => {
-                sub_expr.verify(spendable_lamports)
+            BudgetExpr::After(cond, sub_expr) => {
+                cond.is_valid() && sub_expr.verify(spendable_lamports)
+            }
+            BudgetExpr::And(cond0, cond1, sub_expr) => {
+                cond0.is_valid() && cond1.is_valid() && sub_expr.verify(spendable_lamports)
             }
             BudgetExpr::Or(a, b) => {
-                a.1.verify(spendable_lamports) && b.1.verify(spendable_lamports)
+                a.0.is_valid()
+                    && b.0.is_valid()
+                    && a.1.verify(spendable_lamports)
+                    && b.1.verify(spendable_lamports)
+            }
+            BudgetExpr::Recurring(recurring) => {
+                recurring.lamports > 0 && recurring.interval > 0 && spendable_lamports > 0
             }
         }
     }
@@ -257,6 +381,15 @@ impl BudgetExpr {
         };
         if let Some(expr) = new_expr {
             mem::replace(self, *expr);
+            return;
+        }
+        match self {
+            BudgetExpr::After(cond, _) => cond.apply_witness(witness, from),
+            BudgetExpr::Or((cond0, _), (cond1, _)) | BudgetExpr::And(cond0, cond1, _) => {
+                cond0.apply_witness(witness, from);
+                cond1.apply_witness(witness, from);
+            }
+            _ => (),
         }
     }
 }
@@ -281,6 +414,14 @@ mod tests {
         assert!(!Condition::Timestamp(dt2, from).is_satisfied(&Witness::Timestamp(dt1), &from));
     }
 
+    #[test]
+    fn test_slot_satisfied() {
+        let from = Pubkey::default();
+        assert!(Condition::Slot(1).is_satisfied(&Witness::Slot(1), &from));
+        assert!(Condition::Slot(1).is_satisfied(&Witness::Slot(2), &from));
+        assert!(!Condition::Slot(2).is_satisfied(&Witness::Slot(1), &from));
+    }
+
     #[test]
     fn test_verify() {
         let dt = Utc.ymd(2014, 11, 14).and_hms(8, 9, 10);
@@ -292,6 +433,13 @@ mod tests {
         assert!(
             BudgetExpr::new_cancelable_future_payment(dt, &from, 42, &to, Some(from)).verify(42)
         );
+        assert!(BudgetExpr::new_payment_at_slot(10, 42, &to).verify(42));
+        assert!(BudgetExpr::new_threshold_payment(1, &[from], 42, &to).verify(42));
+        assert!(!BudgetExpr::new_threshold_payment(0, &[from], 42, &to).verify(42));
+        assert!(!BudgetExpr::new_threshold_payment(2, &[from, from], 42, &to).verify(42));
+        assert!(BudgetExpr::new_recurring_payment(10, &to, 5, 0).verify(42));
+        assert!(!BudgetExpr::new_recurring_payment(10, &to, 0, 0).verify(42));
+        assert!(!BudgetExpr::new_recurring_payment(0, &to, 5, 0).verify(42));
     }
 
     #[test]
@@ -388,4 +536,74 @@ mod tests {
         expr.apply_witness(&Witness::Signature, &from0);
         assert_eq!(expr, BudgetExpr::new_authorized_payment(&from1, 42, &to));
     }
+
+    #[test]
+    fn test_payment_at_slot() {
+        let from = Pubkey::new_rand();
+        let to = Pubkey::new_rand();
+
+        let mut expr = BudgetExpr::new_payment_at_slot(10, 42, &to);
+        expr.apply_witness(&Witness::Slot(9), &from);
+        assert_eq!(expr, BudgetExpr::new_payment_at_slot(10, 42, &to));
+
+        expr.apply_witness(&Witness::Slot(10), &from);
+        assert_eq!(expr, BudgetExpr::new_payment(42, &to));
+    }
+
+    #[test]
+    fn test_2_3_threshold_payment() {
+        let from0 = Pubkey::new_rand();
+        let from1 = Pubkey::new_rand();
+        let from2 = Pubkey::new_rand();
+        let mallory = Pubkey::new_rand();
+        let to = Pubkey::default();
+
+        let mut expr = BudgetExpr::new_threshold_payment(2, &[from0, from1, from2], 42, &to);
+        expr.apply_witness(&Witness::Signature, &mallory);
+        assert_eq!(
+            expr,
+            BudgetExpr::new_threshold_payment(2, &[from0, from1, from2], 42, &to)
+        );
+
+        expr.apply_witness(&Witness::Signature, &from1);
+        assert_eq!(
+            expr,
+            BudgetExpr::new_threshold_payment(1, &[from0, from2], 42, &to)
+        );
+
+        // A second signature from the same signer doesn't count twice.
+        expr.apply_witness(&Witness::Signature, &from1);
+        assert_eq!(
+            expr,
+            BudgetExpr::new_threshold_payment(1, &[from0, from2], 42, &to)
+        );
+
+        expr.apply_witness(&Witness::Signature, &from2);
+        assert_eq!(expr, BudgetExpr::new_payment(42, &to));
+    }
+
+    #[test]
+    fn test_recurring_payment() {
+        let to = Pubkey::new_rand();
+        let mut recurring = RecurringPayment {
+            lamports: 10,
+            to,
+            interval: 5,
+            next_slot: 5,
+        };
+        assert_eq!(recurring.take_due_payment(4), None);
+        assert_eq!(
+            recurring.take_due_payment(5),
+            Some(Payment { lamports: 10, to })
+        );
+        assert_eq!(recurring.next_slot, 10);
+        assert_eq!(recurring.take_due_payment(9), None);
+
+        // Missed intervals are paid out together.
+        assert_eq!(
+            recurring.take_due_payment(21),
+            Some(Payment { lamports: 30, to })
+        );
+        assert_eq!(recurring.next_slot, 25);
+    }
 }
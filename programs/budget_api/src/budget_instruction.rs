@@ -4,10 +4,12 @@ use crate::id;
 use bincode::serialized_size;
 use chrono::prelude::{DateTime, Utc};
 use serde_derive::{Deserialize, Serialize};
+use solana_sdk::clock::Slot;
 use solana_sdk::hash::Hash;
 use solana_sdk::instruction::{AccountMeta, Instruction};
 use solana_sdk::pubkey::Pubkey;
 use solana_sdk::system_instruction;
+use solana_sdk::sysvar;
 
 /// A smart contract.
 #[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
@@ -32,6 +34,9 @@ pub enum BudgetInstruction {
 
     /// Load an account and pass its data to the budget for inspection.
     ApplyAccountData,
+
+    /// Tell the budget the current slot, as reported by the `Clock` sysvar.
+    ApplySlot,
 }
 
 fn initialize_account(contract: &Pubkey, expr: BudgetExpr) -> Instruction {
@@ -117,6 +122,46 @@ pub fn when_account_data(
     create_account(from, contract, lamports, expr)
 }
 
+/// Create a payment script that pays out once the given slot is reached.
+pub fn on_slot(
+    from: &Pubkey,
+    to: &Pubkey,
+    contract: &Pubkey,
+    slot: Slot,
+    lamports: u64,
+) -> Vec<Instruction> {
+    let expr = BudgetExpr::new_payment_at_slot(slot, lamports, to);
+    create_account(from, contract, lamports, expr)
+}
+
+/// Create an m-of-n multisig payment script.
+pub fn when_threshold_signed(
+    from: &Pubkey,
+    to: &Pubkey,
+    contract: &Pubkey,
+    m: u8,
+    signers: &[Pubkey],
+    lamports: u64,
+) -> Vec<Instruction> {
+    let expr = BudgetExpr::new_threshold_payment(m, signers, lamports, to);
+    create_account(from, contract, lamports, expr)
+}
+
+/// Create a script that pays `installment` lamports every `interval` slots,
+/// starting at `first_slot`, until `lamports` are spent.
+pub fn recurring(
+    from: &Pubkey,
+    to: &Pubkey,
+    contract: &Pubkey,
+    installment: u64,
+    interval: Slot,
+    first_slot: Slot,
+    lamports: u64,
+) -> Vec<Instruction> {
+    let expr = BudgetExpr::new_recurring_payment(installment, to, interval, first_slot);
+    create_account(from, contract, lamports, expr)
+}
+
 pub fn apply_timestamp(
     from: &Pubkey,
     contract: &Pubkey,
@@ -154,6 +199,17 @@ pub fn apply_account_data(witness_pubkey: &Pubkey, contract: &Pubkey, to: &Pubke
     Instruction::new(id(), &BudgetInstruction::ApplyAccountData, account_metas)
 }
 
+/// Apply the current slot to a contract waiting on a Slot condition or
+/// paying out on a recurring schedule.
+pub fn apply_slot(contract: &Pubkey, to: &Pubkey) -> Instruction {
+    let account_metas = vec![
+        AccountMeta::new_credit_only(sysvar::clock::id(), false),
+        AccountMeta::new(*contract, false),
+        AccountMeta::new_credit_only(*to, false),
+    ];
+    Instruction::new(id(), &BudgetInstruction::ApplySlot, account_metas)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
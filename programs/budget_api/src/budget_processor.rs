@@ -1,14 +1,16 @@
 //! budget program
-use crate::budget_expr::Witness;
+use crate::budget_expr::{BudgetExpr, Witness};
 use crate::budget_instruction::BudgetInstruction;
 use crate::budget_state::{BudgetError, BudgetState};
 use bincode::deserialize;
 use chrono::prelude::{DateTime, Utc};
 use log::*;
 use solana_sdk::account::KeyedAccount;
+use solana_sdk::clock::Slot;
 use solana_sdk::hash::hash;
 use solana_sdk::instruction::InstructionError;
 use solana_sdk::pubkey::Pubkey;
+use solana_sdk::sysvar;
 
 /// Process a Witness Signature. Any payment plans waiting on this signature
 /// will progress one step.
@@ -100,6 +102,51 @@ fn apply_account_data(
     Ok(())
 }
 
+/// Process a Witness Slot read from the Clock sysvar. Any payment plans waiting
+/// on this slot will progress one step, and any recurring payment that has come
+/// due is paid out, up to the contract's remaining lamports.
+fn apply_slot(
+    budget_state: &mut BudgetState,
+    keyed_accounts: &mut [KeyedAccount],
+    slot: Slot,
+) -> Result<(), BudgetError> {
+    let mut final_payment = None;
+    let mut recurring_payment = None;
+
+    if let Some(ref mut expr) = budget_state.pending_budget {
+        let key = keyed_accounts[0].unsigned_key();
+        expr.apply_witness(&Witness::Slot(slot), key);
+        final_payment = expr.final_payment();
+        if let BudgetExpr::Recurring(recurring) = expr {
+            recurring_payment = recurring.take_due_payment(slot);
+        }
+    }
+
+    if let Some(payment) = final_payment {
+        if &payment.to != keyed_accounts[2].unsigned_key() {
+            trace!("destination missing");
+            return Err(BudgetError::DestinationMissing);
+        }
+        budget_state.pending_budget = None;
+        keyed_accounts[1].account.lamports -= payment.lamports;
+        keyed_accounts[2].account.lamports += payment.lamports;
+    }
+
+    if let Some(payment) = recurring_payment {
+        if &payment.to != keyed_accounts[2].unsigned_key() {
+            trace!("destination missing");
+            return Err(BudgetError::DestinationMissing);
+        }
+        let lamports = payment.lamports.min(keyed_accounts[1].account.lamports);
+        keyed_accounts[1].account.lamports -= lamports;
+        keyed_accounts[2].account.lamports += lamports;
+        if keyed_accounts[1].account.lamports == 0 {
+            budget_state.pending_budget = None;
+        }
+    }
+    Ok(())
+}
+
 pub fn process_instruction(
     _program_id: &Pubkey,
     keyed_accounts: &mut [KeyedAccount],
@@ -180,6 +227,22 @@ pub fn process_instruction(
             trace!("apply account data committed");
             budget_state.serialize(&mut keyed_accounts[1].account.data)
         }
+        BudgetInstruction::ApplySlot => {
+            let mut budget_state = BudgetState::deserialize(&keyed_accounts[1].account.data)?;
+            if !budget_state.is_pending() {
+                return Ok(()); // Nothing to do here.
+            }
+            if !budget_state.initialized {
+                trace!("contract is uninitialized");
+                return Err(InstructionError::UninitializedAccount);
+            }
+            let clock = sysvar::clock::from_keyed_account(&keyed_accounts[0])?;
+            trace!("apply slot");
+            apply_slot(&mut budget_state, keyed_accounts, clock.slot)
+                .map_err(|e| InstructionError::CustomError(e as u32))?;
+            trace!("apply slot committed");
+            budget_state.serialize(&mut keyed_accounts[1].account.data)
+        }
     }
 }
 
@@ -198,6 +261,7 @@ mod tests {
     use solana_sdk::message::Message;
     use solana_sdk::signature::{Keypair, KeypairUtil};
     use solana_sdk::transaction::TransactionError;
+    use std::sync::Arc;
 
     fn create_bank(lamports: u64) -> (Bank, Keypair) {
         let (genesis_block, mint_keypair) = create_genesis_block(lamports);
@@ -206,6 +270,17 @@ mod tests {
         (bank, mint_keypair)
     }
 
+    /// Create a child bank at `slot` with a fresh blockhash, so repeated
+    /// witness transactions aren't rejected as duplicates.
+    fn new_bank_at_slot(parent: &Arc<Bank>, slot: u64) -> Arc<Bank> {
+        let mut bank = Bank::new_from_parent(parent, &Pubkey::default(), slot);
+        bank.add_instruction_processor(id(), process_instruction);
+        for _ in 0..bank.ticks_per_slot() {
+            bank.register_tick(&hash(&slot.to_le_bytes()));
+        }
+        Arc::new(bank)
+    }
+
     #[test]
     fn test_budget_payment() {
         let (bank, alice_keypair) = create_bank(10_000);
@@ -509,4 +584,175 @@ mod tests {
         assert_eq!(bank_client.get_balance(&bob_pubkey).unwrap(), 42);
         assert_eq!(bank_client.get_account_data(&budget_pubkey).unwrap(), None);
     }
+
+    #[test]
+    fn test_pay_on_slot() {
+        let (bank, alice_keypair) = create_bank(10_000);
+        let bank = Arc::new(bank);
+        let bank_client = BankClient::new_shared(&bank);
+        let alice_pubkey = alice_keypair.pubkey();
+        let budget_pubkey = Pubkey::new_rand();
+        let bob_pubkey = Pubkey::new_rand();
+        let instructions =
+            budget_instruction::on_slot(&alice_pubkey, &bob_pubkey, &budget_pubkey, 10, 1);
+        let message = Message::new(instructions);
+        bank_client
+            .send_message(&[&alice_keypair], message)
+            .unwrap();
+        assert_eq!(bank_client.get_balance(&budget_pubkey).unwrap(), 1);
+
+        // Too early, nothing should change.
+        let instruction = budget_instruction::apply_slot(&budget_pubkey, &bob_pubkey);
+        let message = Message::new_with_payer(vec![instruction], Some(&alice_pubkey));
+        bank_client
+            .send_message(&[&alice_keypair], message)
+            .unwrap();
+        assert_eq!(bank_client.get_balance(&budget_pubkey).unwrap(), 1);
+        assert_eq!(bank_client.get_balance(&bob_pubkey).unwrap(), 0);
+
+        // Attack! Try to pass off an ordinary account as the clock.
+        let mut instruction = budget_instruction::apply_slot(&budget_pubkey, &bob_pubkey);
+        instruction.accounts[0].pubkey = alice_pubkey;
+        let message = Message::new_with_payer(vec![instruction], Some(&alice_pubkey));
+        assert_eq!(
+            bank_client
+                .send_message(&[&alice_keypair], message)
+                .unwrap_err()
+                .unwrap(),
+            TransactionError::InstructionError(0, InstructionError::InvalidArgument)
+        );
+
+        let bank = new_bank_at_slot(&bank, 10);
+        let bank_client = BankClient::new_shared(&bank);
+        let instruction = budget_instruction::apply_slot(&budget_pubkey, &bob_pubkey);
+        let message = Message::new_with_payer(vec![instruction], Some(&alice_pubkey));
+        bank_client
+            .send_message(&[&alice_keypair], message)
+            .unwrap();
+        assert_eq!(bank_client.get_balance(&budget_pubkey).unwrap(), 0);
+        assert_eq!(bank_client.get_balance(&bob_pubkey).unwrap(), 1);
+        assert_eq!(bank_client.get_account_data(&budget_pubkey).unwrap(), None);
+    }
+
+    #[test]
+    fn test_pay_when_threshold_signed() {
+        let (bank, alice_keypair) = create_bank(4);
+        let bank_client = BankClient::new(bank);
+        let alice_pubkey = alice_keypair.pubkey();
+        let budget_pubkey = Pubkey::new_rand();
+        let bob_pubkey = Pubkey::new_rand();
+        let signer0_keypair = Keypair::new();
+        let signer1_keypair = Keypair::new();
+        let signers = [
+            signer0_keypair.pubkey(),
+            signer1_keypair.pubkey(),
+            alice_pubkey,
+        ];
+
+        // Give the signers some lamports so they can sign witness transactions.
+        bank_client
+            .transfer(1, &alice_keypair, &signer0_keypair.pubkey())
+            .unwrap();
+        bank_client
+            .transfer(1, &alice_keypair, &signer1_keypair.pubkey())
+            .unwrap();
+
+        let instructions = budget_instruction::when_threshold_signed(
+            &alice_pubkey,
+            &bob_pubkey,
+            &budget_pubkey,
+            2,
+            &signers,
+            1,
+        );
+        let message = Message::new(instructions);
+        bank_client
+            .send_message(&[&alice_keypair], message)
+            .unwrap();
+        assert_eq!(bank_client.get_balance(&budget_pubkey).unwrap(), 1);
+
+        let instruction = budget_instruction::apply_signature(
+            &signer0_keypair.pubkey(),
+            &budget_pubkey,
+            &bob_pubkey,
+        );
+        bank_client
+            .send_instruction(&signer0_keypair, instruction)
+            .unwrap();
+        assert_eq!(bank_client.get_balance(&budget_pubkey).unwrap(), 1);
+        assert_eq!(bank_client.get_balance(&bob_pubkey).unwrap(), 0);
+
+        let instruction = budget_instruction::apply_signature(
+            &signer1_keypair.pubkey(),
+            &budget_pubkey,
+            &bob_pubkey,
+        );
+        bank_client
+            .send_instruction(&signer1_keypair, instruction)
+            .unwrap();
+        assert_eq!(bank_client.get_balance(&budget_pubkey).unwrap(), 0);
+        assert_eq!(bank_client.get_balance(&bob_pubkey).unwrap(), 1);
+        assert_eq!(bank_client.get_account_data(&budget_pubkey).unwrap(), None);
+    }
+
+    #[test]
+    fn test_recurring_payment() {
+        let (bank, alice_keypair) = create_bank(10_000);
+        let bank = Arc::new(bank);
+        let bank_client = BankClient::new_shared(&bank);
+        let alice_pubkey = alice_keypair.pubkey();
+        let budget_pubkey = Pubkey::new_rand();
+        let bob_pubkey = Pubkey::new_rand();
+
+        // Pay 10 lamports every 5 slots, starting at slot 5, from a budget of 25.
+        let instructions =
+            budget_instruction::recurring(&alice_pubkey, &bob_pubkey, &budget_pubkey, 10, 5, 5, 25);
+        let message = Message::new(instructions);
+        bank_client
+            .send_message(&[&alice_keypair], message)
+            .unwrap();
+        assert_eq!(bank_client.get_balance(&budget_pubkey).unwrap(), 25);
+
+        let bank = new_bank_at_slot(&bank, 5);
+        let bank_client = BankClient::new_shared(&bank);
+        let instruction = budget_instruction::apply_slot(&budget_pubkey, &bob_pubkey);
+        let message = Message::new_with_payer(vec![instruction], Some(&alice_pubkey));
+        bank_client
+            .send_message(&[&alice_keypair], message)
+            .unwrap();
+        assert_eq!(bank_client.get_balance(&budget_pubkey).unwrap(), 15);
+        assert_eq!(bank_client.get_balance(&bob_pubkey).unwrap(), 10);
+
+        // Nothing more is due until the next interval.
+        let bank = new_bank_at_slot(&bank, 9);
+        let bank_client = BankClient::new_shared(&bank);
+        let instruction = budget_instruction::apply_slot(&budget_pubkey, &bob_pubkey);
+        let message = Message::new_with_payer(vec![instruction], Some(&alice_pubkey));
+        bank_client
+            .send_message(&[&alice_keypair], message)
+            .unwrap();
+        assert_eq!(bank_client.get_balance(&budget_pubkey).unwrap(), 15);
+
+        let bank = new_bank_at_slot(&bank, 10);
+        let bank_client = BankClient::new_shared(&bank);
+        let instruction = budget_instruction::apply_slot(&budget_pubkey, &bob_pubkey);
+        let message = Message::new_with_payer(vec![instruction], Some(&alice_pubkey));
+        bank_client
+            .send_message(&[&alice_keypair], message)
+            .unwrap();
+        assert_eq!(bank_client.get_balance(&budget_pubkey).unwrap(), 5);
+        assert_eq!(bank_client.get_balance(&bob_pubkey).unwrap(), 20);
+
+        // The last payment is whatever is left.
+        let bank = new_bank_at_slot(&bank, 15);
+        let bank_client = BankClient::new_shared(&bank);
+        let instruction = budget_instruction::apply_slot(&budget_pubkey, &bob_pubkey);
+        let message = Message::new_with_payer(vec![instruction], Some(&alice_pubkey));
+        bank_client
+            .send_message(&[&alice_keypair], message)
+            .unwrap();
+        assert_eq!(bank_client.get_balance(&budget_pubkey).unwrap(), 0);
+        assert_eq!(bank_client.get_balance(&bob_pubkey).unwrap(), 25);
+        assert_eq!(bank_client.get_account_data(&budget_pubkey).unwrap(), None);
+    }
 }
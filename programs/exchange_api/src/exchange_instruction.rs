@@ -30,9 +30,9 @@ pub enum ExchangeInstruction {
     AccountRequest,
 
     /// Transfer tokens between two accounts
-    /// key 0 - Account to transfer tokens to
-    /// key 1 - Account to transfer tokens from.  This can be the exchange program itself,
-    ///         the exchange has a limitless number of tokens it can transfer.
+    /// key 0 - Signer
+    /// key 1 - Account to transfer tokens to
+    /// key 2 - Account or settled trade order to transfer tokens from
     TransferRequest(Token, u64),
 
     /// Order request
@@ -44,6 +44,7 @@ pub enum ExchangeInstruction {
     /// Order cancellation
     /// key 0 - Signer
     /// key 1 - Order to cancel
+    /// key 2 - Order book the order was placed in, if any
     OrderCancellation,
 
     /// Trade swap request
@@ -52,6 +53,66 @@ pub enum ExchangeInstruction {
     /// key 3 - `From` trade order
     /// key 6 - Token account in which to deposit the brokers profit from the swap.
     SwapRequest,
+
+    /// New order book
+    /// key 0 - Signer
+    /// key 1 - New order book account
+    OrderBookRequest(AssetPair),
+
+    /// Limit order placed in an order book
+    /// key 0 - Signer
+    /// key 1 - Account in which to record the trade order
+    /// key 2 - Token account to source tokens from
+    /// key 3 - Order book to place the order in
+    PlaceOrder(OrderRequestInfo),
+
+    /// Cancellation of an order resting in an order book
+    /// key 0 - Signer
+    /// key 1 - Order to cancel
+    /// key 2 - Order book the order rests in
+    CancelOrder,
+
+    /// Match the crossing orders of an order book in price-time priority
+    /// key 0 - Signer
+    /// key 1 - Order book
+    /// key 2 - Token account in which to deposit the brokers profit from the matches
+    /// key 3.. - Orders at the top of the book, matching stops at the first one missing
+    MatchOrders,
+
+    /// New vault for deposits of a token, the vault and its authority must have been created at
+    /// the token's `vault_address` and `vault_authority_address`
+    /// key 0 - Signer
+    /// key 1 - New vault account
+    /// key 2 - Token program account to hold the deposits, owned by the vault authority
+    /// key 3 - Vault authority
+    VaultRequest,
+
+    /// Start a deposit into a vault.  The token program transfer from key 3 into the vault's
+    /// token program account, then the `DepositRequest`, must follow in the same transaction
+    /// key 0 - Signer
+    /// key 1 - Vault
+    /// key 2 - Vault's token program account
+    /// key 3 - Signer's token program account to deposit from
+    PrepareDeposit,
+
+    /// Credit the tokens moved into a vault's token program account since the `PrepareDeposit`
+    /// key 0 - Signer
+    /// key 1 - Token account to credit
+    /// key 2 - Vault
+    /// key 3 - Vault's token program account
+    /// key 4 - Signer's token program account the tokens were transferred from
+    DepositRequest(u64),
+
+    /// Debit tokens from a token account and approve their transfer out of the vault.  The
+    /// token program transfer to key 5, with the vault authority as its owner, must follow in
+    /// the same transaction
+    /// key 0 - Signer
+    /// key 1 - Token account to debit
+    /// key 2 - Vault
+    /// key 3 - Vault's token program account
+    /// key 4 - Vault authority
+    /// key 5 - Signer's token program account to transfer the tokens to
+    WithdrawRequest(u64),
 }
 
 pub fn account_request(owner: &Pubkey, new: &Pubkey) -> Instruction {
@@ -129,3 +190,144 @@ pub fn swap_request(
     ];
     Instruction::new(id(), &ExchangeInstruction::SwapRequest, account_metas)
 }
+
+pub fn order_book_request(owner: &Pubkey, order_book: &Pubkey, pair: AssetPair) -> Instruction {
+    let account_metas = vec![
+        AccountMeta::new(*owner, true),
+        AccountMeta::new(*order_book, false),
+    ];
+    Instruction::new(
+        id(),
+        &ExchangeInstruction::OrderBookRequest(pair),
+        account_metas,
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn place_order(
+    owner: &Pubkey,
+    order: &Pubkey,
+    side: OrderSide,
+    pair: AssetPair,
+    tokens: u64,
+    price: u64,
+    src_account: &Pubkey,
+    order_book: &Pubkey,
+) -> Instruction {
+    let account_metas = vec![
+        AccountMeta::new(*owner, true),
+        AccountMeta::new(*order, false),
+        AccountMeta::new(*src_account, false),
+        AccountMeta::new(*order_book, false),
+    ];
+    Instruction::new(
+        id(),
+        &ExchangeInstruction::PlaceOrder(OrderRequestInfo {
+            side,
+            pair,
+            tokens,
+            price,
+        }),
+        account_metas,
+    )
+}
+
+pub fn cancel_order(owner: &Pubkey, order: &Pubkey, order_book: &Pubkey) -> Instruction {
+    let account_metas = vec![
+        AccountMeta::new(*owner, true),
+        AccountMeta::new(*order, false),
+        AccountMeta::new(*order_book, false),
+    ];
+    Instruction::new(id(), &ExchangeInstruction::CancelOrder, account_metas)
+}
+
+pub fn match_orders(
+    owner: &Pubkey,
+    order_book: &Pubkey,
+    profit_account: &Pubkey,
+    orders: &[Pubkey],
+) -> Instruction {
+    let mut account_metas = vec![
+        AccountMeta::new(*owner, true),
+        AccountMeta::new(*order_book, false),
+        AccountMeta::new(*profit_account, false),
+    ];
+    account_metas.extend(orders.iter().map(|order| AccountMeta::new(*order, false)));
+    Instruction::new(id(), &ExchangeInstruction::MatchOrders, account_metas)
+}
+
+pub fn vault_request(
+    owner: &Pubkey,
+    vault: &Pubkey,
+    token_account: &Pubkey,
+    authority: &Pubkey,
+) -> Instruction {
+    let account_metas = vec![
+        AccountMeta::new(*owner, true),
+        AccountMeta::new(*vault, false),
+        AccountMeta::new(*token_account, false),
+        AccountMeta::new(*authority, false),
+    ];
+    Instruction::new(id(), &ExchangeInstruction::VaultRequest, account_metas)
+}
+
+pub fn prepare_deposit(
+    owner: &Pubkey,
+    vault: &Pubkey,
+    vault_token_account: &Pubkey,
+    from_token_account: &Pubkey,
+) -> Instruction {
+    let account_metas = vec![
+        AccountMeta::new(*owner, true),
+        AccountMeta::new(*vault, false),
+        AccountMeta::new(*vault_token_account, false),
+        AccountMeta::new(*from_token_account, false),
+    ];
+    Instruction::new(id(), &ExchangeInstruction::PrepareDeposit, account_metas)
+}
+
+pub fn deposit_request(
+    owner: &Pubkey,
+    to: &Pubkey,
+    vault: &Pubkey,
+    vault_token_account: &Pubkey,
+    from_token_account: &Pubkey,
+    tokens: u64,
+) -> Instruction {
+    let account_metas = vec![
+        AccountMeta::new(*owner, true),
+        AccountMeta::new(*to, false),
+        AccountMeta::new(*vault, false),
+        AccountMeta::new(*vault_token_account, false),
+        AccountMeta::new(*from_token_account, false),
+    ];
+    Instruction::new(
+        id(),
+        &ExchangeInstruction::DepositRequest(tokens),
+        account_metas,
+    )
+}
+
+pub fn withdraw_request(
+    owner: &Pubkey,
+    from: &Pubkey,
+    vault: &Pubkey,
+    vault_token_account: &Pubkey,
+    authority: &Pubkey,
+    to_token_account: &Pubkey,
+    tokens: u64,
+) -> Instruction {
+    let account_metas = vec![
+        AccountMeta::new(*owner, true),
+        AccountMeta::new(*from, false),
+        AccountMeta::new(*vault, false),
+        AccountMeta::new(*vault_token_account, false),
+        AccountMeta::new(*authority, false),
+        AccountMeta::new(*to_token_account, false),
+    ];
+    Instruction::new(
+        id(),
+        &ExchangeInstruction::WithdrawRequest(tokens),
+        account_metas,
+    )
+}
@@ -2,12 +2,12 @@
 
 use crate::exchange_instruction::*;
 use crate::exchange_state::*;
-use crate::faucet;
 use log::*;
 use solana_metrics::inc_new_counter_info;
 use solana_sdk::account::KeyedAccount;
 use solana_sdk::instruction::InstructionError;
 use solana_sdk::pubkey::Pubkey;
+use solana_token_api::token_state::{TokenState, TransferApproval};
 use std::cmp;
 
 pub struct ExchangeProcessor {}
@@ -49,6 +49,42 @@ impl ExchangeProcessor {
         }
     }
 
+    fn deserialize_order_book(data: &[u8]) -> Result<OrderBookInfo, InstructionError> {
+        let state: ExchangeState = bincode::deserialize(data).map_err(Self::map_to_invalid_arg)?;
+        if let ExchangeState::OrderBook(info) = state {
+            Ok(info)
+        } else {
+            error!("Not a valid order book");
+            Err(InstructionError::InvalidAccountData)?
+        }
+    }
+
+    fn deserialize_vault(data: &[u8]) -> Result<VaultInfo, InstructionError> {
+        let state: ExchangeState = bincode::deserialize(data).map_err(Self::map_to_invalid_arg)?;
+        if let ExchangeState::Vault(info) = state {
+            Ok(info)
+        } else {
+            error!("Not a valid vault");
+            Err(InstructionError::InvalidAccountData)?
+        }
+    }
+
+    fn deserialize_token_program_account(
+        keyed_account: &KeyedAccount,
+    ) -> Result<TokenState, InstructionError> {
+        if keyed_account.account.owner != solana_token_api::id() {
+            error!("Not a token program account");
+            Err(InstructionError::InvalidArgument)?
+        }
+        match TokenState::deserialize(&keyed_account.account.data) {
+            Ok(state @ TokenState::Account(_)) => Ok(state),
+            _ => {
+                error!("Not a valid token program account");
+                Err(InstructionError::InvalidAccountData)?
+            }
+        }
+    }
+
     fn serialize(state: &ExchangeState, data: &mut [u8]) -> Result<(), InstructionError> {
         let writer = std::io::BufWriter::new(data);
         match bincode::serialize_into(writer, state) {
@@ -60,6 +96,21 @@ impl ExchangeProcessor {
         }
     }
 
+    /// Record the next transfer out of a vault in its authority's data
+    fn serialize_approval(
+        approval: &Option<TransferApproval>,
+        data: &mut [u8],
+    ) -> Result<(), InstructionError> {
+        let writer = std::io::BufWriter::new(data);
+        match bincode::serialize_into(writer, approval) {
+            Ok(_) => Ok(()),
+            Err(e) => {
+                error!("Serialize failed: {:?}", e);
+                Err(InstructionError::GenericError)?
+            }
+        }
+    }
+
     fn trade_to_token_account(trade: &OrderInfo) -> TokenAccountInfo {
         // Turn trade order into token account
 
@@ -73,6 +124,15 @@ impl ExchangeProcessor {
         account
     }
 
+    fn order_to_state(order: OrderInfo) -> ExchangeState {
+        if order.tokens == 0 {
+            // Turn into token account
+            ExchangeState::Account(Self::trade_to_token_account(&order))
+        } else {
+            ExchangeState::Trade(order)
+        }
+    }
+
     fn calculate_swap(
         scaler: u64,
         to_trade: &mut OrderInfo,
@@ -167,9 +227,7 @@ impl ExchangeProcessor {
         Self::is_account_unallocated(&keyed_accounts[NEW_ACCOUNT_INDEX].account.data)?;
         Self::serialize(
             &ExchangeState::Account(
-                TokenAccountInfo::default()
-                    .owner(&keyed_accounts[OWNER_INDEX].unsigned_key())
-                    .tokens(100_000, 100_000, 100_000, 100_000),
+                TokenAccountInfo::default().owner(keyed_accounts[OWNER_INDEX].unsigned_key()),
             ),
             &mut keyed_accounts[NEW_ACCOUNT_INDEX].account.data,
         )
@@ -192,64 +250,60 @@ impl ExchangeProcessor {
         let mut to_account =
             Self::deserialize_account(&keyed_accounts[TO_ACCOUNT_INDEX].account.data)?;
 
-        if &faucet::id() == keyed_accounts[FROM_ACCOUNT_INDEX].unsigned_key() {
-            to_account.tokens[token] += tokens;
-        } else {
-            let state: ExchangeState =
-                bincode::deserialize(&keyed_accounts[FROM_ACCOUNT_INDEX].account.data)
-                    .map_err(Self::map_to_invalid_arg)?;
-            match state {
-                ExchangeState::Account(mut from_account) => {
-                    if &from_account.owner != keyed_accounts[OWNER_INDEX].unsigned_key() {
-                        error!("Signer does not own from account");
-                        Err(InstructionError::GenericError)?
-                    }
-
-                    if from_account.tokens[token] < tokens {
-                        error!("From account balance too low");
-                        Err(InstructionError::GenericError)?
-                    }
-
-                    from_account.tokens[token] -= tokens;
-                    to_account.tokens[token] += tokens;
-
-                    Self::serialize(
-                        &ExchangeState::Account(from_account),
-                        &mut keyed_accounts[FROM_ACCOUNT_INDEX].account.data,
-                    )?;
+        let state: ExchangeState =
+            bincode::deserialize(&keyed_accounts[FROM_ACCOUNT_INDEX].account.data)
+                .map_err(Self::map_to_invalid_arg)?;
+        match state {
+            ExchangeState::Account(mut from_account) => {
+                if &from_account.owner != keyed_accounts[OWNER_INDEX].unsigned_key() {
+                    error!("Signer does not own from account");
+                    Err(InstructionError::GenericError)?
                 }
-                ExchangeState::Trade(mut from_trade) => {
-                    if &from_trade.owner != keyed_accounts[OWNER_INDEX].unsigned_key() {
-                        error!("Signer does not own from account");
-                        Err(InstructionError::GenericError)?
-                    }
-
-                    let from_token = match from_trade.side {
-                        OrderSide::Ask => from_trade.pair.Quote,
-                        OrderSide::Bid => from_trade.pair.Base,
-                    };
-                    if token != from_token {
-                        error!("Trade to transfer from does not hold correct token");
-                        Err(InstructionError::GenericError)?
-                    }
-
-                    if from_trade.tokens_settled < tokens {
-                        error!("From trade balance too low");
-                        Err(InstructionError::GenericError)?
-                    }
-
-                    from_trade.tokens_settled -= tokens;
-                    to_account.tokens[token] += tokens;
-
-                    Self::serialize(
-                        &ExchangeState::Trade(from_trade),
-                        &mut keyed_accounts[FROM_ACCOUNT_INDEX].account.data,
-                    )?;
+
+                if from_account.tokens[token] < tokens {
+                    error!("From account balance too low");
+                    Err(InstructionError::GenericError)?
                 }
-                _ => {
-                    error!("Not a valid from account for transfer");
-                    Err(InstructionError::InvalidArgument)?
+
+                from_account.tokens[token] -= tokens;
+                to_account.tokens[token] = Self::checked_add(to_account.tokens[token], tokens)?;
+
+                Self::serialize(
+                    &ExchangeState::Account(from_account),
+                    &mut keyed_accounts[FROM_ACCOUNT_INDEX].account.data,
+                )?;
+            }
+            ExchangeState::Trade(mut from_trade) => {
+                if &from_trade.owner != keyed_accounts[OWNER_INDEX].unsigned_key() {
+                    error!("Signer does not own from account");
+                    Err(InstructionError::GenericError)?
+                }
+
+                let from_token = match from_trade.side {
+                    OrderSide::Ask => from_trade.pair.Quote,
+                    OrderSide::Bid => from_trade.pair.Base,
+                };
+                if token != from_token {
+                    error!("Trade to transfer from does not hold correct token");
+                    Err(InstructionError::GenericError)?
                 }
+
+                if from_trade.tokens_settled < tokens {
+                    error!("From trade balance too low");
+                    Err(InstructionError::GenericError)?
+                }
+
+                from_trade.tokens_settled -= tokens;
+                to_account.tokens[token] = Self::checked_add(to_account.tokens[token], tokens)?;
+
+                Self::serialize(
+                    &ExchangeState::Trade(from_trade),
+                    &mut keyed_accounts[FROM_ACCOUNT_INDEX].account.data,
+                )?;
+            }
+            _ => {
+                error!("Not a valid from account for transfer");
+                Err(InstructionError::InvalidArgument)?
             }
         }
 
@@ -262,6 +316,7 @@ impl ExchangeProcessor {
     fn do_order_request(
         keyed_accounts: &mut [KeyedAccount],
         info: &OrderRequestInfo,
+        order_book: Option<Pubkey>,
     ) -> Result<(), InstructionError> {
         const OWNER_INDEX: usize = 0;
         const ORDER_INDEX: usize = 1;
@@ -306,6 +361,7 @@ impl ExchangeProcessor {
                 tokens: info.tokens,
                 price: info.price,
                 tokens_settled: 0,
+                order_book,
             }),
             &mut keyed_accounts[ORDER_INDEX].account.data,
         )?;
@@ -318,6 +374,7 @@ impl ExchangeProcessor {
     fn do_order_cancellation(keyed_accounts: &mut [KeyedAccount]) -> Result<(), InstructionError> {
         const OWNER_INDEX: usize = 0;
         const ORDER_INDEX: usize = 1;
+        const ORDER_BOOK_INDEX: usize = 2;
 
         if keyed_accounts.len() < 2 {
             error!("Not enough accounts");
@@ -331,14 +388,32 @@ impl ExchangeProcessor {
             Err(InstructionError::GenericError)?
         }
 
-        let token = match order.side {
-            OrderSide::Ask => order.pair.Base,
-            OrderSide::Bid => order.pair.Quote,
+        if let Some(order_book_key) = order.order_book {
+            if keyed_accounts.len() < 3
+                || keyed_accounts[ORDER_BOOK_INDEX].unsigned_key() != &order_book_key
+            {
+                error!("Order book the order was placed in not provided");
+                Err(InstructionError::InvalidArgument)?
+            }
+            let mut order_book =
+                Self::deserialize_order_book(&keyed_accounts[ORDER_BOOK_INDEX].account.data)?;
+            // The order already left the book if it was evicted or became too small to trade
+            if order_book.remove(keyed_accounts[ORDER_INDEX].unsigned_key()) {
+                Self::serialize(
+                    &ExchangeState::OrderBook(order_book),
+                    &mut keyed_accounts[ORDER_BOOK_INDEX].account.data,
+                )?;
+            }
+        }
+
+        let (token, settled_token) = match order.side {
+            OrderSide::Ask => (order.pair.Base, order.pair.Quote),
+            OrderSide::Bid => (order.pair.Quote, order.pair.Base),
         };
 
         let mut account = TokenAccountInfo::default().owner(&order.owner);
         account.tokens[token] = order.tokens;
-        account.tokens[token] += order.tokens_settled;
+        account.tokens[settled_token] += order.tokens_settled;
 
         // Turn trade order into a token account
         Self::serialize(
@@ -423,410 +498,1780 @@ impl ExchangeProcessor {
             &mut keyed_accounts[PROFIT_ACCOUNT_INDEX].account.data,
         )
     }
-}
 
-pub fn process_instruction(
-    _program_id: &Pubkey,
-    keyed_accounts: &mut [KeyedAccount],
-    data: &[u8],
-) -> Result<(), InstructionError> {
-    solana_logger::setup();
+    fn do_order_book_request(
+        keyed_accounts: &mut [KeyedAccount],
+        pair: AssetPair,
+    ) -> Result<(), InstructionError> {
+        const ORDER_BOOK_INDEX: usize = 1;
 
-    let command = bincode::deserialize::<ExchangeInstruction>(data).map_err(|err| {
-        info!("Invalid transaction data: {:?} {:?}", data, err);
-        InstructionError::InvalidInstructionData
-    })?;
+        if keyed_accounts.len() < 2 {
+            error!("Not enough accounts");
+            Err(InstructionError::InvalidArgument)?
+        }
+        if pair.Base == pair.Quote {
+            error!("Order book pair trades a token for itself");
+            Err(InstructionError::InvalidArgument)?
+        }
 
-    trace!("{:?}", command);
+        Self::is_account_unallocated(&keyed_accounts[ORDER_BOOK_INDEX].account.data)?;
+        Self::serialize(
+            &ExchangeState::OrderBook(OrderBookInfo::new(pair)),
+            &mut keyed_accounts[ORDER_BOOK_INDEX].account.data,
+        )
+    }
 
-    match command {
-        ExchangeInstruction::AccountRequest => {
-            ExchangeProcessor::do_account_request(keyed_accounts)
+    fn do_place_order(
+        keyed_accounts: &mut [KeyedAccount],
+        info: &OrderRequestInfo,
+    ) -> Result<(), InstructionError> {
+        const ORDER_INDEX: usize = 1;
+        const ORDER_BOOK_INDEX: usize = 3;
+
+        if keyed_accounts.len() < 4 {
+            error!("Not enough accounts");
+            Err(InstructionError::InvalidArgument)?
         }
-        ExchangeInstruction::TransferRequest(token, tokens) => {
-            ExchangeProcessor::do_transfer_request(keyed_accounts, token, tokens)
+
+        let mut order_book =
+            Self::deserialize_order_book(&keyed_accounts[ORDER_BOOK_INDEX].account.data)?;
+        if order_book.pair != info.pair {
+            error!("Mismatched token pairs");
+            Err(InstructionError::InvalidArgument)?
         }
-        ExchangeInstruction::OrderRequest(info) => {
-            ExchangeProcessor::do_order_request(keyed_accounts, &info)
+        if let Err(e) = check_trade(info.side, info.tokens, info.price) {
+            error!("{}", e);
+            Err(InstructionError::InvalidArgument)?
         }
-        ExchangeInstruction::OrderCancellation => {
-            ExchangeProcessor::do_order_cancellation(keyed_accounts)
+        if order_book.num_orders(info.side) >= MAX_BOOK_SIDE_ORDERS {
+            // A full side makes room by evicting its worst priced order for a better priced one
+            let worst_price = order_book.worst_price(info.side).unwrap();
+            let is_better_priced = match info.side {
+                OrderSide::Ask => info.price < worst_price,
+                OrderSide::Bid => info.price > worst_price,
+            };
+            if !is_better_priced {
+                error!("Order book side is full");
+                Err(InstructionError::InvalidArgument)?
+            }
+            let evicted_order = order_book.remove_worst_order(info.side).unwrap();
+            debug!("Evicted order {} from the order book", evicted_order);
         }
-        ExchangeInstruction::SwapRequest => ExchangeProcessor::do_swap_request(keyed_accounts),
-    }
-}
 
-#[cfg(test)]
-mod test {
-    use super::*;
-    use crate::{exchange_instruction, id};
-    use solana_runtime::bank::Bank;
-    use solana_runtime::bank_client::BankClient;
-    use solana_sdk::client::SyncClient;
-    use solana_sdk::genesis_block::create_genesis_block;
-    use solana_sdk::signature::{Keypair, KeypairUtil};
-    use solana_sdk::system_instruction;
-    use std::mem;
+        let order_book_key = *keyed_accounts[ORDER_BOOK_INDEX].unsigned_key();
+        Self::do_order_request(keyed_accounts, info, Some(order_book_key))?;
 
-    fn try_calc(
-        scaler: u64,
-        primary_tokens: u64,
-        primary_price: u64,
-        secondary_tokens: u64,
-        secondary_price: u64,
-        primary_tokens_expect: u64,
-        secondary_tokens_expect: u64,
-        primary_tokens_settled_expect: u64,
-        secondary_tokens_settled_expect: u64,
-        profit_account_tokens: Tokens,
-    ) -> Result<(), InstructionError> {
-        trace!(
-            "Swap {} for {} to {} for {}",
-            primary_tokens,
-            primary_price,
-            secondary_tokens,
-            secondary_price,
+        order_book.insert(
+            *keyed_accounts[ORDER_INDEX].unsigned_key(),
+            info.side,
+            info.price,
         );
-        let mut to_trade = OrderInfo::default();
-        let mut from_trade = OrderInfo::default().side(OrderSide::Bid);
-        let mut profit_account = TokenAccountInfo::default();
+        Self::serialize(
+            &ExchangeState::OrderBook(order_book),
+            &mut keyed_accounts[ORDER_BOOK_INDEX].account.data,
+        )
+    }
 
-        to_trade.tokens = primary_tokens;
-        to_trade.price = primary_price;
-        from_trade.tokens = secondary_tokens;
-        from_trade.price = secondary_price;
-        ExchangeProcessor::calculate_swap(
-            scaler,
-            &mut to_trade,
-            &mut from_trade,
-            &mut profit_account,
-        )?;
+    fn do_cancel_order(keyed_accounts: &mut [KeyedAccount]) -> Result<(), InstructionError> {
+        const ORDER_INDEX: usize = 1;
+        const ORDER_BOOK_INDEX: usize = 2;
 
-        trace!(
-            "{:?} {:?} {:?} {:?}\n{:?}\n{:?}\n{:?}\n{:?}",
-            to_trade.tokens,
-            primary_tokens_expect,
-            from_trade.tokens,
-            secondary_tokens_expect,
-            primary_tokens_settled_expect,
-            secondary_tokens_settled_expect,
-            profit_account.tokens,
-            profit_account_tokens
-        );
+        if keyed_accounts.len() < 3 {
+            error!("Not enough accounts");
+            Err(InstructionError::InvalidArgument)?
+        }
 
-        assert_eq!(to_trade.tokens, primary_tokens_expect);
-        assert_eq!(from_trade.tokens, secondary_tokens_expect);
-        assert_eq!(to_trade.tokens_settled, primary_tokens_settled_expect);
-        assert_eq!(from_trade.tokens_settled, secondary_tokens_settled_expect);
-        assert_eq!(profit_account.tokens, profit_account_tokens);
-        Ok(())
+        let order = Self::deserialize_order(&keyed_accounts[ORDER_INDEX].account.data)?;
+        if order.order_book.as_ref() != Some(keyed_accounts[ORDER_BOOK_INDEX].unsigned_key()) {
+            error!("Order was not placed in the order book");
+            Err(InstructionError::InvalidArgument)?
+        }
+
+        Self::do_order_cancellation(keyed_accounts)
     }
 
-    #[test]
-    #[rustfmt::skip]
-    fn test_calculate_swap() {
-        solana_logger::setup();
+    fn do_match_orders(keyed_accounts: &mut [KeyedAccount]) -> Result<(), InstructionError> {
+        const ORDER_BOOK_INDEX: usize = 1;
+        const PROFIT_ACCOUNT_INDEX: usize = 2;
+        const FIRST_ORDER_INDEX: usize = 3;
 
-        try_calc(1,     50,     2,   50,    1,  0, 0, 50,   50, Tokens::new(   0, 0, 0, 0)).unwrap_err();
-        try_calc(1,     50,     1,    0,    1,  0, 0, 50,   50, Tokens::new(   0, 0, 0, 0)).unwrap_err();
-        try_calc(1,      0,     1,   50,    1,  0, 0, 50,   50, Tokens::new(   0, 0, 0, 0)).unwrap_err();
-        try_calc(1,     50,     1,   50,    0,  0, 0, 50,   50, Tokens::new(   0, 0, 0, 0)).unwrap_err();
-        try_calc(1,     50,     0,   50,    1,  0, 0, 50,   50, Tokens::new(   0, 0, 0, 0)).unwrap_err();
-        try_calc(1,       1,    2,    2,    3,  1, 2,  0,    0, Tokens::new(   0, 0, 0, 0)).unwrap_err();
+        if keyed_accounts.len() < 5 {
+            error!("Not enough accounts");
+            Err(InstructionError::InvalidArgument)?
+        }
 
-        try_calc(1,     50,     1,   50,    1,  0, 0, 50,   50, Tokens::new(   0, 0, 0, 0)).unwrap();
-        try_calc(1,       1,    2,    3,    3,  0, 0,  2,    1, Tokens::new(   0, 1, 0, 0)).unwrap();
-        try_calc(1,       2,    2,    3,    3,  1, 0,  2,    1, Tokens::new(   0, 1, 0, 0)).unwrap();
-        try_calc(1,       3,    2,    3,    3,  2, 0,  2,    1, Tokens::new(   0, 1, 0, 0)).unwrap();
-        try_calc(1,       3,    2,    6,    3,  1, 0,  4,    2, Tokens::new(   0, 2, 0, 0)).unwrap();
-        try_calc(1000,    1, 2000,    3, 3000,  0, 0,  2,    1, Tokens::new(   0, 1, 0, 0)).unwrap();
-        try_calc(1,       3,    2,    7,    3,  1, 1,  4,    2, Tokens::new(   0, 2, 0, 0)).unwrap();
-        try_calc(1000, 3000,  333, 1000,  500,  0, 1,999, 1998, Tokens::new(1002, 0, 0, 0)).unwrap();
-        try_calc(1000,   50,  100,   50,  101,  0,45,  5,   49, Tokens::new(   1, 0, 0, 0)).unwrap();
-    }
+        let mut order_book =
+            Self::deserialize_order_book(&keyed_accounts[ORDER_BOOK_INDEX].account.data)?;
+        let mut profit_account =
+            Self::deserialize_account(&keyed_accounts[PROFIT_ACCOUNT_INDEX].account.data)?;
 
-    fn create_bank(lamports: u64) -> (Bank, Keypair) {
-        let (genesis_block, mint_keypair) = create_genesis_block(lamports);
-        let mut bank = Bank::new(&genesis_block);
-        bank.add_instruction_processor(id(), process_instruction);
-        (bank, mint_keypair)
-    }
+        let mut num_matches = 0;
+        let mut num_evictions = 0;
+        while let (Some(&ask_key), Some(&bid_key)) = (
+            order_book.best_order(OrderSide::Ask),
+            order_book.best_order(OrderSide::Bid),
+        ) {
+            let find_order = |key| {
+                (FIRST_ORDER_INDEX..keyed_accounts.len())
+                    .find(|i| keyed_accounts[*i].unsigned_key() == key)
+            };
+            let (ask_index, bid_index) = match (find_order(&ask_key), find_order(&bid_key)) {
+                (Some(ask_index), Some(bid_index)) => (ask_index, bid_index),
+                _ => {
+                    debug!("Orders at the top of the book not provided");
+                    break;
+                }
+            };
 
-    fn create_client(bank: Bank, mint_keypair: Keypair) -> (BankClient, Keypair) {
-        let owner = Keypair::new();
-        let bank_client = BankClient::new(bank);
-        bank_client
-            .transfer(42, &mint_keypair, &owner.pubkey())
-            .unwrap();
+            // Orders that are no longer trades can't be matched, drop them from the book
+            let ask_state: ExchangeState =
+                bincode::deserialize(&keyed_accounts[ask_index].account.data)
+                    .map_err(Self::map_to_invalid_arg)?;
+            let bid_state: ExchangeState =
+                bincode::deserialize(&keyed_accounts[bid_index].account.data)
+                    .map_err(Self::map_to_invalid_arg)?;
+            let (mut ask, mut bid) = match (ask_state, bid_state) {
+                (ExchangeState::Trade(ask), ExchangeState::Trade(bid)) => (ask, bid),
+                (ExchangeState::Trade(_), _) => {
+                    order_book.remove(&bid_key);
+                    num_evictions += 1;
+                    continue;
+                }
+                _ => {
+                    order_book.remove(&ask_key);
+                    num_evictions += 1;
+                    continue;
+                }
+            };
 
-        (bank_client, owner)
-    }
+            if bid.price < ask.price {
+                break;
+            }
+            if Self::calculate_swap(SCALER, &mut ask, &mut bid, &mut profit_account).is_err() {
+                // Evict whichever order limits the trade so the orders behind it can match,
+                // it stays a trade order its owner can cancel
+                let unfillable_key = if check_trade(ask.side, ask.tokens, ask.price).is_err() {
+                    ask_key
+                } else if check_trade(bid.side, bid.tokens, bid.price).is_err()
+                    || bid
+                        .tokens
+                        .checked_mul(SCALER)
+                        .map_or(false, |scaled_tokens| {
+                            scaled_tokens / bid.price < ask.tokens
+                        })
+                {
+                    bid_key
+                } else {
+                    ask_key
+                };
+                debug!(
+                    "Swap calculation failed from {} for {} to {} for {}, evicting order {}",
+                    bid.tokens, bid.price, ask.tokens, ask.price, unfillable_key,
+                );
+                order_book.remove(&unfillable_key);
+                num_evictions += 1;
+                continue;
+            }
+            num_matches += 1;
 
-    fn create_account(client: &BankClient, owner: &Keypair) -> Pubkey {
-        let new = Pubkey::new_rand();
-        let instruction = system_instruction::create_account(
-            &owner.pubkey(),
-            &new,
-            1,
-            mem::size_of::<ExchangeState>() as u64,
-            &id(),
-        );
-        client
-            .send_instruction(&owner, instruction)
-            .expect(&format!("{}:{}", line!(), file!()));
-        new
-    }
+            // Orders too small to trade at their price any longer leave the book
+            for (key, order) in &[(ask_key, ask), (bid_key, bid)] {
+                if order.tokens == 0 || check_trade(order.side, order.tokens, order.price).is_err()
+                {
+                    order_book.remove(key);
+                }
+            }
+            Self::serialize(
+                &Self::order_to_state(ask),
+                &mut keyed_accounts[ask_index].account.data,
+            )?;
+            Self::serialize(
+                &Self::order_to_state(bid),
+                &mut keyed_accounts[bid_index].account.data,
+            )?;
+        }
 
-    fn create_token_account(client: &BankClient, owner: &Keypair) -> Pubkey {
-        let new = create_account(&client, &owner);
-        let instruction = exchange_instruction::account_request(&owner.pubkey(), &new);
-        client
-            .send_instruction(owner, instruction)
-            .expect(&format!("{}:{}", line!(), file!()));
-        new
-    }
+        if num_matches == 0 && num_evictions == 0 {
+            error!("No crossing orders to match");
+            Err(InstructionError::InvalidArgument)?
+        }
 
-    fn transfer(client: &BankClient, owner: &Keypair, to: &Pubkey, token: Token, tokens: u64) {
-        let instruction = exchange_instruction::transfer_request(
-            &owner.pubkey(),
-            to,
-            &faucet::id(),
-            token,
-            tokens,
-        );
-        client
-            .send_instruction(owner, instruction)
-            .expect(&format!("{}:{}", line!(), file!()));
+        inc_new_counter_info!("exchange_processor-matches", num_matches);
+
+        Self::serialize(
+            &ExchangeState::OrderBook(order_book),
+            &mut keyed_accounts[ORDER_BOOK_INDEX].account.data,
+        )?;
+        Self::serialize(
+            &ExchangeState::Account(profit_account),
+            &mut keyed_accounts[PROFIT_ACCOUNT_INDEX].account.data,
+        )
     }
 
-    fn trade(
-        client: &BankClient,
-        owner: &Keypair,
-        side: OrderSide,
-        pair: AssetPair,
-        from_token: Token,
-        src_tokens: u64,
-        trade_tokens: u64,
-        price: u64,
-    ) -> (Pubkey, Pubkey) {
-        let trade = create_account(&client, &owner);
-        let src = create_token_account(&client, &owner);
-        transfer(&client, &owner, &src, from_token, src_tokens);
+    fn do_vault_request(keyed_accounts: &mut [KeyedAccount]) -> Result<(), InstructionError> {
+        const VAULT_INDEX: usize = 1;
+        const TOKEN_ACCOUNT_INDEX: usize = 2;
+        const AUTHORITY_INDEX: usize = 3;
 
-        let instruction = exchange_instruction::trade_request(
-            &owner.pubkey(),
-            &trade,
-            side,
-            pair,
+        if keyed_accounts.len() < 4 {
+            error!("Not enough accounts");
+            Err(InstructionError::InvalidArgument)?
+        }
+
+        Self::is_account_unallocated(&keyed_accounts[VAULT_INDEX].account.data)?;
+        let token_account =
+            Self::deserialize_token_program_account(&keyed_accounts[TOKEN_ACCOUNT_INDEX])?;
+        let token = token_account.token().unwrap();
+
+        // One vault per token, holding its tokens under an authority no one can sign for
+        if keyed_accounts[VAULT_INDEX].unsigned_key() != &vault_address(&token) {
+            error!("Not the token's vault address");
+            Err(InstructionError::InvalidArgument)?
+        }
+        if keyed_accounts[AUTHORITY_INDEX].unsigned_key() != &vault_authority_address(&token) {
+            error!("Not the token's vault authority address");
+            Err(InstructionError::InvalidArgument)?
+        }
+        if token_account
+            .only_owner(keyed_accounts[AUTHORITY_INDEX].unsigned_key())
+            .is_err()
+        {
+            error!("Vault authority does not own the token program account");
+            Err(InstructionError::InvalidArgument)?
+        }
+
+        Self::serialize_approval(&None, &mut keyed_accounts[AUTHORITY_INDEX].account.data)?;
+        Self::serialize(
+            &ExchangeState::Vault(VaultInfo {
+                token,
+                token_account: *keyed_accounts[TOKEN_ACCOUNT_INDEX].unsigned_key(),
+                tokens: 0,
+                prepared_deposit: None,
+            }),
+            &mut keyed_accounts[VAULT_INDEX].account.data,
+        )
+    }
+
+    /// Amount held by the vault's token program account
+    fn vault_amount(
+        vault: &VaultInfo,
+        token_account: &KeyedAccount,
+    ) -> Result<u64, InstructionError> {
+        if &vault.token_account != token_account.unsigned_key() {
+            error!("Not the vault's token program account");
+            Err(InstructionError::InvalidArgument)?
+        }
+        Ok(Self::deserialize_token_program_account(token_account)?
+            .amount()
+            .unwrap())
+    }
+
+    /// Check that the vault's token program account still holds every token credited to
+    /// exchange token accounts
+    fn check_vault(
+        vault: &VaultInfo,
+        token_account: &KeyedAccount,
+    ) -> Result<(), InstructionError> {
+        let amount = Self::vault_amount(vault, token_account)?;
+        if amount < vault.tokens {
+            error!(
+                "Vault holds {} tokens but credited {}",
+                amount, vault.tokens
+            );
+            Err(InstructionError::GenericError)?
+        }
+        Ok(())
+    }
+
+    fn checked_add(balance: u64, tokens: u64) -> Result<u64, InstructionError> {
+        balance.checked_add(tokens).ok_or_else(|| {
+            error!("Balance of {} overflows adding {} tokens", balance, tokens);
+            InstructionError::InvalidArgument
+        })
+    }
+
+    fn do_prepare_deposit(keyed_accounts: &mut [KeyedAccount]) -> Result<(), InstructionError> {
+        const OWNER_INDEX: usize = 0;
+        const VAULT_INDEX: usize = 1;
+        const TOKEN_ACCOUNT_INDEX: usize = 2;
+        const FROM_TOKEN_ACCOUNT_INDEX: usize = 3;
+
+        if keyed_accounts.len() < 4 {
+            error!("Not enough accounts");
+            Err(InstructionError::InvalidArgument)?
+        }
+
+        let mut vault = Self::deserialize_vault(&keyed_accounts[VAULT_INDEX].account.data)?;
+        let vault_amount = Self::vault_amount(&vault, &keyed_accounts[TOKEN_ACCOUNT_INDEX])?;
+
+        let from_token_account =
+            Self::deserialize_token_program_account(&keyed_accounts[FROM_TOKEN_ACCOUNT_INDEX])?;
+        if from_token_account
+            .only_owner(keyed_accounts[OWNER_INDEX].unsigned_key())
+            .is_err()
+        {
+            error!("Signer does not own the token program account to deposit from");
+            Err(InstructionError::GenericError)?
+        }
+        if from_token_account.token() != Ok(vault.token) {
+            error!("Token program account to deposit from holds another token");
+            Err(InstructionError::InvalidArgument)?
+        }
+        if keyed_accounts[FROM_TOKEN_ACCOUNT_INDEX].unsigned_key() == &vault.token_account {
+            error!("Can't deposit from the vault's own token program account");
+            Err(InstructionError::InvalidArgument)?
+        }
+
+        vault.prepared_deposit = Some(PreparedDeposit {
+            owner: *keyed_accounts[OWNER_INDEX].unsigned_key(),
+            source: *keyed_accounts[FROM_TOKEN_ACCOUNT_INDEX].unsigned_key(),
+            source_amount: from_token_account.amount().unwrap(),
+            vault_amount,
+        });
+
+        Self::serialize(
+            &ExchangeState::Vault(vault),
+            &mut keyed_accounts[VAULT_INDEX].account.data,
+        )
+    }
+
+    fn do_deposit_request(
+        keyed_accounts: &mut [KeyedAccount],
+        tokens: u64,
+    ) -> Result<(), InstructionError> {
+        const OWNER_INDEX: usize = 0;
+        const TO_ACCOUNT_INDEX: usize = 1;
+        const VAULT_INDEX: usize = 2;
+        const TOKEN_ACCOUNT_INDEX: usize = 3;
+        const FROM_TOKEN_ACCOUNT_INDEX: usize = 4;
+
+        if keyed_accounts.len() < 5 {
+            error!("Not enough accounts");
+            Err(InstructionError::InvalidArgument)?
+        }
+
+        let mut to_account =
+            Self::deserialize_account(&keyed_accounts[TO_ACCOUNT_INDEX].account.data)?;
+        let mut vault = Self::deserialize_vault(&keyed_accounts[VAULT_INDEX].account.data)?;
+
+        let prepared = match vault.prepared_deposit.take() {
+            Some(prepared) => prepared,
+            None => {
+                error!("No deposit prepared");
+                Err(InstructionError::InvalidArgument)?
+            }
+        };
+        if &prepared.owner != keyed_accounts[OWNER_INDEX].unsigned_key()
+            || &prepared.source != keyed_accounts[FROM_TOKEN_ACCOUNT_INDEX].unsigned_key()
+        {
+            error!("Deposit was prepared by another signer or from another account");
+            Err(InstructionError::GenericError)?
+        }
+
+        // Only credit the tokens transferred from the signer's account into the vault since the
+        // deposit was prepared
+        let source_amount =
+            Self::deserialize_token_program_account(&keyed_accounts[FROM_TOKEN_ACCOUNT_INDEX])?
+                .amount()
+                .unwrap();
+        let vault_amount = Self::vault_amount(&vault, &keyed_accounts[TOKEN_ACCOUNT_INDEX])?;
+        if prepared.source_amount.checked_sub(tokens) != Some(source_amount)
+            || prepared.vault_amount.checked_add(tokens) != Some(vault_amount)
+        {
+            error!("No transfer of {} tokens into the vault", tokens);
+            Err(InstructionError::InvalidArgument)?
+        }
+
+        vault.tokens = Self::checked_add(vault.tokens, tokens)?;
+        Self::check_vault(&vault, &keyed_accounts[TOKEN_ACCOUNT_INDEX])?;
+        to_account.tokens[vault.token] = Self::checked_add(to_account.tokens[vault.token], tokens)?;
+
+        inc_new_counter_info!("exchange_processor-deposits", 1);
+
+        Self::serialize(
+            &ExchangeState::Account(to_account),
+            &mut keyed_accounts[TO_ACCOUNT_INDEX].account.data,
+        )?;
+        Self::serialize(
+            &ExchangeState::Vault(vault),
+            &mut keyed_accounts[VAULT_INDEX].account.data,
+        )
+    }
+
+    fn do_withdraw_request(
+        keyed_accounts: &mut [KeyedAccount],
+        tokens: u64,
+    ) -> Result<(), InstructionError> {
+        const OWNER_INDEX: usize = 0;
+        const FROM_ACCOUNT_INDEX: usize = 1;
+        const VAULT_INDEX: usize = 2;
+        const TOKEN_ACCOUNT_INDEX: usize = 3;
+        const AUTHORITY_INDEX: usize = 4;
+        const TO_TOKEN_ACCOUNT_INDEX: usize = 5;
+
+        if keyed_accounts.len() < 6 {
+            error!("Not enough accounts");
+            Err(InstructionError::InvalidArgument)?
+        }
+
+        let mut from_account =
+            Self::deserialize_account(&keyed_accounts[FROM_ACCOUNT_INDEX].account.data)?;
+        let mut vault = Self::deserialize_vault(&keyed_accounts[VAULT_INDEX].account.data)?;
+
+        if &from_account.owner != keyed_accounts[OWNER_INDEX].unsigned_key() {
+            error!("Signer does not own from account");
+            Err(InstructionError::GenericError)?
+        }
+        if keyed_accounts[AUTHORITY_INDEX].unsigned_key() != &vault_authority_address(&vault.token)
+        {
+            error!("Not the vault's authority");
+            Err(InstructionError::InvalidArgument)?
+        }
+
+        // The withdrawal may only pay out to the signer's own token program account
+        let to_token_account =
+            Self::deserialize_token_program_account(&keyed_accounts[TO_TOKEN_ACCOUNT_INDEX])?;
+        if to_token_account
+            .only_owner(keyed_accounts[OWNER_INDEX].unsigned_key())
+            .is_err()
+        {
+            error!("Signer does not own the token program account to withdraw to");
+            Err(InstructionError::GenericError)?
+        }
+        if to_token_account.token() != Ok(vault.token) {
+            error!("Token program account to withdraw to holds another token");
+            Err(InstructionError::InvalidArgument)?
+        }
+        if keyed_accounts[TO_TOKEN_ACCOUNT_INDEX].unsigned_key() == &vault.token_account {
+            error!("Can't withdraw to the vault's own token program account");
+            Err(InstructionError::InvalidArgument)?
+        }
+        if from_account.tokens[vault.token] < tokens {
+            error!("From account balance too low");
+            Err(InstructionError::GenericError)?
+        }
+        if vault.tokens < tokens {
+            error!("Vault balance too low");
+            Err(InstructionError::GenericError)?
+        }
+
+        from_account.tokens[vault.token] -= tokens;
+        vault.tokens -= tokens;
+        Self::check_vault(&vault, &keyed_accounts[TOKEN_ACCOUNT_INDEX])?;
+
+        // Approve the one token program transfer paying out the withdrawal.  It is bound to the
+        // vault's current amount, which the transfer changes, so it can't be repeated
+        let approval = TransferApproval {
+            source: vault.token_account,
+            source_amount: Self::vault_amount(&vault, &keyed_accounts[TOKEN_ACCOUNT_INDEX])?,
+            destination: *keyed_accounts[TO_TOKEN_ACCOUNT_INDEX].unsigned_key(),
+            amount: tokens,
+        };
+
+        inc_new_counter_info!("exchange_processor-withdrawals", 1);
+
+        Self::serialize_approval(
+            &Some(approval),
+            &mut keyed_accounts[AUTHORITY_INDEX].account.data,
+        )?;
+        Self::serialize(
+            &ExchangeState::Account(from_account),
+            &mut keyed_accounts[FROM_ACCOUNT_INDEX].account.data,
+        )?;
+        Self::serialize(
+            &ExchangeState::Vault(vault),
+            &mut keyed_accounts[VAULT_INDEX].account.data,
+        )
+    }
+}
+
+pub fn process_instruction(
+    _program_id: &Pubkey,
+    keyed_accounts: &mut [KeyedAccount],
+    data: &[u8],
+) -> Result<(), InstructionError> {
+    solana_logger::setup();
+
+    let command = bincode::deserialize::<ExchangeInstruction>(data).map_err(|err| {
+        info!("Invalid transaction data: {:?} {:?}", data, err);
+        InstructionError::InvalidInstructionData
+    })?;
+
+    trace!("{:?}", command);
+
+    // Every instruction acts on behalf of key 0
+    if keyed_accounts.is_empty() || keyed_accounts[0].signer_key().is_none() {
+        error!("key 0 did not sign the transaction");
+        Err(InstructionError::MissingRequiredSignature)?
+    }
+
+    match command {
+        ExchangeInstruction::AccountRequest => {
+            ExchangeProcessor::do_account_request(keyed_accounts)
+        }
+        ExchangeInstruction::TransferRequest(token, tokens) => {
+            ExchangeProcessor::do_transfer_request(keyed_accounts, token, tokens)
+        }
+        ExchangeInstruction::OrderRequest(info) => {
+            ExchangeProcessor::do_order_request(keyed_accounts, &info, None)
+        }
+        ExchangeInstruction::OrderCancellation => {
+            ExchangeProcessor::do_order_cancellation(keyed_accounts)
+        }
+        ExchangeInstruction::SwapRequest => ExchangeProcessor::do_swap_request(keyed_accounts),
+        ExchangeInstruction::OrderBookRequest(pair) => {
+            ExchangeProcessor::do_order_book_request(keyed_accounts, pair)
+        }
+        ExchangeInstruction::PlaceOrder(info) => {
+            ExchangeProcessor::do_place_order(keyed_accounts, &info)
+        }
+        ExchangeInstruction::CancelOrder => ExchangeProcessor::do_cancel_order(keyed_accounts),
+        ExchangeInstruction::MatchOrders => ExchangeProcessor::do_match_orders(keyed_accounts),
+        ExchangeInstruction::VaultRequest => ExchangeProcessor::do_vault_request(keyed_accounts),
+        ExchangeInstruction::PrepareDeposit => {
+            ExchangeProcessor::do_prepare_deposit(keyed_accounts)
+        }
+        ExchangeInstruction::DepositRequest(tokens) => {
+            ExchangeProcessor::do_deposit_request(keyed_accounts, tokens)
+        }
+        ExchangeInstruction::WithdrawRequest(tokens) => {
+            ExchangeProcessor::do_withdraw_request(keyed_accounts, tokens)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{exchange_instruction, id};
+    use solana_runtime::bank::Bank;
+    use solana_runtime::bank_client::BankClient;
+    use solana_sdk::client::SyncClient;
+    use solana_sdk::genesis_block::create_genesis_block;
+    use solana_sdk::message::Message;
+    use solana_sdk::signature::{Keypair, KeypairUtil};
+    use solana_sdk::system_instruction;
+    use solana_token_api::token_instruction;
+    use solana_token_api::token_state::TokenInfo;
+
+    fn try_calc(
+        scaler: u64,
+        primary_tokens: u64,
+        primary_price: u64,
+        secondary_tokens: u64,
+        secondary_price: u64,
+        primary_tokens_expect: u64,
+        secondary_tokens_expect: u64,
+        primary_tokens_settled_expect: u64,
+        secondary_tokens_settled_expect: u64,
+        primary_profit_expect: u64,
+        secondary_profit_expect: u64,
+    ) -> Result<(), InstructionError> {
+        trace!(
+            "Swap {} for {} to {} for {}",
+            primary_tokens,
+            primary_price,
+            secondary_tokens,
+            secondary_price,
+        );
+        let pair = AssetPair {
+            Base: Pubkey::new_rand(),
+            Quote: Pubkey::new_rand(),
+        };
+        let mut to_trade = OrderInfo::default().pair(pair);
+        let mut from_trade = OrderInfo::default().pair(pair).side(OrderSide::Bid);
+        let mut profit_account = TokenAccountInfo::default();
+
+        to_trade.tokens = primary_tokens;
+        to_trade.price = primary_price;
+        from_trade.tokens = secondary_tokens;
+        from_trade.price = secondary_price;
+        ExchangeProcessor::calculate_swap(
+            scaler,
+            &mut to_trade,
+            &mut from_trade,
+            &mut profit_account,
+        )?;
+
+        trace!(
+            "{:?} {:?} {:?} {:?}\n{:?}\n{:?}\n{:?}\n{:?} {:?}",
+            to_trade.tokens,
+            primary_tokens_expect,
+            from_trade.tokens,
+            secondary_tokens_expect,
+            primary_tokens_settled_expect,
+            secondary_tokens_settled_expect,
+            profit_account.tokens,
+            primary_profit_expect,
+            secondary_profit_expect
+        );
+
+        assert_eq!(to_trade.tokens, primary_tokens_expect);
+        assert_eq!(from_trade.tokens, secondary_tokens_expect);
+        assert_eq!(to_trade.tokens_settled, primary_tokens_settled_expect);
+        assert_eq!(from_trade.tokens_settled, secondary_tokens_settled_expect);
+        assert_eq!(profit_account.tokens[pair.Base], primary_profit_expect);
+        assert_eq!(profit_account.tokens[pair.Quote], secondary_profit_expect);
+        Ok(())
+    }
+
+    #[test]
+    #[rustfmt::skip]
+    fn test_calculate_swap() {
+        solana_logger::setup();
+
+        try_calc(1,     50,     2,   50,    1,  0, 0, 50,   50,    0, 0).unwrap_err();
+        try_calc(1,     50,     1,    0,    1,  0, 0, 50,   50,    0, 0).unwrap_err();
+        try_calc(1,      0,     1,   50,    1,  0, 0, 50,   50,    0, 0).unwrap_err();
+        try_calc(1,     50,     1,   50,    0,  0, 0, 50,   50,    0, 0).unwrap_err();
+        try_calc(1,     50,     0,   50,    1,  0, 0, 50,   50,    0, 0).unwrap_err();
+        try_calc(1,       1,    2,    2,    3,  1, 2,  0,    0,    0, 0).unwrap_err();
+
+        try_calc(1,     50,     1,   50,    1,  0, 0, 50,   50,    0, 0).unwrap();
+        try_calc(1,       1,    2,    3,    3,  0, 0,  2,    1,    0, 1).unwrap();
+        try_calc(1,       2,    2,    3,    3,  1, 0,  2,    1,    0, 1).unwrap();
+        try_calc(1,       3,    2,    3,    3,  2, 0,  2,    1,    0, 1).unwrap();
+        try_calc(1,       3,    2,    6,    3,  1, 0,  4,    2,    0, 2).unwrap();
+        try_calc(1000,    1, 2000,    3, 3000,  0, 0,  2,    1,    0, 1).unwrap();
+        try_calc(1,       3,    2,    7,    3,  1, 1,  4,    2,    0, 2).unwrap();
+        try_calc(1000, 3000,  333, 1000,  500,  0, 1,999, 1998, 1002, 0).unwrap();
+        try_calc(1000,   50,  100,   50,  101,  0,45,  5,   49,    1, 0).unwrap();
+    }
+
+    #[test]
+    fn test_check_trade_overflow() {
+        assert!(check_trade(OrderSide::Ask, std::u64::MAX, 2).is_ok());
+        assert!(check_trade(OrderSide::Bid, std::u64::MAX, 2).is_ok());
+        assert!(check_trade(OrderSide::Bid, std::u64::MAX, 0).is_err());
+    }
+
+    fn create_bank(lamports: u64) -> (Bank, Keypair) {
+        let (genesis_block, mint_keypair) = create_genesis_block(lamports);
+        let mut bank = Bank::new(&genesis_block);
+        bank.add_instruction_processor(id(), process_instruction);
+        bank.add_instruction_processor(
+            solana_token_api::id(),
+            solana_token_api::token_processor::process_instruction,
+        );
+        (bank, mint_keypair)
+    }
+
+    fn create_client(bank: Bank, mint_keypair: Keypair) -> (BankClient, Keypair) {
+        let owner = Keypair::new();
+        let bank_client = BankClient::new(bank);
+        bank_client
+            .transfer(100, &mint_keypair, &owner.pubkey())
+            .unwrap();
+
+        (bank_client, owner)
+    }
+
+    fn create_account(client: &BankClient, owner: &Keypair) -> Pubkey {
+        let new = Pubkey::new_rand();
+        let instruction = system_instruction::create_account(
+            &owner.pubkey(),
+            &new,
+            1,
+            TokenAccountInfo::max_account_size(),
+            &id(),
+        );
+        client
+            .send_instruction(owner, instruction)
+            .expect(&format!("{}:{}", line!(), file!()));
+        new
+    }
+
+    fn create_token_account(client: &BankClient, owner: &Keypair) -> Pubkey {
+        let new = create_account(client, owner);
+        let instruction = exchange_instruction::account_request(&owner.pubkey(), &new);
+        client
+            .send_instruction(owner, instruction)
+            .expect(&format!("{}:{}", line!(), file!()));
+        new
+    }
+
+    fn create_token_program_account(
+        client: &BankClient,
+        payer: &Keypair,
+        owner: &Pubkey,
+        token: &Pubkey,
+    ) -> Pubkey {
+        let new = Keypair::new();
+        let instructions = vec![
+            system_instruction::create_account(
+                &payer.pubkey(),
+                &new.pubkey(),
+                1,
+                256,
+                &solana_token_api::id(),
+            ),
+            token_instruction::new_token_account(&new.pubkey(), owner, token),
+        ];
+        client
+            .send_message(&[payer, &new], Message::new(instructions))
+            .expect(&format!("{}:{}", line!(), file!()));
+        new.pubkey()
+    }
+
+    /// A token program token whose supply the owner holds in `account`, along with the
+    /// token's exchange vault
+    struct Asset {
+        token: Token,
+        account: Pubkey,
+        vault: Pubkey,
+        vault_account: Pubkey,
+        authority: Pubkey,
+    }
+
+    fn create_token(client: &BankClient, owner: &Keypair) -> (Keypair, Pubkey) {
+        let token = Keypair::new();
+        let account = create_token_program_account(client, owner, &owner.pubkey(), &token.pubkey());
+        let instructions = vec![
+            system_instruction::create_account(
+                &owner.pubkey(),
+                &token.pubkey(),
+                1,
+                256,
+                &solana_token_api::id(),
+            ),
+            token_instruction::new_token(
+                &token.pubkey(),
+                &account,
+                TokenInfo::new(1_000_000, 0, "A test token", "TEST"),
+            ),
+        ];
+        client
+            .send_message(&[owner, &token], Message::new(instructions))
+            .expect(&format!("{}:{}", line!(), file!()));
+        (token, account)
+    }
+
+    /// Create the vault and vault authority accounts of a token, which only the holder of the
+    /// token's keypair can do
+    fn create_vault_accounts(client: &BankClient, owner: &Keypair, token: &Keypair) {
+        let instructions = vec![
+            system_instruction::create_account_with_seed(
+                &owner.pubkey(),
+                &vault_address(&token.pubkey()),
+                &token.pubkey(),
+                VAULT_SEED,
+                1,
+                TokenAccountInfo::max_account_size(),
+                &id(),
+            ),
+            system_instruction::create_account_with_seed(
+                &owner.pubkey(),
+                &vault_authority_address(&token.pubkey()),
+                &token.pubkey(),
+                VAULT_AUTHORITY_SEED,
+                1,
+                TransferApproval::max_account_size(),
+                &id(),
+            ),
+        ];
+        client
+            .send_message(&[owner, token], Message::new(instructions))
+            .expect(&format!("{}:{}", line!(), file!()));
+    }
+
+    fn create_asset(client: &BankClient, owner: &Keypair) -> Asset {
+        let (token, account) = create_token(client, owner);
+        create_vault_accounts(client, owner, &token);
+
+        let vault = vault_address(&token.pubkey());
+        let authority = vault_authority_address(&token.pubkey());
+        let vault_account =
+            create_token_program_account(client, owner, &authority, &token.pubkey());
+        let instruction = exchange_instruction::vault_request(
+            &owner.pubkey(),
+            &vault,
+            &vault_account,
+            &authority,
+        );
+        client
+            .send_instruction(owner, instruction)
+            .expect(&format!("{}:{}", line!(), file!()));
+
+        Asset {
+            token: token.pubkey(),
+            account,
+            vault,
+            vault_account,
+            authority,
+        }
+    }
+
+    fn asset_pair(base: &Asset, quote: &Asset) -> AssetPair {
+        AssetPair {
+            Base: base.token,
+            Quote: quote.token,
+        }
+    }
+
+    fn deposit(client: &BankClient, owner: &Keypair, asset: &Asset, to: &Pubkey, tokens: u64) {
+        let instructions = vec![
+            exchange_instruction::prepare_deposit(
+                &owner.pubkey(),
+                &asset.vault,
+                &asset.vault_account,
+                &asset.account,
+            ),
+            token_instruction::transfer(
+                &owner.pubkey(),
+                &asset.account,
+                &asset.vault_account,
+                tokens,
+            ),
+            exchange_instruction::deposit_request(
+                &owner.pubkey(),
+                to,
+                &asset.vault,
+                &asset.vault_account,
+                &asset.account,
+                tokens,
+            ),
+        ];
+        client
+            .send_message(&[owner], Message::new(instructions))
+            .expect(&format!("{}:{}", line!(), file!()));
+    }
+
+    fn get_token_program_amount(client: &BankClient, account: &Pubkey) -> u64 {
+        let account_data = client.get_account_data(account).unwrap().unwrap();
+        TokenState::deserialize(&account_data)
+            .unwrap()
+            .amount()
+            .unwrap()
+    }
+
+    fn trade(
+        client: &BankClient,
+        owner: &Keypair,
+        side: OrderSide,
+        pair: AssetPair,
+        from_asset: &Asset,
+        src_tokens: u64,
+        trade_tokens: u64,
+        price: u64,
+    ) -> (Pubkey, Pubkey) {
+        let trade = create_account(client, owner);
+        let src = create_token_account(client, owner);
+        deposit(client, owner, from_asset, &src, src_tokens);
+
+        let instruction = exchange_instruction::trade_request(
+            &owner.pubkey(),
+            &trade,
+            side,
+            pair,
             trade_tokens,
             price,
             &src,
         );
-        client
-            .send_instruction(owner, instruction)
-            .expect(&format!("{}:{}", line!(), file!()));
-        (trade, src)
+        client
+            .send_instruction(owner, instruction)
+            .expect(&format!("{}:{}", line!(), file!()));
+        (trade, src)
+    }
+
+    fn create_order_book(client: &BankClient, owner: &Keypair, pair: AssetPair) -> Pubkey {
+        let new = Pubkey::new_rand();
+        let instruction = system_instruction::create_account(
+            &owner.pubkey(),
+            &new,
+            1,
+            OrderBookInfo::max_account_size(),
+            &id(),
+        );
+        client
+            .send_instruction(owner, instruction)
+            .expect(&format!("{}:{}", line!(), file!()));
+        let instruction = exchange_instruction::order_book_request(&owner.pubkey(), &new, pair);
+        client
+            .send_instruction(owner, instruction)
+            .expect(&format!("{}:{}", line!(), file!()));
+        new
+    }
+
+    fn place(
+        client: &BankClient,
+        owner: &Keypair,
+        order_book: &Pubkey,
+        from_asset: &Asset,
+        side: OrderSide,
+        tokens: u64,
+        price: u64,
+    ) -> Pubkey {
+        let order = create_account(client, owner);
+        let src = create_token_account(client, owner);
+        deposit(client, owner, from_asset, &src, tokens);
+
+        let instruction = exchange_instruction::place_order(
+            &owner.pubkey(),
+            &order,
+            side,
+            get_order_book(client, order_book).pair,
+            tokens,
+            price,
+            &src,
+            order_book,
+        );
+        client
+            .send_instruction(owner, instruction)
+            .expect(&format!("{}:{}", line!(), file!()));
+        order
+    }
+
+    fn get_order_book(client: &BankClient, order_book: &Pubkey) -> OrderBookInfo {
+        let order_book_data = client.get_account_data(order_book).unwrap().unwrap();
+        ExchangeProcessor::deserialize_order_book(&order_book_data).unwrap()
+    }
+
+    #[test]
+    fn test_exchange_new_account() {
+        solana_logger::setup();
+        let (bank, mint_keypair) = create_bank(10_000);
+        let (client, owner) = create_client(bank, mint_keypair);
+
+        let new = create_token_account(&client, &owner);
+        let new_account_data = client.get_account_data(&new).unwrap().unwrap();
+
+        // Check results
+
+        assert_eq!(
+            TokenAccountInfo::default().owner(&owner.pubkey()),
+            ExchangeProcessor::deserialize_account(&new_account_data).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_exchange_new_account_not_unallocated() {
+        solana_logger::setup();
+        let (bank, mint_keypair) = create_bank(10_000);
+        let (client, owner) = create_client(bank, mint_keypair);
+
+        let new = create_token_account(&client, &owner);
+        let instruction = exchange_instruction::account_request(&owner.pubkey(), &new);
+        client
+            .send_instruction(&owner, instruction)
+            .expect_err(&format!("{}:{}", line!(), file!()));
+    }
+
+    #[test]
+    fn test_exchange_new_transfer_request() {
+        solana_logger::setup();
+        let (bank, mint_keypair) = create_bank(10_000);
+        let (client, owner) = create_client(bank, mint_keypair);
+
+        let asset = create_asset(&client, &owner);
+        let from = create_token_account(&client, &owner);
+        let new = create_token_account(&client, &owner);
+        deposit(&client, &owner, &asset, &from, 42);
+
+        let instruction =
+            exchange_instruction::transfer_request(&owner.pubkey(), &new, &from, asset.token, 40);
+        client
+            .send_instruction(&owner, instruction)
+            .expect(&format!("{}:{}", line!(), file!()));
+
+        let from_account_data = client.get_account_data(&from).unwrap().unwrap();
+        let new_account_data = client.get_account_data(&new).unwrap().unwrap();
+
+        // Check results
+
+        assert_eq!(
+            TokenAccountInfo::default()
+                .owner(&owner.pubkey())
+                .tokens(asset.token, 2),
+            ExchangeProcessor::deserialize_account(&from_account_data).unwrap()
+        );
+        assert_eq!(
+            TokenAccountInfo::default()
+                .owner(&owner.pubkey())
+                .tokens(asset.token, 40),
+            ExchangeProcessor::deserialize_account(&new_account_data).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_exchange_deposit_and_withdraw() {
+        solana_logger::setup();
+        let (bank, mint_keypair) = create_bank(10_000);
+        let (client, owner) = create_client(bank, mint_keypair);
+
+        let asset = create_asset(&client, &owner);
+        let account = create_token_account(&client, &owner);
+        deposit(&client, &owner, &asset, &account, 42);
+
+        let vault_data = client.get_account_data(&asset.vault).unwrap().unwrap();
+        assert_eq!(
+            VaultInfo {
+                token: asset.token,
+                token_account: asset.vault_account,
+                tokens: 42,
+                prepared_deposit: None,
+            },
+            ExchangeProcessor::deserialize_vault(&vault_data).unwrap()
+        );
+        assert_eq!(get_token_program_amount(&client, &asset.vault_account), 42);
+        assert_eq!(
+            get_token_program_amount(&client, &asset.account),
+            1_000_000 - 42
+        );
+
+        let prepare_deposit = exchange_instruction::prepare_deposit(
+            &owner.pubkey(),
+            &asset.vault,
+            &asset.vault_account,
+            &asset.account,
+        );
+        let deposit_request = |tokens| {
+            exchange_instruction::deposit_request(
+                &owner.pubkey(),
+                &account,
+                &asset.vault,
+                &asset.vault_account,
+                &asset.account,
+                tokens,
+            )
+        };
+
+        // A deposit without tokens moved into the vault isn't backed
+        let instructions = vec![prepare_deposit.clone(), deposit_request(1)];
+        client
+            .send_message(&[&owner], Message::new(instructions))
+            .expect_err(&format!("{}:{}", line!(), file!()));
+
+        // Nor is one of more tokens than were moved
+        let instructions = vec![
+            prepare_deposit.clone(),
+            token_instruction::transfer(&owner.pubkey(), &asset.account, &asset.vault_account, 1),
+            deposit_request(std::u64::MAX),
+        ];
+        client
+            .send_message(&[&owner], Message::new(instructions))
+            .expect_err(&format!("{}:{}", line!(), file!()));
+
+        // Nor tokens moved into the vault without preparing the deposit first
+        let instruction =
+            token_instruction::transfer(&owner.pubkey(), &asset.account, &asset.vault_account, 1);
+        client
+            .send_instruction(&owner, instruction)
+            .expect(&format!("{}:{}", line!(), file!()));
+        client
+            .send_instruction(&owner, deposit_request(1))
+            .expect_err(&format!("{}:{}", line!(), file!()));
+
+        // And only the signer who prepared a deposit may claim it
+        client
+            .send_instruction(&owner, prepare_deposit.clone())
+            .expect(&format!("{}:{}", line!(), file!()));
+        let other = Keypair::new();
+        client.transfer(3, &owner, &other.pubkey()).unwrap();
+        let other_account =
+            create_token_program_account(&client, &other, &other.pubkey(), &asset.token);
+        let other_exchange_account = create_token_account(&client, &other);
+        let instruction = exchange_instruction::deposit_request(
+            &other.pubkey(),
+            &other_exchange_account,
+            &asset.vault,
+            &asset.vault_account,
+            &asset.account,
+            0,
+        );
+        client
+            .send_instruction(&other, instruction)
+            .expect_err(&format!("{}:{}", line!(), file!()));
+
+        let withdraw_to = |from, to, tokens| {
+            let instructions = vec![
+                exchange_instruction::withdraw_request(
+                    &owner.pubkey(),
+                    from,
+                    &asset.vault,
+                    &asset.vault_account,
+                    &asset.authority,
+                    to,
+                    tokens,
+                ),
+                token_instruction::approved_transfer(
+                    &asset.authority,
+                    &asset.vault_account,
+                    to,
+                    tokens,
+                ),
+            ];
+            client.send_message(&[&owner], Message::new(instructions))
+        };
+        let withdraw = |from, tokens| withdraw_to(from, &asset.account, tokens);
+        withdraw(&account, 40).expect(&format!("{}:{}", line!(), file!()));
+
+        // The approved transfer can't be repeated
+        let instruction = token_instruction::approved_transfer(
+            &asset.authority,
+            &asset.vault_account,
+            &asset.account,
+            40,
+        );
+        let message = Message::new_with_payer(vec![instruction], Some(&owner.pubkey()));
+        client
+            .send_message(&[&owner], message)
+            .expect_err(&format!("{}:{}", line!(), file!()));
+
+        // The tokens withdrawn must come out of the signer's exchange balance
+        let empty = create_token_account(&client, &owner);
+        withdraw(&empty, 1).expect_err(&format!("{}:{}", line!(), file!()));
+
+        // Withdrawals must be signed by the owner of the exchange account
+        let mut instruction = exchange_instruction::withdraw_request(
+            &owner.pubkey(),
+            &account,
+            &asset.vault,
+            &asset.vault_account,
+            &asset.authority,
+            &asset.account,
+            0,
+        );
+        instruction.accounts[0].is_signer = false;
+        let message = Message::new_with_payer(vec![instruction], Some(&other.pubkey()));
+        client
+            .send_message(&[&other], message)
+            .expect_err(&format!("{}:{}", line!(), file!()));
+
+        // And paid out to a token program account of theirs
+        withdraw_to(&account, &other_account, 1).expect_err(&format!("{}:{}", line!(), file!()));
+
+        // Check results
+
+        let account_data = client.get_account_data(&account).unwrap().unwrap();
+        assert_eq!(
+            TokenAccountInfo::default()
+                .owner(&owner.pubkey())
+                .tokens(asset.token, 2),
+            ExchangeProcessor::deserialize_account(&account_data).unwrap()
+        );
+        assert_eq!(get_token_program_amount(&client, &asset.vault_account), 3);
+        assert_eq!(
+            get_token_program_amount(&client, &asset.account),
+            1_000_000 - 3
+        );
+    }
+
+    #[test]
+    fn test_exchange_vault_tokens_held_by_authority() {
+        solana_logger::setup();
+        let (bank, mint_keypair) = create_bank(10_000);
+        let (client, owner) = create_client(bank, mint_keypair);
+
+        let asset = create_asset(&client, &owner);
+        let account = create_token_account(&client, &owner);
+        deposit(&client, &owner, &asset, &account, 42);
+
+        // Not even the vault's creator can move the vault's tokens
+        let instruction =
+            token_instruction::transfer(&owner.pubkey(), &asset.vault_account, &asset.account, 1);
+        client
+            .send_instruction(&owner, instruction)
+            .expect_err(&format!("{}:{}", line!(), file!()));
+
+        // Nor transfer them without the exchange approving it
+        let instruction = token_instruction::approved_transfer(
+            &asset.authority,
+            &asset.vault_account,
+            &asset.account,
+            1,
+        );
+        let message = Message::new_with_payer(vec![instruction], Some(&owner.pubkey()));
+        client
+            .send_message(&[&owner], message)
+            .expect_err(&format!("{}:{}", line!(), file!()));
+
+        assert_eq!(get_token_program_amount(&client, &asset.vault_account), 42);
+    }
+
+    #[test]
+    fn test_exchange_vault_request_not_derived() {
+        solana_logger::setup();
+        let (bank, mint_keypair) = create_bank(10_000);
+        let (client, owner) = create_client(bank, mint_keypair);
+
+        let (token, _) = create_token(&client, &owner);
+        create_vault_accounts(&client, &owner, &token);
+        let vault = vault_address(&token.pubkey());
+        let authority = vault_authority_address(&token.pubkey());
+
+        // The vault's token program account must be owned by the vault authority
+        let owned_account =
+            create_token_program_account(&client, &owner, &owner.pubkey(), &token.pubkey());
+        let instruction = exchange_instruction::vault_request(
+            &owner.pubkey(),
+            &vault,
+            &owned_account,
+            &authority,
+        );
+        client
+            .send_instruction(&owner, instruction)
+            .expect_err(&format!("{}:{}", line!(), file!()));
+
+        // And the vault live at the token's vault address
+        let vault_account =
+            create_token_program_account(&client, &owner, &authority, &token.pubkey());
+        let other_vault = create_account(&client, &owner);
+        let instruction = exchange_instruction::vault_request(
+            &owner.pubkey(),
+            &other_vault,
+            &vault_account,
+            &authority,
+        );
+        client
+            .send_instruction(&owner, instruction)
+            .expect_err(&format!("{}:{}", line!(), file!()));
+
+        let instruction = exchange_instruction::vault_request(
+            &owner.pubkey(),
+            &vault,
+            &vault_account,
+            &authority,
+        );
+        client
+            .send_instruction(&owner, instruction)
+            .expect(&format!("{}:{}", line!(), file!()));
+
+        // Only once
+        let instruction = exchange_instruction::vault_request(
+            &owner.pubkey(),
+            &vault,
+            &vault_account,
+            &authority,
+        );
+        client
+            .send_instruction(&owner, instruction)
+            .expect_err(&format!("{}:{}", line!(), file!()));
+    }
+
+    #[test]
+    fn test_exchange_new_trade_request() {
+        solana_logger::setup();
+        let (bank, mint_keypair) = create_bank(10_000);
+        let (client, owner) = create_client(bank, mint_keypair);
+
+        let base = create_asset(&client, &owner);
+        let quote = create_asset(&client, &owner);
+        let pair = asset_pair(&base, &quote);
+        let (trade, src) = trade(&client, &owner, OrderSide::Ask, pair, &base, 42, 2, 1000);
+
+        let trade_account_data = client.get_account_data(&trade).unwrap().unwrap();
+        let src_account_data = client.get_account_data(&src).unwrap().unwrap();
+
+        // check results
+
+        assert_eq!(
+            OrderInfo {
+                owner: owner.pubkey(),
+                side: OrderSide::Ask,
+                pair,
+                tokens: 2,
+                price: 1000,
+                tokens_settled: 0,
+                order_book: None,
+            },
+            ExchangeProcessor::deserialize_order(&trade_account_data).unwrap()
+        );
+        assert_eq!(
+            TokenAccountInfo::default()
+                .owner(&owner.pubkey())
+                .tokens(base.token, 40),
+            ExchangeProcessor::deserialize_account(&src_account_data).unwrap()
+        );
     }
 
     #[test]
-    fn test_exchange_new_account() {
+    fn test_exchange_new_swap_request() {
         solana_logger::setup();
         let (bank, mint_keypair) = create_bank(10_000);
         let (client, owner) = create_client(bank, mint_keypair);
 
-        let new = create_token_account(&client, &owner);
-        let new_account_data = client.get_account_data(&new).unwrap().unwrap();
+        let base = create_asset(&client, &owner);
+        let quote = create_asset(&client, &owner);
+        let pair = asset_pair(&base, &quote);
+        let profit = create_token_account(&client, &owner);
+        let (to_trade, _) = trade(&client, &owner, OrderSide::Ask, pair, &base, 2, 2, 2000);
+        let (from_trade, _) = trade(&client, &owner, OrderSide::Bid, pair, &quote, 3, 3, 3000);
 
-        // Check results
+        let instruction =
+            exchange_instruction::swap_request(&owner.pubkey(), &to_trade, &from_trade, &profit);
+        client
+            .send_instruction(&owner, instruction)
+            .expect(&format!("{}:{}", line!(), file!()));
+
+        let to_trade_account_data = client.get_account_data(&to_trade).unwrap().unwrap();
+        let from_trade_account_data = client.get_account_data(&from_trade).unwrap().unwrap();
+        let profit_account_data = client.get_account_data(&profit).unwrap().unwrap();
+
+        // check results
+
+        assert_eq!(
+            OrderInfo {
+                owner: owner.pubkey(),
+                side: OrderSide::Ask,
+                pair,
+                tokens: 1,
+                price: 2000,
+                tokens_settled: 2,
+                order_book: None,
+            },
+            ExchangeProcessor::deserialize_order(&to_trade_account_data).unwrap()
+        );
 
         assert_eq!(
             TokenAccountInfo::default()
                 .owner(&owner.pubkey())
-                .tokens(100_000, 100_000, 100_000, 100_000),
-            ExchangeProcessor::deserialize_account(&new_account_data).unwrap()
+                .tokens(base.token, 1),
+            ExchangeProcessor::deserialize_account(&from_trade_account_data).unwrap()
+        );
+
+        assert_eq!(
+            TokenAccountInfo::default()
+                .owner(&owner.pubkey())
+                .tokens(quote.token, 1),
+            ExchangeProcessor::deserialize_account(&profit_account_data).unwrap()
         );
     }
 
     #[test]
-    fn test_exchange_new_account_not_unallocated() {
+    fn test_exchange_new_order_book() {
         solana_logger::setup();
         let (bank, mint_keypair) = create_bank(10_000);
         let (client, owner) = create_client(bank, mint_keypair);
 
-        let new = create_token_account(&client, &owner);
-        let instruction = exchange_instruction::account_request(&owner.pubkey(), &new);
+        let base = create_asset(&client, &owner);
+        let quote = create_asset(&client, &owner);
+        let pair = asset_pair(&base, &quote);
+        let order_book = create_order_book(&client, &owner, pair);
+        assert_eq!(
+            get_order_book(&client, &order_book),
+            OrderBookInfo::new(pair)
+        );
+
+        let instruction =
+            exchange_instruction::order_book_request(&owner.pubkey(), &order_book, pair);
         client
             .send_instruction(&owner, instruction)
             .expect_err(&format!("{}:{}", line!(), file!()));
     }
 
     #[test]
-    fn test_exchange_new_transfer_request() {
+    fn test_exchange_place_order() {
         solana_logger::setup();
         let (bank, mint_keypair) = create_bank(10_000);
         let (client, owner) = create_client(bank, mint_keypair);
 
-        let new = create_token_account(&client, &owner);
+        let base = create_asset(&client, &owner);
+        let quote = create_asset(&client, &owner);
+        let pair = asset_pair(&base, &quote);
+        let order_book = create_order_book(&client, &owner, pair);
+        let ask0 = place(&client, &owner, &order_book, &base, OrderSide::Ask, 2, 2000);
+        let ask1 = place(&client, &owner, &order_book, &base, OrderSide::Ask, 2, 1000);
+        let ask2 = place(&client, &owner, &order_book, &base, OrderSide::Ask, 2, 1000);
+        let bid0 = place(&client, &owner, &order_book, &quote, OrderSide::Bid, 2, 500);
+        let bid1 = place(&client, &owner, &order_book, &quote, OrderSide::Bid, 2, 800);
 
-        let instruction = exchange_instruction::transfer_request(
+        // Check results
+
+        let mut expected_order_book = OrderBookInfo::new(pair);
+        expected_order_book.asks = vec![
+            PriceLevel {
+                price: 1000,
+                orders: vec![ask1, ask2],
+            },
+            PriceLevel {
+                price: 2000,
+                orders: vec![ask0],
+            },
+        ];
+        expected_order_book.bids = vec![
+            PriceLevel {
+                price: 800,
+                orders: vec![bid1],
+            },
+            PriceLevel {
+                price: 500,
+                orders: vec![bid0],
+            },
+        ];
+        assert_eq!(get_order_book(&client, &order_book), expected_order_book);
+
+        let order_data = client.get_account_data(&ask1).unwrap().unwrap();
+        assert_eq!(
+            OrderInfo {
+                owner: owner.pubkey(),
+                side: OrderSide::Ask,
+                pair,
+                tokens: 2,
+                price: 1000,
+                tokens_settled: 0,
+                order_book: Some(order_book),
+            },
+            ExchangeProcessor::deserialize_order(&order_data).unwrap()
+        );
+
+        // The order must trade the order book's pair
+        let order = create_account(&client, &owner);
+        let src = create_token_account(&client, &owner);
+        deposit(&client, &owner, &quote, &src, 2);
+        let instruction = exchange_instruction::place_order(
             &owner.pubkey(),
-            &new,
-            &faucet::id(),
-            Token::A,
-            42,
+            &order,
+            OrderSide::Ask,
+            asset_pair(&quote, &base),
+            2,
+            1000,
+            &src,
+            &order_book,
         );
         client
             .send_instruction(&owner, instruction)
-            .expect(&format!("{}:{}", line!(), file!()));
+            .expect_err(&format!("{}:{}", line!(), file!()));
+    }
 
-        let new_account_data = client.get_account_data(&new).unwrap().unwrap();
+    #[test]
+    fn test_exchange_cancel_order() {
+        solana_logger::setup();
+        let (bank, mint_keypair) = create_bank(10_000);
+        let (client, owner) = create_client(bank, mint_keypair);
+
+        let base = create_asset(&client, &owner);
+        let quote = create_asset(&client, &owner);
+        let pair = asset_pair(&base, &quote);
+        let order_book = create_order_book(&client, &owner, pair);
+        let order = place(
+            &client,
+            &owner,
+            &order_book,
+            &quote,
+            OrderSide::Bid,
+            2,
+            1000,
+        );
+
+        // The order can't leave the book behind
+        let instruction = exchange_instruction::order_cancellation(&owner.pubkey(), &order);
+        client
+            .send_instruction(&owner, instruction)
+            .expect_err(&format!("{}:{}", line!(), file!()));
+
+        let instruction = exchange_instruction::cancel_order(&owner.pubkey(), &order, &order_book);
+        client
+            .send_instruction(&owner, instruction)
+            .expect(&format!("{}:{}", line!(), file!()));
 
         // Check results
 
+        assert_eq!(
+            get_order_book(&client, &order_book),
+            OrderBookInfo::new(pair)
+        );
+        let order_data = client.get_account_data(&order).unwrap().unwrap();
         assert_eq!(
             TokenAccountInfo::default()
                 .owner(&owner.pubkey())
-                .tokens(100_042, 100_000, 100_000, 100_000),
-            ExchangeProcessor::deserialize_account(&new_account_data).unwrap()
+                .tokens(quote.token, 2),
+            ExchangeProcessor::deserialize_account(&order_data).unwrap()
         );
+
+        // The order is no longer in the book
+        let instruction = exchange_instruction::cancel_order(&owner.pubkey(), &order, &order_book);
+        client
+            .send_instruction(&owner, instruction)
+            .expect_err(&format!("{}:{}", line!(), file!()));
     }
 
     #[test]
-    fn test_exchange_new_trade_request() {
+    fn test_exchange_match_orders() {
         solana_logger::setup();
         let (bank, mint_keypair) = create_bank(10_000);
         let (client, owner) = create_client(bank, mint_keypair);
 
-        let (trade, src) = trade(
+        let base = create_asset(&client, &owner);
+        let quote = create_asset(&client, &owner);
+        let pair = asset_pair(&base, &quote);
+        let profit = create_token_account(&client, &owner);
+        let order_book = create_order_book(&client, &owner, pair);
+        let ask0 = place(&client, &owner, &order_book, &base, OrderSide::Ask, 2, 1000);
+        let ask1 = place(&client, &owner, &order_book, &base, OrderSide::Ask, 2, 1000);
+        let bid = place(&client, &owner, &order_book, &quote, OrderSide::Bid, 3, 500);
+
+        // The book doesn't cross yet
+        let instruction = exchange_instruction::match_orders(
+            &owner.pubkey(),
+            &order_book,
+            &profit,
+            &[ask0, ask1, bid],
+        );
+        client
+            .send_instruction(&owner, instruction)
+            .expect_err(&format!("{}:{}", line!(), file!()));
+
+        let instruction = exchange_instruction::cancel_order(&owner.pubkey(), &bid, &order_book);
+        client
+            .send_instruction(&owner, instruction)
+            .expect(&format!("{}:{}", line!(), file!()));
+        let bid = place(
             &client,
             &owner,
-            OrderSide::Ask,
-            AssetPair::default(),
-            Token::A,
-            42,
-            2,
+            &order_book,
+            &quote,
+            OrderSide::Bid,
+            3,
             1000,
         );
 
-        let trade_account_data = client.get_account_data(&trade).unwrap().unwrap();
-        let src_account_data = client.get_account_data(&src).unwrap().unwrap();
+        let instruction = exchange_instruction::match_orders(
+            &owner.pubkey(),
+            &order_book,
+            &profit,
+            &[ask0, ask1, bid],
+        );
+        client
+            .send_instruction(&owner, instruction)
+            .expect(&format!("{}:{}", line!(), file!()));
 
-        // check results
+        // Check results, the first ask placed fills first
+
+        let ask0_data = client.get_account_data(&ask0).unwrap().unwrap();
+        assert_eq!(
+            TokenAccountInfo::default()
+                .owner(&owner.pubkey())
+                .tokens(quote.token, 2),
+            ExchangeProcessor::deserialize_account(&ask0_data).unwrap()
+        );
 
+        let ask1_data = client.get_account_data(&ask1).unwrap().unwrap();
         assert_eq!(
             OrderInfo {
                 owner: owner.pubkey(),
                 side: OrderSide::Ask,
-                pair: AssetPair::default(),
-                tokens: 2,
+                pair,
+                tokens: 1,
                 price: 1000,
-                tokens_settled: 0
+                tokens_settled: 1,
+                order_book: Some(order_book),
             },
-            ExchangeProcessor::deserialize_order(&trade_account_data).unwrap()
+            ExchangeProcessor::deserialize_order(&ask1_data).unwrap()
         );
+
+        let bid_data = client.get_account_data(&bid).unwrap().unwrap();
         assert_eq!(
             TokenAccountInfo::default()
                 .owner(&owner.pubkey())
-                .tokens(100_040, 100_000, 100_000, 100_000),
-            ExchangeProcessor::deserialize_account(&src_account_data).unwrap()
+                .tokens(base.token, 3),
+            ExchangeProcessor::deserialize_account(&bid_data).unwrap()
         );
+
+        let mut expected_order_book = OrderBookInfo::new(pair);
+        expected_order_book.asks = vec![PriceLevel {
+            price: 1000,
+            orders: vec![ask1],
+        }];
+        assert_eq!(get_order_book(&client, &order_book), expected_order_book);
     }
 
     #[test]
-    fn test_exchange_new_swap_request() {
+    fn test_exchange_match_orders_missing_order() {
         solana_logger::setup();
         let (bank, mint_keypair) = create_bank(10_000);
         let (client, owner) = create_client(bank, mint_keypair);
 
+        let base = create_asset(&client, &owner);
+        let quote = create_asset(&client, &owner);
+        let pair = asset_pair(&base, &quote);
         let profit = create_token_account(&client, &owner);
-        let (to_trade, _) = trade(
-            &client,
-            &owner,
-            OrderSide::Ask,
-            AssetPair::default(),
-            Token::A,
-            2,
-            2,
-            2000,
-        );
-        let (from_trade, _) = trade(
+        let order_book = create_order_book(&client, &owner, pair);
+        let ask0 = place(&client, &owner, &order_book, &base, OrderSide::Ask, 2, 1000);
+        let ask1 = place(&client, &owner, &order_book, &base, OrderSide::Ask, 2, 1000);
+        let bid = place(
             &client,
             &owner,
+            &order_book,
+            &quote,
             OrderSide::Bid,
-            AssetPair::default(),
-            Token::B,
-            3,
-            3,
-            3000,
+            2,
+            1000,
         );
 
+        // Matching stops at the first order at the top of the book that's not provided
         let instruction =
-            exchange_instruction::swap_request(&owner.pubkey(), &to_trade, &from_trade, &profit);
+            exchange_instruction::match_orders(&owner.pubkey(), &order_book, &profit, &[ask1, bid]);
+        client
+            .send_instruction(&owner, instruction)
+            .expect_err(&format!("{}:{}", line!(), file!()));
+
+        let instruction =
+            exchange_instruction::match_orders(&owner.pubkey(), &order_book, &profit, &[ask0, bid]);
         client
             .send_instruction(&owner, instruction)
             .expect(&format!("{}:{}", line!(), file!()));
 
-        let to_trade_account_data = client.get_account_data(&to_trade).unwrap().unwrap();
-        let from_trade_account_data = client.get_account_data(&from_trade).unwrap().unwrap();
-        let profit_account_data = client.get_account_data(&profit).unwrap().unwrap();
+        let mut expected_order_book = OrderBookInfo::new(pair);
+        expected_order_book.asks = vec![PriceLevel {
+            price: 1000,
+            orders: vec![ask1],
+        }];
+        assert_eq!(get_order_book(&client, &order_book), expected_order_book);
+    }
 
-        // check results
+    #[test]
+    fn test_exchange_place_order_full_book() {
+        solana_logger::setup();
+        let (bank, mint_keypair) = create_bank(10_000);
+        let (client, owner) = create_client(bank, mint_keypair);
 
-        assert_eq!(
-            OrderInfo {
-                owner: owner.pubkey(),
-                side: OrderSide::Ask,
-                pair: AssetPair::default(),
-                tokens: 1,
-                price: 2000,
-                tokens_settled: 2,
-            },
-            ExchangeProcessor::deserialize_order(&to_trade_account_data).unwrap()
-        );
+        let base = create_asset(&client, &owner);
+        let quote = create_asset(&client, &owner);
+        let pair = asset_pair(&base, &quote);
+        let order_book = create_order_book(&client, &owner, pair);
+        let asks: Vec<_> = (0..MAX_BOOK_SIDE_ORDERS)
+            .map(|_| place(&client, &owner, &order_book, &base, OrderSide::Ask, 2, 2000))
+            .collect();
 
-        assert_eq!(
-            TokenAccountInfo::default()
-                .owner(&owner.pubkey())
-                .tokens(1, 0, 0, 0),
-            ExchangeProcessor::deserialize_account(&from_trade_account_data).unwrap()
+        // A full side only takes an order priced better than its worst
+        let order = create_account(&client, &owner);
+        let src = create_token_account(&client, &owner);
+        deposit(&client, &owner, &base, &src, 2);
+        let instruction = exchange_instruction::place_order(
+            &owner.pubkey(),
+            &order,
+            OrderSide::Ask,
+            pair,
+            2,
+            2000,
+            &src,
+            &order_book,
         );
+        client
+            .send_instruction(&owner, instruction)
+            .expect_err(&format!("{}:{}", line!(), file!()));
 
+        // The other side of the book has room of its own
+        let bid = place(&client, &owner, &order_book, &quote, OrderSide::Bid, 2, 500);
+        let ask = place(&client, &owner, &order_book, &base, OrderSide::Ask, 2, 1000);
+
+        // Check results, the last order placed at the worst price was evicted
+
+        let mut expected_order_book = OrderBookInfo::new(pair);
+        expected_order_book.asks = vec![
+            PriceLevel {
+                price: 1000,
+                orders: vec![ask],
+            },
+            PriceLevel {
+                price: 2000,
+                orders: asks[..MAX_BOOK_SIDE_ORDERS - 1].to_vec(),
+            },
+        ];
+        expected_order_book.bids = vec![PriceLevel {
+            price: 500,
+            orders: vec![bid],
+        }];
+        assert_eq!(get_order_book(&client, &order_book), expected_order_book);
+
+        // The evicted order can still be cancelled
+        let evicted = asks[MAX_BOOK_SIDE_ORDERS - 1];
+        let instruction =
+            exchange_instruction::cancel_order(&owner.pubkey(), &evicted, &order_book);
+        client
+            .send_instruction(&owner, instruction)
+            .expect(&format!("{}:{}", line!(), file!()));
+        let evicted_data = client.get_account_data(&evicted).unwrap().unwrap();
         assert_eq!(
             TokenAccountInfo::default()
                 .owner(&owner.pubkey())
-                .tokens(100_000, 100_001, 100_000, 100_000),
-            ExchangeProcessor::deserialize_account(&profit_account_data).unwrap()
+                .tokens(base.token, 2),
+            ExchangeProcessor::deserialize_account(&evicted_data).unwrap()
         );
+        assert_eq!(get_order_book(&client, &order_book), expected_order_book);
     }
 
     #[test]
-    fn test_exchange_trade_to_token_account() {
+    fn test_exchange_match_orders_unfillable_order() {
         solana_logger::setup();
         let (bank, mint_keypair) = create_bank(10_000);
         let (client, owner) = create_client(bank, mint_keypair);
 
+        let base = create_asset(&client, &owner);
+        let quote = create_asset(&client, &owner);
+        let pair = asset_pair(&base, &quote);
         let profit = create_token_account(&client, &owner);
-        let (to_trade, _) = trade(
+        let order_book = create_order_book(&client, &owner, pair);
+        let ask = place(&client, &owner, &order_book, &base, OrderSide::Ask, 2, 500);
+        // Too small to buy a whole token from the ask once rounded
+        let bid0 = place(
             &client,
             &owner,
-            OrderSide::Ask,
-            AssetPair::default(),
-            Token::A,
-            3,
-            3,
-            2000,
+            &order_book,
+            &quote,
+            OrderSide::Bid,
+            1,
+            1000,
         );
-        let (from_trade, _) = trade(
+        let bid1 = place(
             &client,
             &owner,
+            &order_book,
+            &quote,
             OrderSide::Bid,
-            AssetPair::default(),
-            Token::B,
-            3,
-            3,
-            3000,
+            2,
+            1000,
+        );
+
+        let instruction = exchange_instruction::match_orders(
+            &owner.pubkey(),
+            &order_book,
+            &profit,
+            &[ask, bid0, bid1],
+        );
+        client
+            .send_instruction(&owner, instruction)
+            .expect(&format!("{}:{}", line!(), file!()));
+
+        // Check results, the unfillable bid left the book and the next one matched
+
+        let ask_data = client.get_account_data(&ask).unwrap().unwrap();
+        assert_eq!(
+            TokenAccountInfo::default()
+                .owner(&owner.pubkey())
+                .tokens(quote.token, 1),
+            ExchangeProcessor::deserialize_account(&ask_data).unwrap()
+        );
+
+        let bid0_data = client.get_account_data(&bid0).unwrap().unwrap();
+        assert_eq!(
+            ExchangeProcessor::deserialize_order(&bid0_data)
+                .unwrap()
+                .tokens,
+            1
         );
 
+        let mut expected_order_book = OrderBookInfo::new(pair);
+        expected_order_book.bids = vec![PriceLevel {
+            price: 1000,
+            orders: vec![bid1],
+        }];
+        assert_eq!(get_order_book(&client, &order_book), expected_order_book);
+    }
+
+    #[test]
+    fn test_exchange_trade_to_token_account() {
+        solana_logger::setup();
+        let (bank, mint_keypair) = create_bank(10_000);
+        let (client, owner) = create_client(bank, mint_keypair);
+
+        let base = create_asset(&client, &owner);
+        let quote = create_asset(&client, &owner);
+        let pair = asset_pair(&base, &quote);
+        let profit = create_token_account(&client, &owner);
+        let (to_trade, _) = trade(&client, &owner, OrderSide::Ask, pair, &base, 3, 3, 2000);
+        let (from_trade, _) = trade(&client, &owner, OrderSide::Bid, pair, &quote, 3, 3, 3000);
+
         let instruction =
             exchange_instruction::swap_request(&owner.pubkey(), &to_trade, &from_trade, &profit);
         client
@@ -835,14 +2280,24 @@ mod test {
 
         let new = create_token_account(&client, &owner);
 
-        let instruction =
-            exchange_instruction::transfer_request(&owner.pubkey(), &new, &to_trade, Token::B, 1);
+        let instruction = exchange_instruction::transfer_request(
+            &owner.pubkey(),
+            &new,
+            &to_trade,
+            quote.token,
+            1,
+        );
         client
             .send_instruction(&owner, instruction)
             .expect(&format!("{}:{}", line!(), file!()));
 
-        let instruction =
-            exchange_instruction::transfer_request(&owner.pubkey(), &new, &from_trade, Token::A, 1);
+        let instruction = exchange_instruction::transfer_request(
+            &owner.pubkey(),
+            &new,
+            &from_trade,
+            base.token,
+            1,
+        );
         client
             .send_instruction(&owner, instruction)
             .expect(&format!("{}:{}", line!(), file!()));
@@ -854,7 +2309,8 @@ mod test {
         assert_eq!(
             TokenAccountInfo::default()
                 .owner(&owner.pubkey())
-                .tokens(100_001, 100_001, 100_000, 100_000),
+                .tokens(base.token, 1)
+                .tokens(quote.token, 1),
             ExchangeProcessor::deserialize_account(&new_account_data).unwrap()
         );
     }
@@ -1,6 +1,7 @@
+use crate::id;
 use serde_derive::{Deserialize, Serialize};
 use solana_sdk::pubkey::Pubkey;
-use std::{error, fmt};
+use std::{cmp, error, fmt};
 
 /// Fixed-point scaler, 10 = one base 10 digit to the right of the decimal, 100 = 2, ...
 /// Used by both price and amount in their fixed point representation
@@ -19,82 +20,68 @@ impl fmt::Display for ExchangeError {
     }
 }
 
-/// Supported token types
-#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
-pub enum Token {
-    A,
-    B,
-    C,
-    D,
-}
-impl Default for Token {
-    fn default() -> Self {
-        Token::A
-    }
-}
+/// Tokens are identified by the pubkey of their token program mint
+pub type Token = Pubkey;
+
+/// Most distinct tokens a single token account can hold
+pub const MAX_ACCOUNT_TOKENS: usize = 4;
 
 // Values of tokens, could be quantities, prices, etc...
-#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
-#[allow(non_snake_case)]
-pub struct Tokens {
-    pub A: u64,
-    pub B: u64,
-    pub C: u64,
-    pub D: u64,
-}
-impl Tokens {
-    pub fn new(a: u64, b: u64, c: u64, d: u64) -> Self {
-        Self {
-            A: a,
-            B: b,
-            C: c,
-            D: d,
-        }
-    }
-}
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct Tokens(Vec<(Token, u64)>);
 impl std::ops::Index<Token> for Tokens {
     type Output = u64;
     fn index(&self, t: Token) -> &u64 {
-        match t {
-            Token::A => &self.A,
-            Token::B => &self.B,
-            Token::C => &self.C,
-            Token::D => &self.D,
-        }
+        self.0
+            .iter()
+            .find(|(token, _)| *token == t)
+            .map_or(&0, |(_, tokens)| tokens)
     }
 }
 impl std::ops::IndexMut<Token> for Tokens {
     fn index_mut(&mut self, t: Token) -> &mut u64 {
-        match t {
-            Token::A => &mut self.A,
-            Token::B => &mut self.B,
-            Token::C => &mut self.C,
-            Token::D => &mut self.D,
-        }
+        // Reuse the slot of a token that was emptied before growing
+        let i = match self.0.iter().position(|(token, _)| *token == t) {
+            Some(i) => i,
+            None => match self.0.iter().position(|(_, tokens)| *tokens == 0) {
+                Some(i) => {
+                    self.0[i].0 = t;
+                    i
+                }
+                None => {
+                    self.0.push((t, 0));
+                    self.0.len() - 1
+                }
+            },
+        };
+        &mut self.0[i].1
+    }
+}
+impl PartialEq for Tokens {
+    fn eq(&self, other: &Self) -> bool {
+        self.0
+            .iter()
+            .all(|(token, tokens)| other[*token] == *tokens)
+            && other
+                .0
+                .iter()
+                .all(|(token, tokens)| self[*token] == *tokens)
     }
 }
+impl Eq for Tokens {}
 
-#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
 #[allow(non_snake_case)]
 pub struct AssetPair {
-    // represents a pair of two token enums that defines a market
+    // represents a pair of two token mints that defines a market
     pub Base: Token,
     // "primary" token and numerator for pricing purposes
     pub Quote: Token,
     // "secondary" token and denominator for pricing purposes
 }
 
-impl Default for AssetPair {
-    fn default() -> AssetPair {
-        AssetPair {
-            Base: Token::A,
-            Quote: Token::B,
-        }
-    }
-}
-
 /// Token accounts are populated with this structure
-#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
 pub struct TokenAccountInfo {
     /// Investor who owns this account
     pub owner: Pubkey,
@@ -107,15 +94,27 @@ impl TokenAccountInfo {
         self.owner = *owner;
         self
     }
-    pub fn tokens(mut self, a: u64, b: u64, c: u64, d: u64) -> Self {
-        self.tokens = Tokens {
-            A: a,
-            B: b,
-            C: c,
-            D: d,
-        };
+    pub fn tokens(mut self, token: Token, tokens: u64) -> Self {
+        self.tokens[token] = tokens;
         self
     }
+
+    /// Account data size needed to hold a token account or a trade order, which turns into a
+    /// token account once it's filled
+    pub fn max_account_size() -> u64 {
+        let account = TokenAccountInfo {
+            tokens: Tokens(vec![(Token::default(), 0); MAX_ACCOUNT_TOKENS]),
+            ..Self::default()
+        };
+        cmp::max(
+            bincode::serialized_size(&ExchangeState::Account(account)).unwrap(),
+            bincode::serialized_size(&ExchangeState::Trade(OrderInfo {
+                order_book: Some(Pubkey::default()),
+                ..OrderInfo::default()
+            }))
+            .unwrap(),
+        )
+    }
 }
 
 /// side of the exchange between two tokens in a pair
@@ -154,6 +153,8 @@ pub struct OrderInfo {
     /// Number of tokens that have been settled so far.  These nay be transferred to another
     /// token account by the owner.
     pub tokens_settled: u64,
+    /// Order book the order was placed in, if any
+    pub order_book: Option<Pubkey>,
 }
 impl Default for OrderInfo {
     fn default() -> Self {
@@ -164,6 +165,7 @@ impl Default for OrderInfo {
             tokens: 0,
             price: 0,
             tokens_settled: 0,
+            order_book: None,
         }
     }
 }
@@ -187,9 +189,13 @@ impl OrderInfo {
 }
 
 pub fn check_trade(side: OrderSide, tokens: u64, price: u64) -> Result<(), ExchangeError> {
+    // A product too large for a u64 trades plenty of tokens
     match side {
         OrderSide::Ask => {
-            if tokens * price / SCALER == 0 {
+            if tokens
+                .checked_mul(price)
+                .map_or(false, |scaled| scaled / SCALER == 0)
+            {
                 Err(ExchangeError::InvalidTrade(format!(
                     "To trade of {} for {}/{} results in 0 tradeable tokens",
                     tokens, SCALER, price
@@ -197,7 +203,11 @@ pub fn check_trade(side: OrderSide, tokens: u64, price: u64) -> Result<(), Excha
             }
         }
         OrderSide::Bid => {
-            if tokens * SCALER / price == 0 {
+            if price == 0
+                || tokens
+                    .checked_mul(SCALER)
+                    .map_or(false, |scaled| scaled / price == 0)
+            {
                 Err(ExchangeError::InvalidTrade(format!(
                     "From trade of {} for {}?{} results in 0 tradeable tokens",
                     tokens, SCALER, price
@@ -208,8 +218,190 @@ pub fn check_trade(side: OrderSide, tokens: u64, price: u64) -> Result<(), Excha
     Ok(())
 }
 
+/// Most orders each side of an order book can hold
+pub const MAX_BOOK_SIDE_ORDERS: usize = 32;
+
+/// Trade orders resting in an order book at the same price
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct PriceLevel {
+    /// Scaled price of the orders, see `OrderInfo::price`
+    pub price: u64,
+    /// Trade order accounts in the order they were placed
+    pub orders: Vec<Pubkey>,
+}
+
+/// Order book accounts are populated with this structure
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct OrderBookInfo {
+    /// Token pair every order in the book trades
+    pub pair: AssetPair,
+    /// Bid price levels, best (highest) price first
+    pub bids: Vec<PriceLevel>,
+    /// Ask price levels, best (lowest) price first
+    pub asks: Vec<PriceLevel>,
+}
+impl OrderBookInfo {
+    pub fn new(pair: AssetPair) -> Self {
+        Self {
+            pair,
+            ..Self::default()
+        }
+    }
+
+    /// Account data size needed to hold a full order book
+    pub fn max_account_size() -> u64 {
+        let level = PriceLevel {
+            price: 0,
+            orders: vec![Pubkey::default()],
+        };
+        let book = OrderBookInfo {
+            bids: vec![level.clone(); MAX_BOOK_SIDE_ORDERS],
+            asks: vec![level; MAX_BOOK_SIDE_ORDERS],
+            ..Self::default()
+        };
+        bincode::serialized_size(&ExchangeState::OrderBook(book)).unwrap()
+    }
+
+    fn levels(&self, side: OrderSide) -> &Vec<PriceLevel> {
+        match side {
+            OrderSide::Ask => &self.asks,
+            OrderSide::Bid => &self.bids,
+        }
+    }
+
+    fn levels_mut(&mut self, side: OrderSide) -> &mut Vec<PriceLevel> {
+        match side {
+            OrderSide::Ask => &mut self.asks,
+            OrderSide::Bid => &mut self.bids,
+        }
+    }
+
+    pub fn num_orders(&self, side: OrderSide) -> usize {
+        self.levels(side)
+            .iter()
+            .map(|level| level.orders.len())
+            .sum()
+    }
+
+    pub fn contains(&self, order: &Pubkey) -> bool {
+        self.bids
+            .iter()
+            .chain(self.asks.iter())
+            .any(|level| level.orders.contains(order))
+    }
+
+    /// The order with price-time priority on one side of the book
+    pub fn best_order(&self, side: OrderSide) -> Option<&Pubkey> {
+        self.levels(side)
+            .first()
+            .and_then(|level| level.orders.first())
+    }
+
+    /// Price of the worst priced orders on one side of the book
+    pub fn worst_price(&self, side: OrderSide) -> Option<u64> {
+        self.levels(side).last().map(|level| level.price)
+    }
+
+    /// Take out the order with the lowest price-time priority on one side of the book
+    pub fn remove_worst_order(&mut self, side: OrderSide) -> Option<Pubkey> {
+        let levels = self.levels_mut(side);
+        let order = levels.last_mut().and_then(|level| level.orders.pop());
+        if levels.last().map_or(false, |level| level.orders.is_empty()) {
+            levels.pop();
+        }
+        order
+    }
+
+    /// Queue the order behind any others at its price
+    pub fn insert(&mut self, order: Pubkey, side: OrderSide, price: u64) {
+        let levels = self.levels_mut(side);
+        let position = levels.iter().position(|level| match side {
+            OrderSide::Ask => level.price >= price,
+            OrderSide::Bid => level.price <= price,
+        });
+        match position {
+            Some(i) if levels[i].price == price => levels[i].orders.push(order),
+            Some(i) => levels.insert(
+                i,
+                PriceLevel {
+                    price,
+                    orders: vec![order],
+                },
+            ),
+            None => levels.push(PriceLevel {
+                price,
+                orders: vec![order],
+            }),
+        }
+    }
+
+    /// Take the order out of the book, returns false if it wasn't in the book
+    pub fn remove(&mut self, order: &Pubkey) -> bool {
+        for levels in &mut [&mut self.bids, &mut self.asks] {
+            for i in 0..levels.len() {
+                if let Some(j) = levels[i].orders.iter().position(|o| o == order) {
+                    levels[i].orders.remove(j);
+                    if levels[i].orders.is_empty() {
+                        levels.remove(i);
+                    }
+                    return true;
+                }
+            }
+        }
+        false
+    }
+}
+
+/// Seed of the address of a token's vault, derived from the token
+pub const VAULT_SEED: &str = "vault";
+/// Seed of the address of a token's vault authority, derived from the token
+pub const VAULT_AUTHORITY_SEED: &str = "vault authority";
+
+/// Address of the one vault a token may have.  Only the holder of the token's keypair can create
+/// the account, with `system_instruction::create_account_with_seed`
+pub fn vault_address(token: &Token) -> Pubkey {
+    Pubkey::create_with_seed(token, VAULT_SEED, &id()).unwrap()
+}
+
+/// Address of the exchange account owning a vault's token program account.  No one holds its
+/// keypair, so tokens only leave the vault in the transfers the exchange approves in its data
+pub fn vault_authority_address(token: &Token) -> Pubkey {
+    Pubkey::create_with_seed(token, VAULT_AUTHORITY_SEED, &id()).unwrap()
+}
+
+/// Deposit started by `PrepareDeposit`, the token program transfer that follows it must move
+/// the deposited tokens from `source` into the vault
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct PreparedDeposit {
+    /// Signer making the deposit
+    pub owner: Pubkey,
+    /// Signer's token program account to deposit from
+    pub source: Pubkey,
+    /// Amount `source` held when the deposit was prepared
+    pub source_amount: u64,
+    /// Amount the vault's token program account held when the deposit was prepared
+    pub vault_amount: u64,
+}
+
+/// Vault accounts are populated with this structure.  Each token has at most one vault, at its
+/// `vault_address`, and the vault's token program account is owned by the token's
+/// `vault_authority_address`.  The vault keeps the record that every credited token stays in
+/// that account
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+pub struct VaultInfo {
+    /// Token the vault holds
+    pub token: Token,
+    /// Token program account holding the deposited tokens
+    pub token_account: Pubkey,
+    /// Number of tokens credited to exchange token accounts, the token program account must
+    /// always hold at least this many
+    pub tokens: u64,
+    /// Deposit waiting for its token program transfer, if any
+    pub prepared_deposit: Option<PreparedDeposit>,
+}
+
 /// Type of exchange account, account's user data is populated with this enum
-#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub enum ExchangeState {
     /// Account's Userdata is unallocated
     Unallocated,
@@ -218,6 +410,10 @@ pub enum ExchangeState {
     // Trade order account
     Trade(OrderInfo),
     Invalid,
+    // Order book account
+    OrderBook(OrderBookInfo),
+    // Record of the deposits held by one of the exchange's token program accounts
+    Vault(VaultInfo),
 }
 impl Default for ExchangeState {
     fn default() -> Self {
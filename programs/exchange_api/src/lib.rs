@@ -14,16 +14,3 @@ solana_sdk::solana_name_id!(
     EXCHANGE_PROGRAM_ID,
     "Exchange11111111111111111111111111111111111"
 );
-
-pub mod faucet {
-    pub const EXCHANGE_FAUCET_ID: [u8; 32] = [
-        3, 147, 111, 103, 210, 47, 23, 11, 176, 29, 147, 89, 237, 155, 21, 62, 107, 105, 157, 1,
-        98, 204, 206, 211, 54, 212, 79, 15, 160, 0, 0, 0,
-    ];
-
-    solana_sdk::solana_name_id!(
-        EXCHANGE_FAUCET_ID,
-        "ExchangeFaucet11111111111111111111111111111"
-    );
-
-}
@@ -1,13 +1,12 @@
 use crate::{
     config, id,
-    stake_state::{StakeAccount, StakeState},
+    stake_state::{Authorized, Lockup, StakeAccount, StakeAuthorize, StakeState},
 };
 use bincode::deserialize;
 use log::*;
 use serde_derive::{Deserialize, Serialize};
 use solana_sdk::{
     account::KeyedAccount,
-    clock::Slot,
     instruction::{AccountMeta, Instruction, InstructionError},
     pubkey::Pubkey,
     system_instruction, sysvar,
@@ -15,24 +14,36 @@ use solana_sdk::{
 
 #[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
 pub enum StakeInstruction {
-    /// `Lockup` a stake until the specified slot
+    /// `Initialize` a stake with its authorities and lockup
     ///
     /// Expects 1 Account:
-    ///    0 - Uninitialized StakeAccount to be lockup'd
+    ///    0 - Uninitialized StakeAccount to be initialized
     ///
-    /// The u64 is the portion of the Stake account balance to be activated,
-    ///    must be less than StakeAccount.lamports
+    /// The Authorized carries the keys that may stake and withdraw, the
+    ///    Lockup the slot before which lamports may only be withdrawn
+    ///    with the custodian's signature
     ///
-    Lockup(Slot),
+    Initialize(Authorized, Lockup),
+
+    /// Authorize a key to manage stake or withdrawal
+    ///
+    /// Expects 1 Account:
+    ///    0 - Initialized or delegated StakeAccount
+    ///
+    /// The current staker or withdrawer, as given by the StakeAuthorize,
+    ///    must sign, as any of the accounts that follow
+    Authorize(Pubkey, StakeAuthorize),
 
     /// `Delegate` a stake to a particular node
     ///
-    /// Expects 3 Accounts:
-    ///    0 - Lockup'd StakeAccount to be delegated <= must have this signature
+    /// Expects 4 Accounts:
+    ///    0 - Initialized StakeAccount to be delegated
     ///    1 - VoteAccount to which this Stake will be delegated
     ///    2 - Clock sysvar Account that carries clock bank epoch
     ///    3 - Config Account that carries stake config
     ///
+    /// The staker must sign, as the StakeAccount or as an account following these
+    ///
     /// The u64 is the portion of the Stake account balance to be activated,
     ///    must be less than StakeAccount.lamports
     ///
@@ -50,30 +61,59 @@ pub enum StakeInstruction {
 
     /// Withdraw unstaked lamports from the stake account
     ///
-    /// Expects 3 Accounts:
+    /// Expects 4 Accounts:
     ///    0 - Delegate StakeAccount
     ///    1 - System account to which the lamports will be transferred,
     ///    2 - Syscall Account that carries epoch
     ///    3 - StakeHistory sysvar that carries stake warmup/cooldown history
     ///
+    /// The withdrawer must sign, as the StakeAccount or as an account following
+    ///    these, and so must the lockup's custodian before the lockup slot
+    ///
     /// The u64 is the portion of the Stake account balance to be withdrawn,
     ///    must be <= StakeAccount.lamports - staked lamports
     Withdraw(u64),
 
     /// Deactivates the stake in the account
     ///
-    /// Expects 2 Accounts:
+    /// Expects 3 Accounts:
     ///    0 - Delegate StakeAccount
     ///    1 - VoteAccount to which the Stake is delegated
     ///    2 - Syscall Account that carries epoch
+    ///
+    /// The staker must sign, as the StakeAccount or as an account following these
     Deactivate,
+
+    /// Split off part of a stake into a new stake account
+    ///
+    /// Expects 2 Accounts:
+    ///    0 - Initialized or delegated StakeAccount to be split
+    ///    1 - Uninitialized StakeAccount that will take the split-off lamports
+    ///
+    /// The staker must sign, as the StakeAccount or as an account following these
+    ///
+    /// The u64 is the portion of the Stake account balance to be moved, which
+    ///    for a delegated StakeAccount must be no more than its stake
+    Split(u64),
+
+    /// Merge one stake account into another with the same delegation
+    ///
+    /// Expects 2 Accounts:
+    ///    0 - StakeAccount to merge into
+    ///    1 - StakeAccount to be merged and emptied
+    ///
+    /// Both must have the same authorities and lockup, and if delegated, must have
+    ///    redeemed the same vote credits. The staker must sign, as the StakeAccount
+    ///    or as an account following these
+    Merge,
 }
 
 pub fn create_stake_account_with_lockup(
     from_pubkey: &Pubkey,
     stake_pubkey: &Pubkey,
     lamports: u64,
-    lockup: Slot,
+    authorized: &Authorized,
+    lockup: &Lockup,
 ) -> Vec<Instruction> {
     vec![
         system_instruction::create_account(
@@ -85,7 +125,7 @@ pub fn create_stake_account_with_lockup(
         ),
         Instruction::new(
             id(),
-            &StakeInstruction::Lockup(lockup),
+            &StakeInstruction::Initialize(*authorized, *lockup),
             vec![AccountMeta::new(*stake_pubkey, false)],
         ),
     ]
@@ -96,7 +136,13 @@ pub fn create_stake_account(
     stake_pubkey: &Pubkey,
     lamports: u64,
 ) -> Vec<Instruction> {
-    create_stake_account_with_lockup(from_pubkey, stake_pubkey, lamports, 0)
+    create_stake_account_with_lockup(
+        from_pubkey,
+        stake_pubkey,
+        lamports,
+        &Authorized::auto(stake_pubkey),
+        &Lockup::default(),
+    )
 }
 
 pub fn create_stake_account_and_delegate_stake(
@@ -106,7 +152,12 @@ pub fn create_stake_account_and_delegate_stake(
     lamports: u64,
 ) -> Vec<Instruction> {
     let mut instructions = create_stake_account(from_pubkey, stake_pubkey, lamports);
-    instructions.push(delegate_stake(stake_pubkey, vote_pubkey, lamports));
+    instructions.push(delegate_stake(
+        stake_pubkey,
+        stake_pubkey,
+        vote_pubkey,
+        lamports,
+    ));
     instructions
 }
 
@@ -121,35 +172,132 @@ pub fn redeem_vote_credits(stake_pubkey: &Pubkey, vote_pubkey: &Pubkey) -> Instr
     Instruction::new(id(), &StakeInstruction::RedeemVoteCredits, account_metas)
 }
 
-pub fn delegate_stake(stake_pubkey: &Pubkey, vote_pubkey: &Pubkey, stake: u64) -> Instruction {
-    let account_metas = vec![
-        AccountMeta::new(*stake_pubkey, true),
-        AccountMeta::new_credit_only(*vote_pubkey, false),
-        AccountMeta::new_credit_only(sysvar::clock::id(), false),
-        AccountMeta::new_credit_only(crate::config::id(), false),
-    ];
+fn metas_for_authorized_signer(
+    stake_pubkey: &Pubkey,
+    authorized_pubkey: &Pubkey, // currently authorized
+    other_params: &[AccountMeta],
+) -> Vec<AccountMeta> {
+    let is_own_signer = authorized_pubkey == stake_pubkey;
+
+    // stake account
+    let mut account_metas = vec![AccountMeta::new(*stake_pubkey, is_own_signer)];
+
+    for meta in other_params {
+        account_metas.push(meta.clone());
+    }
+
+    // append signer at the end
+    if !is_own_signer {
+        account_metas.push(AccountMeta::new_credit_only(*authorized_pubkey, true)) // signer
+    }
+
+    account_metas
+}
+
+pub fn authorize(
+    stake_pubkey: &Pubkey,
+    authorized_pubkey: &Pubkey, // currently authorized
+    new_authorized_pubkey: &Pubkey,
+    stake_authorize: StakeAuthorize,
+) -> Instruction {
+    let account_metas = metas_for_authorized_signer(stake_pubkey, authorized_pubkey, &[]);
+
+    Instruction::new(
+        id(),
+        &StakeInstruction::Authorize(*new_authorized_pubkey, stake_authorize),
+        account_metas,
+    )
+}
+
+pub fn delegate_stake(
+    stake_pubkey: &Pubkey,
+    authorized_pubkey: &Pubkey,
+    vote_pubkey: &Pubkey,
+    stake: u64,
+) -> Instruction {
+    let account_metas = metas_for_authorized_signer(
+        stake_pubkey,
+        authorized_pubkey,
+        &[
+            AccountMeta::new_credit_only(*vote_pubkey, false),
+            AccountMeta::new_credit_only(sysvar::clock::id(), false),
+            AccountMeta::new_credit_only(crate::config::id(), false),
+        ],
+    );
     Instruction::new(id(), &StakeInstruction::DelegateStake(stake), account_metas)
 }
 
-pub fn withdraw(stake_pubkey: &Pubkey, to_pubkey: &Pubkey, lamports: u64) -> Instruction {
-    let account_metas = vec![
-        AccountMeta::new(*stake_pubkey, true),
-        AccountMeta::new_credit_only(*to_pubkey, false),
-        AccountMeta::new_credit_only(sysvar::clock::id(), false),
-        AccountMeta::new_credit_only(sysvar::stake_history::id(), false),
-    ];
+pub fn withdraw(
+    stake_pubkey: &Pubkey,
+    authorized_pubkey: &Pubkey,
+    to_pubkey: &Pubkey,
+    lamports: u64,
+) -> Instruction {
+    let account_metas = metas_for_authorized_signer(
+        stake_pubkey,
+        authorized_pubkey,
+        &[
+            AccountMeta::new_credit_only(*to_pubkey, false),
+            AccountMeta::new_credit_only(sysvar::clock::id(), false),
+            AccountMeta::new_credit_only(sysvar::stake_history::id(), false),
+        ],
+    );
     Instruction::new(id(), &StakeInstruction::Withdraw(lamports), account_metas)
 }
 
-pub fn deactivate_stake(stake_pubkey: &Pubkey, vote_pubkey: &Pubkey) -> Instruction {
-    let account_metas = vec![
-        AccountMeta::new(*stake_pubkey, true),
-        AccountMeta::new_credit_only(*vote_pubkey, false),
-        AccountMeta::new_credit_only(sysvar::clock::id(), false),
-    ];
+pub fn deactivate_stake(
+    stake_pubkey: &Pubkey,
+    authorized_pubkey: &Pubkey,
+    vote_pubkey: &Pubkey,
+) -> Instruction {
+    let account_metas = metas_for_authorized_signer(
+        stake_pubkey,
+        authorized_pubkey,
+        &[
+            AccountMeta::new_credit_only(*vote_pubkey, false),
+            AccountMeta::new_credit_only(sysvar::clock::id(), false),
+        ],
+    );
     Instruction::new(id(), &StakeInstruction::Deactivate, account_metas)
 }
 
+pub fn split(
+    from_pubkey: &Pubkey,
+    stake_pubkey: &Pubkey,
+    authorized_pubkey: &Pubkey,
+    lamports: u64,
+    split_stake_pubkey: &Pubkey,
+) -> Vec<Instruction> {
+    let account_metas = metas_for_authorized_signer(
+        stake_pubkey,
+        authorized_pubkey,
+        &[AccountMeta::new(*split_stake_pubkey, false)],
+    );
+    vec![
+        system_instruction::create_account(
+            from_pubkey,
+            split_stake_pubkey,
+            0,
+            std::mem::size_of::<StakeState>() as u64,
+            &id(),
+        ),
+        Instruction::new(id(), &StakeInstruction::Split(lamports), account_metas),
+    ]
+}
+
+pub fn merge(
+    stake_pubkey: &Pubkey,
+    authorized_pubkey: &Pubkey,
+    source_stake_pubkey: &Pubkey,
+) -> Instruction {
+    let account_metas = metas_for_authorized_signer(
+        stake_pubkey,
+        authorized_pubkey,
+        &[AccountMeta::new(*source_stake_pubkey, false)],
+    );
+    Instruction::new(id(), &StakeInstruction::Merge, account_metas)
+}
+
 pub fn process_instruction(
     _program_id: &Pubkey,
     keyed_accounts: &mut [KeyedAccount],
@@ -169,18 +317,23 @@ pub fn process_instruction(
 
     // TODO: data-driven unpack and dispatch of KeyedAccounts
     match deserialize(data).map_err(|_| InstructionError::InvalidInstructionData)? {
-        StakeInstruction::Lockup(slot) => me.lockup(slot),
+        StakeInstruction::Initialize(authorized, lockup) => me.initialize(&authorized, &lockup),
+        StakeInstruction::Authorize(authorized_pubkey, stake_authorize) => {
+            me.authorize(&authorized_pubkey, stake_authorize, rest)
+        }
         StakeInstruction::DelegateStake(stake) => {
-            if rest.len() != 3 {
+            if rest.len() < 3 {
                 Err(InstructionError::InvalidInstructionData)?;
             }
-            let vote = &rest[0];
+            let (vote_and_sysvars, other_signers) = rest.split_at_mut(3);
+            let vote = &vote_and_sysvars[0];
 
             me.delegate_stake(
                 vote,
                 stake,
-                &sysvar::clock::from_keyed_account(&rest[1])?,
-                &config::from_keyed_account(&rest[2])?,
+                &sysvar::clock::from_keyed_account(&vote_and_sysvars[1])?,
+                &config::from_keyed_account(&vote_and_sysvars[2])?,
+                other_signers,
             )
         }
         StakeInstruction::RedeemVoteCredits => {
@@ -200,28 +353,53 @@ pub fn process_instruction(
             )
         }
         StakeInstruction::Withdraw(lamports) => {
-            if rest.len() != 3 {
+            if rest.len() < 3 {
                 Err(InstructionError::InvalidInstructionData)?;
             }
-            let (to, sysvar) = &mut rest.split_at_mut(1);
+            let (to, rest) = &mut rest.split_at_mut(1);
             let mut to = &mut to[0];
+            let (sysvar, other_signers) = rest.split_at_mut(2);
 
             me.withdraw(
                 lamports,
                 &mut to,
                 &sysvar::clock::from_keyed_account(&sysvar[0])?,
                 &sysvar::stake_history::from_keyed_account(&sysvar[1])?,
+                other_signers,
             )
         }
         StakeInstruction::Deactivate => {
-            if rest.len() != 2 {
+            if rest.len() < 2 {
                 Err(InstructionError::InvalidInstructionData)?;
             }
             let (vote, rest) = rest.split_at_mut(1);
             let vote = &mut vote[0];
-            let clock = &rest[0];
+            let (clock, other_signers) = rest.split_at_mut(1);
+            let clock = &clock[0];
 
-            me.deactivate_stake(vote, &sysvar::clock::from_keyed_account(&clock)?)
+            me.deactivate_stake(
+                vote,
+                &sysvar::clock::from_keyed_account(&clock)?,
+                other_signers,
+            )
+        }
+        StakeInstruction::Split(lamports) => {
+            if rest.is_empty() {
+                Err(InstructionError::InvalidInstructionData)?;
+            }
+            let (split_stake, other_signers) = rest.split_at_mut(1);
+            let split_stake = &mut split_stake[0];
+
+            me.split(lamports, split_stake, other_signers)
+        }
+        StakeInstruction::Merge => {
+            if rest.is_empty() {
+                Err(InstructionError::InvalidInstructionData)?;
+            }
+            let (source_stake, other_signers) = rest.split_at_mut(1);
+            let source_stake = &mut source_stake[0];
+
+            me.merge(source_stake, other_signers)
         }
     }
 }
@@ -269,15 +447,58 @@ mod tests {
             Err(InstructionError::InvalidAccountData),
         );
         assert_eq!(
-            process_instruction(&delegate_stake(&Pubkey::default(), &Pubkey::default(), 0)),
+            process_instruction(&delegate_stake(
+                &Pubkey::default(),
+                &Pubkey::default(),
+                &Pubkey::default(),
+                0
+            )),
             Err(InstructionError::InvalidAccountData),
         );
         assert_eq!(
-            process_instruction(&withdraw(&Pubkey::default(), &Pubkey::new_rand(), 100)),
+            process_instruction(&withdraw(
+                &Pubkey::default(),
+                &Pubkey::default(),
+                &Pubkey::new_rand(),
+                100
+            )),
             Err(InstructionError::InvalidAccountData),
         );
         assert_eq!(
-            process_instruction(&deactivate_stake(&Pubkey::default(), &Pubkey::default())),
+            process_instruction(&deactivate_stake(
+                &Pubkey::default(),
+                &Pubkey::default(),
+                &Pubkey::default()
+            )),
+            Err(InstructionError::InvalidAccountData),
+        );
+        assert_eq!(
+            process_instruction(
+                &split(
+                    &Pubkey::default(),
+                    &Pubkey::default(),
+                    &Pubkey::default(),
+                    100,
+                    &Pubkey::new_rand()
+                )[1]
+            ),
+            Err(InstructionError::InsufficientFunds),
+        );
+        assert_eq!(
+            process_instruction(&merge(
+                &Pubkey::default(),
+                &Pubkey::default(),
+                &Pubkey::new_rand()
+            )),
+            Err(InstructionError::InvalidAccountData),
+        );
+        assert_eq!(
+            process_instruction(&authorize(
+                &Pubkey::default(),
+                &Pubkey::default(),
+                &Pubkey::default(),
+                StakeAuthorize::Staker
+            )),
             Err(InstructionError::InvalidAccountData),
         );
     }
@@ -23,8 +23,8 @@ use solana_vote_api::vote_state::VoteState;
 #[derive(Debug, Serialize, Deserialize, PartialEq, Clone)]
 pub enum StakeState {
     Uninitialized,
-    Lockup(Slot),
-    Stake(Stake),
+    Initialized(Authorized, Lockup),
+    Stake(Authorized, Lockup, Stake),
     RewardsPool,
 }
 
@@ -44,18 +44,129 @@ impl StakeState {
         Self::from(account).and_then(|state: Self| state.stake())
     }
 
+    pub fn authorized_from(account: &Account) -> Option<Authorized> {
+        Self::from(account).and_then(|state: Self| state.authorized())
+    }
+
     pub fn stake(&self) -> Option<Stake> {
         match self {
-            StakeState::Stake(stake) => Some(stake.clone()),
+            StakeState::Stake(_authorized, _lockup, stake) => Some(stake.clone()),
             _ => None,
         }
     }
+
+    pub fn authorized(&self) -> Option<Authorized> {
+        match self {
+            StakeState::Initialized(authorized, _lockup) => Some(*authorized),
+            StakeState::Stake(authorized, _lockup, _stake) => Some(*authorized),
+            _ => None,
+        }
+    }
+
+    pub fn lockup(&self) -> Option<Lockup> {
+        match self {
+            StakeState::Initialized(_authorized, lockup) => Some(*lockup),
+            StakeState::Stake(_authorized, lockup, _stake) => Some(*lockup),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy)]
+pub enum StakeAuthorize {
+    Staker,
+    Withdrawer,
+}
+
+/// the keys that control a stake account
+#[derive(Default, Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy)]
+pub struct Authorized {
+    /// may delegate, deactivate, split and merge the stake
+    pub staker: Pubkey,
+    /// may withdraw lamports from the stake account
+    pub withdrawer: Pubkey,
+}
+
+impl Authorized {
+    /// both authorities held by the same key, typically the stake account's own
+    pub fn auto(authorized: &Pubkey) -> Self {
+        Self {
+            staker: *authorized,
+            withdrawer: *authorized,
+        }
+    }
+
+    fn check(
+        &self,
+        stake_account: &KeyedAccount,
+        other_signers: &[KeyedAccount],
+        stake_authorize: StakeAuthorize,
+    ) -> Result<(), InstructionError> {
+        let authorized = match stake_authorize {
+            StakeAuthorize::Staker => &self.staker,
+            StakeAuthorize::Withdrawer => &self.withdrawer,
+        };
+        if is_signer(authorized, stake_account, other_signers) {
+            Ok(())
+        } else {
+            Err(InstructionError::MissingRequiredSignature)
+        }
+    }
+
+    fn authorize(
+        &mut self,
+        stake_account: &KeyedAccount,
+        other_signers: &[KeyedAccount],
+        new_authorized: &Pubkey,
+        stake_authorize: StakeAuthorize,
+    ) -> Result<(), InstructionError> {
+        self.check(stake_account, other_signers, stake_authorize)?;
+        match stake_authorize {
+            StakeAuthorize::Staker => self.staker = *new_authorized,
+            StakeAuthorize::Withdrawer => self.withdrawer = *new_authorized,
+        }
+        Ok(())
+    }
+}
+
+/// lamports may not be withdrawn until `slot`, unless `custodian` signs
+#[derive(Default, Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy)]
+pub struct Lockup {
+    pub slot: Slot,
+    pub custodian: Pubkey,
+}
+
+impl Lockup {
+    fn is_in_force(
+        &self,
+        clock: &sysvar::clock::Clock,
+        stake_account: &KeyedAccount,
+        other_signers: &[KeyedAccount],
+    ) -> bool {
+        self.slot > clock.slot && !is_signer(&self.custodian, stake_account, other_signers)
+    }
+}
+
+// the stake account itself, or any of the accounts trailing an instruction's
+//  fixed accounts, may carry the signature of an authority
+fn is_signer(
+    pubkey: &Pubkey,
+    stake_account: &KeyedAccount,
+    other_signers: &[KeyedAccount],
+) -> bool {
+    stake_account.signer_key() == Some(pubkey)
+        || other_signers
+            .iter()
+            .any(|account| account.signer_key() == Some(pubkey))
 }
 
 /// Reasons the stake might have had an error
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, FromPrimitive, ToPrimitive)]
 pub enum StakeError {
     NoCreditsToRedeem,
+    InsufficientStake,
+    MergeMismatch,
+    LockupInForce,
 }
 impl<E> DecodeError<E> for StakeError {
     fn type_of() -> &'static str {
@@ -66,6 +177,14 @@ impl std::fmt::Display for StakeError {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
             StakeError::NoCreditsToRedeem => write!(f, "not enough credits to redeem"),
+            StakeError::InsufficientStake => write!(f, "split amount is more than is staked"),
+            StakeError::MergeMismatch => {
+                write!(
+                    f,
+                    "stake accounts with different delegations or unredeemed credits can't be merged"
+                )
+            }
+            StakeError::LockupInForce => write!(f, "lockup has not yet expired"),
         }
     }
 }
@@ -79,7 +198,6 @@ pub struct Stake {
     pub activation_epoch: Epoch, // epoch the stake was activated, std::Epoch::MAX if is a bootstrap stake
     pub deactivation_epoch: Epoch, // epoch the stake was deactivated, std::Epoch::MAX if not deactivated
     pub config: Config,
-    pub lockup: Slot,
 }
 
 impl Default for Stake {
@@ -91,7 +209,6 @@ impl Default for Stake {
             activation_epoch: 0,
             deactivation_epoch: std::u64::MAX,
             config: Config::default(),
-            lockup: 0,
         }
     }
 }
@@ -280,7 +397,6 @@ impl Stake {
             vote_state,
             std::u64::MAX,
             &Config::default(),
-            0,
         )
     }
 
@@ -290,7 +406,6 @@ impl Stake {
         vote_state: &VoteState,
         activation_epoch: Epoch,
         config: &Config,
-        lockup: Slot,
     ) -> Self {
         Self {
             stake,
@@ -298,7 +413,6 @@ impl Stake {
             voter_pubkey: *voter_pubkey,
             credits_observed: vote_state.credits(),
             config: *config,
-            lockup,
             ..Stake::default()
         }
     }
@@ -306,21 +420,63 @@ impl Stake {
     fn deactivate(&mut self, epoch: u64) {
         self.deactivation_epoch = epoch;
     }
+
+    /// split off `lamports` of stake into a new Stake that warms up and cools down
+    ///  alongside this one, so that the two add up to this one in StakeHistory
+    fn split(&mut self, lamports: u64) -> Result<Self, StakeError> {
+        if lamports > self.stake {
+            return Err(StakeError::InsufficientStake);
+        }
+        self.stake -= lamports;
+        Ok(Self {
+            stake: lamports,
+            ..self.clone()
+        })
+    }
+
+    /// fold in a Stake with the same delegation, activated and deactivated in the same
+    ///  epochs, so that warmup and cooldown proceed as they would have for the two.
+    ///  Both must have observed the same credits, so that neither has credits left to
+    ///  redeem that the merged stake would forfeit or redeem twice
+    fn merge(&mut self, other: &Stake) -> Result<(), StakeError> {
+        if self.voter_pubkey != other.voter_pubkey
+            || self.activation_epoch != other.activation_epoch
+            || self.deactivation_epoch != other.deactivation_epoch
+            || self.credits_observed != other.credits_observed
+            || self.config != other.config
+        {
+            return Err(StakeError::MergeMismatch);
+        }
+        self.stake += other.stake;
+        Ok(())
+    }
 }
 
 pub trait StakeAccount {
-    fn lockup(&mut self, slot: Slot) -> Result<(), InstructionError>;
+    fn initialize(
+        &mut self,
+        authorized: &Authorized,
+        lockup: &Lockup,
+    ) -> Result<(), InstructionError>;
+    fn authorize(
+        &mut self,
+        authorized_pubkey: &Pubkey,
+        stake_authorize: StakeAuthorize,
+        other_signers: &[KeyedAccount],
+    ) -> Result<(), InstructionError>;
     fn delegate_stake(
         &mut self,
         vote_account: &KeyedAccount,
         stake: u64,
         clock: &sysvar::clock::Clock,
         config: &Config,
+        other_signers: &[KeyedAccount],
     ) -> Result<(), InstructionError>;
     fn deactivate_stake(
         &mut self,
         vote_account: &KeyedAccount,
         clock: &sysvar::clock::Clock,
+        other_signers: &[KeyedAccount],
     ) -> Result<(), InstructionError>;
     fn redeem_vote_credits(
         &mut self,
@@ -335,43 +491,77 @@ pub trait StakeAccount {
         to: &mut KeyedAccount,
         clock: &sysvar::clock::Clock,
         stake_history: &sysvar::stake_history::StakeHistory,
+        other_signers: &[KeyedAccount],
+    ) -> Result<(), InstructionError>;
+    fn split(
+        &mut self,
+        lamports: u64,
+        split_stake: &mut KeyedAccount,
+        other_signers: &[KeyedAccount],
+    ) -> Result<(), InstructionError>;
+    fn merge(
+        &mut self,
+        source_stake: &mut KeyedAccount,
+        other_signers: &[KeyedAccount],
     ) -> Result<(), InstructionError>;
 }
 
 impl<'a> StakeAccount for KeyedAccount<'a> {
-    fn lockup(&mut self, lockup: Slot) -> Result<(), InstructionError> {
+    fn initialize(
+        &mut self,
+        authorized: &Authorized,
+        lockup: &Lockup,
+    ) -> Result<(), InstructionError> {
         if let StakeState::Uninitialized = self.state()? {
-            self.set_state(&StakeState::Lockup(lockup))
+            self.set_state(&StakeState::Initialized(*authorized, *lockup))
         } else {
             Err(InstructionError::InvalidAccountData)
         }
     }
+    /// Authorize the given pubkey to act as staker or withdrawer. This may be called multiple
+    /// times, but will implicitly withdraw authorization from the previously authorized key,
+    /// which must sign.
+    fn authorize(
+        &mut self,
+        authorized_pubkey: &Pubkey,
+        stake_authorize: StakeAuthorize,
+        other_signers: &[KeyedAccount],
+    ) -> Result<(), InstructionError> {
+        match self.state()? {
+            StakeState::Initialized(mut authorized, lockup) => {
+                authorized.authorize(self, other_signers, authorized_pubkey, stake_authorize)?;
+                self.set_state(&StakeState::Initialized(authorized, lockup))
+            }
+            StakeState::Stake(mut authorized, lockup, stake) => {
+                authorized.authorize(self, other_signers, authorized_pubkey, stake_authorize)?;
+                self.set_state(&StakeState::Stake(authorized, lockup, stake))
+            }
+            _ => Err(InstructionError::InvalidAccountData),
+        }
+    }
     fn delegate_stake(
         &mut self,
         vote_account: &KeyedAccount,
         new_stake: u64,
         clock: &sysvar::clock::Clock,
         config: &Config,
+        other_signers: &[KeyedAccount],
     ) -> Result<(), InstructionError> {
-        if self.signer_key().is_none() {
-            return Err(InstructionError::MissingRequiredSignature);
-        }
-
         if new_stake > self.account.lamports {
             return Err(InstructionError::InsufficientFunds);
         }
 
-        if let StakeState::Lockup(lockup) = self.state()? {
+        if let StakeState::Initialized(authorized, lockup) = self.state()? {
+            authorized.check(self, other_signers, StakeAuthorize::Staker)?;
             let stake = Stake::new(
                 new_stake,
                 vote_account.unsigned_key(),
                 &vote_account.state()?,
                 clock.epoch,
                 config,
-                lockup,
             );
 
-            self.set_state(&StakeState::Stake(stake))
+            self.set_state(&StakeState::Stake(authorized, lockup, stake))
         } else {
             Err(InstructionError::InvalidAccountData)
         }
@@ -380,15 +570,13 @@ impl<'a> StakeAccount for KeyedAccount<'a> {
         &mut self,
         _vote_account: &KeyedAccount, // TODO: used in slashing
         clock: &sysvar::clock::Clock,
+        other_signers: &[KeyedAccount],
     ) -> Result<(), InstructionError> {
-        if self.signer_key().is_none() {
-            return Err(InstructionError::MissingRequiredSignature);
-        }
-
-        if let StakeState::Stake(mut stake) = self.state()? {
+        if let StakeState::Stake(authorized, lockup, mut stake) = self.state()? {
+            authorized.check(self, other_signers, StakeAuthorize::Staker)?;
             stake.deactivate(clock.epoch);
 
-            self.set_state(&StakeState::Stake(stake))
+            self.set_state(&StakeState::Stake(authorized, lockup, stake))
         } else {
             Err(InstructionError::InvalidAccountData)
         }
@@ -400,7 +588,7 @@ impl<'a> StakeAccount for KeyedAccount<'a> {
         rewards: &sysvar::rewards::Rewards,
         stake_history: &sysvar::stake_history::StakeHistory,
     ) -> Result<(), InstructionError> {
-        if let (StakeState::Stake(mut stake), StakeState::RewardsPool) =
+        if let (StakeState::Stake(authorized, lockup, mut stake), StakeState::RewardsPool) =
             (self.state()?, rewards_account.state()?)
         {
             let vote_state: VoteState = vote_account.state()?;
@@ -426,7 +614,7 @@ impl<'a> StakeAccount for KeyedAccount<'a> {
 
                 stake.credits_observed = credits_observed;
 
-                self.set_state(&StakeState::Stake(stake))
+                self.set_state(&StakeState::Stake(authorized, lockup, stake))
             } else {
                 // not worth collecting
                 Err(StakeError::NoCreditsToRedeem.into())
@@ -441,11 +629,8 @@ impl<'a> StakeAccount for KeyedAccount<'a> {
         to: &mut KeyedAccount,
         clock: &sysvar::clock::Clock,
         stake_history: &sysvar::stake_history::StakeHistory,
+        other_signers: &[KeyedAccount],
     ) -> Result<(), InstructionError> {
-        if self.signer_key().is_none() {
-            return Err(InstructionError::MissingRequiredSignature);
-        }
-
         fn transfer(
             from: &mut Account,
             to: &mut Account,
@@ -459,8 +644,9 @@ impl<'a> StakeAccount for KeyedAccount<'a> {
             Ok(())
         }
 
-        match self.state()? {
-            StakeState::Stake(stake) => {
+        let (lockup, staked) = match self.state()? {
+            StakeState::Stake(authorized, lockup, stake) => {
+                authorized.check(self, other_signers, StakeAuthorize::Withdrawer)?;
                 // if we have a deactivation epoch and we're in cooldown
                 let staked = if clock.epoch >= stake.deactivation_epoch {
                     stake.stake(clock.epoch, Some(stake_history))
@@ -470,20 +656,98 @@ impl<'a> StakeAccount for KeyedAccount<'a> {
                     //  might be higher than stake.stake(), 'cuz warmup
                     stake.stake
                 };
+                (lockup, staked)
+            }
+            StakeState::Initialized(authorized, lockup) => {
+                authorized.check(self, other_signers, StakeAuthorize::Withdrawer)?;
+                (lockup, 0)
+            }
+            StakeState::Uninitialized => {
+                if self.signer_key().is_none() {
+                    return Err(InstructionError::MissingRequiredSignature);
+                }
+                (Lockup::default(), 0)
+            }
+            _ => return Err(InstructionError::InvalidAccountData),
+        };
 
-                if lamports > self.account.lamports.saturating_sub(staked) {
-                    return Err(InstructionError::InsufficientFunds);
+        if lockup.is_in_force(clock, self, other_signers) {
+            return Err(StakeError::LockupInForce.into());
+        }
+        if lamports > self.account.lamports.saturating_sub(staked) {
+            return Err(InstructionError::InsufficientFunds);
+        }
+        transfer(&mut self.account, &mut to.account, lamports)
+    }
+    fn split(
+        &mut self,
+        lamports: u64,
+        split_stake: &mut KeyedAccount,
+        other_signers: &[KeyedAccount],
+    ) -> Result<(), InstructionError> {
+        if lamports > self.account.lamports {
+            return Err(InstructionError::InsufficientFunds);
+        }
+        let split_state: StakeState = split_stake.state()?;
+        if split_state != StakeState::Uninitialized {
+            return Err(InstructionError::InvalidAccountData);
+        }
+
+        match self.state()? {
+            StakeState::Stake(authorized, lockup, mut stake) => {
+                authorized.check(self, other_signers, StakeAuthorize::Staker)?;
+                let split = stake.split(lamports)?;
+                self.set_state(&StakeState::Stake(authorized, lockup, stake))?;
+                split_stake.set_state(&StakeState::Stake(authorized, lockup, split))?;
+            }
+            StakeState::Initialized(authorized, lockup) => {
+                authorized.check(self, other_signers, StakeAuthorize::Staker)?;
+                split_stake.set_state(&StakeState::Initialized(authorized, lockup))?;
+            }
+            _ => return Err(InstructionError::InvalidAccountData),
+        }
+        self.account.lamports -= lamports;
+        split_stake.account.lamports += lamports;
+        Ok(())
+    }
+    fn merge(
+        &mut self,
+        source_stake: &mut KeyedAccount,
+        other_signers: &[KeyedAccount],
+    ) -> Result<(), InstructionError> {
+        if self.unsigned_key() == source_stake.unsigned_key() {
+            return Err(InstructionError::InvalidArgument);
+        }
+
+        // the source's authorities and lockup carry over only if they're the same as ours,
+        //  so the staker that signs for this account speaks for both
+        match (self.state()?, source_stake.state()?) {
+            (
+                StakeState::Stake(authorized, lockup, mut stake),
+                StakeState::Stake(source_authorized, source_lockup, source),
+            ) => {
+                authorized.check(self, other_signers, StakeAuthorize::Staker)?;
+                if authorized != source_authorized || lockup != source_lockup {
+                    return Err(StakeError::MergeMismatch.into());
                 }
+                stake.merge(&source)?;
+                self.set_state(&StakeState::Stake(authorized, lockup, stake))?;
             }
-            StakeState::Lockup(lockup) => {
-                if lockup > clock.slot {
-                    return Err(InstructionError::InsufficientFunds);
+            (
+                StakeState::Initialized(authorized, lockup),
+                StakeState::Initialized(source_authorized, source_lockup),
+            ) => {
+                authorized.check(self, other_signers, StakeAuthorize::Staker)?;
+                if authorized != source_authorized || lockup != source_lockup {
+                    return Err(StakeError::MergeMismatch.into());
                 }
             }
-            StakeState::Uninitialized => {}
             _ => return Err(InstructionError::InvalidAccountData),
         }
-        transfer(&mut self.account, &mut to.account, lamports)
+        source_stake.set_state(&StakeState::Uninitialized)?;
+        self.account.lamports += source_stake.account.lamports;
+        source_stake.account.lamports = 0;
+        Ok(())
     }
 }
 
@@ -513,17 +777,22 @@ where
 }
 
 // utility function, used by Bank, tests, genesis
-pub fn create_account(voter_pubkey: &Pubkey, vote_account: &Account, lamports: u64) -> Account {
+pub fn create_account(
+    authorized: &Pubkey,
+    voter_pubkey: &Pubkey,
+    vote_account: &Account,
+    lamports: u64,
+) -> Account {
     let mut stake_account = Account::new(lamports, std::mem::size_of::<StakeState>(), &id());
 
     let vote_state = VoteState::from(vote_account).expect("vote_state");
 
     stake_account
-        .set_state(&StakeState::Stake(Stake::new_bootstrap(
-            lamports,
-            voter_pubkey,
-            &vote_state,
-        )))
+        .set_state(&StakeState::Stake(
+            Authorized::auto(authorized),
+            Lockup::default(),
+            Stake::new_bootstrap(lamports, voter_pubkey, &vote_state),
+        ))
         .expect("set_state");
 
     stake_account
@@ -593,7 +862,7 @@ mod tests {
         let stake_lamports = 42;
         let mut stake_account = Account::new_data_with_space(
             stake_lamports,
-            &StakeState::Lockup(0),
+            &StakeState::Initialized(Authorized::auto(&stake_pubkey), Lockup::default()),
             std::mem::size_of::<StakeState>(),
             &id(),
         )
@@ -604,11 +873,20 @@ mod tests {
 
         {
             let stake_state: StakeState = stake_keyed_account.state().unwrap();
-            assert_eq!(stake_state, StakeState::Lockup(0));
+            assert_eq!(
+                stake_state,
+                StakeState::Initialized(Authorized::auto(&stake_pubkey), Lockup::default())
+            );
         }
 
         assert_eq!(
-            stake_keyed_account.delegate_stake(&vote_keyed_account, 0, &clock, &Config::default()),
+            stake_keyed_account.delegate_stake(
+                &vote_keyed_account,
+                0,
+                &clock,
+                &Config::default(),
+                &[]
+            ),
             Err(InstructionError::MissingRequiredSignature)
         );
 
@@ -619,7 +897,8 @@ mod tests {
                 &vote_keyed_account,
                 stake_lamports,
                 &clock,
-                &Config::default()
+                &Config::default(),
+                &[]
             )
             .is_ok());
 
@@ -627,15 +906,18 @@ mod tests {
         let stake_state: StakeState = stake_keyed_account.state().unwrap();
         assert_eq!(
             stake_state,
-            StakeState::Stake(Stake {
-                voter_pubkey: vote_keypair.pubkey(),
-                credits_observed: vote_state.credits(),
-                stake: stake_lamports,
-                activation_epoch: clock.epoch,
-                deactivation_epoch: std::u64::MAX,
-                config: Config::default(),
-                lockup: 0
-            })
+            StakeState::Stake(
+                Authorized::auto(&stake_pubkey),
+                Lockup::default(),
+                Stake {
+                    voter_pubkey: vote_keypair.pubkey(),
+                    credits_observed: vote_state.credits(),
+                    stake: stake_lamports,
+                    activation_epoch: clock.epoch,
+                    deactivation_epoch: std::u64::MAX,
+                    config: Config::default(),
+                }
+            )
         );
         // verify that delegate_stake can't be called twice StakeState::default()
         // signed keyed account
@@ -644,7 +926,8 @@ mod tests {
                 &vote_keyed_account,
                 stake_lamports,
                 &clock,
-                &Config::default()
+                &Config::default(),
+                &[]
             ),
             Err(InstructionError::InvalidAccountData)
         );
@@ -656,7 +939,8 @@ mod tests {
                 &vote_keyed_account,
                 stake_lamports + 1,
                 &clock,
-                &Config::default()
+                &Config::default(),
+                &[]
             ),
             Err(InstructionError::InsufficientFunds)
         );
@@ -665,7 +949,7 @@ mod tests {
 
         stake_keyed_account.set_state(&stake_state).unwrap();
         assert!(stake_keyed_account
-            .delegate_stake(&vote_keyed_account, 0, &clock, &Config::default())
+            .delegate_stake(&vote_keyed_account, 0, &clock, &Config::default(), &[])
             .is_err());
     }
 
@@ -916,36 +1200,169 @@ mod tests {
     }
 
     #[test]
-    fn test_stake_lockup() {
+    fn test_stake_initialize() {
         let stake_pubkey = Pubkey::new_rand();
         let stake_lamports = 42;
         let mut stake_account =
             Account::new(stake_lamports, std::mem::size_of::<StakeState>(), &id());
+        let custodian = Pubkey::new_rand();
+        let lockup = Lockup { slot: 1, custodian };
 
         // unsigned keyed account
         let mut stake_keyed_account = KeyedAccount::new(&stake_pubkey, false, &mut stake_account);
-        assert_eq!(stake_keyed_account.lockup(1), Ok(()));
+        assert_eq!(
+            stake_keyed_account.initialize(&Authorized::auto(&stake_pubkey), &lockup),
+            Ok(())
+        );
 
         // first time works, as is uninit
         assert_eq!(
             StakeState::from(&stake_keyed_account.account).unwrap(),
-            StakeState::Lockup(1)
+            StakeState::Initialized(Authorized::auto(&stake_pubkey), lockup)
         );
 
-        // 2nd time fails, can't move it from anything other than uninit->lockup
+        // 2nd time fails, can't move it from anything other than uninit->initialized
         assert_eq!(
-            stake_keyed_account.lockup(1),
+            stake_keyed_account.initialize(&Authorized::auto(&stake_pubkey), &lockup),
             Err(InstructionError::InvalidAccountData)
         );
     }
 
+    #[test]
+    fn test_authorize_stake() {
+        let stake_pubkey = Pubkey::new_rand();
+        let stake_lamports = 42;
+        let mut stake_account = Account::new_data_with_space(
+            stake_lamports,
+            &StakeState::Initialized(Authorized::auto(&stake_pubkey), Lockup::default()),
+            std::mem::size_of::<StakeState>(),
+            &id(),
+        )
+        .expect("stake_account");
+
+        let staker = Pubkey::new_rand();
+        let withdrawer = Pubkey::new_rand();
+
+        // unsigned keyed account
+        let mut stake_keyed_account = KeyedAccount::new(&stake_pubkey, false, &mut stake_account);
+        assert_eq!(
+            stake_keyed_account.authorize(&staker, StakeAuthorize::Staker, &[]),
+            Err(InstructionError::MissingRequiredSignature)
+        );
+
+        // signed by the stake account, which holds both authorities to start
+        let mut stake_keyed_account = KeyedAccount::new(&stake_pubkey, true, &mut stake_account);
+        assert_eq!(
+            stake_keyed_account.authorize(&staker, StakeAuthorize::Staker, &[]),
+            Ok(())
+        );
+        assert_eq!(
+            stake_keyed_account.authorize(&withdrawer, StakeAuthorize::Withdrawer, &[]),
+            Ok(())
+        );
+        assert_eq!(
+            StakeState::authorized_from(&stake_keyed_account.account),
+            Some(Authorized { staker, withdrawer })
+        );
+
+        // the stake account's own signature no longer counts
+        assert_eq!(
+            stake_keyed_account.authorize(&stake_pubkey, StakeAuthorize::Staker, &[]),
+            Err(InstructionError::MissingRequiredSignature)
+        );
+
+        // nor does the staker's for rotating the withdrawer
+        let mut staker_account = Account::default();
+        let staker_keyed_account = KeyedAccount::new(&staker, true, &mut staker_account);
+        assert_eq!(
+            stake_keyed_account.authorize(
+                &staker,
+                StakeAuthorize::Withdrawer,
+                &[staker_keyed_account]
+            ),
+            Err(InstructionError::MissingRequiredSignature)
+        );
+
+        // the staker rotates the staker
+        let new_staker = Pubkey::new_rand();
+        let staker_keyed_account = KeyedAccount::new(&staker, true, &mut staker_account);
+        assert_eq!(
+            stake_keyed_account.authorize(
+                &new_staker,
+                StakeAuthorize::Staker,
+                &[staker_keyed_account]
+            ),
+            Ok(())
+        );
+
+        // the old staker may no longer delegate, the new one may
+        let vote_pubkey = Pubkey::new_rand();
+        let mut vote_account =
+            vote_state::create_account(&vote_pubkey, &Pubkey::new_rand(), 0, 100);
+        let vote_keyed_account = KeyedAccount::new(&vote_pubkey, false, &mut vote_account);
+        let staker_keyed_account = KeyedAccount::new(&staker, true, &mut staker_account);
+        assert_eq!(
+            stake_keyed_account.delegate_stake(
+                &vote_keyed_account,
+                stake_lamports,
+                &sysvar::clock::Clock::default(),
+                &Config::default(),
+                &[staker_keyed_account]
+            ),
+            Err(InstructionError::MissingRequiredSignature)
+        );
+        let mut new_staker_account = Account::default();
+        let new_staker_keyed_account =
+            KeyedAccount::new(&new_staker, true, &mut new_staker_account);
+        assert_eq!(
+            stake_keyed_account.delegate_stake(
+                &vote_keyed_account,
+                stake_lamports,
+                &sysvar::clock::Clock::default(),
+                &Config::default(),
+                &[new_staker_keyed_account]
+            ),
+            Ok(())
+        );
+
+        // only the withdrawer may withdraw, even with the staker's signature
+        let to = Pubkey::new_rand();
+        let mut to_account = Account::new(1, 0, &system_program::id());
+        let mut to_keyed_account = KeyedAccount::new(&to, false, &mut to_account);
+        let new_staker_keyed_account =
+            KeyedAccount::new(&new_staker, true, &mut new_staker_account);
+        assert_eq!(
+            stake_keyed_account.withdraw(
+                0,
+                &mut to_keyed_account,
+                &sysvar::clock::Clock::default(),
+                &StakeHistory::default(),
+                &[new_staker_keyed_account]
+            ),
+            Err(InstructionError::MissingRequiredSignature)
+        );
+        let mut withdrawer_account = Account::default();
+        let withdrawer_keyed_account =
+            KeyedAccount::new(&withdrawer, true, &mut withdrawer_account);
+        assert_eq!(
+            stake_keyed_account.withdraw(
+                0,
+                &mut to_keyed_account,
+                &sysvar::clock::Clock::default(),
+                &StakeHistory::default(),
+                &[withdrawer_keyed_account]
+            ),
+            Ok(())
+        );
+    }
+
     #[test]
     fn test_deactivate_stake() {
         let stake_pubkey = Pubkey::new_rand();
         let stake_lamports = 42;
         let mut stake_account = Account::new_data_with_space(
             stake_lamports,
-            &StakeState::Lockup(0),
+            &StakeState::Initialized(Authorized::auto(&stake_pubkey), Lockup::default()),
             std::mem::size_of::<StakeState>(),
             &id(),
         )
@@ -961,17 +1378,10 @@ mod tests {
             vote_state::create_account(&vote_pubkey, &Pubkey::new_rand(), 0, 100);
         let vote_keyed_account = KeyedAccount::new(&vote_pubkey, false, &mut vote_account);
 
-        // unsigned keyed account
-        let mut stake_keyed_account = KeyedAccount::new(&stake_pubkey, false, &mut stake_account);
-        assert_eq!(
-            stake_keyed_account.deactivate_stake(&vote_keyed_account, &clock),
-            Err(InstructionError::MissingRequiredSignature)
-        );
-
         // signed keyed account but not staked yet
         let mut stake_keyed_account = KeyedAccount::new(&stake_pubkey, true, &mut stake_account);
         assert_eq!(
-            stake_keyed_account.deactivate_stake(&vote_keyed_account, &clock),
+            stake_keyed_account.deactivate_stake(&vote_keyed_account, &clock, &[]),
             Err(InstructionError::InvalidAccountData)
         );
 
@@ -986,14 +1396,23 @@ mod tests {
                 &vote_keyed_account,
                 stake_lamports,
                 &clock,
-                &Config::default()
+                &Config::default(),
+                &[]
             ),
             Ok(())
         );
 
+        // unsigned keyed account
+        let mut stake_keyed_account = KeyedAccount::new(&stake_pubkey, false, &mut stake_account);
+        assert_eq!(
+            stake_keyed_account.deactivate_stake(&vote_keyed_account, &clock, &[]),
+            Err(InstructionError::MissingRequiredSignature)
+        );
+
         // Deactivate after staking
+        let mut stake_keyed_account = KeyedAccount::new(&stake_pubkey, true, &mut stake_account);
         assert_eq!(
-            stake_keyed_account.deactivate_stake(&vote_keyed_account, &clock),
+            stake_keyed_account.deactivate_stake(&vote_keyed_account, &clock, &[]),
             Ok(())
         );
     }
@@ -1005,7 +1424,7 @@ mod tests {
         let stake_lamports = 42;
         let mut stake_account = Account::new_data_with_space(
             total_lamports,
-            &StakeState::Lockup(0),
+            &StakeState::Initialized(Authorized::auto(&stake_pubkey), Lockup::default()),
             std::mem::size_of::<StakeState>(),
             &id(),
         )
@@ -1024,7 +1443,8 @@ mod tests {
                 total_lamports,
                 &mut to_keyed_account,
                 &clock,
-                &StakeHistory::default()
+                &StakeHistory::default(),
+                &[]
             ),
             Err(InstructionError::MissingRequiredSignature)
         );
@@ -1036,7 +1456,8 @@ mod tests {
                 total_lamports,
                 &mut to_keyed_account,
                 &clock,
-                &StakeHistory::default()
+                &StakeHistory::default(),
+                &[]
             ),
             Ok(())
         );
@@ -1052,7 +1473,8 @@ mod tests {
                 total_lamports + 1,
                 &mut to_keyed_account,
                 &clock,
-                &StakeHistory::default()
+                &StakeHistory::default(),
+                &[]
             ),
             Err(InstructionError::InsufficientFunds)
         );
@@ -1068,7 +1490,8 @@ mod tests {
                 &vote_keyed_account,
                 stake_lamports,
                 &clock,
-                &Config::default()
+                &Config::default(),
+                &[]
             ),
             Ok(())
         );
@@ -1080,7 +1503,8 @@ mod tests {
                 total_lamports - stake_lamports,
                 &mut to_keyed_account,
                 &clock,
-                &StakeHistory::default()
+                &StakeHistory::default(),
+                &[]
             ),
             Ok(())
         );
@@ -1094,14 +1518,15 @@ mod tests {
                 total_lamports - stake_lamports + 1,
                 &mut to_keyed_account,
                 &clock,
-                &StakeHistory::default()
+                &StakeHistory::default(),
+                &[]
             ),
             Err(InstructionError::InsufficientFunds)
         );
 
         // deactivate the stake before withdrawal
         assert_eq!(
-            stake_keyed_account.deactivate_stake(&vote_keyed_account, &clock),
+            stake_keyed_account.deactivate_stake(&vote_keyed_account, &clock, &[]),
             Ok(())
         );
         // simulate time passing
@@ -1113,7 +1538,8 @@ mod tests {
                 total_lamports + 1,
                 &mut to_keyed_account,
                 &clock,
-                &StakeHistory::default()
+                &StakeHistory::default(),
+                &[]
             ),
             Err(InstructionError::InsufficientFunds)
         );
@@ -1124,7 +1550,8 @@ mod tests {
                 total_lamports,
                 &mut to_keyed_account,
                 &clock,
-                &StakeHistory::default()
+                &StakeHistory::default(),
+                &[]
             ),
             Ok(())
         );
@@ -1138,7 +1565,7 @@ mod tests {
         let stake_lamports = 42;
         let mut stake_account = Account::new_data_with_space(
             total_lamports,
-            &StakeState::Lockup(0),
+            &StakeState::Initialized(Authorized::auto(&stake_pubkey), Lockup::default()),
             std::mem::size_of::<StakeState>(),
             &id(),
         )
@@ -1165,7 +1592,8 @@ mod tests {
                 &vote_keyed_account,
                 stake_lamports,
                 &future,
-                &Config::default()
+                &Config::default(),
+                &[]
             ),
             Ok(())
         );
@@ -1182,7 +1610,8 @@ mod tests {
                 total_lamports - stake_lamports + 1,
                 &mut to_keyed_account,
                 &clock,
-                &stake_history
+                &stake_history,
+                &[]
             ),
             Err(InstructionError::InsufficientFunds)
         );
@@ -1198,7 +1627,7 @@ mod tests {
             std::mem::size_of::<StakeState>(),
             &id(),
         )
-        .expect("stake_account");;
+        .expect("stake_account");
 
         let to = Pubkey::new_rand();
         let mut to_account = Account::new(1, 0, &system_program::id());
@@ -1210,7 +1639,8 @@ mod tests {
                 total_lamports,
                 &mut to_keyed_account,
                 &sysvar::clock::Clock::default(),
-                &StakeHistory::default()
+                &StakeHistory::default(),
+                &[]
             ),
             Err(InstructionError::InvalidAccountData)
         );
@@ -1219,10 +1649,14 @@ mod tests {
     #[test]
     fn test_withdraw_lockout() {
         let stake_pubkey = Pubkey::new_rand();
+        let custodian = Pubkey::new_rand();
         let total_lamports = 100;
         let mut stake_account = Account::new_data_with_space(
             total_lamports,
-            &StakeState::Lockup(1),
+            &StakeState::Initialized(
+                Authorized::auto(&stake_pubkey),
+                Lockup { slot: 1, custodian },
+            ),
             std::mem::size_of::<StakeState>(),
             &id(),
         )
@@ -1240,18 +1674,34 @@ mod tests {
                 total_lamports,
                 &mut to_keyed_account,
                 &clock,
-                &StakeHistory::default()
+                &StakeHistory::default(),
+                &[]
             ),
-            Err(InstructionError::InsufficientFunds)
+            Err(StakeError::LockupInForce.into())
+        );
+
+        // the custodian may sign to withdraw before the lockup expires
+        let mut custodian_account = Account::default();
+        let custodian_keyed_account = KeyedAccount::new(&custodian, true, &mut custodian_account);
+        assert_eq!(
+            stake_keyed_account.withdraw(
+                total_lamports / 2,
+                &mut to_keyed_account,
+                &clock,
+                &StakeHistory::default(),
+                &[custodian_keyed_account]
+            ),
+            Ok(())
         );
 
         clock.slot += 1;
         assert_eq!(
             stake_keyed_account.withdraw(
-                total_lamports,
+                total_lamports / 2,
                 &mut to_keyed_account,
                 &clock,
-                &StakeHistory::default()
+                &StakeHistory::default(),
+                &[]
             ),
             Ok(())
         );
@@ -1378,7 +1828,7 @@ mod tests {
         let stake_lamports = 100;
         let mut stake_account = Account::new_data_with_space(
             stake_lamports,
-            &StakeState::Lockup(0),
+            &StakeState::Initialized(Authorized::auto(&pubkey), Lockup::default()),
             std::mem::size_of::<StakeState>(),
             &id(),
         )
@@ -1408,7 +1858,8 @@ mod tests {
                 &vote_keyed_account,
                 stake_lamports,
                 &clock,
-                &Config::default()
+                &Config::default(),
+                &[]
             )
             .is_ok());
 
@@ -1493,4 +1944,218 @@ mod tests {
         );
     }
 
+    fn new_delegated_stake_account(
+        lamports: u64,
+        stake: u64,
+        authorized: &Authorized,
+        vote_pubkey: &Pubkey,
+        activation_epoch: Epoch,
+    ) -> Account {
+        Account::new_data_with_space(
+            lamports,
+            &StakeState::Stake(
+                *authorized,
+                Lockup::default(),
+                Stake {
+                    voter_pubkey: *vote_pubkey,
+                    stake,
+                    activation_epoch,
+                    ..Stake::default()
+                },
+            ),
+            std::mem::size_of::<StakeState>(),
+            &id(),
+        )
+        .expect("stake_account")
+    }
+
+    #[test]
+    fn test_split_stake() {
+        let stake_pubkey = Pubkey::new_rand();
+        let vote_pubkey = Pubkey::new_rand();
+        let mut stake_account =
+            new_delegated_stake_account(100, 80, &Authorized::auto(&stake_pubkey), &vote_pubkey, 0);
+        let split_stake_pubkey = Pubkey::new_rand();
+        let mut split_stake_account = Account::new_data_with_space(
+            0,
+            &StakeState::Uninitialized,
+            std::mem::size_of::<StakeState>(),
+            &id(),
+        )
+        .expect("split_stake_account");
+        let mut split_stake_keyed_account =
+            KeyedAccount::new(&split_stake_pubkey, false, &mut split_stake_account);
+
+        // unsigned keyed account should fail
+        let mut stake_keyed_account = KeyedAccount::new(&stake_pubkey, false, &mut stake_account);
+        assert_eq!(
+            stake_keyed_account.split(30, &mut split_stake_keyed_account, &[]),
+            Err(InstructionError::MissingRequiredSignature)
+        );
+
+        // can't split off more than is staked
+        let mut stake_keyed_account = KeyedAccount::new(&stake_pubkey, true, &mut stake_account);
+        assert_eq!(
+            stake_keyed_account.split(81, &mut split_stake_keyed_account, &[]),
+            Err(StakeError::InsufficientStake.into())
+        );
+
+        assert_eq!(
+            stake_keyed_account.split(30, &mut split_stake_keyed_account, &[]),
+            Ok(())
+        );
+        let stake = StakeState::stake_from(&stake_account).unwrap();
+        let split_stake = StakeState::stake_from(&split_stake_account).unwrap();
+        assert_eq!(stake_account.lamports, 70);
+        assert_eq!(split_stake_account.lamports, 30);
+        assert_eq!(stake.stake, 50);
+        assert_eq!(
+            split_stake,
+            Stake {
+                stake: 30,
+                ..stake.clone()
+            }
+        );
+        // the split account answers to the same authorities
+        assert_eq!(
+            StakeState::authorized_from(&split_stake_account),
+            Some(Authorized::auto(&stake_pubkey))
+        );
+
+        // the split account is now in use
+        let mut stake_keyed_account = KeyedAccount::new(&stake_pubkey, true, &mut stake_account);
+        let mut split_stake_keyed_account =
+            KeyedAccount::new(&split_stake_pubkey, false, &mut split_stake_account);
+        assert_eq!(
+            stake_keyed_account.split(10, &mut split_stake_keyed_account, &[]),
+            Err(InstructionError::InvalidAccountData)
+        );
+
+        // the two warm up and cool down together as the original would have
+        let mut original = Stake {
+            stake: 80,
+            ..stake.clone()
+        };
+        let (mut stake, mut split_stake) = (stake, split_stake);
+        for stake in &mut [&mut original, &mut stake, &mut split_stake] {
+            stake.deactivate(10);
+        }
+        let original_history =
+            create_stake_history_from_stakes(Some(1_000), 0..20, &[original.clone()]);
+        let split_history = create_stake_history_from_stakes(
+            Some(1_000),
+            0..20,
+            &[stake.clone(), split_stake.clone()],
+        );
+        for epoch in &[0, 1, 10, 20] {
+            let (effective, activating, deactivating) =
+                stake.stake_activating_and_deactivating(*epoch, Some(&split_history));
+            let (split_effective, split_activating, split_deactivating) =
+                split_stake.stake_activating_and_deactivating(*epoch, Some(&split_history));
+            assert_eq!(
+                original.stake_activating_and_deactivating(*epoch, Some(&original_history)),
+                (
+                    effective + split_effective,
+                    activating + split_activating,
+                    deactivating + split_deactivating
+                )
+            );
+        }
+    }
+
+    #[test]
+    fn test_merge_stake() {
+        let stake_pubkey = Pubkey::new_rand();
+        let source_stake_pubkey = Pubkey::new_rand();
+        let vote_pubkey = Pubkey::new_rand();
+        let staker = Pubkey::new_rand();
+        let authorized = Authorized::auto(&staker);
+        let mut stake_account = new_delegated_stake_account(100, 80, &authorized, &vote_pubkey, 1);
+        let mut source_stake_account =
+            new_delegated_stake_account(50, 40, &authorized, &vote_pubkey, 1);
+
+        // the staker must sign
+        let mut stake_keyed_account = KeyedAccount::new(&stake_pubkey, true, &mut stake_account);
+        let mut source_stake_keyed_account =
+            KeyedAccount::new(&source_stake_pubkey, true, &mut source_stake_account);
+        assert_eq!(
+            stake_keyed_account.merge(&mut source_stake_keyed_account, &[]),
+            Err(InstructionError::MissingRequiredSignature)
+        );
+
+        let mut staker_account = Account::default();
+
+        // delegations activated in different epochs don't warm up together
+        let mut other_stake_account =
+            new_delegated_stake_account(50, 40, &authorized, &vote_pubkey, 2);
+        let mut other_stake_keyed_account =
+            KeyedAccount::new(&source_stake_pubkey, false, &mut other_stake_account);
+        let staker_keyed_account = KeyedAccount::new(&staker, true, &mut staker_account);
+        assert_eq!(
+            stake_keyed_account.merge(&mut other_stake_keyed_account, &[staker_keyed_account]),
+            Err(StakeError::MergeMismatch.into())
+        );
+
+        // nor are delegations to different voters merged
+        let mut other_stake_account =
+            new_delegated_stake_account(50, 40, &authorized, &Pubkey::new_rand(), 1);
+        let mut other_stake_keyed_account =
+            KeyedAccount::new(&source_stake_pubkey, false, &mut other_stake_account);
+        let staker_keyed_account = KeyedAccount::new(&staker, true, &mut staker_account);
+        assert_eq!(
+            stake_keyed_account.merge(&mut other_stake_keyed_account, &[staker_keyed_account]),
+            Err(StakeError::MergeMismatch.into())
+        );
+
+        // nor stakes that have observed different credits, until both are redeemed
+        let mut other_stake_account =
+            new_delegated_stake_account(50, 40, &authorized, &vote_pubkey, 1);
+        let mut other_stake = StakeState::stake_from(&other_stake_account).unwrap();
+        other_stake.credits_observed = 1;
+        other_stake_account
+            .set_state(&StakeState::Stake(
+                authorized,
+                Lockup::default(),
+                other_stake,
+            ))
+            .unwrap();
+        let mut other_stake_keyed_account =
+            KeyedAccount::new(&source_stake_pubkey, false, &mut other_stake_account);
+        let staker_keyed_account = KeyedAccount::new(&staker, true, &mut staker_account);
+        assert_eq!(
+            stake_keyed_account.merge(&mut other_stake_keyed_account, &[staker_keyed_account]),
+            Err(StakeError::MergeMismatch.into())
+        );
+
+        // nor stakes held by different authorities
+        let mut other_stake_account = new_delegated_stake_account(
+            50,
+            40,
+            &Authorized::auto(&Pubkey::new_rand()),
+            &vote_pubkey,
+            1,
+        );
+        let mut other_stake_keyed_account =
+            KeyedAccount::new(&source_stake_pubkey, false, &mut other_stake_account);
+        let staker_keyed_account = KeyedAccount::new(&staker, true, &mut staker_account);
+        assert_eq!(
+            stake_keyed_account.merge(&mut other_stake_keyed_account, &[staker_keyed_account]),
+            Err(StakeError::MergeMismatch.into())
+        );
+
+        let mut source_stake_keyed_account =
+            KeyedAccount::new(&source_stake_pubkey, false, &mut source_stake_account);
+        let staker_keyed_account = KeyedAccount::new(&staker, true, &mut staker_account);
+        assert_eq!(
+            stake_keyed_account.merge(&mut source_stake_keyed_account, &[staker_keyed_account]),
+            Ok(())
+        );
+        assert_eq!(stake_account.lamports, 150);
+        assert_eq!(StakeState::stake_from(&stake_account).unwrap().stake, 120);
+        assert_eq!(source_stake_account.lamports, 0);
+        assert_eq!(
+            StakeState::from(&source_stake_account),
+            Some(StakeState::Uninitialized)
+        );
+    }
 }
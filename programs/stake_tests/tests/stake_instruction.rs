@@ -4,6 +4,7 @@ use solana_runtime::bank_client::BankClient;
 use solana_runtime::genesis_utils::{create_genesis_block_with_leader, GenesisBlockInfo};
 use solana_sdk::account_utils::State;
 use solana_sdk::client::SyncClient;
+use solana_sdk::instruction::AccountMeta;
 use solana_sdk::message::Message;
 use solana_sdk::pubkey::Pubkey;
 use solana_sdk::signature::{Keypair, KeypairUtil};
@@ -12,7 +13,7 @@ use solana_sdk::sysvar::rewards::Rewards;
 use solana_stake_api::id;
 use solana_stake_api::stake_instruction;
 use solana_stake_api::stake_instruction::process_instruction;
-use solana_stake_api::stake_state::StakeState;
+use solana_stake_api::stake_state::{Authorized, Lockup, StakeAuthorize, StakeState};
 use solana_vote_api::vote_instruction;
 use solana_vote_api::vote_state::{Vote, VoteState};
 use std::sync::Arc;
@@ -98,7 +99,7 @@ fn test_stake_account_delegate() {
     // Test that correct lamports are staked
     let account = bank.get_account(&staker_pubkey).expect("account not found");
     let stake_state = account.state().expect("couldn't unpack account data");
-    if let StakeState::Stake(stake) = stake_state {
+    if let StakeState::Stake(_authorized, _lockup, stake) = stake_state {
         assert_eq!(stake.stake, 20000);
     } else {
         assert!(false, "wrong account type found")
@@ -107,6 +108,7 @@ fn test_stake_account_delegate() {
     // Test that we cannot withdraw staked lamports
     let message = Message::new_with_payer(
         vec![stake_instruction::withdraw(
+            &staker_pubkey,
             &staker_pubkey,
             &Pubkey::new_rand(),
             20000,
@@ -120,7 +122,7 @@ fn test_stake_account_delegate() {
     // Test that lamports are still staked
     let account = bank.get_account(&staker_pubkey).expect("account not found");
     let stake_state = account.state().expect("couldn't unpack account data");
-    if let StakeState::Stake(stake) = stake_state {
+    if let StakeState::Stake(_authorized, _lockup, stake) = stake_state {
         assert_eq!(stake.stake, 20000);
     } else {
         assert!(false, "wrong account type found")
@@ -164,7 +166,7 @@ fn test_stake_account_delegate() {
     let rewards;
     let account = bank.get_account(&staker_pubkey).expect("account not found");
     let stake_state = account.state().expect("couldn't unpack account data");
-    if let StakeState::Stake(stake) = stake_state {
+    if let StakeState::Stake(_authorized, _lockup, stake) = stake_state {
         assert!(account.lamports > 20000);
         assert_eq!(stake.stake, 20000);
         rewards = account.lamports - 20000;
@@ -176,6 +178,7 @@ fn test_stake_account_delegate() {
     // Deactivate the stake
     let message = Message::new_with_payer(
         vec![stake_instruction::deactivate_stake(
+            &staker_pubkey,
             &staker_pubkey,
             &vote_pubkey,
         )],
@@ -234,6 +237,7 @@ fn test_stake_account_delegate() {
     // Test that we can withdraw now
     let message = Message::new_with_payer(
         vec![stake_instruction::withdraw(
+            &staker_pubkey,
             &staker_pubkey,
             &Pubkey::new_rand(),
             20000,
@@ -247,9 +251,240 @@ fn test_stake_account_delegate() {
     // Test that balance and stake is updated correctly (we have withdrawn all lamports except rewards)
     let account = bank.get_account(&staker_pubkey).expect("account not found");
     let stake_state = account.state().expect("couldn't unpack account data");
-    if let StakeState::Stake(_stake) = stake_state {
+    if let StakeState::Stake(_authorized, _lockup, _stake) = stake_state {
         assert_eq!(account.lamports, rewards);
     } else {
         assert!(false, "wrong account type found")
     }
 }
+
+#[test]
+fn test_stake_account_split_and_merge() {
+    let staker_keypair = Keypair::new();
+    let staker_pubkey = staker_keypair.pubkey();
+    let split_staker_pubkey = Pubkey::new_rand();
+    let vote_keypair = Keypair::new();
+    let vote_pubkey = vote_keypair.pubkey();
+    let node_pubkey = Pubkey::new_rand();
+
+    let GenesisBlockInfo {
+        mut genesis_block,
+        mint_keypair,
+        ..
+    } = create_genesis_block_with_leader(100_000_000_000, &Pubkey::new_rand(), 1_000_000);
+    genesis_block
+        .native_instruction_processors
+        .push(solana_stake_program::solana_stake_program!());
+    let bank = Bank::new(&genesis_block);
+    let mint_pubkey = mint_keypair.pubkey();
+    let bank_client = BankClient::new(bank);
+
+    // Create Vote Account
+    let message = Message::new(vote_instruction::create_account(
+        &mint_pubkey,
+        &vote_pubkey,
+        &node_pubkey,
+        std::u8::MAX / 2,
+        10,
+    ));
+    bank_client
+        .send_message(&[&mint_keypair], message)
+        .expect("failed to create vote account");
+
+    // Create stake account and delegate to vote account
+    let message = Message::new(stake_instruction::create_stake_account_and_delegate_stake(
+        &mint_pubkey,
+        &staker_pubkey,
+        &vote_pubkey,
+        20000,
+    ));
+    bank_client
+        .send_message(&[&mint_keypair, &staker_keypair], message)
+        .expect("failed to create and delegate stake account");
+
+    // Split off part of the stake into a new account
+    let message = Message::new_with_payer(
+        stake_instruction::split(
+            &mint_pubkey,
+            &staker_pubkey,
+            &staker_pubkey,
+            5000,
+            &split_staker_pubkey,
+        ),
+        Some(&mint_pubkey),
+    );
+    bank_client
+        .send_message(&[&mint_keypair, &staker_keypair], message)
+        .expect("failed to split stake account");
+
+    let get_stake = |pubkey| {
+        let account = bank_client.get_account(pubkey).unwrap().unwrap();
+        (account.lamports, StakeState::stake_from(&account).unwrap())
+    };
+    let (lamports, stake) = get_stake(&staker_pubkey);
+    let (split_lamports, split_stake) = get_stake(&split_staker_pubkey);
+    assert_eq!((lamports, stake.stake), (15000, 15000));
+    assert_eq!((split_lamports, split_stake.stake), (5000, 5000));
+    assert_eq!(split_stake.voter_pubkey, vote_pubkey);
+    assert_eq!(split_stake.activation_epoch, stake.activation_epoch);
+
+    // Merge it back
+    let message = Message::new_with_payer(
+        vec![stake_instruction::merge(
+            &staker_pubkey,
+            &staker_pubkey,
+            &split_staker_pubkey,
+        )],
+        Some(&mint_pubkey),
+    );
+    bank_client
+        .send_message(&[&mint_keypair, &staker_keypair], message)
+        .expect("failed to merge stake accounts");
+
+    let (lamports, stake) = get_stake(&staker_pubkey);
+    assert_eq!((lamports, stake.stake), (20000, 20000));
+    assert_eq!(bank_client.get_balance(&split_staker_pubkey).unwrap(), 0);
+}
+
+#[test]
+fn test_stake_account_authorize() {
+    let stake_pubkey = Pubkey::new_rand();
+    let staker_keypair = Keypair::new();
+    let withdrawer_keypair = Keypair::new();
+    let custodian_keypair = Keypair::new();
+    let vote_keypair = Keypair::new();
+    let vote_pubkey = vote_keypair.pubkey();
+    let node_pubkey = Pubkey::new_rand();
+
+    let GenesisBlockInfo {
+        mut genesis_block,
+        mint_keypair,
+        ..
+    } = create_genesis_block_with_leader(100_000_000_000, &Pubkey::new_rand(), 1_000_000);
+    genesis_block
+        .native_instruction_processors
+        .push(solana_stake_program::solana_stake_program!());
+    let bank = Bank::new(&genesis_block);
+    let mint_pubkey = mint_keypair.pubkey();
+    let bank_client = BankClient::new(bank);
+
+    // Create Vote Account
+    let message = Message::new(vote_instruction::create_account(
+        &mint_pubkey,
+        &vote_pubkey,
+        &node_pubkey,
+        std::u8::MAX / 2,
+        10,
+    ));
+    bank_client
+        .send_message(&[&mint_keypair], message)
+        .expect("failed to create vote account");
+
+    // Create a stake account held by a staker and a withdrawer, locked up
+    //  far into the future
+    let authorized = Authorized {
+        staker: staker_keypair.pubkey(),
+        withdrawer: withdrawer_keypair.pubkey(),
+    };
+    let lockup = Lockup {
+        slot: std::u64::MAX,
+        custodian: custodian_keypair.pubkey(),
+    };
+    let message = Message::new(stake_instruction::create_stake_account_with_lockup(
+        &mint_pubkey,
+        &stake_pubkey,
+        20000,
+        &authorized,
+        &lockup,
+    ));
+    bank_client
+        .send_message(&[&mint_keypair], message)
+        .expect("failed to create stake account");
+
+    // The staker delegates
+    let message = Message::new_with_payer(
+        vec![stake_instruction::delegate_stake(
+            &stake_pubkey,
+            &staker_keypair.pubkey(),
+            &vote_pubkey,
+            10000,
+        )],
+        Some(&mint_pubkey),
+    );
+    bank_client
+        .send_message(&[&mint_keypair, &staker_keypair], message)
+        .expect("failed to delegate stake");
+
+    // The staker can't withdraw
+    let message = Message::new_with_payer(
+        vec![stake_instruction::withdraw(
+            &stake_pubkey,
+            &staker_keypair.pubkey(),
+            &Pubkey::new_rand(),
+            10000,
+        )],
+        Some(&mint_pubkey),
+    );
+    assert!(bank_client
+        .send_message(&[&mint_keypair, &staker_keypair], message)
+        .is_err());
+
+    // Nor can the withdrawer while the lockup is in force...
+    let to_pubkey = Pubkey::new_rand();
+    let message = Message::new_with_payer(
+        vec![stake_instruction::withdraw(
+            &stake_pubkey,
+            &withdrawer_keypair.pubkey(),
+            &to_pubkey,
+            10000,
+        )],
+        Some(&mint_pubkey),
+    );
+    assert!(bank_client
+        .send_message(&[&mint_keypair, &withdrawer_keypair], message)
+        .is_err());
+
+    // ...unless the custodian signs too
+    let mut instruction = stake_instruction::withdraw(
+        &stake_pubkey,
+        &withdrawer_keypair.pubkey(),
+        &to_pubkey,
+        10000,
+    );
+    instruction.accounts.push(AccountMeta::new_credit_only(
+        custodian_keypair.pubkey(),
+        true,
+    ));
+    let message = Message::new_with_payer(vec![instruction], Some(&mint_pubkey));
+    bank_client
+        .send_message(
+            &[&mint_keypair, &withdrawer_keypair, &custodian_keypair],
+            message,
+        )
+        .expect("failed to withdraw with the custodian's signature");
+    assert_eq!(bank_client.get_balance(&to_pubkey).unwrap(), 10000);
+
+    // The withdrawer hands off the withdrawal key
+    let new_withdrawer_pubkey = Pubkey::new_rand();
+    let message = Message::new_with_payer(
+        vec![stake_instruction::authorize(
+            &stake_pubkey,
+            &withdrawer_keypair.pubkey(),
+            &new_withdrawer_pubkey,
+            StakeAuthorize::Withdrawer,
+        )],
+        Some(&mint_pubkey),
+    );
+    bank_client
+        .send_message(&[&mint_keypair, &withdrawer_keypair], message)
+        .expect("failed to authorize a new withdrawer");
+
+    let account = bank_client.get_account(&stake_pubkey).unwrap().unwrap();
+    assert_eq!(
+        StakeState::authorized_from(&account),
+        Some(Authorized {
+            staker: staker_keypair.pubkey(),
+            withdrawer: new_withdrawer_pubkey,
+        })
+    );
+}
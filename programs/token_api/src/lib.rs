@@ -1,5 +1,6 @@
+pub mod token_instruction;
 pub mod token_processor;
-mod token_state;
+pub mod token_state;
 
 use solana_sdk::pubkey::Pubkey;
 
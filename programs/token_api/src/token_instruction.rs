@@ -0,0 +1,48 @@
+use crate::id;
+use crate::token_state::{TokenInfo, TokenInstruction};
+use solana_sdk::instruction::{AccountMeta, Instruction};
+use solana_sdk::pubkey::Pubkey;
+
+/// Create a token and give its whole supply to `account`, which must already be a token
+/// account for `token`
+pub fn new_token(token: &Pubkey, account: &Pubkey, token_info: TokenInfo) -> Instruction {
+    let account_metas = vec![
+        AccountMeta::new(*token, true),
+        AccountMeta::new(*account, false),
+    ];
+    Instruction::new(id(), &TokenInstruction::NewToken(token_info), account_metas)
+}
+
+pub fn new_token_account(account: &Pubkey, owner: &Pubkey, token: &Pubkey) -> Instruction {
+    let account_metas = vec![
+        AccountMeta::new(*account, true),
+        AccountMeta::new(*owner, false),
+        AccountMeta::new(*token, false),
+    ];
+    Instruction::new(id(), &TokenInstruction::NewTokenAccount, account_metas)
+}
+
+pub fn transfer(owner: &Pubkey, source: &Pubkey, dest: &Pubkey, amount: u64) -> Instruction {
+    let account_metas = vec![
+        AccountMeta::new(*owner, true),
+        AccountMeta::new(*source, false),
+        AccountMeta::new(*dest, false),
+    ];
+    Instruction::new(id(), &TokenInstruction::Transfer(amount), account_metas)
+}
+
+/// Transfer from a token account whose owner, an account of another program, approved the
+/// transfer with a `TransferApproval`
+pub fn approved_transfer(
+    owner: &Pubkey,
+    source: &Pubkey,
+    dest: &Pubkey,
+    amount: u64,
+) -> Instruction {
+    let account_metas = vec![
+        AccountMeta::new(*owner, false),
+        AccountMeta::new(*source, false),
+        AccountMeta::new(*dest, false),
+    ];
+    Instruction::new(id(), &TokenInstruction::Transfer(amount), account_metas)
+}
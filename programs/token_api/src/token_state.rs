@@ -4,12 +4,16 @@ use serde_derive::{Deserialize, Serialize};
 use solana_sdk::account::KeyedAccount;
 use solana_sdk::instruction_processor_utils::DecodeError;
 use solana_sdk::pubkey::Pubkey;
+use solana_sdk::system_program;
 
 #[derive(Serialize, Debug, PartialEq, FromPrimitive)]
 pub enum TokenError {
     InvalidArgument,
     InsufficentFunds,
     NotOwner,
+    NotMintAuthority,
+    NotFreezeAuthority,
+    AccountFrozen,
 }
 
 impl<T> DecodeError<T> for TokenError {
@@ -27,7 +31,7 @@ impl std::error::Error for TokenError {}
 
 pub type Result<T> = std::result::Result<T, TokenError>;
 
-#[derive(Debug, Default, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize, PartialEq)]
 pub struct TokenInfo {
     /// Total supply of tokens
     supply: u64,
@@ -40,6 +44,26 @@ pub struct TokenInfo {
 
     /// Symbol for this token
     symbol: String,
+
+    /// If `mint_authority` is Some(_), it may mint new tokens and the supply is not fixed
+    mint_authority: Option<Pubkey>,
+
+    /// If `freeze_authority` is Some(_), it may freeze and thaw accounts holding this token
+    freeze_authority: Option<Pubkey>,
+}
+
+impl TokenInfo {
+    /// A token with a fixed supply
+    pub fn new(supply: u64, decimals: u8, name: &str, symbol: &str) -> Self {
+        Self {
+            supply,
+            decimals,
+            name: name.to_string(),
+            symbol: symbol.to_string(),
+            mint_authority: None,
+            freeze_authority: None,
+        }
+    }
 }
 
 #[derive(Debug, Default, Clone, Serialize, Deserialize, PartialEq)]
@@ -66,15 +90,62 @@ pub struct TokenAccountInfo {
     /// If `delegate` is Option<_>, `amount` represents the remaining allowance
     /// of tokens that may be transferred from the `source` account.
     delegate: Option<TokenAccountDelegateInfo>,
+
+    /// A frozen account can't send, receive or burn tokens until the token's freeze authority
+    /// thaws it
+    is_frozen: bool,
+}
+
+/// A transfer out of a token account whose owner is an account of another program, which
+/// approves the transfer instead of signing it.  The program keeps the
+/// `Option<TransferApproval>` for the next transfer in the owner account's data
+#[derive(Debug, Default, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TransferApproval {
+    /// Token account to transfer from
+    pub source: Pubkey,
+
+    /// Amount `source` holds before the transfer, which the transfer changes, so the approval
+    /// can't be used for a second transfer in a row
+    pub source_amount: u64,
+
+    /// Token account to transfer to
+    pub destination: Pubkey,
+
+    /// Amount of tokens to transfer
+    pub amount: u64,
+}
+
+impl TransferApproval {
+    /// Account data size needed to hold an approval
+    pub fn max_account_size() -> u64 {
+        bincode::serialized_size(&Some(Self::default())).unwrap()
+    }
+}
+
+/// Maximum number of signers a multisig account may have
+pub const MAX_SIGNERS: usize = 11;
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MultisigInfo {
+    /// Number of `signers` that must sign to act as this account
+    threshold: u8,
+
+    /// The keys that may sign on behalf of this account
+    signers: Vec<Pubkey>,
 }
 
 #[derive(Debug, Serialize, Deserialize, PartialEq)]
-enum TokenInstruction {
+pub enum TokenInstruction {
     NewToken(TokenInfo),
     NewTokenAccount,
     Transfer(u64),
     Approve(u64),
     SetOwner,
+    MintTo(u64),
+    Burn(u64),
+    Freeze,
+    Thaw,
+    NewMultisig(u8),
 }
 
 #[derive(Debug, Serialize, Deserialize, PartialEq)]
@@ -82,6 +153,7 @@ pub enum TokenState {
     Unallocated,
     Token(TokenInfo),
     Account(TokenAccountInfo),
+    Multisig(MultisigInfo),
     Invalid,
 }
 impl Default for TokenState {
@@ -109,6 +181,9 @@ impl TokenState {
             2 => Ok(TokenState::Account(
                 bincode::deserialize(&input[1..]).map_err(Self::map_to_invalid_args)?,
             )),
+            3 => Ok(TokenState::Multisig(
+                bincode::deserialize(&input[1..]).map_err(Self::map_to_invalid_args)?,
+            )),
             _ => Err(TokenError::InvalidArgument),
         }
     }
@@ -130,6 +205,11 @@ impl TokenState {
                 let writer = std::io::BufWriter::new(&mut output[1..]);
                 bincode::serialize_into(writer, &account_info).map_err(Self::map_to_invalid_args)
             }
+            TokenState::Multisig(multisig_info) => {
+                output[0] = 3;
+                let writer = std::io::BufWriter::new(&mut output[1..]);
+                bincode::serialize_into(writer, &multisig_info).map_err(Self::map_to_invalid_args)
+            }
         }
     }
 
@@ -142,6 +222,14 @@ impl TokenState {
         }
     }
 
+    pub fn token(&self) -> Result<Pubkey> {
+        if let TokenState::Account(account_info) = self {
+            Ok(account_info.token)
+        } else {
+            Err(TokenError::InvalidArgument)
+        }
+    }
+
     #[allow(dead_code)]
     pub fn only_owner(&self, key: &Pubkey) -> Result<()> {
         if *key != Pubkey::default() {
@@ -155,6 +243,70 @@ impl TokenState {
         Err(TokenError::NotOwner)
     }
 
+    /// Check that `authority` authorized the instruction.  Either key 0 is the authority and
+    /// signed, or key 0 is the authority's multisig account and at least its threshold of
+    /// signers signed among the keys from `signers_index` on
+    fn is_authorized(
+        authority: &Pubkey,
+        info: &[KeyedAccount],
+        input_accounts: &[TokenState],
+        signers_index: usize,
+    ) -> bool {
+        if info[0].unsigned_key() != authority {
+            return false;
+        }
+        if let TokenState::Multisig(multisig_info) = &input_accounts[0] {
+            let num_signers = multisig_info
+                .signers
+                .iter()
+                .filter(|signer| {
+                    info.iter()
+                        .skip(signers_index)
+                        .any(|keyed_account| keyed_account.signer_key() == Some(signer))
+                })
+                .count();
+            if num_signers < multisig_info.threshold as usize {
+                warn!(
+                    "{} of {} required multisig signers present",
+                    num_signers, multisig_info.threshold
+                );
+                return false;
+            }
+            true
+        } else {
+            info[0].signer_key().is_some()
+        }
+    }
+
+    /// Check that key 0 is `authority`, and that it is an account of another program that
+    /// approved transferring `amount` tokens from key 1, which holds `source_amount`, to key 2
+    fn is_transfer_approved(
+        authority: &Pubkey,
+        info: &[KeyedAccount],
+        input_accounts: &[TokenState],
+        source_amount: u64,
+        amount: u64,
+    ) -> bool {
+        if info[0].unsigned_key() != authority {
+            return false;
+        }
+        // Token program and system accounts hold no approvals
+        if input_accounts[0] != TokenState::Invalid || info[0].account.owner == system_program::id()
+        {
+            return false;
+        }
+        let approval = TransferApproval {
+            source: *info[1].unsigned_key(),
+            source_amount,
+            destination: *info[2].unsigned_key(),
+            amount,
+        };
+        match bincode::deserialize::<Option<TransferApproval>>(&info[0].account.data) {
+            Ok(Some(approved)) => approved == approval,
+            _ => false,
+        }
+    }
+
     pub fn process_newtoken(
         info: &mut [KeyedAccount],
         token_info: TokenInfo,
@@ -167,7 +319,7 @@ impl TokenState {
         }
 
         if let TokenState::Account(dest_account) = &input_accounts[1] {
-            if info[0].signer_key().unwrap() != &dest_account.token {
+            if info[0].signer_key() != Some(&dest_account.token) {
                 error!("account 1 token mismatch");
                 Err(TokenError::InvalidArgument)?;
             }
@@ -215,6 +367,7 @@ impl TokenState {
             owner: *info[1].unsigned_key(),
             amount: 0,
             delegate: None,
+            is_frozen: false,
         };
         if input_accounts.len() >= 4 {
             token_account_info.delegate = Some(TokenAccountDelegateInfo {
@@ -250,7 +403,26 @@ impl TokenState {
                 Err(TokenError::InvalidArgument)?;
             }
 
-            if info[0].signer_key().unwrap() != &source_account.owner {
+            if source_account.is_frozen || dest_account.is_frozen {
+                error!("account 1 and/or 2 are frozen");
+                Err(TokenError::AccountFrozen)?;
+            }
+
+            // Multisig signers follow the source account of a delegate
+            let signers_index = if source_account.delegate.is_some() {
+                4
+            } else {
+                3
+            };
+            if !Self::is_authorized(&source_account.owner, info, input_accounts, signers_index)
+                && !Self::is_transfer_approved(
+                    &source_account.owner,
+                    info,
+                    input_accounts,
+                    source_account.amount,
+                    amount,
+                )
+            {
                 error!("owner of account 1 not present");
                 Err(TokenError::InvalidArgument)?;
             }
@@ -264,7 +436,7 @@ impl TokenState {
             output_accounts.push((1, TokenState::Account(output_source_account)));
 
             if let Some(ref delegate_info) = source_account.delegate {
-                if input_accounts.len() != 4 {
+                if input_accounts.len() < 4 {
                     error!("Expected 4 accounts");
                     Err(TokenError::InvalidArgument)?;
                 }
@@ -280,6 +452,11 @@ impl TokenState {
                         Err(TokenError::InvalidArgument)?;
                     }
 
+                    if source_account.is_frozen {
+                        error!("account 3 is frozen");
+                        Err(TokenError::AccountFrozen)?;
+                    }
+
                     if source_account.amount < amount {
                         Err(TokenError::InsufficentFunds)?;
                     }
@@ -309,7 +486,7 @@ impl TokenState {
         input_accounts: &[TokenState],
         output_accounts: &mut Vec<(usize, TokenState)>,
     ) -> Result<()> {
-        if input_accounts.len() != 3 {
+        if input_accounts.len() < 3 {
             error!("Expected 3 accounts");
             Err(TokenError::InvalidArgument)?;
         }
@@ -322,7 +499,7 @@ impl TokenState {
                 Err(TokenError::InvalidArgument)?;
             }
 
-            if info[0].signer_key().unwrap() != &source_account.owner {
+            if !Self::is_authorized(&source_account.owner, info, input_accounts, 3) {
                 error!("owner of account 1 not present");
                 Err(TokenError::InvalidArgument)?;
             }
@@ -332,6 +509,11 @@ impl TokenState {
                 Err(TokenError::InvalidArgument)?;
             }
 
+            if source_account.is_frozen {
+                error!("account 1 is frozen");
+                Err(TokenError::AccountFrozen)?;
+            }
+
             match &delegate_account.delegate {
                 None => {
                     error!("account 2 is not a delegate");
@@ -370,11 +552,16 @@ impl TokenState {
         }
 
         if let TokenState::Account(source_account) = &input_accounts[1] {
-            if info[0].signer_key().unwrap() != &source_account.owner {
+            if !Self::is_authorized(&source_account.owner, info, input_accounts, 3) {
                 info!("owner of account 1 not present");
                 Err(TokenError::InvalidArgument)?;
             }
 
+            if source_account.is_frozen {
+                info!("account 1 is frozen");
+                Err(TokenError::AccountFrozen)?;
+            }
+
             let mut output_source_account = source_account.clone();
             output_source_account.owner = *info[2].unsigned_key();
             output_accounts.push((1, TokenState::Account(output_source_account)));
@@ -385,15 +572,214 @@ impl TokenState {
         Ok(())
     }
 
+    pub fn process_mintto(
+        info: &mut [KeyedAccount],
+        amount: u64,
+        input_accounts: &[TokenState],
+        output_accounts: &mut Vec<(usize, TokenState)>,
+    ) -> Result<()> {
+        // key 0 - Mint authority of the token
+        // key 1 - Token to mint
+        // key 2 - Destination token account
+        // key 3.. - Signers of a multisig authority (optional)
+        if input_accounts.len() < 3 {
+            error!("Expected 3 accounts");
+            Err(TokenError::InvalidArgument)?;
+        }
+
+        if let (TokenState::Token(token_info), TokenState::Account(dest_account)) =
+            (&input_accounts[1], &input_accounts[2])
+        {
+            let is_mint_authority = match token_info.mint_authority {
+                Some(mint_authority) => {
+                    Self::is_authorized(&mint_authority, info, input_accounts, 3)
+                }
+                None => false,
+            };
+            if !is_mint_authority {
+                error!("mint authority of account 1 not present");
+                Err(TokenError::NotMintAuthority)?;
+            }
+
+            if info[1].unsigned_key() != &dest_account.token {
+                error!("account 1/2 token mismatch");
+                Err(TokenError::InvalidArgument)?;
+            }
+
+            if dest_account.delegate.is_some() {
+                error!("account 2 is a delegate and cannot accept tokens");
+                Err(TokenError::InvalidArgument)?;
+            }
+
+            if dest_account.is_frozen {
+                error!("account 2 is frozen");
+                Err(TokenError::AccountFrozen)?;
+            }
+
+            let mut output_token_info = token_info.clone();
+            output_token_info.supply = token_info.supply.checked_add(amount).ok_or_else(|| {
+                error!("supply overflow");
+                TokenError::InvalidArgument
+            })?;
+            output_accounts.push((1, TokenState::Token(output_token_info)));
+
+            let mut output_dest_account = dest_account.clone();
+            output_dest_account.amount += amount;
+            output_accounts.push((2, TokenState::Account(output_dest_account)));
+        } else {
+            error!("account 1 and/or 2 are invalid accounts");
+            Err(TokenError::InvalidArgument)?;
+        }
+        Ok(())
+    }
+
+    pub fn process_burn(
+        info: &mut [KeyedAccount],
+        amount: u64,
+        input_accounts: &[TokenState],
+        output_accounts: &mut Vec<(usize, TokenState)>,
+    ) -> Result<()> {
+        // key 0 - Owner of the source account
+        // key 1 - Source token account to burn from
+        // key 2 - Token of the source account
+        // key 3.. - Signers of a multisig authority (optional)
+        if input_accounts.len() < 3 {
+            error!("Expected 3 accounts");
+            Err(TokenError::InvalidArgument)?;
+        }
+
+        if let (TokenState::Account(source_account), TokenState::Token(token_info)) =
+            (&input_accounts[1], &input_accounts[2])
+        {
+            if info[2].unsigned_key() != &source_account.token {
+                error!("account 1/2 token mismatch");
+                Err(TokenError::InvalidArgument)?;
+            }
+
+            if !Self::is_authorized(&source_account.owner, info, input_accounts, 3) {
+                error!("owner of account 1 not present");
+                Err(TokenError::InvalidArgument)?;
+            }
+
+            if source_account.delegate.is_some() {
+                error!("account 1 is a delegate");
+                Err(TokenError::InvalidArgument)?;
+            }
+
+            if source_account.is_frozen {
+                error!("account 1 is frozen");
+                Err(TokenError::AccountFrozen)?;
+            }
+
+            if source_account.amount < amount {
+                Err(TokenError::InsufficentFunds)?;
+            }
+
+            let mut output_source_account = source_account.clone();
+            output_source_account.amount -= amount;
+            output_accounts.push((1, TokenState::Account(output_source_account)));
+
+            let mut output_token_info = token_info.clone();
+            output_token_info.supply -= amount;
+            output_accounts.push((2, TokenState::Token(output_token_info)));
+        } else {
+            error!("account 1 and/or 2 are invalid accounts");
+            Err(TokenError::InvalidArgument)?;
+        }
+        Ok(())
+    }
+
+    pub fn process_freeze(
+        info: &mut [KeyedAccount],
+        is_frozen: bool,
+        input_accounts: &[TokenState],
+        output_accounts: &mut Vec<(usize, TokenState)>,
+    ) -> Result<()> {
+        // key 0 - Freeze authority of the token
+        // key 1 - Token account to freeze or thaw
+        // key 2 - Token of the token account
+        // key 3.. - Signers of a multisig authority (optional)
+        if input_accounts.len() < 3 {
+            error!("Expected 3 accounts");
+            Err(TokenError::InvalidArgument)?;
+        }
+
+        if let (TokenState::Account(target_account), TokenState::Token(token_info)) =
+            (&input_accounts[1], &input_accounts[2])
+        {
+            if info[2].unsigned_key() != &target_account.token {
+                error!("account 1/2 token mismatch");
+                Err(TokenError::InvalidArgument)?;
+            }
+
+            let is_freeze_authority = match token_info.freeze_authority {
+                Some(freeze_authority) => {
+                    Self::is_authorized(&freeze_authority, info, input_accounts, 3)
+                }
+                None => false,
+            };
+            if !is_freeze_authority {
+                error!("freeze authority of account 2 not present");
+                Err(TokenError::NotFreezeAuthority)?;
+            }
+
+            let mut output_target_account = target_account.clone();
+            output_target_account.is_frozen = is_frozen;
+            output_accounts.push((1, TokenState::Account(output_target_account)));
+        } else {
+            error!("account 1 and/or 2 are invalid accounts");
+            Err(TokenError::InvalidArgument)?;
+        }
+        Ok(())
+    }
+
+    pub fn process_newmultisig(
+        info: &mut [KeyedAccount],
+        threshold: u8,
+        input_accounts: &[TokenState],
+        output_accounts: &mut Vec<(usize, TokenState)>,
+    ) -> Result<()> {
+        // key 0 - Destination new multisig account
+        // key 1.. - Signers of the multisig account
+        if input_accounts[0] != TokenState::Unallocated {
+            error!("account 0 is already allocated");
+            Err(TokenError::InvalidArgument)?;
+        }
+
+        let signers: Vec<_> = info[1..]
+            .iter()
+            .map(|keyed_account| *keyed_account.unsigned_key())
+            .collect();
+        if signers.is_empty() || signers.len() > MAX_SIGNERS {
+            error!("Expected 1 to {} signers", MAX_SIGNERS);
+            Err(TokenError::InvalidArgument)?;
+        }
+        if threshold == 0 || threshold as usize > signers.len() {
+            error!(
+                "invalid threshold {} of {} signers",
+                threshold,
+                signers.len()
+            );
+            Err(TokenError::InvalidArgument)?;
+        }
+        if signers
+            .iter()
+            .enumerate()
+            .any(|(i, signer)| signers[..i].contains(signer))
+        {
+            error!("duplicate signer");
+            Err(TokenError::InvalidArgument)?;
+        }
+
+        output_accounts.push((0, TokenState::Multisig(MultisigInfo { threshold, signers })));
+        Ok(())
+    }
+
     pub fn process(program_id: &Pubkey, info: &mut [KeyedAccount], input: &[u8]) -> Result<()> {
         let command =
             bincode::deserialize::<TokenInstruction>(input).map_err(Self::map_to_invalid_args)?;
         info!("process_transaction: command={:?}", command);
 
-        if info[0].signer_key().is_none() {
-            Err(TokenError::InvalidArgument)?;
-        }
-
         let input_accounts: Vec<TokenState> = info
             .iter()
             .map(|keyed_account| {
@@ -416,6 +802,33 @@ impl TokenState {
             info!("input_account: data={:?}", account);
         }
 
+        if info[0].signer_key().is_none() {
+            // A multisig account acts through its signers instead, but only in the instructions
+            // that check them with `is_authorized`, and another program's account approves
+            // transfers in its data
+            let approved_by_program = match command {
+                TokenInstruction::Transfer(_) => info[0].account.owner != system_program::id(),
+                _ => false,
+            };
+            let authorized_by_signers = match command {
+                TokenInstruction::Transfer(_)
+                | TokenInstruction::Approve(_)
+                | TokenInstruction::SetOwner
+                | TokenInstruction::MintTo(_)
+                | TokenInstruction::Burn(_)
+                | TokenInstruction::Freeze
+                | TokenInstruction::Thaw => true,
+                TokenInstruction::NewToken(_)
+                | TokenInstruction::NewTokenAccount
+                | TokenInstruction::NewMultisig(_) => false,
+            };
+            match input_accounts[0] {
+                TokenState::Multisig(_) if authorized_by_signers => (),
+                TokenState::Invalid if approved_by_program => (),
+                _ => Err(TokenError::InvalidArgument)?,
+            }
+        }
+
         let mut output_accounts: Vec<(_, _)> = vec![];
 
         match command {
@@ -437,6 +850,26 @@ impl TokenState {
             TokenInstruction::SetOwner => {
                 Self::process_setowner(info, &input_accounts, &mut output_accounts)?
             }
+
+            TokenInstruction::MintTo(amount) => {
+                Self::process_mintto(info, amount, &input_accounts, &mut output_accounts)?
+            }
+
+            TokenInstruction::Burn(amount) => {
+                Self::process_burn(info, amount, &input_accounts, &mut output_accounts)?
+            }
+
+            TokenInstruction::Freeze => {
+                Self::process_freeze(info, true, &input_accounts, &mut output_accounts)?
+            }
+
+            TokenInstruction::Thaw => {
+                Self::process_freeze(info, false, &input_accounts, &mut output_accounts)?
+            }
+
+            TokenInstruction::NewMultisig(threshold) => {
+                Self::process_newmultisig(info, threshold, &input_accounts, &mut output_accounts)?
+            }
         }
 
         for (index, account) in &output_accounts {
@@ -450,6 +883,7 @@ impl TokenState {
 #[cfg(test)]
 mod test {
     use super::*;
+    use solana_sdk::account::Account;
     #[test]
     pub fn serde() {
         assert_eq!(TokenState::deserialize(&[0]), Ok(TokenState::default()));
@@ -461,6 +895,14 @@ mod test {
             owner: Pubkey::new(&[2; 32]),
             amount: 123,
             delegate: None,
+            is_frozen: true,
+        });
+        account.serialize(&mut data).unwrap();
+        assert_eq!(TokenState::deserialize(&data), Ok(account));
+
+        let account = TokenState::Multisig(MultisigInfo {
+            threshold: 2,
+            signers: vec![Pubkey::new(&[4; 32]), Pubkey::new(&[5; 32])],
         });
         account.serialize(&mut data).unwrap();
         assert_eq!(TokenState::deserialize(&data), Ok(account));
@@ -470,6 +912,8 @@ mod test {
             decimals: 2,
             name: "A test token".to_string(),
             symbol: "TEST".to_string(),
+            mint_authority: Some(Pubkey::new(&[3; 32])),
+            freeze_authority: None,
         });
         account.serialize(&mut data).unwrap();
         assert_eq!(TokenState::deserialize(&data), Ok(account));
@@ -495,5 +939,395 @@ mod test {
         assert!(TokenState::deserialize(&[3]).is_err());
     }
 
-    // Note: business logic tests are located in the @solana/web3.js test suite
+    fn new_token(supply: u64, authority: Option<Pubkey>) -> Account {
+        let mut account = Account::new(1, 256, &crate::id());
+        TokenState::Token(TokenInfo {
+            supply,
+            decimals: 2,
+            name: "A test token".to_string(),
+            symbol: "TEST".to_string(),
+            mint_authority: authority,
+            freeze_authority: authority,
+        })
+        .serialize(&mut account.data)
+        .unwrap();
+        account
+    }
+
+    fn new_token_account(token: &Pubkey, owner: &Pubkey, amount: u64) -> Account {
+        let mut account = Account::new(1, 256, &crate::id());
+        TokenState::Account(TokenAccountInfo {
+            token: *token,
+            owner: *owner,
+            amount,
+            delegate: None,
+            is_frozen: false,
+        })
+        .serialize(&mut account.data)
+        .unwrap();
+        account
+    }
+
+    fn process_instruction(
+        instruction: &TokenInstruction,
+        keys: &[&Pubkey],
+        accounts: &mut [Account],
+    ) -> Result<()> {
+        process_instruction_with_signers(instruction, keys, &[keys[0]], accounts)
+    }
+
+    fn process_instruction_with_signers(
+        instruction: &TokenInstruction,
+        keys: &[&Pubkey],
+        signers: &[&Pubkey],
+        accounts: &mut [Account],
+    ) -> Result<()> {
+        let mut keyed_accounts: Vec<_> = keys
+            .iter()
+            .zip(accounts.iter_mut())
+            .map(|(key, account)| KeyedAccount::new(key, signers.contains(key), account))
+            .collect();
+        TokenState::process(
+            &crate::id(),
+            &mut keyed_accounts,
+            &bincode::serialize(instruction).unwrap(),
+        )
+    }
+
+    fn supply(account: &Account) -> u64 {
+        match TokenState::deserialize(&account.data).unwrap() {
+            TokenState::Token(token_info) => token_info.supply,
+            _ => panic!("not a token"),
+        }
+    }
+
+    fn amount(account: &Account) -> u64 {
+        TokenState::deserialize(&account.data)
+            .unwrap()
+            .amount()
+            .unwrap()
+    }
+
+    #[test]
+    pub fn mint_and_burn() {
+        let authority = Pubkey::new_rand();
+        let owner = Pubkey::new_rand();
+        let token = Pubkey::new_rand();
+        let holder = Pubkey::new_rand();
+        let mut accounts = [
+            Account::default(),
+            new_token(100, Some(authority)),
+            new_token_account(&token, &owner, 100),
+        ];
+
+        let mint_keys = [&authority, &token, &holder];
+        assert_eq!(
+            process_instruction(&TokenInstruction::MintTo(50), &mint_keys, &mut accounts),
+            Ok(())
+        );
+        assert_eq!(supply(&accounts[1]), 150);
+        assert_eq!(amount(&accounts[2]), 150);
+
+        assert_eq!(
+            process_instruction(
+                &TokenInstruction::MintTo(50),
+                &[&owner, &token, &holder],
+                &mut accounts
+            ),
+            Err(TokenError::NotMintAuthority)
+        );
+
+        // Burn takes the token account before the token
+        accounts.swap(1, 2);
+        let burn_keys = [&owner, &holder, &token];
+        assert_eq!(
+            process_instruction(&TokenInstruction::Burn(120), &burn_keys, &mut accounts),
+            Ok(())
+        );
+        assert_eq!(amount(&accounts[1]), 30);
+        assert_eq!(supply(&accounts[2]), 30);
+
+        assert_eq!(
+            process_instruction(&TokenInstruction::Burn(31), &burn_keys, &mut accounts),
+            Err(TokenError::InsufficentFunds)
+        );
+    }
+
+    #[test]
+    pub fn mint_fixed_supply() {
+        let authority = Pubkey::new_rand();
+        let token = Pubkey::new_rand();
+        let mut accounts = [
+            Account::default(),
+            new_token(100, None),
+            new_token_account(&token, &authority, 100),
+        ];
+        assert_eq!(
+            process_instruction(
+                &TokenInstruction::MintTo(50),
+                &[&authority, &token, &Pubkey::new_rand()],
+                &mut accounts
+            ),
+            Err(TokenError::NotMintAuthority)
+        );
+    }
+
+    #[test]
+    pub fn freeze_and_thaw() {
+        let authority = Pubkey::new_rand();
+        let owner = Pubkey::new_rand();
+        let token = Pubkey::new_rand();
+        let holder = Pubkey::new_rand();
+        let recipient = Pubkey::new_rand();
+        let mut accounts = [
+            Account::default(),
+            new_token_account(&token, &owner, 100),
+            new_token(100, Some(authority)),
+        ];
+
+        let freeze_keys = [&authority, &holder, &token];
+        assert_eq!(
+            process_instruction(
+                &TokenInstruction::Freeze,
+                &[&owner, &holder, &token],
+                &mut accounts
+            ),
+            Err(TokenError::NotFreezeAuthority)
+        );
+        assert_eq!(
+            process_instruction(&TokenInstruction::Freeze, &freeze_keys, &mut accounts),
+            Ok(())
+        );
+        assert_eq!(
+            process_instruction(
+                &TokenInstruction::Burn(10),
+                &[&owner, &holder, &token],
+                &mut accounts
+            ),
+            Err(TokenError::AccountFrozen)
+        );
+
+        let mut transfer_accounts = [
+            Account::default(),
+            accounts[1].clone(),
+            new_token_account(&token, &owner, 0),
+        ];
+        let transfer_keys = [&owner, &holder, &recipient];
+        assert_eq!(
+            process_instruction(
+                &TokenInstruction::Transfer(10),
+                &transfer_keys,
+                &mut transfer_accounts
+            ),
+            Err(TokenError::AccountFrozen)
+        );
+
+        assert_eq!(
+            process_instruction(&TokenInstruction::Thaw, &freeze_keys, &mut accounts),
+            Ok(())
+        );
+        transfer_accounts[1] = accounts[1].clone();
+        assert_eq!(
+            process_instruction(
+                &TokenInstruction::Transfer(10),
+                &transfer_keys,
+                &mut transfer_accounts
+            ),
+            Ok(())
+        );
+        assert_eq!(amount(&transfer_accounts[1]), 90);
+        assert_eq!(amount(&transfer_accounts[2]), 10);
+    }
+
+    #[test]
+    pub fn multisig() {
+        let multisig = Pubkey::new_rand();
+        let signers: Vec<_> = (0..3).map(|_| Pubkey::new_rand()).collect();
+        let multisig_keys = [&multisig, &signers[0], &signers[1], &signers[2]];
+        let mut accounts = vec![Account::default(); 4];
+        accounts[0] = Account::new(1, 256, &crate::id());
+
+        assert_eq!(
+            process_instruction(
+                &TokenInstruction::NewMultisig(4),
+                &multisig_keys,
+                &mut accounts
+            ),
+            Err(TokenError::InvalidArgument)
+        );
+        assert_eq!(
+            process_instruction(
+                &TokenInstruction::NewMultisig(2),
+                &[&multisig, &signers[0], &signers[0]],
+                &mut accounts[..3]
+            ),
+            Err(TokenError::InvalidArgument)
+        );
+        assert_eq!(
+            process_instruction(
+                &TokenInstruction::NewMultisig(2),
+                &multisig_keys,
+                &mut accounts
+            ),
+            Ok(())
+        );
+        let multisig_account = accounts[0].clone();
+        assert_eq!(
+            TokenState::deserialize(&multisig_account.data),
+            Ok(TokenState::Multisig(MultisigInfo {
+                threshold: 2,
+                signers: signers.clone(),
+            }))
+        );
+
+        // A token account owned by the multisig needs 2 of its signers to transfer
+        let token = Pubkey::new_rand();
+        let source = Pubkey::new_rand();
+        let dest = Pubkey::new_rand();
+        let mut accounts = [
+            multisig_account.clone(),
+            new_token_account(&token, &multisig, 100),
+            new_token_account(&token, &dest, 0),
+            Account::default(),
+            Account::default(),
+        ];
+        let transfer_keys = [&multisig, &source, &dest, &signers[0], &signers[2]];
+        assert_eq!(
+            process_instruction_with_signers(
+                &TokenInstruction::Transfer(10),
+                &transfer_keys,
+                &[&signers[0]],
+                &mut accounts
+            ),
+            Err(TokenError::InvalidArgument)
+        );
+        assert_eq!(
+            process_instruction_with_signers(
+                &TokenInstruction::Transfer(10),
+                &[&multisig, &source, &dest, &signers[0], &signers[0]],
+                &[&signers[0]],
+                &mut accounts
+            ),
+            Err(TokenError::InvalidArgument)
+        );
+        assert_eq!(
+            process_instruction_with_signers(
+                &TokenInstruction::Transfer(10),
+                &transfer_keys,
+                &[&signers[0], &signers[2]],
+                &mut accounts
+            ),
+            Ok(())
+        );
+        assert_eq!(amount(&accounts[1]), 90);
+        assert_eq!(amount(&accounts[2]), 10);
+
+        // The multisig as a mint authority
+        let mut accounts = [
+            multisig_account,
+            new_token(100, Some(multisig)),
+            new_token_account(&token, &dest, 0),
+            Account::default(),
+            Account::default(),
+        ];
+        assert_eq!(
+            process_instruction_with_signers(
+                &TokenInstruction::MintTo(50),
+                &[&multisig, &token, &dest, &signers[1], &signers[2]],
+                &[&signers[1], &signers[2]],
+                &mut accounts
+            ),
+            Ok(())
+        );
+        assert_eq!(supply(&accounts[1]), 150);
+        assert_eq!(amount(&accounts[2]), 50);
+
+        // Only instructions checking the multisig's signers may go without key 0's signature
+        let mut accounts = [
+            accounts[0].clone(),
+            new_token_account(&multisig, &dest, 0),
+            Account::default(),
+        ];
+        assert_eq!(
+            process_instruction_with_signers(
+                &TokenInstruction::NewToken(TokenInfo::default()),
+                &[&multisig, &dest, &signers[0]],
+                &[&signers[0]],
+                &mut accounts
+            ),
+            Err(TokenError::InvalidArgument)
+        );
+    }
+
+    #[test]
+    pub fn transfer_approved_by_program() {
+        let program_id = Pubkey::new_rand();
+        let authority = Pubkey::new_rand();
+        let token = Pubkey::new_rand();
+        let source = Pubkey::new_rand();
+        let dest = Pubkey::new_rand();
+        let approval = TransferApproval {
+            source,
+            source_amount: 100,
+            destination: dest,
+            amount: 10,
+        };
+        let mut authority_account = Account::new(1, 0, &program_id);
+        authority_account.data = bincode::serialize(&Some(approval)).unwrap();
+        let mut accounts = [
+            authority_account.clone(),
+            new_token_account(&token, &authority, 100),
+            new_token_account(&token, &dest, 0),
+        ];
+        let transfer_keys = [&authority, &source, &dest];
+
+        // Only the approved amount may be transferred
+        assert_eq!(
+            process_instruction_with_signers(
+                &TokenInstruction::Transfer(11),
+                &transfer_keys,
+                &[],
+                &mut accounts
+            ),
+            Err(TokenError::InvalidArgument)
+        );
+        assert_eq!(
+            process_instruction_with_signers(
+                &TokenInstruction::Transfer(10),
+                &transfer_keys,
+                &[],
+                &mut accounts
+            ),
+            Ok(())
+        );
+        assert_eq!(amount(&accounts[1]), 90);
+        assert_eq!(amount(&accounts[2]), 10);
+
+        // and only once
+        assert_eq!(
+            process_instruction_with_signers(
+                &TokenInstruction::Transfer(10),
+                &transfer_keys,
+                &[],
+                &mut accounts
+            ),
+            Err(TokenError::InvalidArgument)
+        );
+
+        // A system account can't approve transfers
+        accounts[0].owner = system_program::id();
+        accounts[1] = new_token_account(&token, &authority, 100);
+        assert_eq!(
+            process_instruction_with_signers(
+                &TokenInstruction::Transfer(10),
+                &transfer_keys,
+                &[],
+                &mut accounts
+            ),
+            Err(TokenError::InvalidArgument)
+        );
+    }
+
+    // Note: business logic tests for the other instructions are located in the @solana/web3.js
+    // test suite
 }
@@ -25,8 +25,14 @@ pub enum VoteInstruction {
     /// A Vote instruction with recent votes
     Vote(Vote),
 
-    /// Withdraw some amount of funds
+    /// Withdraw some amount of funds, signed by the authorized withdrawer
     Withdraw(u64),
+
+    /// Authorize a key to withdraw funds and update the commission
+    AuthorizeWithdrawer(Pubkey),
+
+    /// Update the commission, signed by the authorized withdrawer
+    UpdateCommission(u8),
 }
 
 fn initialize_account(vote_pubkey: &Pubkey, node_pubkey: &Pubkey, commission: u8) -> Instruction {
@@ -60,10 +66,10 @@ pub fn create_account(
 
 fn metas_for_authorized_signer(
     vote_pubkey: &Pubkey,
-    authorized_voter_pubkey: &Pubkey, // currently authorized
+    authorized_pubkey: &Pubkey, // currently authorized
     other_params: &[AccountMeta],
 ) -> Vec<AccountMeta> {
-    let is_own_signer = authorized_voter_pubkey == vote_pubkey;
+    let is_own_signer = authorized_pubkey == vote_pubkey;
 
     // vote account
     let mut account_metas = vec![AccountMeta::new(*vote_pubkey, is_own_signer)];
@@ -74,7 +80,7 @@ fn metas_for_authorized_signer(
 
     // append signer at the end
     if !is_own_signer {
-        account_metas.push(AccountMeta::new_credit_only(*authorized_voter_pubkey, true)) // signer
+        account_metas.push(AccountMeta::new_credit_only(*authorized_pubkey, true)) // signer
     }
 
     account_metas
@@ -94,6 +100,34 @@ pub fn authorize_voter(
     )
 }
 
+pub fn authorize_withdrawer(
+    vote_pubkey: &Pubkey,
+    authorized_withdrawer_pubkey: &Pubkey, // currently authorized
+    new_authorized_withdrawer_pubkey: &Pubkey,
+) -> Instruction {
+    let account_metas = metas_for_authorized_signer(vote_pubkey, authorized_withdrawer_pubkey, &[]);
+
+    Instruction::new(
+        id(),
+        &VoteInstruction::AuthorizeWithdrawer(*new_authorized_withdrawer_pubkey),
+        account_metas,
+    )
+}
+
+pub fn update_commission(
+    vote_pubkey: &Pubkey,
+    authorized_withdrawer_pubkey: &Pubkey,
+    commission: u8,
+) -> Instruction {
+    let account_metas = metas_for_authorized_signer(vote_pubkey, authorized_withdrawer_pubkey, &[]);
+
+    Instruction::new(
+        id(),
+        &VoteInstruction::UpdateCommission(commission),
+        account_metas,
+    )
+}
+
 pub fn vote(vote_pubkey: &Pubkey, authorized_voter_pubkey: &Pubkey, vote: Vote) -> Instruction {
     let account_metas = metas_for_authorized_signer(
         vote_pubkey,
@@ -109,11 +143,17 @@ pub fn vote(vote_pubkey: &Pubkey, authorized_voter_pubkey: &Pubkey, vote: Vote)
     Instruction::new(id(), &VoteInstruction::Vote(vote), account_metas)
 }
 
-pub fn withdraw(vote_pubkey: &Pubkey, lamports: u64, to_pubkey: &Pubkey) -> Instruction {
-    let account_metas = vec![
-        AccountMeta::new(*vote_pubkey, true),
-        AccountMeta::new_credit_only(*to_pubkey, false),
-    ];
+pub fn withdraw(
+    vote_pubkey: &Pubkey,
+    authorized_withdrawer_pubkey: &Pubkey,
+    lamports: u64,
+    to_pubkey: &Pubkey,
+) -> Instruction {
+    let account_metas = metas_for_authorized_signer(
+        vote_pubkey,
+        authorized_withdrawer_pubkey,
+        &[AccountMeta::new_credit_only(*to_pubkey, false)],
+    );
 
     Instruction::new(id(), &VoteInstruction::Withdraw(lamports), account_metas)
 }
@@ -163,7 +203,14 @@ pub fn process_instruction(
             if rest.is_empty() {
                 Err(InstructionError::InvalidInstructionData)?;
             }
-            vote_state::withdraw(me, lamports, &mut rest[0])
+            let (to, other_signers) = rest.split_at_mut(1);
+            vote_state::withdraw(me, lamports, &mut to[0], other_signers)
+        }
+        VoteInstruction::AuthorizeWithdrawer(withdrawer_pubkey) => {
+            vote_state::authorize_withdrawer(me, rest, &withdrawer_pubkey)
+        }
+        VoteInstruction::UpdateCommission(commission) => {
+            vote_state::update_commission(me, rest, commission)
         }
     }
 }
@@ -240,6 +287,31 @@ mod tests {
             )),
             Err(InstructionError::InvalidAccountData),
         );
+        assert_eq!(
+            process_instruction(&authorize_withdrawer(
+                &Pubkey::default(),
+                &Pubkey::default(),
+                &Pubkey::default(),
+            )),
+            Err(InstructionError::InvalidAccountData),
+        );
+        assert_eq!(
+            process_instruction(&update_commission(
+                &Pubkey::default(),
+                &Pubkey::default(),
+                0,
+            )),
+            Err(InstructionError::InvalidAccountData),
+        );
+        assert_eq!(
+            process_instruction(&withdraw(
+                &Pubkey::default(),
+                &Pubkey::default(),
+                0,
+                &Pubkey::new_rand(),
+            )),
+            Err(InstructionError::InvalidAccountData),
+        );
     }
 
     #[test]
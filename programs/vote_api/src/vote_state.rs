@@ -103,6 +103,8 @@ pub struct VoteState {
     pub votes: VecDeque<Lockout>,
     pub node_pubkey: Pubkey,
     pub authorized_voter_pubkey: Pubkey,
+    /// the signer for withdrawals and commission updates
+    pub authorized_withdrawer_pubkey: Pubkey,
     /// fraction of std::u8::MAX that represents what part of a rewards
     ///  payout should be given to this VoteAccount
     pub commission: u8,
@@ -126,6 +128,7 @@ impl VoteState {
         Self {
             node_pubkey: *node_pubkey,
             authorized_voter_pubkey: *vote_pubkey,
+            authorized_withdrawer_pubkey: *vote_pubkey,
             commission,
             ..VoteState::default()
         }
@@ -344,6 +347,24 @@ impl VoteState {
     }
 }
 
+// the vote account itself, or any of the other signers, must carry the
+//  signature of the authorized pubkey
+fn verify_authorized_signer(
+    authorized: &Pubkey,
+    vote_account: &KeyedAccount,
+    other_signers: &[KeyedAccount],
+) -> Result<(), InstructionError> {
+    let authorized = Some(authorized);
+    if vote_account.signer_key() != authorized
+        && other_signers
+            .iter()
+            .all(|account| account.signer_key() != authorized)
+    {
+        return Err(InstructionError::MissingRequiredSignature);
+    }
+    Ok(())
+}
+
 /// Authorize the given pubkey to sign votes. This may be called multiple times,
 /// but will implicitly withdraw authorization from the previously authorized
 /// voter. The default voter is the owner of the vote account's pubkey.
@@ -355,28 +376,70 @@ pub fn authorize_voter(
     let mut vote_state: VoteState = vote_account.state()?;
 
     // clock authorized signer must say "yay"
-    let authorized = Some(&vote_state.authorized_voter_pubkey);
-    if vote_account.signer_key() != authorized
-        && other_signers
-            .iter()
-            .all(|account| account.signer_key() != authorized)
-    {
-        return Err(InstructionError::MissingRequiredSignature);
-    }
+    verify_authorized_signer(
+        &vote_state.authorized_voter_pubkey,
+        vote_account,
+        other_signers,
+    )?;
 
     vote_state.authorized_voter_pubkey = *authorized_voter_pubkey;
     vote_account.set_state(&vote_state)
 }
 
-/// Withdraw funds from the vote account
+/// Authorize the given pubkey to withdraw funds and update the commission. This
+/// may be called multiple times, but will implicitly withdraw authorization from
+/// the previously authorized withdrawer. The default withdrawer is the owner of
+/// the vote account's pubkey.
+pub fn authorize_withdrawer(
+    vote_account: &mut KeyedAccount,
+    other_signers: &[KeyedAccount],
+    authorized_withdrawer_pubkey: &Pubkey,
+) -> Result<(), InstructionError> {
+    let mut vote_state: VoteState = vote_account.state()?;
+
+    verify_authorized_signer(
+        &vote_state.authorized_withdrawer_pubkey,
+        vote_account,
+        other_signers,
+    )?;
+
+    vote_state.authorized_withdrawer_pubkey = *authorized_withdrawer_pubkey;
+    vote_account.set_state(&vote_state)
+}
+
+/// Update the commission on the vote account, signed by the authorized withdrawer
+pub fn update_commission(
+    vote_account: &mut KeyedAccount,
+    other_signers: &[KeyedAccount],
+    commission: u8,
+) -> Result<(), InstructionError> {
+    let mut vote_state: VoteState = vote_account.state()?;
+
+    verify_authorized_signer(
+        &vote_state.authorized_withdrawer_pubkey,
+        vote_account,
+        other_signers,
+    )?;
+
+    vote_state.commission = commission;
+    vote_account.set_state(&vote_state)
+}
+
+/// Withdraw funds from the vote account, signed by the authorized withdrawer
 pub fn withdraw(
     vote_account: &mut KeyedAccount,
     lamports: u64,
     to_account: &mut KeyedAccount,
+    other_signers: &[KeyedAccount],
 ) -> Result<(), InstructionError> {
-    if vote_account.signer_key().is_none() {
-        return Err(InstructionError::MissingRequiredSignature);
-    }
+    let vote_state: VoteState = vote_account.state()?;
+
+    verify_authorized_signer(
+        &vote_state.authorized_withdrawer_pubkey,
+        vote_account,
+        other_signers,
+    )?;
+
     if vote_account.account.lamports < lamports {
         return Err(InstructionError::InsufficientFunds);
     }
@@ -418,15 +481,12 @@ pub fn process_vote(
         return Err(InstructionError::UninitializedAccount);
     }
 
-    let authorized = Some(&vote_state.authorized_voter_pubkey);
     // find a signer that matches the authorized_voter_pubkey
-    if vote_account.signer_key() != authorized
-        && other_signers
-            .iter()
-            .all(|account| account.signer_key() != authorized)
-    {
-        return Err(InstructionError::MissingRequiredSignature);
-    }
+    verify_authorized_signer(
+        &vote_state.authorized_voter_pubkey,
+        vote_account,
+        other_signers,
+    )?;
 
     vote_state.process_vote(vote, slot_hashes, clock.epoch)?;
     vote_account.set_state(&vote_state)
@@ -536,6 +596,7 @@ mod tests {
 
         let vote_state: VoteState = vote_account.state().unwrap();
         assert_eq!(vote_state.authorized_voter_pubkey, vote_pubkey);
+        assert_eq!(vote_state.authorized_withdrawer_pubkey, vote_pubkey);
         assert!(vote_state.votes.is_empty());
     }
 
@@ -984,6 +1045,7 @@ mod tests {
             &mut KeyedAccount::new(&vote_pubkey, false, &mut vote_account),
             0,
             &mut KeyedAccount::new(&Pubkey::new_rand(), false, &mut Account::default()),
+            &[],
         );
         assert_eq!(res, Err(InstructionError::MissingRequiredSignature));
 
@@ -992,6 +1054,7 @@ mod tests {
             &mut KeyedAccount::new(&vote_pubkey, true, &mut vote_account),
             101,
             &mut KeyedAccount::new(&Pubkey::new_rand(), false, &mut Account::default()),
+            &[],
         );
         assert_eq!(res, Err(InstructionError::InsufficientFunds));
 
@@ -1002,12 +1065,83 @@ mod tests {
             &mut KeyedAccount::new(&vote_pubkey, true, &mut vote_account),
             lamports,
             &mut KeyedAccount::new(&Pubkey::new_rand(), false, &mut to_account),
+            &[],
         );
         assert_eq!(res, Ok(()));
         assert_eq!(vote_account.lamports, 0);
         assert_eq!(to_account.lamports, lamports);
     }
 
+    #[test]
+    fn test_vote_state_authorize_withdrawer() {
+        let (vote_pubkey, mut vote_account) = create_test_account();
+        let authorized_withdrawer_pubkey = Pubkey::new_rand();
+
+        // unsigned
+        let res = authorize_withdrawer(
+            &mut KeyedAccount::new(&vote_pubkey, false, &mut vote_account),
+            &[],
+            &authorized_withdrawer_pubkey,
+        );
+        assert_eq!(res, Err(InstructionError::MissingRequiredSignature));
+
+        let res = authorize_withdrawer(
+            &mut KeyedAccount::new(&vote_pubkey, true, &mut vote_account),
+            &[],
+            &authorized_withdrawer_pubkey,
+        );
+        assert_eq!(res, Ok(()));
+
+        // the voter is untouched, and can no longer withdraw or update the commission
+        let vote_state: VoteState = vote_account.state().unwrap();
+        assert_eq!(vote_state.authorized_voter_pubkey, vote_pubkey);
+        assert_eq!(
+            vote_state.authorized_withdrawer_pubkey,
+            authorized_withdrawer_pubkey
+        );
+        let res = withdraw(
+            &mut KeyedAccount::new(&vote_pubkey, true, &mut vote_account),
+            0,
+            &mut KeyedAccount::new(&Pubkey::new_rand(), false, &mut Account::default()),
+            &[],
+        );
+        assert_eq!(res, Err(InstructionError::MissingRequiredSignature));
+        let res = update_commission(
+            &mut KeyedAccount::new(&vote_pubkey, true, &mut vote_account),
+            &[],
+            42,
+        );
+        assert_eq!(res, Err(InstructionError::MissingRequiredSignature));
+
+        // signed by the authorized withdrawer
+        let mut to_account = Account::default();
+        let res = withdraw(
+            &mut KeyedAccount::new(&vote_pubkey, false, &mut vote_account),
+            10,
+            &mut KeyedAccount::new(&Pubkey::new_rand(), false, &mut to_account),
+            &[KeyedAccount::new(
+                &authorized_withdrawer_pubkey,
+                true,
+                &mut Account::default(),
+            )],
+        );
+        assert_eq!(res, Ok(()));
+        assert_eq!(to_account.lamports, 10);
+
+        let res = update_commission(
+            &mut KeyedAccount::new(&vote_pubkey, false, &mut vote_account),
+            &[KeyedAccount::new(
+                &authorized_withdrawer_pubkey,
+                true,
+                &mut Account::default(),
+            )],
+            42,
+        );
+        assert_eq!(res, Ok(()));
+        let vote_state: VoteState = vote_account.state().unwrap();
+        assert_eq!(vote_state.commission, 42);
+    }
+
     #[test]
     fn test_vote_state_epoch_credits() {
         let mut vote_state = VoteState::default();
@@ -3,6 +3,7 @@ use crate::accounts_index::{AccountsIndex, Fork};
 use crate::append_vec::StoredAccount;
 use crate::blockhash_queue::BlockhashQueue;
 use crate::message_processor::has_duplicates;
+use crate::nonce_utils;
 use crate::rent_collector::RentCollector;
 use bincode::serialize;
 use log::*;
@@ -112,7 +113,7 @@ impl Accounts {
                 .filter(|key| !message.program_ids().contains(&key))
             {
                 let (account, rent) = AccountsDB::load(storage, ancestors, accounts_index, key)
-                    .and_then(|(account, _)| rent_collector.update(account))
+                    .map(|(account, _)| rent_collector.update(account))
                     .unwrap_or_default();
 
                 accounts.push(account);
@@ -235,6 +236,12 @@ impl Accounts {
                 (tx, Ok(())) => {
                     let fee_calculator = hash_queue
                         .get_fee_calculator(&tx.message().recent_blockhash)
+                        .or_else(|| {
+                            // durable nonce transactions pay the current fee
+                            nonce_utils::transaction_uses_durable_nonce(tx).and_then(|_| {
+                                hash_queue.get_fee_calculator(&hash_queue.last_hash())
+                            })
+                        })
                         .ok_or(TransactionError::BlockhashNotFound)?;
 
                     let fee = fee_calculator.calculate_fee(tx.message());
@@ -322,7 +329,8 @@ impl Accounts {
         ancestors: &HashMap<Fork, usize>,
         program_id: &Pubkey,
     ) -> Vec<(Pubkey, Account)> {
-        self.accounts_db.scan_accounts(
+        self.accounts_db.scan_program_accounts(
+            program_id,
             ancestors,
             |collector: &mut Vec<(Pubkey, Account)>, option| {
                 if let Some(data) = option
@@ -551,6 +559,22 @@ impl Accounts {
         self.accounts_db.add_root(fork)
     }
 
+    /// Merkle hash over every account on the rooted path
+    pub fn accounts_hash(&self) -> Hash {
+        self.accounts_db.accounts_hash()
+    }
+
+    pub fn calculate_accounts_hash(&self, ancestors: &HashMap<Fork, usize>) -> Hash {
+        self.accounts_db.calculate_accounts_hash(ancestors)
+    }
+
+    /// Purge zero-lamport accounts rooted at or before `max_clean_root`, then compact the
+    /// storage of rooted forks
+    pub fn clean_and_shrink(&self, max_clean_root: Option<Fork>) {
+        self.accounts_db.clean_accounts(max_clean_root);
+        self.accounts_db.shrink_all_forks();
+    }
+
     /// Commit remaining credit-only changes, regardless of reference count
     ///
     /// We do a take() on `self.credit_only_account_locks` so that the hashmap is no longer
@@ -1183,6 +1207,42 @@ mod tests {
         assert_eq!(loaded, vec![]);
     }
 
+    #[test]
+    fn test_load_by_program() {
+        let accounts = Accounts::new(None);
+        let program0 = Pubkey::new(&[2; 32]);
+        let program1 = Pubkey::new(&[3; 32]);
+
+        let pubkey0 = Pubkey::new_rand();
+        let account0 = Account::new(1, 0, &program0);
+        accounts.store_slow(0, &pubkey0, &account0);
+        let pubkey1 = Pubkey::new_rand();
+        let account1 = Account::new(1, 0, &program1);
+        accounts.store_slow(0, &pubkey1, &account1);
+
+        // fork 1 hands pubkey0 over to program1
+        let account0_moved = Account::new(1, 0, &program1);
+        accounts.store_slow(1, &pubkey0, &account0_moved);
+
+        let ancestors = vec![(0, 0)].into_iter().collect();
+        assert_eq!(
+            accounts.load_by_program(&ancestors, &program0),
+            vec![(pubkey0, account0)]
+        );
+        assert_eq!(
+            accounts.load_by_program(&ancestors, &program1),
+            vec![(pubkey1, account1.clone())]
+        );
+
+        let ancestors = vec![(0, 0), (1, 1)].into_iter().collect();
+        assert_eq!(accounts.load_by_program(&ancestors, &program0), vec![]);
+        let mut loaded = accounts.load_by_program(&ancestors, &program1);
+        loaded.sort_by_key(|(pubkey, _)| *pubkey);
+        let mut expected = vec![(pubkey0, account0_moved), (pubkey1, account1)];
+        expected.sort_by_key(|(pubkey, _)| *pubkey);
+        assert_eq!(loaded, expected);
+    }
+
     #[test]
     fn test_accounts_account_not_found() {
         let accounts = Accounts::new(None);
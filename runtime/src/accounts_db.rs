@@ -20,7 +20,7 @@
 
 use crate::accounts_index::{AccountsIndex, Fork};
 use crate::append_vec::{AppendVec, StorageMeta, StoredAccount};
-use bincode::{deserialize_from, serialize_into};
+use bincode::{deserialize_from, serialize, serialize_into};
 use fs_extra::dir::CopyOptions;
 use log::*;
 use rand::{thread_rng, Rng};
@@ -31,8 +31,9 @@ use serde::ser::{SerializeMap, Serializer};
 use serde::{Deserialize, Serialize};
 use solana_measure::measure::Measure;
 use solana_sdk::account::Account;
+use solana_sdk::hash::{Hash, Hasher};
 use solana_sdk::pubkey::Pubkey;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fmt;
 use std::io::{BufReader, Cursor, Error as IOError, ErrorKind, Read, Result as IOResult};
 use std::path::Path;
@@ -45,6 +46,11 @@ use tempfile::TempDir;
 pub const DEFAULT_FILE_SIZE: u64 = 4 * 1024 * 1024;
 pub const DEFAULT_NUM_THREADS: u32 = 8;
 pub const DEFAULT_NUM_DIRS: u32 = 4;
+// A rooted fork is rewritten once fewer than this percentage of the accounts appended to its
+//  stores are still alive
+pub const SHRINK_THRESHOLD_PERCENT: usize = 50;
+// Number of children hashed together at each level of the accounts merkle tree
+pub const MERKLE_FANOUT: usize = 16;
 
 #[derive(Debug, Default)]
 pub struct ErrorCounters {
@@ -185,6 +191,11 @@ pub struct AccountStorageEntry {
     /// status corresponding to the storage, lets us know that
     ///  the append_vec, once maxed out, then emptied, can be reclaimed
     count_and_status: RwLock<(usize, AccountStorageStatus)>,
+
+    /// The number of accounts appended to the storage since it was last reset, alive or
+    ///  dead, which tells sparse stores apart from full ones
+    #[serde(skip)]
+    approx_store_count: AtomicUsize,
 }
 
 impl AccountStorageEntry {
@@ -198,6 +209,7 @@ impl AccountStorageEntry {
             fork_id,
             accounts,
             count_and_status: RwLock::new((0, AccountStorageStatus::Available)),
+            approx_store_count: AtomicUsize::new(0),
         }
     }
 
@@ -216,6 +228,7 @@ impl AccountStorageEntry {
             //  the append_vec has previously been completely full
             //
             self.accounts.reset();
+            self.approx_store_count.store(0, Ordering::Relaxed);
             status = AccountStorageStatus::Available;
         }
 
@@ -230,6 +243,10 @@ impl AccountStorageEntry {
         self.count_and_status.read().unwrap().0
     }
 
+    pub fn approx_stored_count(&self) -> usize {
+        self.approx_store_count.load(Ordering::Relaxed)
+    }
+
     pub fn fork_id(&self) -> Fork {
         self.fork_id
     }
@@ -238,9 +255,15 @@ impl AccountStorageEntry {
         self.id
     }
 
+    /// Number of bytes appended to the storage since it was last reset
+    pub fn written_len(&self) -> usize {
+        self.accounts.len()
+    }
+
     fn add_account(&self) {
         let mut count_and_status = self.count_and_status.write().unwrap();
         *count_and_status = (count_and_status.0 + 1, count_and_status.1);
+        self.approx_store_count.fetch_add(1, Ordering::Relaxed);
     }
 
     fn try_available(&self) -> bool {
@@ -272,6 +295,7 @@ impl AccountStorageEntry {
             // otherwise, the storage may be in flight with a store()
             //   call
             self.accounts.reset();
+            self.approx_store_count.store(0, Ordering::Relaxed);
             status = AccountStorageStatus::Available;
         }
 
@@ -366,6 +390,12 @@ pub struct AccountsDB {
     pub thread_pool: ThreadPool,
 
     min_num_stores: usize,
+
+    /// Hash of every live account on the rooted path, kept up to date as forks are rooted
+    rooted_account_hashes: RwLock<BTreeMap<Pubkey, Hash>>,
+
+    /// Roots whose accounts `clean_accounts` hasn't looked at yet
+    uncleaned_roots: RwLock<HashSet<Fork>>,
 }
 
 impl Default for AccountsDB {
@@ -385,6 +415,8 @@ impl Default for AccountsDB {
                 .build()
                 .unwrap(),
             min_num_stores: num_threads,
+            rooted_account_hashes: RwLock::new(BTreeMap::new()),
+            uncleaned_roots: RwLock::new(HashSet::new()),
         }
     }
 }
@@ -478,6 +510,8 @@ impl AccountsDB {
                     u_storage_entry
                         .set_file(local_path)
                         .map_err(|e| AccountsDB::get_io_error(&e.to_string()))?;
+                    u_storage_entry.approx_store_count =
+                        AtomicUsize::new(u_storage_entry.accounts.accounts(0).len());
                     new_fork_storage.insert(id, Arc::new(u_storage_entry));
                 }
                 Ok((fork_id, new_fork_storage))
@@ -513,6 +547,7 @@ impl AccountsDB {
         self.write_version
             .fetch_add(version as usize, Ordering::Relaxed);
         self.generate_index();
+        *self.rooted_account_hashes.write().unwrap() = self.account_hashes(&HashMap::new());
         Ok(())
     }
 
@@ -566,6 +601,43 @@ impl AccountsDB {
         collector
     }
 
+    /// Like `scan_accounts`, but only over the accounts that have been stored under `owner`,
+    ///  found through the index rather than by visiting every account
+    pub fn scan_program_accounts<F, A>(
+        &self,
+        owner: &Pubkey,
+        ancestors: &HashMap<Fork, usize>,
+        scan_func: F,
+    ) -> A
+    where
+        F: Fn(&mut A, Option<(&Pubkey, Account, Fork)>) -> (),
+        A: Default,
+    {
+        let mut collector = A::default();
+        let accounts_index = self.accounts_index.read().unwrap();
+        let storage = self.storage.read().unwrap();
+        accounts_index.scan_program_accounts(owner, ancestors, |pubkey, (account_info, fork)| {
+            scan_func(
+                &mut collector,
+                storage
+                    .0
+                    .get(&fork)
+                    .and_then(|storage_map| storage_map.get(&account_info.id))
+                    .and_then(|store| {
+                        Some(
+                            store
+                                .accounts
+                                .get_account(account_info.offset)?
+                                .0
+                                .clone_account(),
+                        )
+                    })
+                    .map(|account| (pubkey, account, fork)),
+            )
+        });
+        collector
+    }
+
     /// Scan a specific fork through all the account storage in parallel with sequential read
     // PERF: Sequentially read each storage entry in parallel
     pub fn scan_account_storage<F, B>(&self, fork_id: Fork, scan_func: F) -> Vec<B>
@@ -762,21 +834,53 @@ impl AccountsDB {
         for (info, pubkey_account) in infos.into_iter().zip(accounts.iter()) {
             let pubkey = pubkey_account.0;
             if let Some(info) = index.update(fork_id, pubkey, info, &mut reclaims) {
-                inserts.push((pubkey, info));
+                inserts.push((pubkey, pubkey_account.1, info));
+            } else if index.add_program_account(&pubkey_account.1.owner, pubkey) {
+                self.retain_program_account_owners(&index, pubkey);
             }
         }
         let last_root = index.last_root;
         drop(index);
         if !inserts.is_empty() {
             let mut index = self.accounts_index.write().unwrap();
-            for (pubkey, info) in inserts {
+            for (pubkey, account, info) in inserts {
                 index.insert(fork_id, pubkey, info, &mut reclaims);
+                if index.add_program_account(&account.owner, pubkey) {
+                    self.retain_program_account_owners(&index, pubkey);
+                }
             }
         }
         update_index_work.stop();
         (reclaims, last_root)
     }
 
+    /// Once an account's owner has changed, keep it listed only under the owners of the
+    ///  versions still in the index, so the old owner drops it once the version it had
+    ///  is replaced on a root
+    fn retain_program_account_owners(&self, index: &AccountsIndex<AccountInfo>, pubkey: &Pubkey) {
+        let list = match index.account_maps.get(pubkey) {
+            Some(list) => list.read().unwrap(),
+            None => return,
+        };
+        let storage = self.storage.read().unwrap();
+        let owners: Option<HashSet<Pubkey>> = list
+            .iter()
+            .map(|(fork, info)| {
+                storage
+                    .0
+                    .get(fork)
+                    .and_then(|fork_stores| fork_stores.get(&info.id))
+                    .and_then(|store| store.accounts.get_account(info.offset))
+                    .map(|(stored_account, _)| stored_account.balance.owner)
+            })
+            .collect();
+        drop(storage);
+        // the list stays locked so no version with another owner is stored meanwhile
+        if let Some(owners) = owners {
+            index.retain_program_account_owners(pubkey, &owners);
+        }
+    }
+
     fn remove_dead_accounts(&self, reclaims: Vec<(Fork, AccountInfo)>) -> HashSet<Fork> {
         let storage = self.storage.read().unwrap();
         let mut dead_forks = HashSet::new();
@@ -831,6 +935,10 @@ impl AccountsDB {
         update_index.stop();
         trace!("reclaim: {}", reclaims.len());
 
+        self.handle_reclaims(reclaims, last_root);
+    }
+
+    fn handle_reclaims(&self, reclaims: Vec<(Fork, AccountInfo)>, last_root: Fork) {
         let mut remove_dead_accounts = Measure::start("store::remove_dead");
         let mut dead_forks = self.remove_dead_accounts(reclaims);
         remove_dead_accounts.stop();
@@ -849,7 +957,359 @@ impl AccountsDB {
     }
 
     pub fn add_root(&self, fork: Fork) {
-        self.accounts_index.write().unwrap().add_root(fork)
+        let is_new_root = !self.accounts_index.read().unwrap().is_root(fork);
+        self.accounts_index.write().unwrap().add_root(fork);
+        if is_new_root {
+            self.update_rooted_account_hashes(fork);
+            self.uncleaned_roots.write().unwrap().insert(fork);
+        }
+    }
+
+    pub fn hash_account(pubkey: &Pubkey, account: &Account) -> Hash {
+        let mut hasher = Hasher::default();
+        hasher.hash(pubkey.as_ref());
+        hasher.hash(&serialize(account).unwrap());
+        hasher.result()
+    }
+
+    /// Merkle root over `hashes`, which the caller orders by pubkey
+    pub fn compute_merkle_root(mut hashes: Vec<Hash>, fanout: usize) -> Hash {
+        if hashes.is_empty() {
+            return Hash::default();
+        }
+        loop {
+            hashes = hashes
+                .chunks(fanout)
+                .map(|chunk| {
+                    let mut hasher = Hasher::default();
+                    for hash in chunk {
+                        hasher.hash(hash.as_ref());
+                    }
+                    hasher.result()
+                })
+                .collect();
+            if hashes.len() == 1 {
+                return hashes[0];
+            }
+        }
+    }
+
+    /// Hash of all the accounts on the rooted path, as of the last root added
+    pub fn accounts_hash(&self) -> Hash {
+        let hashes = self
+            .rooted_account_hashes
+            .read()
+            .unwrap()
+            .values()
+            .cloned()
+            .collect();
+        Self::compute_merkle_root(hashes, MERKLE_FANOUT)
+    }
+
+    /// Recompute the hash of all the accounts visible from `ancestors` from scratch, used to
+    ///  check the hash `accounts_hash` maintained incrementally
+    pub fn calculate_accounts_hash(&self, ancestors: &HashMap<Fork, usize>) -> Hash {
+        let hashes = self.account_hashes(ancestors).values().cloned().collect();
+        Self::compute_merkle_root(hashes, MERKLE_FANOUT)
+    }
+
+    fn account_hashes(&self, ancestors: &HashMap<Fork, usize>) -> BTreeMap<Pubkey, Hash> {
+        self.scan_accounts(
+            ancestors,
+            |collector: &mut BTreeMap<Pubkey, Hash>, option| {
+                if let Some((pubkey, account, _fork)) = option {
+                    if account.lamports != 0 {
+                        collector.insert(*pubkey, Self::hash_account(pubkey, &account));
+                    }
+                }
+            },
+        )
+    }
+
+    // Fold the latest version of each account stored in a newly rooted fork into
+    //  `rooted_account_hashes`, dropping the accounts it left with no lamports
+    fn update_rooted_account_hashes(&self, fork: Fork) {
+        let accumulator: Vec<HashMap<Pubkey, (u64, Option<Hash>)>> = self.scan_account_storage(
+            fork,
+            |stored_account: &StoredAccount,
+             _id: AppendVecId,
+             accum: &mut HashMap<Pubkey, (u64, Option<Hash>)>| {
+                let pubkey = stored_account.meta.pubkey;
+                let write_version = stored_account.meta.write_version;
+                if let Some((version, _)) = accum.get(&pubkey) {
+                    if *version > write_version {
+                        return;
+                    }
+                }
+                let hash = if stored_account.balance.lamports != 0 {
+                    Some(Self::hash_account(&pubkey, &stored_account.clone_account()))
+                } else {
+                    None
+                };
+                accum.insert(pubkey, (write_version, hash));
+            },
+        );
+
+        let mut delta: HashMap<Pubkey, (u64, Option<Hash>)> = HashMap::new();
+        for maps in accumulator {
+            for (pubkey, (write_version, hash)) in maps {
+                if let Some((version, _)) = delta.get(&pubkey) {
+                    if *version > write_version {
+                        continue;
+                    }
+                }
+                delta.insert(pubkey, (write_version, hash));
+            }
+        }
+
+        let mut rooted_account_hashes = self.rooted_account_hashes.write().unwrap();
+        for (pubkey, (_, hash)) in delta {
+            match hash {
+                Some(hash) => rooted_account_hashes.insert(pubkey, hash),
+                None => rooted_account_hashes.remove(&pubkey),
+            };
+        }
+    }
+
+    /// Rewrite the live accounts of a rooted fork into a single new store once most of what
+    ///  its stores hold is dead, otherwise just drop the stores with no live accounts left
+    pub fn shrink_fork(&self, fork: Fork) {
+        if !self.accounts_index.read().unwrap().is_root(fork) {
+            return;
+        }
+        let stores: Vec<Arc<AccountStorageEntry>> = match self.storage.read().unwrap().0.get(&fork)
+        {
+            Some(fork_stores) => fork_stores.values().cloned().collect(),
+            None => return,
+        };
+        let alive_count: usize = stores.iter().map(|store| store.count()).sum();
+        let stored_count: usize = stores.iter().map(|store| store.approx_stored_count()).sum();
+        if alive_count * 100 >= stored_count * SHRINK_THRESHOLD_PERCENT {
+            let dead_ids: Vec<AppendVecId> = stores
+                .iter()
+                .filter(|store| store.count() == 0)
+                .map(|store| store.id)
+                .collect();
+            self.remove_stores(fork, &dead_ids);
+            return;
+        }
+
+        // (meta, account, old store id, old offset) of every account still in the index
+        let mut alive = vec![];
+        let mut alive_size = 0;
+        {
+            let index = self.accounts_index.read().unwrap();
+            for store in &stores {
+                let mut start = 0;
+                while let Some((stored_account, next)) = store.accounts.get_account(start) {
+                    let is_alive = index
+                        .account_maps
+                        .get(&stored_account.meta.pubkey)
+                        .map(|list| {
+                            list.read().unwrap().iter().any(|(list_fork, info)| {
+                                *list_fork == fork
+                                    && info.id == store.id
+                                    && info.offset == stored_account.offset
+                            })
+                        })
+                        .unwrap_or(false);
+                    if is_alive {
+                        alive.push((
+                            stored_account.meta.clone(),
+                            stored_account.clone_account(),
+                            store.id,
+                            stored_account.offset,
+                        ));
+                        alive_size += next - start;
+                    }
+                    start = next;
+                }
+            }
+        }
+
+        let old_ids: Vec<AppendVecId> = stores.iter().map(|store| store.id).collect();
+        if !alive.is_empty() {
+            let new_store = self.create_and_insert_store(fork, alive_size as u64);
+            let with_meta: Vec<(StorageMeta, &Account)> = alive
+                .iter()
+                .map(|(meta, account, _, _)| (meta.clone(), account))
+                .collect();
+            let offsets = new_store.accounts.append_accounts(&with_meta);
+            assert_eq!(offsets.len(), with_meta.len());
+
+            // counted before the index points at them, so that a store that replaces one
+            //  in the meantime finds it counted when it removes it
+            for _ in 0..offsets.len() {
+                new_store.add_account();
+            }
+
+            // accounts stored over since they were read are left dead in the new store
+            let index = self.accounts_index.read().unwrap();
+            for ((meta, account, old_id, old_offset), offset) in alive.iter().zip(offsets) {
+                let moved = index.account_maps.get(&meta.pubkey).map(|list| {
+                    let mut list = list.write().unwrap();
+                    list.iter_mut()
+                        .find(|(list_fork, info)| {
+                            *list_fork == fork && info.id == *old_id && info.offset == *old_offset
+                        })
+                        .map(|(_, info)| {
+                            *info = AccountInfo {
+                                id: new_store.id,
+                                offset,
+                                lamports: account.lamports,
+                            }
+                        })
+                        .is_some()
+                });
+                if moved != Some(true) {
+                    new_store.remove_account();
+                }
+            }
+        }
+        self.remove_stores(fork, &old_ids);
+    }
+
+    fn remove_stores(&self, fork: Fork, ids: &[AppendVecId]) {
+        if ids.is_empty() {
+            return;
+        }
+        let mut storage = self.storage.write().unwrap();
+        if let Some(fork_stores) = storage.0.get_mut(&fork) {
+            for id in ids {
+                fork_stores.remove(id);
+            }
+            if fork_stores.is_empty() {
+                storage.0.remove(&fork);
+            }
+        }
+    }
+
+    /// Shrink every rooted fork, see `shrink_fork`
+    pub fn shrink_all_forks(&self) {
+        let mut forks: Vec<Fork> = self.storage.read().unwrap().0.keys().cloned().collect();
+        forks.sort();
+        for fork in forks {
+            self.shrink_fork(fork);
+        }
+    }
+
+    /// Purge the accounts whose latest version is a zero-lamport one rooted at or before
+    ///  `max_clean_root`, so that snapshots taken up to it keep seeing the account drained.
+    ///  Only the accounts stored in forks rooted since they were last cleaned are looked at.
+    ///  An account is only purged once no older copy of it is left in storage, which could
+    ///  otherwise come back when the index is regenerated from storage.
+    pub fn clean_accounts(&self, max_clean_root: Option<Fork>) {
+        let is_purgeable = |index: &AccountsIndex<AccountInfo>, list: &[(Fork, AccountInfo)]| {
+            list.iter().all(|(fork, _)| index.is_root(*fork))
+                && list
+                    .iter()
+                    .max_by_key(|(fork, _)| *fork)
+                    .map(|(fork, info)| {
+                        info.lamports == 0
+                            && max_clean_root.map(|root| *fork <= root).unwrap_or(true)
+                    })
+                    .unwrap_or(false)
+        };
+
+        let roots: Vec<Fork> = self
+            .uncleaned_roots
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|fork| max_clean_root.map(|root| **fork <= root).unwrap_or(true))
+            .cloned()
+            .collect();
+        if roots.is_empty() {
+            return;
+        }
+
+        // the accounts stored in those roots, by the root their latest version is in
+        let mut candidates: HashMap<Pubkey, Fork> = HashMap::new();
+        for fork in &roots {
+            let stored: Vec<Vec<Pubkey>> = self.scan_account_storage(
+                *fork,
+                |stored_account: &StoredAccount, _id: AppendVecId, accum: &mut Vec<Pubkey>| {
+                    accum.push(stored_account.meta.pubkey)
+                },
+            );
+            let index = self.accounts_index.read().unwrap();
+            for pubkey in stored.into_iter().flatten() {
+                if let Some(list) = index.account_maps.get(&pubkey) {
+                    let list = list.read().unwrap();
+                    if is_purgeable(&index, &list) {
+                        let latest_fork = list.iter().map(|(fork, _)| *fork).max().unwrap();
+                        candidates.insert(pubkey, latest_fork);
+                    }
+                }
+            }
+        }
+
+        // an older copy the index no longer points to is dead, so it can only be in a
+        //  store that's had some of its accounts removed
+        let mut has_old_copy = HashSet::new();
+        if !candidates.is_empty() {
+            let index = self.accounts_index.read().unwrap();
+            for store in self.get_storage_entries() {
+                if store.count() >= store.approx_stored_count() {
+                    continue;
+                }
+                for stored_account in store.accounts.accounts(0) {
+                    let pubkey = stored_account.meta.pubkey;
+                    if !candidates.contains_key(&pubkey) {
+                        continue;
+                    }
+                    let is_indexed = index
+                        .account_maps
+                        .get(&pubkey)
+                        .map(|list| {
+                            list.read().unwrap().iter().any(|(fork, info)| {
+                                *fork == store.fork_id
+                                    && info.id == store.id
+                                    && info.offset == stored_account.offset
+                            })
+                        })
+                        .unwrap_or(false);
+                    if !is_indexed {
+                        has_old_copy.insert(pubkey);
+                    }
+                }
+            }
+        }
+
+        let mut reclaims = vec![];
+        let mut index = self.accounts_index.write().unwrap();
+        for pubkey in candidates.keys() {
+            if has_old_copy.contains(pubkey) {
+                continue;
+            }
+            let purge = index
+                .account_maps
+                .get(pubkey)
+                .map(|list| is_purgeable(&index, &list.read().unwrap()))
+                .unwrap_or(false);
+            if purge {
+                let list = index.account_maps.remove(pubkey).unwrap();
+                reclaims.extend(list.into_inner().unwrap());
+                index.remove_program_account(pubkey);
+            }
+        }
+        let last_root = index.last_root;
+        drop(index);
+
+        // the roots holding accounts that couldn't be purged yet are looked at again
+        let retry: HashSet<Fork> = has_old_copy
+            .iter()
+            .map(|pubkey| candidates[pubkey])
+            .collect();
+        let mut uncleaned_roots = self.uncleaned_roots.write().unwrap();
+        for fork in roots {
+            if !retry.contains(&fork) {
+                uncleaned_roots.remove(&fork);
+            }
+        }
+        drop(uncleaned_roots);
+
+        self.handle_reclaims(reclaims, last_root);
     }
 
     pub fn get_storage_entries(&self) -> Vec<Arc<AccountStorageEntry>> {
@@ -862,16 +1322,16 @@ impl AccountsDB {
     }
 
     fn merge(
-        dest: &mut HashMap<Pubkey, (u64, AccountInfo)>,
-        source: &HashMap<Pubkey, (u64, AccountInfo)>,
+        dest: &mut HashMap<Pubkey, (u64, AccountInfo, Pubkey)>,
+        source: &HashMap<Pubkey, (u64, AccountInfo, Pubkey)>,
     ) {
-        for (key, (source_version, source_info)) in source.iter() {
-            if let Some((dest_version, _)) = dest.get(key) {
+        for (key, (source_version, source_info, source_owner)) in source.iter() {
+            if let Some((dest_version, _, _)) = dest.get(key) {
                 if dest_version > source_version {
                     continue;
                 }
             }
-            dest.insert(*key, (*source_version, source_info.clone()));
+            dest.insert(*key, (*source_version, source_info.clone(), *source_owner));
         }
     }
 
@@ -886,12 +1346,12 @@ impl AccountsDB {
         forks.sort();
         let mut accounts_index = self.accounts_index.write().unwrap();
         for fork_id in forks.iter() {
-            let mut accumulator: Vec<HashMap<Pubkey, (u64, AccountInfo)>> = self
+            let mut accumulator: Vec<HashMap<Pubkey, (u64, AccountInfo, Pubkey)>> = self
                 .scan_account_storage(
                     *fork_id,
                     |stored_account: &StoredAccount,
                      id: AppendVecId,
-                     accum: &mut HashMap<Pubkey, (u64, AccountInfo)>| {
+                     accum: &mut HashMap<Pubkey, (u64, AccountInfo, Pubkey)>| {
                         let account_info = AccountInfo {
                             id,
                             offset: stored_account.offset,
@@ -899,7 +1359,11 @@ impl AccountsDB {
                         };
                         accum.insert(
                             stored_account.meta.pubkey,
-                            (stored_account.meta.write_version, account_info),
+                            (
+                                stored_account.meta.write_version,
+                                account_info,
+                                stored_account.balance.owner,
+                            ),
                         );
                     },
                 );
@@ -910,9 +1374,15 @@ impl AccountsDB {
             }
             if !account_maps.is_empty() {
                 accounts_index.roots.insert(*fork_id);
+                self.uncleaned_roots.write().unwrap().insert(*fork_id);
                 let mut _reclaims: Vec<(u64, AccountInfo)> = vec![];
-                for (pubkey, (_, account_info)) in account_maps.iter() {
+                for (pubkey, (_, account_info, owner)) in account_maps.iter() {
                     accounts_index.insert(*fork_id, pubkey, account_info.clone(), &mut _reclaims);
+                    // every fork is a root, so this version replaces the older ones
+                    if accounts_index.add_program_account(owner, pubkey) {
+                        let owners = vec![*owner].into_iter().collect();
+                        accounts_index.retain_program_account_owners(pubkey, &owners);
+                    }
                 }
             }
         }
@@ -926,6 +1396,7 @@ pub mod tests {
     use bincode::serialize_into;
     use rand::{thread_rng, Rng};
     use solana_sdk::account::Account;
+    use solana_sdk::hash::hash;
     use std::fs;
     use tempfile::TempDir;
 
@@ -1395,6 +1866,220 @@ pub mod tests {
         assert_eq!(accounts.load_slow(&ancestors, &pubkey), Some((account, 1)));
     }
 
+    #[test]
+    fn test_shrink_fork() {
+        let accounts = AccountsDB::new_single();
+        let pubkeys: Vec<Pubkey> = (0..10).map(|_| Pubkey::new_rand()).collect();
+        let account = Account::new(1, 16, &Account::default().owner);
+        for pubkey in &pubkeys {
+            accounts.store(0, &[(pubkey, &account)]);
+        }
+        accounts.add_root(0);
+
+        let old_ids: Vec<AppendVecId> = accounts.storage.read().unwrap().0[&0]
+            .keys()
+            .cloned()
+            .collect();
+
+        // not sparse yet, nothing to do
+        accounts.shrink_fork(0);
+        let ids: Vec<AppendVecId> = accounts.storage.read().unwrap().0[&0]
+            .keys()
+            .cloned()
+            .collect();
+        assert_eq!(ids, old_ids);
+
+        let account1 = Account::new(2, 16, &Account::default().owner);
+        for pubkey in &pubkeys[2..] {
+            accounts.store(1, &[(pubkey, &account1)]);
+        }
+        accounts.add_root(1);
+        // storing them again once fork 1 is rooted purges their versions in fork 0
+        for pubkey in &pubkeys[2..] {
+            accounts.store(2, &[(pubkey, &account1)]);
+        }
+        assert_eq!(
+            accounts.storage.read().unwrap().0[&0]
+                .values()
+                .map(|store| store.count())
+                .sum::<usize>(),
+            2
+        );
+
+        accounts.shrink_fork(0);
+        {
+            let storage = accounts.storage.read().unwrap();
+            assert_eq!(storage.0[&0].len(), 1);
+            let store = storage.0[&0].values().next().unwrap();
+            assert!(!old_ids.contains(&store.id));
+            assert_eq!(store.count(), 2);
+            assert_eq!(store.approx_stored_count(), 2);
+            assert_eq!(store.accounts.accounts(0).len(), 2);
+        }
+
+        let ancestors = HashMap::new();
+        for pubkey in &pubkeys[..2] {
+            assert_eq!(
+                accounts.load_slow(&ancestors, pubkey),
+                Some((account.clone(), 0))
+            );
+        }
+        let ancestors = vec![(2, 0)].into_iter().collect();
+        for pubkey in &pubkeys[2..] {
+            assert_eq!(
+                accounts.load_slow(&ancestors, pubkey),
+                Some((account1.clone(), 2))
+            );
+        }
+    }
+
+    #[test]
+    fn test_clean_zero_lamport_account() {
+        let accounts = AccountsDB::new_single();
+        let pubkey = Pubkey::new_rand();
+        let other = Pubkey::new_rand();
+        let dead: Vec<Pubkey> = (0..2).map(|_| Pubkey::new_rand()).collect();
+        let account = Account::new(1, 0, &Account::default().owner);
+        let zero_account = Account::new(0, 0, &Account::default().owner);
+
+        accounts.store(0, &[(&pubkey, &account), (&other, &account)]);
+        accounts.store(0, &[(&dead[0], &account), (&dead[1], &account)]);
+        accounts.add_root(0);
+        for fork in 1..3 {
+            accounts.store(fork, &[(&pubkey, &zero_account)]);
+            accounts.store(fork, &[(&dead[0], &account), (&dead[1], &account)]);
+            accounts.add_root(fork);
+        }
+        let is_indexed = |pubkey| {
+            accounts
+                .accounts_index
+                .read()
+                .unwrap()
+                .account_maps
+                .contains_key(pubkey)
+        };
+
+        // the drained account's old version is still in fork 0's storage, so the root it
+        //  was drained in is looked at again
+        accounts.clean_accounts(None);
+        assert!(is_indexed(&pubkey));
+        let uncleaned_roots = |accounts: &AccountsDB| {
+            let mut roots: Vec<Fork> = accounts
+                .uncleaned_roots
+                .read()
+                .unwrap()
+                .iter()
+                .cloned()
+                .collect();
+            roots.sort();
+            roots
+        };
+        assert_eq!(uncleaned_roots(&accounts), vec![2]);
+
+        accounts.shrink_all_forks();
+        let ancestors = HashMap::new();
+        assert_eq!(
+            accounts.load_slow(&ancestors, &other),
+            Some((account.clone(), 0))
+        );
+
+        // rooted past the latest snapshot
+        accounts.clean_accounts(Some(1));
+        assert!(is_indexed(&pubkey));
+
+        accounts.clean_accounts(Some(2));
+        assert!(!is_indexed(&pubkey));
+        assert!(uncleaned_roots(&accounts).is_empty());
+        assert_eq!(accounts.load_slow(&ancestors, &pubkey), None);
+        assert!(is_indexed(&other));
+        for pubkey in &dead {
+            assert_eq!(
+                accounts.load_slow(&ancestors, pubkey),
+                Some((account.clone(), 2))
+            );
+        }
+    }
+
+    #[test]
+    fn test_compute_merkle_root() {
+        assert_eq!(
+            AccountsDB::compute_merkle_root(vec![], MERKLE_FANOUT),
+            Hash::default()
+        );
+
+        let hashes: Vec<Hash> = (0..5).map(|i| hash(&[i])).collect();
+        let hash_chunk = |chunk: &[Hash]| {
+            let mut hasher = Hasher::default();
+            for hash in chunk {
+                hasher.hash(hash.as_ref());
+            }
+            hasher.result()
+        };
+        assert_eq!(
+            AccountsDB::compute_merkle_root(hashes[..1].to_vec(), 2),
+            hash_chunk(&hashes[..1])
+        );
+        let expected = hash_chunk(&[
+            hash_chunk(&[hash_chunk(&hashes[0..2]), hash_chunk(&hashes[2..4])]),
+            hash_chunk(&[hash_chunk(&hashes[4..5])]),
+        ]);
+        assert_eq!(AccountsDB::compute_merkle_root(hashes.clone(), 2), expected);
+
+        // the order of the leaves matters
+        let mut reversed = hashes.clone();
+        reversed.reverse();
+        assert_ne!(AccountsDB::compute_merkle_root(reversed, 2), expected);
+    }
+
+    #[test]
+    fn test_accounts_hash_incremental() {
+        let accounts = AccountsDB::new_single();
+        let ancestors = HashMap::new();
+        let mut pubkeys: Vec<Pubkey> = vec![];
+        assert_eq!(accounts.accounts_hash(), Hash::default());
+
+        create_account(&accounts, &mut pubkeys, 0, 100, 0, 0);
+        accounts.add_root(0);
+        let hash0 = accounts.accounts_hash();
+        assert_ne!(hash0, Hash::default());
+        assert_eq!(hash0, accounts.calculate_accounts_hash(&ancestors));
+
+        // storing several versions in an unrooted fork leaves the hash alone
+        modify_accounts(&accounts, &pubkeys, 1, 10, 2);
+        modify_accounts(&accounts, &pubkeys, 1, 10, 3);
+        let zero_account = Account::new(0, 0, &Account::default().owner);
+        accounts.store(1, &[(&pubkeys[99], &zero_account)]);
+        assert_eq!(accounts.accounts_hash(), hash0);
+
+        accounts.add_root(1);
+        let hash1 = accounts.accounts_hash();
+        assert_ne!(hash1, hash0);
+        assert_eq!(hash1, accounts.calculate_accounts_hash(&ancestors));
+        assert!(!accounts
+            .rooted_account_hashes
+            .read()
+            .unwrap()
+            .contains_key(&pubkeys[99]));
+
+        // adding the last root again doesn't fold its delta back in
+        modify_accounts(&accounts, &pubkeys, 2, 10, 4);
+        accounts.add_root(2);
+        let hash2 = accounts.accounts_hash();
+        accounts.store(2, &[(&pubkeys[0], &zero_account)]);
+        accounts.add_root(2);
+        assert_eq!(accounts.accounts_hash(), hash2);
+        accounts.store(
+            2,
+            &[(&pubkeys[0], &Account::new(4, 0, &Account::default().owner))],
+        );
+        assert_eq!(hash2, accounts.calculate_accounts_hash(&ancestors));
+
+        // purging and compacting rooted storage doesn't change what is visible
+        accounts.clean_accounts(None);
+        accounts.shrink_all_forks();
+        assert_eq!(hash2, accounts.calculate_accounts_hash(&ancestors));
+    }
+
     #[test]
     fn test_accounts_db_serialize() {
         solana_logger::setup();
@@ -1521,6 +2206,47 @@ pub mod tests {
         assert_eq!(accounts.len(), 2);
     }
 
+    #[test]
+    fn test_accountsdb_program_account_owner_change() {
+        solana_logger::setup();
+        let db = AccountsDB::new(None);
+        let key = Pubkey::new_rand();
+        let owner = Pubkey::new_rand();
+        let new_owner = Pubkey::new_rand();
+        let is_listed = |owner: &Pubkey| {
+            let ancestors = vec![(0, 0), (1, 1), (2, 2)].into_iter().collect();
+            let accounts: Vec<Pubkey> =
+                db.scan_program_accounts(owner, &ancestors, |pubkeys: &mut Vec<Pubkey>, option| {
+                    if let Some(data) = option {
+                        pubkeys.push(*data.0);
+                    }
+                });
+            !accounts.is_empty()
+        };
+
+        db.store(0, &[(&key, &Account::new(1, 0, &owner))]);
+        db.add_root(0);
+
+        // fork 0 still has the old owner's version
+        db.store(1, &[(&key, &Account::new(1, 0, &new_owner))]);
+        assert!(is_listed(&owner));
+        assert!(is_listed(&new_owner));
+
+        // until it's replaced on a root
+        db.add_root(1);
+        db.store(2, &[(&key, &Account::new(2, 0, &new_owner))]);
+        assert!(!is_listed(&owner));
+        assert!(!db
+            .accounts_index
+            .read()
+            .unwrap()
+            .program_accounts
+            .read()
+            .unwrap()
+            .contains_key(&owner));
+        assert!(is_listed(&new_owner));
+    }
+
     #[test]
     fn test_store_large_account() {
         solana_logger::setup();
@@ -9,6 +9,13 @@ type ForkList<T> = Vec<(Fork, T)>;
 pub struct AccountsIndex<T> {
     pub account_maps: HashMap<Pubkey, RwLock<ForkList<T>>>,
 
+    /// every pubkey that has been stored under a given owner on some fork, a superset
+    ///  of the program's accounts as seen from any one set of ancestors
+    pub program_accounts: RwLock<HashMap<Pubkey, HashSet<Pubkey>>>,
+
+    /// the owners each pubkey is listed under in `program_accounts`
+    pub account_owners: RwLock<HashMap<Pubkey, HashSet<Pubkey>>>,
+
     pub roots: HashSet<Fork>,
 
     //This value that needs to be stored to recover the index from AppendVec
@@ -29,6 +36,31 @@ impl<T: Clone> AccountsIndex<T> {
         }
     }
 
+    /// call func with every pubkey and index visible from a given set of ancestors that
+    ///  has been stored under `owner`, the caller checks the owner of the version found
+    pub fn scan_program_accounts<F>(
+        &self,
+        owner: &Pubkey,
+        ancestors: &HashMap<Fork, usize>,
+        mut func: F,
+    ) where
+        F: FnMut(&Pubkey, (&T, Fork)) -> (),
+    {
+        // copied out, since a store takes the lock on a pubkey's list before `program_accounts`
+        let pubkeys = match self.program_accounts.read().unwrap().get(owner) {
+            Some(pubkeys) => pubkeys.clone(),
+            None => return,
+        };
+        for pubkey in pubkeys.iter() {
+            if let Some(list) = self.account_maps.get(pubkey) {
+                let list_r = list.read().unwrap();
+                if let Some(index) = self.latest_fork(ancestors, &list_r) {
+                    func(pubkey, (&list_r[index].1, list_r[index].0));
+                }
+            }
+        }
+    }
+
     // find the latest fork and T in a list for a given ancestor
     // returns index into 'list' if found, None if not.
     fn latest_fork(&self, ancestors: &HashMap<Fork, usize>, list: &[(Fork, T)]) -> Option<usize> {
@@ -121,6 +153,57 @@ impl<T: Clone> AccountsIndex<T> {
         }
     }
 
+    /// Record that `pubkey` has been stored under `owner`, returns whether it's also listed
+    ///  under other owners, which the versions still in the index may no longer have
+    pub fn add_program_account(&self, owner: &Pubkey, pubkey: &Pubkey) -> bool {
+        if let Some(owners) = self.account_owners.read().unwrap().get(pubkey) {
+            if owners.contains(owner) {
+                return owners.len() > 1;
+            }
+        }
+        let mut program_accounts = self.program_accounts.write().unwrap();
+        let mut account_owners = self.account_owners.write().unwrap();
+        let owners = account_owners.entry(*pubkey).or_insert_with(HashSet::new);
+        owners.insert(*owner);
+        program_accounts
+            .entry(*owner)
+            .or_insert_with(HashSet::new)
+            .insert(*pubkey);
+        owners.len() > 1
+    }
+
+    /// Drop `pubkey` from the owners none of its versions in the index have any more
+    pub fn retain_program_account_owners(&self, pubkey: &Pubkey, owners: &HashSet<Pubkey>) {
+        let mut program_accounts = self.program_accounts.write().unwrap();
+        let mut account_owners = self.account_owners.write().unwrap();
+        if let Some(listed_owners) = account_owners.get_mut(pubkey) {
+            for owner in listed_owners.difference(owners) {
+                if let Some(pubkeys) = program_accounts.get_mut(owner) {
+                    pubkeys.remove(pubkey);
+                    if pubkeys.is_empty() {
+                        program_accounts.remove(owner);
+                    }
+                }
+            }
+            listed_owners.retain(|owner| owners.contains(owner));
+        }
+    }
+
+    /// Forget the owners of a pubkey that has been removed from the index
+    pub fn remove_program_account(&self, pubkey: &Pubkey) {
+        let mut program_accounts = self.program_accounts.write().unwrap();
+        if let Some(owners) = self.account_owners.write().unwrap().remove(pubkey) {
+            for owner in owners {
+                if let Some(pubkeys) = program_accounts.get_mut(&owner) {
+                    pubkeys.remove(pubkey);
+                    if pubkeys.is_empty() {
+                        program_accounts.remove(&owner);
+                    }
+                }
+            }
+        }
+    }
+
     pub fn add_index(&mut self, fork: Fork, pubkey: &Pubkey, account_info: T) {
         let entry = self
             .account_maps
@@ -229,6 +312,85 @@ mod tests {
         assert!(found_key);
     }
 
+    #[test]
+    fn test_scan_program_accounts() {
+        let key = Keypair::new();
+        let other_key = Keypair::new();
+        let owner = Pubkey::new_rand();
+        let mut index = AccountsIndex::<bool>::default();
+        let mut gc = Vec::new();
+        index.insert(0, &key.pubkey(), true, &mut gc);
+        index.add_program_account(&owner, &key.pubkey());
+        index.insert(1, &other_key.pubkey(), true, &mut gc);
+        index.add_program_account(&owner, &other_key.pubkey());
+        index.insert(1, &Pubkey::new_rand(), true, &mut gc);
+        assert!(gc.is_empty());
+
+        let scan = |index: &AccountsIndex<bool>, ancestors: &HashMap<Fork, usize>| {
+            let mut found = vec![];
+            index.scan_program_accounts(&owner, ancestors, |pubkey, (_, fork)| {
+                found.push((*pubkey, fork))
+            });
+            found.sort();
+            found
+        };
+
+        // nothing from a fork that isn't an ancestor
+        let ancestors = vec![(0, 0)].into_iter().collect();
+        assert_eq!(scan(&index, &ancestors), vec![(key.pubkey(), 0)]);
+
+        let ancestors = vec![(0, 0), (1, 0)].into_iter().collect();
+        let mut expected = vec![(key.pubkey(), 0), (other_key.pubkey(), 1)];
+        expected.sort();
+        assert_eq!(scan(&index, &ancestors), expected);
+
+        // rooted forks are visible without ancestors
+        index.add_root(0);
+        assert_eq!(scan(&index, &HashMap::new()), vec![(key.pubkey(), 0)]);
+
+        // and other owners see nothing
+        let mut num = 0;
+        index.scan_program_accounts(&Pubkey::new_rand(), &ancestors, |_pubkey, _index| num += 1);
+        assert_eq!(num, 0);
+    }
+
+    #[test]
+    fn test_program_account_owners() {
+        let key = Keypair::new();
+        let owner = Pubkey::new_rand();
+        let new_owner = Pubkey::new_rand();
+        let index = AccountsIndex::<bool>::default();
+        let owned_by = |owner: &Pubkey| {
+            index
+                .program_accounts
+                .read()
+                .unwrap()
+                .get(owner)
+                .map(|pubkeys| pubkeys.contains(&key.pubkey()))
+                .unwrap_or(false)
+        };
+
+        assert!(!index.add_program_account(&owner, &key.pubkey()));
+        assert!(!index.add_program_account(&owner, &key.pubkey()));
+
+        // a new owner is listed alongside the old one
+        assert!(index.add_program_account(&new_owner, &key.pubkey()));
+        assert!(owned_by(&owner));
+        assert!(owned_by(&new_owner));
+
+        // until no version is left with the old owner
+        let owners = vec![new_owner].into_iter().collect();
+        index.retain_program_account_owners(&key.pubkey(), &owners);
+        assert!(!owned_by(&owner));
+        assert!(owned_by(&new_owner));
+        assert!(!index.program_accounts.read().unwrap().contains_key(&owner));
+        assert!(!index.add_program_account(&new_owner, &key.pubkey()));
+
+        index.remove_program_account(&key.pubkey());
+        assert!(index.program_accounts.read().unwrap().is_empty());
+        assert!(index.account_owners.read().unwrap().is_empty());
+    }
+
     #[test]
     fn test_is_root() {
         let mut index = AccountsIndex::<bool>::default();
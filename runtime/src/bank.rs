@@ -12,7 +12,11 @@ use crate::{
     blockhash_queue::BlockhashQueue,
     epoch_schedule::EpochSchedule,
     locked_accounts_results::LockedAccountsResults,
+    log_collector::{
+        LogCollector, TransactionLogInfo, TransactionLogMessages, MAX_TRANSACTION_LOGS_PER_BANK,
+    },
     message_processor::{MessageProcessor, ProcessInstruction},
+    nonce_utils,
     rent_collector::RentCollector,
     serde_utils::{
         deserialize_atomicbool, deserialize_atomicusize, serialize_atomicbool,
@@ -38,21 +42,24 @@ use solana_sdk::{
     genesis_block::GenesisBlock,
     hash::{hashv, Hash},
     inflation::Inflation,
+    instruction_processor_utils::{ComputeBudget, ComputeMeter},
     native_loader,
     pubkey::Pubkey,
     signature::{Keypair, Signature},
     system_transaction,
     sysvar::{
-        clock, fees, rewards,
+        clock, fees, recent_blockhashes, rewards,
         slot_hashes::{self, SlotHashes},
         stake_history,
     },
     timing::duration_as_ns,
     transaction::{Result, Transaction, TransactionError},
 };
+use std::cell::RefCell;
 use std::collections::{HashMap, HashSet};
 use std::io::{BufReader, Cursor, Error as IOError, Read};
 use std::path::Path;
+use std::rc::Rc;
 use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::{Arc, RwLock, RwLockReadGuard};
 
@@ -60,6 +67,16 @@ pub const SECONDS_PER_YEAR: f64 = (365.25 * 24.0 * 60.0 * 60.0);
 
 type BankStatusCache = StatusCache<Result<()>>;
 
+pub type TransactionBalances = Vec<Vec<u64>>;
+
+/// The balance of every account referenced by each transaction of a batch, in the order of the
+/// batch, before and after the transaction executed
+#[derive(Debug, Default, PartialEq)]
+pub struct TransactionBalancesSet {
+    pub pre_balances: TransactionBalances,
+    pub post_balances: TransactionBalances,
+}
+
 #[derive(Default)]
 pub struct BankRc {
     /// where all the Accounts are stored
@@ -170,6 +187,10 @@ pub struct Bank {
     /// Hash of this Bank's parent's state
     parent_hash: Hash,
 
+    /// Merkle hash of all the accounts as of this Bank. Only meaningful after squashing
+    ///  and `update_accounts_hash`.
+    accounts_hash: RwLock<Hash>,
+
     /// The number of transactions processed without error
     #[serde(serialize_with = "serialize_atomicusize")]
     #[serde(deserialize_with = "deserialize_atomicusize")]
@@ -219,6 +240,11 @@ pub struct Bank {
     #[serde(deserialize_with = "deserialize_atomicusize")]
     collector_fees: AtomicUsize, // TODO: Use AtomicU64 if/when available
 
+    /// Rent that has been collected
+    #[serde(serialize_with = "serialize_atomicusize")]
+    #[serde(deserialize_with = "deserialize_atomicusize")]
+    collected_rent: AtomicUsize, // TODO: Use AtomicU64 if/when available
+
     /// Latest transaction fees for transactions processed by this bank
     fee_calculator: FeeCalculator,
 
@@ -249,6 +275,10 @@ pub struct Bank {
 
     /// The Message processor
     message_processor: MessageProcessor,
+
+    /// Log messages of the transactions committed to this bank, for log subscribers
+    #[serde(skip)]
+    transaction_log_collector: RwLock<Vec<TransactionLogInfo>>,
 }
 
 impl Default for BlockhashQueue {
@@ -277,6 +307,7 @@ impl Bank {
             bank.update_stake_history(None);
         }
         bank.update_clock();
+        bank.update_recent_blockhashes();
         bank
     }
 
@@ -324,12 +355,15 @@ impl Bank {
             parent_hash: parent.hash(),
             collector_id: *collector_id,
             collector_fees: AtomicUsize::new(0),
+            collected_rent: AtomicUsize::new(0),
             ancestors: HashMap::new(),
             hash: RwLock::new(Hash::default()),
+            accounts_hash: RwLock::new(Hash::default()),
             is_delta: AtomicBool::new(false),
             tick_height: AtomicUsize::new(parent.tick_height.load(Ordering::Relaxed)),
             signature_count: AtomicUsize::new(0),
             message_processor: MessageProcessor::default(),
+            transaction_log_collector: RwLock::new(vec![]),
         };
 
         datapoint_info!(
@@ -352,10 +386,12 @@ impl Bank {
             new.ancestors.insert(p.slot(), i + 1);
         });
 
+        new.set_compute_budget(parent.compute_budget());
         new.update_rewards(parent.epoch());
         new.update_stake_history(Some(parent.epoch()));
         new.update_clock();
         new.update_fees();
+        new.update_recent_blockhashes();
         new
     }
 
@@ -395,6 +431,25 @@ impl Bank {
         *self.hash.read().unwrap()
     }
 
+    pub fn accounts_hash(&self) -> Hash {
+        *self.accounts_hash.read().unwrap()
+    }
+
+    /// Record the merkle hash of the accounts on the rooted path, as of this Bank once it's
+    ///  been squashed. Only done for the Banks that are snapshotted, the rooted accounts
+    ///  are hashed as they're rooted but hashing them all together is left until then.
+    pub fn update_accounts_hash(&self) -> Hash {
+        let accounts_hash = self.rc.accounts.accounts_hash();
+        *self.accounts_hash.write().unwrap() = accounts_hash;
+        accounts_hash
+    }
+
+    /// Recompute the accounts hash from the accounts this Bank can see and check it against
+    ///  the one recorded by `update_accounts_hash`, e.g. after loading it from a snapshot
+    pub fn verify_accounts_hash(&self) -> bool {
+        self.rc.accounts.calculate_accounts_hash(&self.ancestors) == self.accounts_hash()
+    }
+
     pub fn is_frozen(&self) -> bool {
         *self.hash.read().unwrap() != Hash::default()
     }
@@ -424,6 +479,18 @@ impl Bank {
         self.store_account(&slot_hashes::id(), &account);
     }
 
+    fn update_recent_blockhashes(&self) {
+        let recent_blockhashes = self
+            .blockhash_queue
+            .read()
+            .unwrap()
+            .get_recent_blockhashes();
+        self.store_account(
+            &recent_blockhashes::id(),
+            &recent_blockhashes::create_account(1, &recent_blockhashes),
+        );
+    }
+
     fn update_fees(&self) {
         self.store_account(&fees::id(), &fees::create_account(1, &self.fee_calculator));
     }
@@ -513,6 +580,18 @@ impl Bank {
         }
     }
 
+    fn distribute_rent(&self) {
+        let collected_rent = self.collected_rent.load(Ordering::Relaxed) as u64;
+
+        if collected_rent != 0 {
+            let (unburned, burned) = self.rent_collector.rent.calculate_burn(collected_rent);
+            // burn a portion of rent
+            self.deposit(&self.collector_id, unburned);
+            self.capitalization
+                .fetch_sub(burned as usize, Ordering::Relaxed);
+        }
+    }
+
     fn set_hash(&self) -> bool {
         let mut hash = self.hash.write().unwrap();
 
@@ -520,6 +599,7 @@ impl Bank {
             // finish up any deferred changes to account state
             self.commit_credits();
             self.collect_fees();
+            self.distribute_rent();
 
             // freeze is a one-way trip, idempotent
             *hash = self.hash_internal_state();
@@ -539,6 +619,12 @@ impl Bank {
         &self.epoch_schedule
     }
 
+    /// Reclaim the disk held by dead accounts in rooted slots, purging zero-lamport accounts
+    /// no later than `max_clean_root`
+    pub fn clean_and_shrink_accounts(&self, max_clean_root: Option<Slot>) {
+        self.rc.accounts.clean_and_shrink(max_clean_root);
+    }
+
     /// squash the parent's state up into this Bank,
     ///   this Bank becomes a root
     pub fn squash(&self) {
@@ -620,6 +706,13 @@ impl Bank {
             genesis_block.epoch_warmup,
         );
 
+        self.rent_collector = RentCollector::new(
+            self.epoch,
+            &self.epoch_schedule,
+            self.slots_per_year,
+            &genesis_block.rent,
+        );
+
         self.inflation = genesis_block.inflation;
 
         // Add additional native programs specified in the genesis block
@@ -651,6 +744,11 @@ impl Bank {
         )
     }
 
+    pub fn get_fee_calculator(&self, hash: &Hash) -> Option<FeeCalculator> {
+        let blockhash_queue = self.blockhash_queue.read().unwrap();
+        blockhash_queue.get_fee_calculator(hash).cloned()
+    }
+
     pub fn confirmed_last_blockhash(&self) -> (Hash, FeeCalculator) {
         const NUM_BLOCKHASH_CONFIRMATIONS: usize = 3;
 
@@ -695,6 +793,49 @@ impl Bank {
         }
     }
 
+    fn update_transaction_logs(
+        &self,
+        txs: &[Transaction],
+        txs_iteration_order: Option<&[usize]>,
+        res: &[Result<()>],
+        transaction_logs: &[TransactionLogMessages],
+    ) {
+        let mut transaction_log_collector = self.transaction_log_collector.write().unwrap();
+        let mut dropped = 0;
+        for (i, tx) in OrderedIterator::new(txs, txs_iteration_order).enumerate() {
+            if Self::can_commit(&res[i])
+                && !transaction_logs[i].is_empty()
+                && !tx.signatures.is_empty()
+            {
+                if transaction_log_collector.len() >= MAX_TRANSACTION_LOGS_PER_BANK {
+                    dropped += 1;
+                    continue;
+                }
+                transaction_log_collector.push(TransactionLogInfo {
+                    signature: tx.signatures[0],
+                    result: res[i].clone(),
+                    account_keys: tx.message().account_keys.clone(),
+                    log_messages: transaction_logs[i].clone(),
+                });
+            }
+        }
+        if dropped > 0 {
+            inc_new_counter_info!("bank-transaction_logs-dropped", dropped);
+        }
+    }
+
+    /// Return the log messages of the transactions committed to this bank that logged
+    /// anything, optionally only those that reference `address`
+    pub fn get_transaction_logs(&self, address: Option<&Pubkey>) -> Vec<TransactionLogInfo> {
+        self.transaction_log_collector
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|info| address.map_or(true, |address| info.account_keys.contains(address)))
+            .cloned()
+            .collect()
+    }
+
     /// Looks through a list of tick heights and stakes, and finds the latest
     /// tick that has achieved confirmation
     pub fn get_confirmation_timestamp(
@@ -749,6 +890,7 @@ impl Bank {
                 .write()
                 .unwrap()
                 .register_hash(hash, &self.fee_calculator);
+            self.update_recent_blockhashes();
         }
     }
 
@@ -848,6 +990,7 @@ impl Bank {
             .map(|(tx, lock_res)| {
                 if lock_res.is_ok()
                     && !hash_queue.check_hash_age(&tx.message().recent_blockhash, max_age)
+                    && !self.check_tx_durable_nonce(tx)
                 {
                     error_counters.reserve_blockhash += 1;
                     Err(TransactionError::BlockhashNotFound)
@@ -889,6 +1032,27 @@ impl Bank {
             .collect()
     }
 
+    /// True if `tx` advances a nonce account whose stored nonce is the
+    /// transaction's recent_blockhash
+    pub fn check_tx_durable_nonce(&self, tx: &Transaction) -> bool {
+        nonce_utils::transaction_uses_durable_nonce(tx)
+            .and_then(|instruction| nonce_utils::get_nonce_pubkey_from_instruction(instruction, tx))
+            .and_then(|nonce_pubkey| self.get_account(nonce_pubkey))
+            .map_or(false, |nonce_account| {
+                nonce_utils::verify_nonce(&nonce_account, &tx.message().recent_blockhash)
+            })
+    }
+
+    /// The last slot a transaction using the blockhash can be processed in, assuming no slots
+    /// are skipped from here on, or None if the blockhash is unknown or has already expired
+    pub fn get_blockhash_last_valid_slot(&self, blockhash: &Hash) -> Option<Slot> {
+        self.blockhash_queue
+            .read()
+            .unwrap()
+            .get_hash_age(blockhash)
+            .map(|hash_age| self.slot + MAX_RECENT_BLOCKHASHES as u64 - hash_age)
+    }
+
     pub fn check_hash_age(&self, hash: &Hash, max_age: usize) -> bool {
         self.blockhash_queue
             .read()
@@ -978,6 +1142,8 @@ impl Bank {
             )>,
         >,
         Vec<Result<()>>,
+        Vec<TransactionLogMessages>,
+        Vec<u64>,
         Vec<usize>,
         usize,
         usize,
@@ -1010,15 +1176,35 @@ impl Bank {
 
         let mut execution_time = Measure::start("execution_time");
         let mut signature_count = 0;
+        let mut transaction_logs = Vec::with_capacity(txs.len());
+        let mut compute_units_consumed = Vec::with_capacity(txs.len());
+        let compute_budget = self.message_processor.get_compute_budget();
         let executed: Vec<Result<()>> = loaded_accounts
             .iter_mut()
             .zip(OrderedIterator::new(txs, txs_iteration_order))
             .map(|(accs, tx)| match accs {
-                Err(e) => Err(e.clone()),
+                Err(e) => {
+                    transaction_logs.push(vec![]);
+                    compute_units_consumed.push(0);
+                    Err(e.clone())
+                }
                 Ok((ref mut accounts, ref mut loaders, ref mut credits, ref mut _rents)) => {
                     signature_count += tx.message().header.num_required_signatures as usize;
-                    self.message_processor
-                        .process_message(tx.message(), loaders, accounts, credits)
+                    let log_collector = LogCollector::default();
+                    let compute_meter =
+                        Rc::new(RefCell::new(ComputeMeter::new(compute_budget.max_units)));
+                    let result = self.message_processor.process_message(
+                        tx.message(),
+                        loaders,
+                        accounts,
+                        credits,
+                        &log_collector,
+                        &compute_meter,
+                    );
+                    transaction_logs.push(log_collector.into());
+                    compute_units_consumed
+                        .push(compute_budget.max_units - compute_meter.borrow().get_remaining());
+                    result
                 }
             })
             .collect();
@@ -1056,6 +1242,8 @@ impl Bank {
         (
             loaded_accounts,
             executed,
+            transaction_logs,
+            compute_units_consumed,
             retryable_txs,
             tx_count,
             signature_count,
@@ -1073,19 +1261,21 @@ impl Bank {
         let results = OrderedIterator::new(txs, txs_iteration_order)
             .zip(executed.iter())
             .map(|(tx, res)| {
-                let fee_calculator = hash_queue
-                    .get_fee_calculator(&tx.message().recent_blockhash)
+                let fee_calculator = Self::transaction_fee_calculator(&hash_queue, tx)
                     .ok_or(TransactionError::BlockhashNotFound)?;
                 let fee = fee_calculator.calculate_fee(tx.message());
 
                 let message = tx.message();
                 match *res {
-                    Err(TransactionError::InstructionError(_, _)) => {
+                    Err(TransactionError::InstructionError(instruction_index, _)) => {
                         // credit the transaction fee even in case of InstructionError
                         // necessary to withdraw from account[0] here because previous
                         // work of doing so (in accounts.load()) is ignored by store_account()
                         self.withdraw(&message.account_keys[0], fee)?;
                         fees += fee;
+                        if instruction_index > 0 {
+                            self.advance_durable_nonce(tx, &hash_queue.last_hash());
+                        }
                         Ok(())
                     }
                     Ok(()) => {
@@ -1102,6 +1292,33 @@ impl Bank {
         results
     }
 
+    fn transaction_fee_calculator<'a>(
+        hash_queue: &'a BlockhashQueue,
+        tx: &Transaction,
+    ) -> Option<&'a FeeCalculator> {
+        hash_queue
+            .get_fee_calculator(&tx.message().recent_blockhash)
+            .or_else(|| {
+                // durable nonce transactions pay the current fee
+                nonce_utils::transaction_uses_durable_nonce(tx)
+                    .and_then(|_| hash_queue.get_fee_calculator(&hash_queue.last_hash()))
+            })
+    }
+
+    // The nonce of a durable nonce transaction is advanced by its first instruction, and must
+    // stay advanced when a later instruction fails, or the transaction could be replayed
+    fn advance_durable_nonce(&self, tx: &Transaction, nonce: &Hash) {
+        if let Some(nonce_pubkey) = nonce_utils::transaction_uses_durable_nonce(tx)
+            .and_then(|instruction| nonce_utils::get_nonce_pubkey_from_instruction(instruction, tx))
+        {
+            if let Some(mut nonce_account) = self.get_account(nonce_pubkey) {
+                if nonce_utils::advance_nonce_account(&mut nonce_account, nonce) {
+                    self.store_account(nonce_pubkey, &nonce_account);
+                }
+            }
+        }
+    }
+
     pub fn commit_transactions(
         &self,
         txs: &[Transaction],
@@ -1113,6 +1330,7 @@ impl Bank {
             TransactionRents,
         )>],
         executed: &[Result<()>],
+        transaction_logs: &[TransactionLogMessages],
         tx_count: usize,
         signature_count: usize,
     ) -> Vec<Result<()>> {
@@ -1142,15 +1360,19 @@ impl Bank {
         );
 
         self.update_cached_accounts(txs, txs_iteration_order, executed, loaded_accounts);
+        self.collect_rent(txs, txs_iteration_order, executed, loaded_accounts);
 
         // once committed there is no way to unroll
         write_time.stop();
         debug!("store: {}us txs_len={}", write_time.as_us(), txs.len(),);
         self.update_transaction_statuses(txs, txs_iteration_order, &executed);
+        self.update_transaction_logs(txs, txs_iteration_order, executed, transaction_logs);
         self.filter_program_errors_and_collect_fee(txs, txs_iteration_order, executed)
     }
 
-    /// Process a batch of transactions.
+    /// Process a batch of transactions, returning their results along with the messages
+    /// logged and the compute units consumed while executing each of them, all in execution
+    /// order, and if `collect_balances` is set, the balances of their accounts.
     #[must_use]
     pub fn load_execute_and_commit_transactions(
         &self,
@@ -1158,24 +1380,114 @@ impl Bank {
         txs_iteration_order: Option<&[usize]>,
         lock_results: &LockedAccountsResults,
         max_age: usize,
-    ) -> Vec<Result<()>> {
-        let (mut loaded_accounts, executed, _, tx_count, signature_count) =
-            self.load_and_execute_transactions(txs, txs_iteration_order, lock_results, max_age);
+        collect_balances: bool,
+    ) -> (
+        Vec<Result<()>>,
+        Vec<TransactionLogMessages>,
+        Vec<u64>,
+        TransactionBalancesSet,
+    ) {
+        let pre_balances = if collect_balances {
+            self.collect_balances(txs)
+        } else {
+            vec![]
+        };
+        let (
+            mut loaded_accounts,
+            executed,
+            transaction_logs,
+            compute_units_consumed,
+            _,
+            tx_count,
+            signature_count,
+        ) = self.load_and_execute_transactions(txs, txs_iteration_order, lock_results, max_age);
 
-        self.commit_transactions(
+        let results = self.commit_transactions(
             txs,
             txs_iteration_order,
             &mut loaded_accounts,
             &executed,
+            &transaction_logs,
             tx_count,
             signature_count,
+        );
+        let post_balances = if collect_balances {
+            self.collect_post_balances(
+                txs,
+                txs_iteration_order,
+                &loaded_accounts,
+                &executed,
+                &pre_balances,
+            )
+        } else {
+            vec![]
+        };
+        (
+            results,
+            transaction_logs,
+            compute_units_consumed,
+            TransactionBalancesSet {
+                pre_balances,
+                post_balances,
+            },
         )
     }
 
     #[must_use]
     pub fn process_transactions(&self, txs: &[Transaction]) -> Vec<Result<()>> {
         let lock_results = self.lock_accounts(txs, None);
-        self.load_execute_and_commit_transactions(txs, None, &lock_results, MAX_RECENT_BLOCKHASHES)
+        self.load_execute_and_commit_transactions(
+            txs,
+            None,
+            &lock_results,
+            MAX_RECENT_BLOCKHASHES,
+            false,
+        )
+        .0
+    }
+
+    /// Execute a transaction without committing it, returning its result along with the
+    /// accounts it would have modified, the messages its programs logged and the compute units
+    /// they consumed. The bank must be frozen: nothing else executes against it, so no account
+    /// locks are taken.
+    pub fn simulate_transaction(
+        &self,
+        tx: &Transaction,
+    ) -> (
+        Result<()>,
+        Vec<(Pubkey, Account)>,
+        TransactionLogMessages,
+        u64,
+    ) {
+        assert!(self.is_frozen(), "simulation bank is not frozen");
+
+        let txs = vec![tx.clone()];
+        let mut lock_results = LockedAccountsResults::new(vec![Ok(())], self, &txs);
+        lock_results.needs_unlock = false;
+        let (loaded_accounts, executed, mut transaction_logs, compute_units_consumed, _, _, _) =
+            self.load_and_execute_transactions(&txs, None, &lock_results, MAX_RECENT_BLOCKHASHES);
+
+        let result = executed[0].clone();
+        let accounts = match (&result, &loaded_accounts[0]) {
+            (Ok(()), Ok((accounts, _, credits, _))) => {
+                let message = tx.message();
+                message
+                    .account_keys
+                    .iter()
+                    .enumerate()
+                    .zip(accounts.iter().zip(credits.iter()))
+                    .filter(|((i, _), (_, credit))| message.is_debitable(*i) || **credit > 0)
+                    .map(|((_, pubkey), (account, _))| (*pubkey, account.clone()))
+                    .collect()
+            }
+            _ => vec![],
+        };
+        (
+            result,
+            accounts,
+            transaction_logs.remove(0),
+            compute_units_consumed[0],
+        )
     }
 
     /// Create, sign, and process a Transaction from `keypair` to `to` of
@@ -1198,6 +1510,69 @@ impl Bank {
             .unwrap_or(0)
     }
 
+    /// Collect the stored balance of every account referenced by each transaction. Credits to
+    /// credit-only accounts are not committed until the bank is frozen and are left out, so a
+    /// transaction's balances don't depend on the other transactions in its batch
+    pub fn collect_balances(&self, txs: &[Transaction]) -> TransactionBalances {
+        txs.iter()
+            .map(|tx| {
+                tx.message()
+                    .account_keys
+                    .iter()
+                    .map(|pubkey| self.get_balance(pubkey))
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Collect the balance of every account referenced by each transaction once it executed,
+    /// from the transaction's own loaded accounts rather than the stored ones, which other
+    /// transactions may have changed since. Like `pre_balances`, collected by `collect_balances`
+    /// before the transactions executed, these leave out other transactions' credits to
+    /// credit-only accounts, but include the transaction's own. A failed transaction only pays
+    /// its fee, and one that wasn't committed keeps its `pre_balances`
+    pub fn collect_post_balances(
+        &self,
+        txs: &[Transaction],
+        txs_iteration_order: Option<&[usize]>,
+        loaded_accounts: &[Result<(
+            TransactionAccounts,
+            TransactionLoaders,
+            TransactionCredits,
+            TransactionRents,
+        )>],
+        executed: &[Result<()>],
+        pre_balances: &[Vec<u64>],
+    ) -> TransactionBalances {
+        let hash_queue = self.blockhash_queue.read().unwrap();
+        let mut post_balances = pre_balances.to_vec();
+        for (i, (loaded_accounts, result)) in loaded_accounts.iter().zip(executed).enumerate() {
+            let index = txs_iteration_order.map_or(i, |order| order[i]);
+            let tx = &txs[index];
+            let message = tx.message();
+            let balances = &mut post_balances[index];
+            match (result, loaded_accounts) {
+                (Ok(()), Ok((accounts, _, _, _))) => {
+                    // Program accounts aren't loaded with the rest, and can't change
+                    let program_ids = message.program_ids();
+                    let mut accounts = accounts.iter();
+                    for (balance, key) in balances.iter_mut().zip(&message.account_keys) {
+                        if !program_ids.contains(&key) {
+                            *balance = accounts.next().map_or(0, Self::read_balance);
+                        }
+                    }
+                }
+                (Err(TransactionError::InstructionError(_, _)), _) => {
+                    let fee = Self::transaction_fee_calculator(&hash_queue, tx)
+                        .map_or(0, |fee_calculator| fee_calculator.calculate_fee(message));
+                    balances[0] = balances[0].saturating_sub(fee);
+                }
+                _ => (),
+            }
+        }
+        post_balances
+    }
+
     /// Compute all the parents of the bank in order
     pub fn parents(&self) -> Vec<Arc<Bank>> {
         let mut parents = vec![];
@@ -1369,6 +1744,11 @@ impl Bank {
         self.capitalization.load(Ordering::Relaxed) as u64
     }
 
+    /// Return the rent collected by this Bank so far
+    pub fn collected_rent(&self) -> u64 {
+        self.collected_rent.load(Ordering::Relaxed) as u64
+    }
+
     /// Return this bank's max_tick_height
     pub fn max_tick_height(&self) -> u64 {
         self.max_tick_height
@@ -1431,6 +1811,46 @@ impl Bank {
         }
     }
 
+    /// total the rent collected from the debitable accounts of committed transactions,
+    ///  the only accounts whose collection is stored
+    fn collect_rent(
+        &self,
+        txs: &[Transaction],
+        txs_iteration_order: Option<&[usize]>,
+        res: &[Result<()>],
+        loaded: &[Result<(
+            TransactionAccounts,
+            TransactionLoaders,
+            TransactionCredits,
+            TransactionRents,
+        )>],
+    ) {
+        let mut collected_rent = 0;
+        for (i, (raccs, tx)) in loaded
+            .iter()
+            .zip(OrderedIterator::new(txs, txs_iteration_order))
+            .enumerate()
+        {
+            if res[i].is_err() || raccs.is_err() {
+                continue;
+            }
+
+            let message = &tx.message();
+            let acc = raccs.as_ref().unwrap();
+
+            collected_rent += acc
+                .3
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| message.is_debitable(*i))
+                .map(|(_, rent)| rent)
+                .sum::<u64>();
+        }
+
+        self.collected_rent
+            .fetch_add(collected_rent as usize, Ordering::Relaxed);
+    }
+
     pub fn storage_accounts(&self) -> StorageAccounts {
         self.storage_accounts.read().unwrap().clone()
     }
@@ -1473,6 +1893,16 @@ impl Bank {
         self.register_native_instruction_processor("", &program_id);
     }
 
+    /// Replace the compute budget each transaction's programs are charged against, which the
+    /// bank's children inherit
+    pub fn set_compute_budget(&mut self, compute_budget: ComputeBudget) {
+        self.message_processor.set_compute_budget(compute_budget);
+    }
+
+    pub fn compute_budget(&self) -> ComputeBudget {
+        self.message_processor.get_compute_budget()
+    }
+
     pub fn compare_bank(&self, dbank: &Bank) {
         assert_eq!(self.slot, dbank.slot);
         assert_eq!(self.collector_id, dbank.collector_id);
@@ -1495,6 +1925,7 @@ impl Bank {
         let bh = self.hash.read().unwrap();
         let dbh = dbank.hash.read().unwrap();
         assert_eq!(*bh, *dbh);
+        assert_eq!(self.accounts_hash(), dbank.accounts_hash());
 
         let bhq = self.blockhash_queue.read().unwrap();
         let dbhq = dbank.blockhash_queue.read().unwrap();
@@ -1537,12 +1968,17 @@ mod tests {
     use solana_sdk::clock::DEFAULT_TICKS_PER_SLOT;
     use solana_sdk::genesis_block::create_genesis_block;
     use solana_sdk::hash;
-    use solana_sdk::instruction::InstructionError;
+    use solana_sdk::instruction::{AccountMeta, Instruction, InstructionError};
+    use solana_sdk::nonce_state::{Meta, NonceState};
     use solana_sdk::poh_config::PohConfig;
+    use solana_sdk::rent::Rent;
     use solana_sdk::signature::{Keypair, KeypairUtil};
-    use solana_sdk::system_instruction;
+    use solana_sdk::system_instruction::{self, SystemError, SystemInstruction};
+    use solana_sdk::system_program;
     use solana_sdk::system_transaction;
-    use solana_sdk::sysvar::{fees::Fees, rewards::Rewards};
+    use solana_sdk::sysvar::{
+        self, fees::Fees, recent_blockhashes::RecentBlockhashes, rewards::Rewards,
+    };
     use solana_stake_api::stake_state::Stake;
     use solana_vote_api::vote_instruction;
     use solana_vote_api::vote_state::{VoteState, MAX_LOCKOUT_HISTORY};
@@ -1872,6 +2308,86 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_bank_collect_rent() {
+        let leader = Pubkey::new_rand();
+        let GenesisBlockInfo {
+            mut genesis_block,
+            mint_keypair,
+            ..
+        } = create_genesis_block_with_leader(1_000_000, &leader, 3);
+        genesis_block.fee_calculator.lamports_per_signature = 0;
+        genesis_block.rent = Rent {
+            lamports_per_byte_year: 1_000_000_000,
+            exemption_threshold: 1_000_000.0,
+            burn_percent: 10,
+        };
+        let mut bank = Bank::new(&genesis_block);
+        let capitalization = bank.capitalization();
+        let initial_balance = bank.get_balance(&leader);
+
+        let rent_paying_keypair = Keypair::new();
+        let delinquent_keypair = Keypair::new();
+        let rent_paying_account = Account::new(100_000, 100, &system_program::id());
+        bank.store_account(&rent_paying_keypair.pubkey(), &rent_paying_account);
+        bank.store_account(
+            &delinquent_keypair.pubkey(),
+            &Account::new(1, 100, &system_program::id()),
+        );
+        let mut bank = Bank::new_from_parent(&Arc::new(bank), &leader, 1);
+        let (_, rent_due) = bank.rent_collector.update(rent_paying_account);
+        assert!(rent_due > 1);
+
+        // rent is collected from the debitable accounts the transaction stores
+        let credit = |to_pubkey: Pubkey, lamports: u64| {
+            Instruction::new(
+                system_program::id(),
+                &SystemInstruction::Transfer { lamports },
+                vec![
+                    AccountMeta::new(mint_keypair.pubkey(), true),
+                    AccountMeta::new(to_pubkey, false),
+                ],
+            )
+        };
+        let instructions = vec![
+            credit(rent_paying_keypair.pubkey(), 1),
+            credit(delinquent_keypair.pubkey(), 0),
+        ];
+        let tx = Transaction::new_signed_instructions(
+            &[&mint_keypair],
+            instructions,
+            bank.last_blockhash(),
+        );
+        assert_eq!(bank.process_transaction(&tx), Ok(()));
+        assert_eq!(
+            bank.get_balance(&rent_paying_keypair.pubkey()),
+            100_000 - rent_due + 1
+        );
+
+        // an account that can't pay the rent it owes forfeits its lamports and is purged
+        assert_eq!(bank.get_account(&delinquent_keypair.pubkey()), None);
+        assert_eq!(bank.collected_rent(), rent_due + 1);
+
+        // the leader is paid the rent once the bank is frozen, less what is burned
+        let (unburned, burned) = genesis_block.rent.calculate_burn(rent_due + 1);
+        goto_end_of_slot(&mut bank);
+        assert_eq!(bank.get_balance(&leader), initial_balance + unburned);
+        assert_eq!(bank.capitalization(), capitalization - burned);
+
+        // once rooted, cleaning removes the drained account from the index
+        bank.squash();
+        bank.clean_and_shrink_accounts(None);
+        assert!(!bank
+            .rc
+            .accounts
+            .accounts_db
+            .accounts_index
+            .read()
+            .unwrap()
+            .account_maps
+            .contains_key(&delinquent_keypair.pubkey()));
+    }
+
     #[test]
     fn test_bank_tx_fee() {
         let arbitrary_transfer_amount = 42;
@@ -2111,6 +2627,187 @@ mod tests {
         assert_eq!(results[1], Err(TransactionError::AccountInUse));
     }
 
+    #[test]
+    fn test_simulate_transaction() {
+        let (genesis_block, mint_keypair) = create_genesis_block(100);
+        let bank = Bank::new(&genesis_block);
+        bank.freeze();
+        let recipient = Pubkey::new_rand();
+
+        let tx = system_transaction::transfer(&mint_keypair, &recipient, 10, genesis_block.hash());
+        let (result, accounts, logs, compute_units_consumed) = bank.simulate_transaction(&tx);
+        assert_eq!(result, Ok(()));
+        assert!(logs.is_empty());
+        // Native programs aren't metered
+        assert_eq!(compute_units_consumed, 0);
+        assert_eq!(accounts.len(), 2);
+        assert_eq!(accounts[0].0, mint_keypair.pubkey());
+        assert_eq!(accounts[0].1.lamports, 90);
+        assert_eq!(accounts[1].0, recipient);
+        assert_eq!(accounts[1].1.lamports, 10);
+
+        // Nothing was committed
+        assert_eq!(bank.get_balance(&mint_keypair.pubkey()), 100);
+        assert_eq!(bank.get_balance(&recipient), 0);
+        assert_eq!(bank.get_signature_status(&tx.signatures[0]), None);
+
+        let tx = system_transaction::transfer(&mint_keypair, &recipient, 101, genesis_block.hash());
+        let (result, accounts, _, _) = bank.simulate_transaction(&tx);
+        assert_eq!(
+            result,
+            Err(TransactionError::InstructionError(
+                0,
+                InstructionError::CustomError(1)
+            ))
+        );
+        assert!(accounts.is_empty());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_simulate_transaction_unfrozen_bank() {
+        let (genesis_block, mint_keypair) = create_genesis_block(100);
+        let bank = Bank::new(&genesis_block);
+        let tx = system_transaction::transfer(
+            &mint_keypair,
+            &Pubkey::new_rand(),
+            10,
+            genesis_block.hash(),
+        );
+        let _ = bank.simulate_transaction(&tx);
+    }
+
+    #[test]
+    fn test_bank_compute_budget() {
+        let (genesis_block, _) = create_genesis_block(100);
+        let mut bank = Bank::new(&genesis_block);
+        assert_eq!(bank.compute_budget(), ComputeBudget::default());
+
+        let compute_budget = ComputeBudget {
+            max_units: 1_000,
+            ..ComputeBudget::default()
+        };
+        bank.set_compute_budget(compute_budget);
+        let bank = Bank::new_from_parent(&Arc::new(bank), &Pubkey::default(), 1);
+        assert_eq!(bank.compute_budget(), compute_budget);
+    }
+
+    #[test]
+    fn test_transaction_logs() {
+        let (genesis_block, mint_keypair) = create_genesis_block(100);
+        let bank = Bank::new(&genesis_block);
+        let recipient = Pubkey::new_rand();
+
+        let tx = system_transaction::transfer(&mint_keypair, &recipient, 10, genesis_block.hash());
+        let txs = vec![tx];
+        let lock_results = bank.lock_accounts(&txs, None);
+        let (mut loaded_accounts, executed, transaction_logs, _, _, tx_count, signature_count) =
+            bank.load_and_execute_transactions(&txs, None, &lock_results, MAX_RECENT_BLOCKHASHES);
+        assert_eq!(transaction_logs, vec![Vec::<String>::new()]);
+
+        // The system program doesn't log, so stand in for a program that does
+        let transaction_logs = vec![vec!["hello".to_string()]];
+        bank.commit_transactions(
+            &txs,
+            None,
+            &mut loaded_accounts,
+            &executed,
+            &transaction_logs,
+            tx_count,
+            signature_count,
+        );
+        let expected = TransactionLogInfo {
+            signature: txs[0].signatures[0],
+            result: Ok(()),
+            account_keys: txs[0].message().account_keys.clone(),
+            log_messages: transaction_logs[0].clone(),
+        };
+        assert_eq!(bank.get_transaction_logs(None), vec![expected.clone()]);
+        assert_eq!(bank.get_transaction_logs(Some(&recipient)), vec![expected]);
+        assert!(bank
+            .get_transaction_logs(Some(&Pubkey::new_rand()))
+            .is_empty());
+    }
+
+    #[test]
+    fn test_collect_balances() {
+        let (genesis_block, mint_keypair) = create_genesis_block(100);
+        let bank = Bank::new(&genesis_block);
+        let payer = Keypair::new();
+        let recipient = Keypair::new();
+        bank.transfer(3, &mint_keypair, &payer.pubkey()).unwrap();
+
+        let tx0 = system_transaction::transfer(
+            &mint_keypair,
+            &recipient.pubkey(),
+            1,
+            genesis_block.hash(),
+        );
+        let tx1 =
+            system_transaction::transfer(&payer, &recipient.pubkey(), 2, genesis_block.hash());
+        let txs = vec![tx0, tx1];
+        assert_eq!(
+            bank.collect_balances(&txs),
+            vec![vec![97, 0, 1], vec![3, 0, 1]]
+        );
+
+        let results = bank.process_transactions(&txs);
+        assert_eq!(results, vec![Ok(()), Ok(())]);
+
+        // Credits to the credit-only recipient are left out until they are committed
+        assert_eq!(
+            bank.collect_balances(&txs),
+            vec![vec![96, 0, 1], vec![1, 0, 1]]
+        );
+        bank.freeze();
+        assert_eq!(
+            bank.collect_balances(&txs),
+            vec![vec![96, 3, 1], vec![1, 3, 1]]
+        );
+    }
+
+    #[test]
+    fn test_load_execute_and_commit_transactions_balances() {
+        let (genesis_block, mint_keypair) = create_genesis_block(100);
+        let bank = Bank::new(&genesis_block);
+        let payer0 = Keypair::new();
+        let payer1 = Keypair::new();
+        let recipient = Pubkey::new_rand();
+        bank.transfer(3, &mint_keypair, &payer0.pubkey()).unwrap();
+        bank.transfer(2, &mint_keypair, &payer1.pubkey()).unwrap();
+
+        let txs = vec![
+            system_transaction::transfer(&mint_keypair, &recipient, 1, genesis_block.hash()),
+            system_transaction::transfer(&payer0, &recipient, 2, genesis_block.hash()),
+            system_transaction::transfer(&payer1, &recipient, 3, genesis_block.hash()),
+        ];
+        let lock_results = bank.lock_accounts(&txs, None);
+        let (results, _, _, balances) = bank.load_execute_and_commit_transactions(
+            &txs,
+            None,
+            &lock_results,
+            MAX_RECENT_BLOCKHASHES,
+            true,
+        );
+        assert_eq!(results, vec![Ok(()), Ok(()), Ok(())]);
+        assert!(bank
+            .get_signature_status(&txs[2].signatures[0])
+            .unwrap()
+            .is_err());
+
+        // Each transaction sees only its own credit to the shared credit-only recipient, and
+        // the failed transfer moves nothing
+        assert_eq!(
+            balances,
+            TransactionBalancesSet {
+                pre_balances: vec![vec![95, 0, 1], vec![3, 0, 1], vec![2, 0, 1]],
+                post_balances: vec![vec![94, 1, 1], vec![1, 2, 1], vec![2, 0, 1]],
+            }
+        );
+        bank.freeze();
+        assert_eq!(bank.get_balance(&recipient), 3);
+    }
+
     #[test]
     fn test_interleaving_locks() {
         let (genesis_block, mint_keypair) = create_genesis_block(3);
@@ -2127,11 +2824,12 @@ mod tests {
         let pay_alice = vec![tx1];
 
         let lock_result = bank.lock_accounts(&pay_alice, None);
-        let results_alice = bank.load_execute_and_commit_transactions(
+        let (results_alice, _, _, _) = bank.load_execute_and_commit_transactions(
             &pay_alice,
             None,
             &lock_result,
             MAX_RECENT_BLOCKHASHES,
+            false,
         );
         assert_eq!(results_alice[0], Ok(()));
 
@@ -2774,6 +3472,186 @@ mod tests {
         assert!(bank.is_votable());
     }
 
+    #[test]
+    fn test_bank_recent_blockhashes_account() {
+        let (genesis_block, _) = create_genesis_block(500);
+        let mut bank = Bank::new(&genesis_block);
+        let recent_blockhashes =
+            RecentBlockhashes::from(&bank.get_account(&sysvar::recent_blockhashes::id()).unwrap())
+                .unwrap();
+        assert_eq!(recent_blockhashes[..], [bank.last_blockhash()]);
+
+        goto_end_of_slot(&mut bank);
+        let recent_blockhashes =
+            RecentBlockhashes::from(&bank.get_account(&sysvar::recent_blockhashes::id()).unwrap())
+                .unwrap();
+        assert_eq!(recent_blockhashes.len(), 2);
+        assert_eq!(recent_blockhashes[0], bank.last_blockhash());
+        assert_eq!(recent_blockhashes[1], genesis_block.hash());
+    }
+
+    #[test]
+    fn test_bank_blockhash_last_valid_slot() {
+        let (genesis_block, _) = create_genesis_block(500);
+        let bank0 = Arc::new(Bank::new(&genesis_block));
+        let genesis_hash = bank0.last_blockhash();
+        let max_age = MAX_RECENT_BLOCKHASHES as u64;
+        assert_eq!(
+            bank0.get_blockhash_last_valid_slot(&genesis_hash),
+            Some(max_age)
+        );
+        assert_eq!(bank0.get_blockhash_last_valid_slot(&Hash::default()), None);
+
+        let mut bank1 = Bank::new_from_parent(&bank0, &Pubkey::default(), 1);
+        goto_end_of_slot(&mut bank1);
+        assert_eq!(
+            bank1.get_blockhash_last_valid_slot(&genesis_hash),
+            Some(max_age)
+        );
+        assert_eq!(
+            bank1.get_blockhash_last_valid_slot(&bank1.last_blockhash()),
+            Some(max_age + 1)
+        );
+
+        // Skipped slots don't age the blockhash, so it stays valid for longer
+        let bank1 = Arc::new(bank1);
+        let bank5 = Bank::new_from_parent(&bank1, &Pubkey::default(), 5);
+        assert_eq!(
+            bank5.get_blockhash_last_valid_slot(&genesis_hash),
+            Some(max_age + 4)
+        );
+    }
+
+    #[test]
+    fn test_durable_nonce_transaction() {
+        let (genesis_block, mint_keypair) = create_genesis_block(10_000);
+        let mint_pubkey = mint_keypair.pubkey();
+        let bank = Bank::new(&genesis_block);
+        let nonce_keypair = Keypair::new();
+        let nonce_pubkey = nonce_keypair.pubkey();
+        let authority_keypair = Keypair::new();
+        let authority_pubkey = authority_keypair.pubkey();
+        let to_pubkey = Pubkey::new_rand();
+
+        let tx = Transaction::new_signed_with_payer(
+            system_instruction::create_nonce_account(
+                &mint_pubkey,
+                &nonce_pubkey,
+                &authority_pubkey,
+                1_000,
+            ),
+            Some(&mint_pubkey),
+            &[&mint_keypair],
+            bank.last_blockhash(),
+        );
+        assert_eq!(bank.process_transaction(&tx), Ok(()));
+        let nonce_hash = match NonceState::from(&bank.get_account(&nonce_pubkey).unwrap()) {
+            Some(NonceState::Initialized(meta, hash)) => {
+                assert_eq!(meta.nonce_authority, authority_pubkey);
+                hash
+            }
+            state => panic!("unexpected nonce state {:?}", state),
+        };
+        assert_eq!(nonce_hash, bank.last_blockhash());
+
+        // age the stored nonce out of the blockhash queue
+        for i in 0..(MAX_RECENT_BLOCKHASHES as u64 + 1) * bank.ticks_per_slot() {
+            bank.register_tick(&hash::hash(&i.to_le_bytes()));
+        }
+        assert!(!bank.check_hash_age(&nonce_hash, MAX_RECENT_BLOCKHASHES));
+
+        // without advancing the nonce the transaction has expired
+        let tx = Transaction::new_signed_with_payer(
+            vec![system_instruction::transfer(&mint_pubkey, &to_pubkey, 42)],
+            Some(&mint_pubkey),
+            &[&mint_keypair],
+            nonce_hash,
+        );
+        assert_eq!(
+            bank.process_transaction(&tx),
+            Err(TransactionError::BlockhashNotFound)
+        );
+
+        let durable_tx = Transaction::new_signed_with_payer(
+            vec![
+                system_instruction::advance_nonce(&nonce_pubkey, &authority_pubkey),
+                system_instruction::transfer(&mint_pubkey, &to_pubkey, 42),
+            ],
+            Some(&mint_pubkey),
+            &[&mint_keypair, &authority_keypair],
+            nonce_hash,
+        );
+        assert!(bank.check_tx_durable_nonce(&durable_tx));
+        assert_eq!(bank.process_transaction(&durable_tx), Ok(()));
+        assert_eq!(bank.get_balance(&to_pubkey), 42);
+        assert_eq!(
+            NonceState::from(&bank.get_account(&nonce_pubkey).unwrap()),
+            Some(NonceState::Initialized(
+                Meta::new(&authority_pubkey),
+                bank.last_blockhash()
+            ))
+        );
+
+        // the nonce has moved on, so the transaction can't be replayed
+        assert!(!bank.check_tx_durable_nonce(&durable_tx));
+        assert_eq!(
+            bank.process_transaction(&durable_tx),
+            Err(TransactionError::BlockhashNotFound)
+        );
+
+        // a failing durable transaction still advances the nonce and pays its fee
+        let nonce_hash = bank.last_blockhash();
+        for i in 0..bank.ticks_per_slot() {
+            bank.register_tick(&hash::hash(&i.to_be_bytes()));
+        }
+        assert_ne!(bank.last_blockhash(), nonce_hash);
+        let mint_balance = bank.get_balance(&mint_pubkey);
+        let failing_tx = Transaction::new_signed_with_payer(
+            vec![
+                system_instruction::advance_nonce(&nonce_pubkey, &authority_pubkey),
+                system_instruction::transfer(&mint_pubkey, &to_pubkey, mint_balance + 1),
+            ],
+            Some(&mint_pubkey),
+            &[&mint_keypair, &authority_keypair],
+            nonce_hash,
+        );
+        assert!(bank.check_tx_durable_nonce(&failing_tx));
+        assert_eq!(
+            bank.process_transaction(&failing_tx),
+            Err(TransactionError::InstructionError(
+                1,
+                SystemError::ResultWithNegativeLamports.into()
+            ))
+        );
+        assert_eq!(bank.get_balance(&to_pubkey), 42);
+        assert_eq!(
+            NonceState::from(&bank.get_account(&nonce_pubkey).unwrap()),
+            Some(NonceState::Initialized(
+                Meta::new(&authority_pubkey),
+                bank.last_blockhash()
+            ))
+        );
+        assert!(!bank.check_tx_durable_nonce(&failing_tx));
+
+        // the nonce can't be advanced again until a new blockhash is registered
+        let tx = Transaction::new_signed_with_payer(
+            vec![system_instruction::advance_nonce(
+                &nonce_pubkey,
+                &authority_pubkey,
+            )],
+            Some(&mint_pubkey),
+            &[&mint_keypair, &authority_keypair],
+            bank.last_blockhash(),
+        );
+        assert_eq!(
+            bank.process_transaction(&tx),
+            Err(TransactionError::InstructionError(
+                0,
+                SystemError::NonceBlockhashNotExpired.into()
+            ))
+        );
+    }
+
     #[test]
     fn test_bank_fees_account() {
         let (mut genesis_block, _) = create_genesis_block(500);
@@ -2866,6 +3744,55 @@ mod tests {
         bank.compare_bank(&dbank);
     }
 
+    #[test]
+    fn test_bank_accounts_hash() {
+        let (genesis_block, _) = create_genesis_block(500);
+        let bank0 = Arc::new(Bank::new(&genesis_block));
+        let key = Keypair::new();
+        bank0.deposit(&key.pubkey(), 10);
+        bank0.squash();
+        // only hashed on request
+        assert_eq!(bank0.accounts_hash(), Hash::default());
+        let hash0 = bank0.update_accounts_hash();
+        assert_ne!(hash0, Hash::default());
+        assert_eq!(bank0.accounts_hash(), hash0);
+        assert!(bank0.verify_accounts_hash());
+
+        let bank1 = Arc::new(new_from_parent(&bank0));
+        bank1.deposit(&key.pubkey(), 10);
+        bank1.squash();
+        assert_ne!(bank1.update_accounts_hash(), hash0);
+        assert!(bank1.verify_accounts_hash());
+
+        let len = serialized_size(&*bank1).unwrap() + serialized_size(&bank1.rc).unwrap();
+        let mut buf = vec![0u8; len as usize];
+        let mut writer = Cursor::new(&mut buf[..]);
+        serialize_into(&mut writer, &*bank1).unwrap();
+        serialize_into(&mut writer, &bank1.rc).unwrap();
+
+        let mut rdr = Cursor::new(&buf[..]);
+        let mut dbank: Bank = deserialize_from(&mut rdr).unwrap();
+        let mut reader = BufReader::new(&buf[rdr.position() as usize..]);
+        let (_accounts_dir, dbank_paths) = get_temp_accounts_paths(4).unwrap();
+        dbank.set_bank_rc(
+            &BankRc::new(dbank_paths.clone(), 0, dbank.slot()),
+            &StatusCacheRc::default(),
+        );
+        let copied_accounts = TempDir::new().unwrap();
+        copy_append_vecs(&bank1.rc.accounts.accounts_db, copied_accounts.path()).unwrap();
+        dbank
+            .rc
+            .accounts_from_stream(&mut reader, dbank_paths, copied_accounts.path())
+            .unwrap();
+        assert_eq!(dbank.accounts_hash(), bank1.accounts_hash());
+        assert!(dbank.verify_accounts_hash());
+        assert_eq!(dbank.rc.accounts.accounts_hash(), bank1.accounts_hash());
+
+        // an account that differs from what was hashed is caught
+        dbank.deposit(&key.pubkey(), 1);
+        assert!(!dbank.verify_accounts_hash());
+    }
+
     #[test]
     fn test_check_point_values() {
         let (genesis_block, _) = create_genesis_block(500);
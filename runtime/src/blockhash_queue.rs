@@ -59,6 +59,14 @@ impl BlockhashQueue {
         }
     }
 
+    /// The number of hashes registered since the hash, or None if it's older than max_age
+    pub fn get_hash_age(&self, hash: &Hash) -> Option<u64> {
+        self.ages
+            .get(hash)
+            .map(|age| self.hash_height - age.hash_height)
+            .filter(|hash_age| *hash_age <= self.max_age as u64)
+    }
+
     /// check if hash is valid
     #[cfg(test)]
     pub fn check_hash(&self, hash: Hash) -> bool {
@@ -105,6 +113,16 @@ impl BlockhashQueue {
         self.last_hash = Some(*hash);
     }
 
+    /// The registered hashes, most recently registered first
+    pub fn get_recent_blockhashes(&self) -> Vec<Hash> {
+        let mut recent_blockhashes: Vec<_> = self.ages.iter().collect();
+        recent_blockhashes.sort_by(|(_, a), (_, b)| b.hash_height.cmp(&a.hash_height));
+        recent_blockhashes
+            .into_iter()
+            .map(|(hash, _)| *hash)
+            .collect()
+    }
+
     /// Maps a hash height to a timestamp
     pub fn hash_height_to_timestamp(&self, hash_height: u64) -> Option<u64> {
         for age in self.ages.values() {
@@ -150,4 +168,30 @@ mod tests {
         assert_eq!(last_hash, hash_queue.last_hash());
         assert!(hash_queue.check_hash_age(&last_hash, 0));
     }
+
+    #[test]
+    fn test_get_hash_age() {
+        let mut hash_queue = BlockhashQueue::new(2);
+        let hashes: Vec<_> = (0..4).map(|i| hash(&serialize(&i).unwrap())).collect();
+        assert_eq!(hash_queue.get_hash_age(&hashes[0]), None);
+        for hash in &hashes {
+            hash_queue.register_hash(hash, &FeeCalculator::default());
+        }
+        assert_eq!(hash_queue.get_hash_age(&hashes[3]), Some(0));
+        assert_eq!(hash_queue.get_hash_age(&hashes[1]), Some(2));
+        assert_eq!(hash_queue.get_hash_age(&hashes[0]), None);
+    }
+
+    #[test]
+    fn test_get_recent_blockhashes() {
+        let mut hash_queue = BlockhashQueue::new(100);
+        let hashes: Vec<_> = (0..10).map(|i| hash(&serialize(&i).unwrap())).collect();
+        for hash in &hashes {
+            hash_queue.register_hash(hash, &FeeCalculator::default());
+        }
+        let recent_blockhashes = hash_queue.get_recent_blockhashes();
+        assert_eq!(recent_blockhashes.len(), hashes.len());
+        assert_eq!(recent_blockhashes[0], hash_queue.last_hash());
+        assert!(recent_blockhashes.iter().eq(hashes.iter().rev()));
+    }
 }
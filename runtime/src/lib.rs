@@ -10,8 +10,10 @@ pub mod epoch_schedule;
 pub mod genesis_utils;
 pub mod loader_utils;
 pub mod locked_accounts_results;
+pub mod log_collector;
 pub mod message_processor;
 mod native_loader;
+pub mod nonce_utils;
 pub mod rent_collector;
 mod serde_utils;
 pub mod stakes;
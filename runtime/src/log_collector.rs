@@ -0,0 +1,88 @@
+//! collect the messages programs log while processing a transaction
+use solana_sdk::instruction_processor_utils::Logger;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::signature::Signature;
+use solana_sdk::transaction::Result;
+use std::cell::RefCell;
+
+/// Upper bound on the total bytes of messages collected for a single transaction
+pub const LOG_MESSAGES_BYTES_LIMIT: usize = 10 * 1000;
+
+/// Upper bound on the number of transactions whose log messages a bank keeps for subscribers
+pub const MAX_TRANSACTION_LOGS_PER_BANK: usize = 1_000;
+
+pub type TransactionLogMessages = Vec<String>;
+
+/// The messages logged by a committed transaction
+#[derive(Clone, Debug, PartialEq)]
+pub struct TransactionLogInfo {
+    pub signature: Signature,
+    pub result: Result<()>,
+    pub account_keys: Vec<Pubkey>,
+    pub log_messages: TransactionLogMessages,
+}
+
+#[derive(Default)]
+struct LogCollectorInner {
+    messages: TransactionLogMessages,
+    bytes_written: usize,
+    limit_warning: bool,
+}
+
+/// Collects the messages logged by the programs invoked by a single transaction. Once
+/// `LOG_MESSAGES_BYTES_LIMIT` is reached, further messages are dropped and a single
+/// "Log truncated" message is recorded in their place.
+#[derive(Default)]
+pub struct LogCollector {
+    inner: RefCell<LogCollectorInner>,
+}
+
+impl Logger for LogCollector {
+    fn log(&self, message: &str) {
+        let mut inner = self.inner.borrow_mut();
+        let bytes_written = inner.bytes_written.saturating_add(message.len());
+        if bytes_written >= LOG_MESSAGES_BYTES_LIMIT {
+            if !inner.limit_warning {
+                inner.limit_warning = true;
+                inner.messages.push(String::from("Log truncated"));
+            }
+        } else {
+            inner.bytes_written = bytes_written;
+            inner.messages.push(message.to_string());
+        }
+    }
+}
+
+impl From<LogCollector> for TransactionLogMessages {
+    fn from(log_collector: LogCollector) -> Self {
+        log_collector.inner.into_inner().messages
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_log_collector() {
+        let log_collector = LogCollector::default();
+        log_collector.log("first");
+        log_collector.log("second");
+        let messages: TransactionLogMessages = log_collector.into();
+        assert_eq!(messages, vec!["first".to_string(), "second".to_string()]);
+    }
+
+    #[test]
+    fn test_log_collector_limit() {
+        let log_collector = LogCollector::default();
+        let message = "x".repeat(100);
+        for _ in 0..LOG_MESSAGES_BYTES_LIMIT {
+            log_collector.log(&message);
+        }
+        let messages: TransactionLogMessages = log_collector.into();
+        let len = LOG_MESSAGES_BYTES_LIMIT / message.len();
+        assert_eq!(messages.len(), len);
+        assert!(messages[..len - 1].iter().all(|m| *m == message));
+        assert_eq!(messages[len - 1], "Log truncated");
+    }
+}
@@ -1,18 +1,23 @@
+use crate::log_collector::LogCollector;
 use crate::native_loader;
 use crate::system_instruction_processor;
 use serde::{Deserialize, Serialize};
 use solana_sdk::account::{
     create_keyed_credit_only_accounts, Account, KeyedAccount, LamportCredit,
 };
-use solana_sdk::instruction::{CompiledInstruction, InstructionError};
-use solana_sdk::instruction_processor_utils;
+use solana_sdk::instruction::{CompiledInstruction, Instruction, InstructionError};
+use solana_sdk::instruction_processor_utils::{
+    self, ComputeBudget, ComputeMeter, InvokeContext, Logger,
+};
 use solana_sdk::loader_instruction::LoaderInstruction;
 use solana_sdk::message::Message;
 use solana_sdk::pubkey::Pubkey;
 use solana_sdk::system_program;
 use solana_sdk::transaction::TransactionError;
+use std::cell::RefCell;
 use std::collections::HashMap;
 use std::io::Write;
+use std::rc::Rc;
 use std::sync::RwLock;
 
 #[cfg(unix)]
@@ -20,6 +25,10 @@ use libloading::os::unix::*;
 #[cfg(windows)]
 use libloading::os::windows::*;
 
+/// Maximum number of programs that may be invoked one within another, starting with the
+/// program an instruction of the message calls
+const MAX_INVOKE_DEPTH: usize = 4;
+
 /// Return true if the slice has any duplicate elements
 pub fn has_duplicates<T: PartialEq>(xs: &[T]) -> bool {
     // Note: This is an O(n^2) algorithm, but requires no heap allocations. The benchmark
@@ -128,6 +137,175 @@ fn get_loader_instruction_data<'a>(
     }
 }
 
+/// Give a loader a way to invoke other programs on behalf of the program it runs.  The
+/// account changes each program makes are verified against that program before another
+/// program is handed the accounts, so `pre_accounts` holds every account of the message
+/// instruction as it was last verified.  Every program the message invokes is charged
+/// against the same `compute_meter`.
+#[derive(Clone, Copy)]
+struct MessageInvokeContext<'a> {
+    message_processor: &'a MessageProcessor,
+    log_collector: &'a LogCollector,
+    compute_meter: &'a Rc<RefCell<ComputeMeter>>,
+    loader_accounts: &'a HashMap<Pubkey, Account>,
+    pre_accounts: &'a RefCell<HashMap<Pubkey, Account>>,
+    depth: usize,
+}
+
+impl<'a> MessageInvokeContext<'a> {
+    /// Verify the changes `program_id` made to an account since it was last verified, and
+    /// record its current state
+    fn verify_and_update(
+        &self,
+        program_id: &Pubkey,
+        keyed_account: &KeyedAccount,
+    ) -> Result<(), InstructionError> {
+        let mut pre_accounts = self.pre_accounts.borrow_mut();
+        let pre_account = pre_accounts
+            .get_mut(keyed_account.unsigned_key())
+            .ok_or(InstructionError::InvalidArgument)?;
+        verify_instruction(
+            keyed_account.is_debitable(),
+            program_id,
+            pre_account,
+            keyed_account.account,
+        )?;
+        *pre_account = keyed_account.account.clone();
+        Ok(())
+    }
+
+    /// Return the executable accounts to invoke a program with: the program's own account,
+    /// preceded by its loader's account unless the program is owned by the native loader.
+    /// Only loaders of programs called by the message's instructions are available.
+    fn get_executable_accounts(
+        &self,
+        program_account: Account,
+    ) -> Result<Vec<(Pubkey, Account)>, InstructionError> {
+        if !program_account.executable {
+            return Err(InstructionError::IncorrectProgramId);
+        }
+        if solana_sdk::native_loader::check_id(&program_account.owner) {
+            return Ok(vec![(program_account.owner, program_account)]);
+        }
+        let loader_account = self
+            .loader_accounts
+            .get(&program_account.owner)
+            .ok_or(InstructionError::IncorrectProgramId)?;
+        Ok(vec![
+            (loader_account.owner, loader_account.clone()),
+            (program_account.owner, program_account),
+        ])
+    }
+}
+
+impl<'a> Logger for MessageInvokeContext<'a> {
+    fn log(&self, message: &str) {
+        self.log_collector.log(message);
+    }
+}
+
+impl<'a> InvokeContext for MessageInvokeContext<'a> {
+    fn invoke(
+        &self,
+        caller_program_id: &Pubkey,
+        instruction: &Instruction,
+        keyed_accounts: &mut [KeyedAccount],
+    ) -> Result<(), InstructionError> {
+        if self.depth >= MAX_INVOKE_DEPTH {
+            return Err(InstructionError::CallDepth);
+        }
+
+        let program_account = keyed_accounts
+            .iter()
+            .find(|keyed_account| keyed_account.unsigned_key() == &instruction.program_id)
+            .map(|keyed_account| keyed_account.account.clone())
+            .ok_or(InstructionError::InvalidArgument)?;
+        let mut executable_accounts = self.get_executable_accounts(program_account)?;
+
+        // The caller may only pass down the privileges it was granted
+        let mut indexes = vec![];
+        for account_meta in &instruction.accounts {
+            let index = keyed_accounts
+                .iter()
+                .position(|keyed_account| keyed_account.unsigned_key() == &account_meta.pubkey)
+                .ok_or(InstructionError::InvalidArgument)?;
+            let keyed_account = &keyed_accounts[index];
+            if (account_meta.is_signer && keyed_account.signer_key().is_none())
+                || (account_meta.is_debitable && !keyed_account.is_debitable())
+            {
+                return Err(InstructionError::PrivilegeEscalation);
+            }
+            indexes.push(index as u8);
+        }
+        let program_accounts = get_subset_unchecked_mut(keyed_accounts, &indexes)?;
+        for keyed_account in program_accounts.iter() {
+            self.verify_and_update(caller_program_id, keyed_account)?;
+        }
+        let pre_total: u128 = program_accounts
+            .iter()
+            .map(|keyed_account| u128::from(keyed_account.account.lamports))
+            .sum();
+
+        let mut loader_ix_data = vec![];
+        let ix_data = get_loader_instruction_data(
+            &executable_accounts,
+            &instruction.data,
+            &mut loader_ix_data,
+        );
+        let num_executable_accounts = executable_accounts.len();
+        let mut keyed_accounts = create_keyed_credit_only_accounts(&mut executable_accounts);
+        keyed_accounts.extend(instruction.accounts.iter().zip(program_accounts).map(
+            |(account_meta, keyed_account)| {
+                if account_meta.is_debitable {
+                    KeyedAccount::new(
+                        &account_meta.pubkey,
+                        account_meta.is_signer,
+                        keyed_account.account,
+                    )
+                } else {
+                    KeyedAccount::new_credit_only(
+                        &account_meta.pubkey,
+                        account_meta.is_signer,
+                        keyed_account.account,
+                    )
+                }
+            },
+        ));
+
+        let invoke_context = MessageInvokeContext {
+            depth: self.depth + 1,
+            ..*self
+        };
+        self.message_processor.call_program(
+            &instruction.program_id,
+            &mut keyed_accounts,
+            ix_data,
+            &invoke_context,
+        )?;
+
+        // Verify the invoked program
+        for keyed_account in &keyed_accounts[num_executable_accounts..] {
+            self.verify_and_update(&instruction.program_id, keyed_account)?;
+        }
+        let post_total: u128 = keyed_accounts[num_executable_accounts..]
+            .iter()
+            .map(|keyed_account| u128::from(keyed_account.account.lamports))
+            .sum();
+        if pre_total != post_total {
+            return Err(InstructionError::UnbalancedInstruction);
+        }
+        Ok(())
+    }
+
+    fn get_compute_budget(&self) -> ComputeBudget {
+        self.message_processor.compute_budget
+    }
+
+    fn get_compute_meter(&self) -> Rc<RefCell<ComputeMeter>> {
+        self.compute_meter.clone()
+    }
+}
+
 pub type ProcessInstruction =
     fn(&Pubkey, &mut [KeyedAccount], &[u8]) -> Result<(), InstructionError>;
 
@@ -139,6 +317,8 @@ pub struct MessageProcessor {
     instruction_processors: Vec<(Pubkey, ProcessInstruction)>,
     #[serde(skip)]
     symbol_cache: SymbolCache,
+    #[serde(skip)]
+    compute_budget: ComputeBudget,
 }
 
 impl Default for MessageProcessor {
@@ -151,6 +331,7 @@ impl Default for MessageProcessor {
         Self {
             instruction_processors,
             symbol_cache: RwLock::new(HashMap::new()),
+            compute_budget: ComputeBudget::default(),
         }
     }
 }
@@ -166,6 +347,15 @@ impl MessageProcessor {
             .push((program_id, process_instruction));
     }
 
+    /// Replace the compute budget the messages this processor runs are charged against
+    pub fn set_compute_budget(&mut self, compute_budget: ComputeBudget) {
+        self.compute_budget = compute_budget;
+    }
+
+    pub fn get_compute_budget(&self) -> ComputeBudget {
+        self.compute_budget
+    }
+
     /// Process an instruction
     /// This method calls the instruction's program entrypoint method
     fn process_instruction(
@@ -174,6 +364,7 @@ impl MessageProcessor {
         instruction: &CompiledInstruction,
         executable_accounts: &mut [(Pubkey, Account)],
         program_accounts: &mut [&mut Account],
+        invoke_context: &dyn InvokeContext,
     ) -> Result<(), InstructionError> {
         let program_id = instruction.program_id(&message.account_keys);
 
@@ -209,9 +400,23 @@ impl MessageProcessor {
             .collect();
         keyed_accounts.append(&mut keyed_accounts2);
 
+        self.call_program(program_id, &mut keyed_accounts, ix_data, invoke_context)
+    }
+
+    /// Call the entrypoint of the program `program_id`, passing it `keyed_accounts`, which
+    /// start with the program's executable accounts
+    fn call_program(
+        &self,
+        program_id: &Pubkey,
+        keyed_accounts: &mut [KeyedAccount],
+        ix_data: &[u8],
+        invoke_context: &dyn InvokeContext,
+    ) -> Result<(), InstructionError> {
         for (id, process_instruction) in &self.instruction_processors {
             if id == program_id {
-                return process_instruction(&program_id, &mut keyed_accounts[1..], &ix_data);
+                return instruction_processor_utils::with_logger(invoke_context, || {
+                    process_instruction(&program_id, &mut keyed_accounts[1..], &ix_data)
+                });
             }
         }
 
@@ -222,9 +427,10 @@ impl MessageProcessor {
 
         native_loader::invoke_entrypoint(
             &program_id,
-            &mut keyed_accounts,
+            keyed_accounts,
             ix_data,
             &self.symbol_cache,
+            invoke_context,
         )
     }
 
@@ -239,37 +445,50 @@ impl MessageProcessor {
         executable_accounts: &mut [(Pubkey, Account)],
         program_accounts: &mut [&mut Account],
         credits: &mut [&mut LamportCredit],
+        log_collector: &LogCollector,
+        compute_meter: &Rc<RefCell<ComputeMeter>>,
+        loader_accounts: &HashMap<Pubkey, Account>,
     ) -> Result<(), InstructionError> {
         let program_id = instruction.program_id(&message.account_keys);
         assert_eq!(instruction.accounts.len(), program_accounts.len());
         // TODO: the runtime should be checking read/write access to memory
         // we are trusting the hard-coded programs not to clobber or allocate
-        let pre_total: u128 = program_accounts
-            .iter()
-            .map(|a| u128::from(a.lamports))
-            .sum();
+        let pre_lamports: Vec<_> = program_accounts.iter().map(|a| a.lamports).collect();
+        let pre_total: u128 = pre_lamports.iter().map(|l| u128::from(*l)).sum();
+        // cloned() doesn't work on & &
         #[allow(clippy::map_clone)]
-        let pre_accounts: Vec<_> = program_accounts
-            .iter_mut()
-            .map(|account| account.clone()) // cloned() doesn't work on & &
+        let pre_accounts: HashMap<_, _> = instruction
+            .accounts
+            .iter()
+            .map(|index| message.account_keys[*index as usize])
+            .zip(program_accounts.iter_mut().map(|account| account.clone()))
             .collect();
-
-        self.process_instruction(message, instruction, executable_accounts, program_accounts)?;
-        // Verify the instruction
-        for (pre_account, (i, post_account, is_debitable)) in
-            pre_accounts
-                .iter()
-                .zip(program_accounts.iter().enumerate().map(|(i, account)| {
-                    (
-                        i,
-                        account,
-                        message.is_debitable(instruction.accounts[i] as usize),
-                    )
-                }))
-        {
+        let pre_accounts = RefCell::new(pre_accounts);
+
+        let invoke_context = MessageInvokeContext {
+            message_processor: self,
+            log_collector,
+            compute_meter,
+            loader_accounts,
+            pre_accounts: &pre_accounts,
+            depth: 0,
+        };
+        self.process_instruction(
+            message,
+            instruction,
+            executable_accounts,
+            program_accounts,
+            &invoke_context,
+        )?;
+        // Verify the instruction, against the accounts as of the last program it invoked
+        let pre_accounts = pre_accounts.into_inner();
+        for (i, post_account) in program_accounts.iter().enumerate() {
+            let index = instruction.accounts[i] as usize;
+            let is_debitable = message.is_debitable(index);
+            let pre_account = &pre_accounts[&message.account_keys[index]];
             verify_instruction(is_debitable, &program_id, pre_account, post_account)?;
             if !is_debitable {
-                *credits[i] += post_account.lamports - pre_account.lamports;
+                *credits[i] += post_account.lamports - pre_lamports[i];
             }
         }
         // The total sum of all the lamports in all the accounts cannot change.
@@ -287,13 +506,24 @@ impl MessageProcessor {
     /// Process a message.
     /// This method calls each instruction in the message over the set of loaded Accounts
     /// The accounts are committed back to the bank only if every instruction succeeds
+    /// Messages logged by the invoked programs are recorded in `log_collector`, and their work
+    /// is charged against `compute_meter`, which is left with the units the message did not use
     pub fn process_message(
         &self,
         message: &Message,
         loaders: &mut [Vec<(Pubkey, Account)>],
         accounts: &mut [Account],
         credits: &mut [LamportCredit],
+        log_collector: &LogCollector,
+        compute_meter: &Rc<RefCell<ComputeMeter>>,
     ) -> Result<(), TransactionError> {
+        // The loaders of the message's programs, through which their programs may invoke
+        // other programs of the same loader
+        let loader_accounts: HashMap<_, _> = loaders
+            .iter()
+            .filter(|executable_accounts| executable_accounts.len() > 1)
+            .map(|executable_accounts| (executable_accounts[1].0, executable_accounts[0].1.clone()))
+            .collect();
         for (instruction_index, instruction) in message.instructions.iter().enumerate() {
             let executable_index = message
                 .program_position(instruction.program_id_index as usize)
@@ -312,8 +542,14 @@ impl MessageProcessor {
                 executable_accounts,
                 &mut program_accounts,
                 &mut instruction_credits,
+                log_collector,
+                compute_meter,
+                &loader_accounts,
             )
-            .map_err(|err| TransactionError::InstructionError(instruction_index as u8, err))?;
+            .map_err(|err| match err {
+                InstructionError::CallDepth => TransactionError::CallChainTooDeep,
+                err => TransactionError::InstructionError(instruction_index as u8, err),
+            })?;
         }
         Ok(())
     }
@@ -322,6 +558,7 @@ impl MessageProcessor {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::log_collector::TransactionLogMessages;
     use solana_sdk::instruction::{AccountMeta, Instruction, InstructionError};
     use solana_sdk::message::Message;
     use solana_sdk::native_loader::{create_loadable_account, id};
@@ -551,6 +788,7 @@ mod tests {
             if let Ok(instruction) = bincode::deserialize(data) {
                 match instruction {
                     MockSystemInstruction::Correct { lamports } => {
+                        instruction_processor_utils::log("correct");
                         keyed_accounts[0].account.lamports -= lamports;
                         keyed_accounts[1].account.lamports += lamports;
                         Ok(())
@@ -600,12 +838,21 @@ mod tests {
         )]);
         let mut deltas = vec![0, 0];
 
-        let result =
-            message_processor.process_message(&message, &mut loaders, &mut accounts, &mut deltas);
+        let log_collector = LogCollector::default();
+        let result = message_processor.process_message(
+            &message,
+            &mut loaders,
+            &mut accounts,
+            &mut deltas,
+            &log_collector,
+            &Rc::new(RefCell::new(ComputeMeter::new(0))),
+        );
         assert_eq!(result, Ok(()));
         assert_eq!(accounts[0].lamports, 50);
         assert_eq!(accounts[1].lamports, 50);
         assert_eq!(deltas, vec![0, 50]);
+        let log_messages: TransactionLogMessages = log_collector.into();
+        assert_eq!(log_messages, vec!["correct".to_string()]);
 
         let message = Message::new(vec![Instruction::new(
             mock_system_program_id,
@@ -614,8 +861,14 @@ mod tests {
         )]);
         let mut deltas = vec![0, 0];
 
-        let result =
-            message_processor.process_message(&message, &mut loaders, &mut accounts, &mut deltas);
+        let result = message_processor.process_message(
+            &message,
+            &mut loaders,
+            &mut accounts,
+            &mut deltas,
+            &LogCollector::default(),
+            &Rc::new(RefCell::new(ComputeMeter::new(0))),
+        );
         assert_eq!(
             result,
             Err(TransactionError::InstructionError(
@@ -631,8 +884,14 @@ mod tests {
         )]);
         let mut deltas = vec![0, 0];
 
-        let result =
-            message_processor.process_message(&message, &mut loaders, &mut accounts, &mut deltas);
+        let result = message_processor.process_message(
+            &message,
+            &mut loaders,
+            &mut accounts,
+            &mut deltas,
+            &LogCollector::default(),
+            &Rc::new(RefCell::new(ComputeMeter::new(0))),
+        );
         assert_eq!(
             result,
             Err(TransactionError::InstructionError(
@@ -642,6 +901,105 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_invoke() {
+        fn mock_process_instruction(
+            _program_id: &Pubkey,
+            keyed_accounts: &mut [KeyedAccount],
+            data: &[u8],
+        ) -> Result<(), InstructionError> {
+            let lamports: u64 = bincode::deserialize(data).unwrap();
+            keyed_accounts[0].account.lamports -= lamports;
+            keyed_accounts[1].account.lamports += lamports;
+            Ok(())
+        }
+
+        let caller_program_id = Pubkey::new_rand();
+        let callee_program_id = Pubkey::new_rand();
+        let mut message_processor = MessageProcessor::default();
+        message_processor.add_instruction_processor(callee_program_id, mock_process_instruction);
+
+        let from_pubkey = Pubkey::new_rand();
+        let to_pubkey = Pubkey::new_rand();
+        let mut accounts = vec![
+            (from_pubkey, Account::new(100, 0, &callee_program_id)),
+            (to_pubkey, Account::new(0, 0, &callee_program_id)),
+            (callee_program_id, create_loadable_account("mock")),
+        ];
+        let pre_accounts = RefCell::new(accounts.iter().cloned().collect());
+        let loader_accounts = HashMap::new();
+        let log_collector = LogCollector::default();
+        let compute_meter = Rc::new(RefCell::new(ComputeMeter::new(0)));
+        let invoke_context = MessageInvokeContext {
+            message_processor: &message_processor,
+            log_collector: &log_collector,
+            compute_meter: &compute_meter,
+            loader_accounts: &loader_accounts,
+            pre_accounts: &pre_accounts,
+            depth: 0,
+        };
+        let mut keyed_accounts: Vec<_> = accounts
+            .iter_mut()
+            .map(|(key, account)| KeyedAccount::new(key, *key == from_pubkey, account))
+            .collect();
+
+        let instruction = Instruction::new(
+            callee_program_id,
+            &50u64,
+            vec![
+                AccountMeta::new(from_pubkey, true),
+                AccountMeta::new(to_pubkey, false),
+            ],
+        );
+        assert_eq!(
+            invoke_context.invoke(&caller_program_id, &instruction, &mut keyed_accounts),
+            Ok(())
+        );
+        assert_eq!(keyed_accounts[0].account.lamports, 50);
+        assert_eq!(keyed_accounts[1].account.lamports, 50);
+
+        // the callee can't be granted privileges the caller doesn't hold
+        let mut escalated_instruction = instruction.clone();
+        escalated_instruction.accounts[1].is_signer = true;
+        assert_eq!(
+            invoke_context.invoke(
+                &caller_program_id,
+                &escalated_instruction,
+                &mut keyed_accounts
+            ),
+            Err(InstructionError::PrivilegeEscalation)
+        );
+
+        // every account the instruction references must be one of the caller's
+        let mut unknown_instruction = instruction.clone();
+        unknown_instruction.accounts[1].pubkey = Pubkey::new_rand();
+        assert_eq!(
+            invoke_context.invoke(
+                &caller_program_id,
+                &unknown_instruction,
+                &mut keyed_accounts
+            ),
+            Err(InstructionError::InvalidArgument)
+        );
+
+        let deep_invoke_context = MessageInvokeContext {
+            depth: MAX_INVOKE_DEPTH,
+            ..invoke_context
+        };
+        assert_eq!(
+            deep_invoke_context.invoke(&caller_program_id, &instruction, &mut keyed_accounts),
+            Err(InstructionError::CallDepth)
+        );
+
+        // the caller's own changes are verified before the callee gets the accounts
+        keyed_accounts[0].account.lamports -= 1;
+        keyed_accounts[1].account.lamports += 1;
+        assert_eq!(
+            invoke_context.invoke(&caller_program_id, &instruction, &mut keyed_accounts),
+            Err(InstructionError::ExternalAccountLamportSpend)
+        );
+    }
+
     #[test]
     fn test_get_loader_instruction_data() {
         // First ensure the ix_data is unaffected if not invoking via a loader.
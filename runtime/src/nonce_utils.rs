@@ -0,0 +1,132 @@
+//! helpers for transactions that use a durable nonce in place of a recent blockhash
+use solana_sdk::account::Account;
+use solana_sdk::hash::Hash;
+use solana_sdk::instruction::CompiledInstruction;
+use solana_sdk::nonce_state::NonceState;
+use solana_sdk::pubkey::Pubkey;
+use solana_sdk::system_instruction::SystemInstruction;
+use solana_sdk::system_program;
+use solana_sdk::transaction::Transaction;
+
+/// Returns the transaction's first instruction if it advances a nonce
+pub fn transaction_uses_durable_nonce(tx: &Transaction) -> Option<&CompiledInstruction> {
+    let message = tx.message();
+    message.instructions.get(0).filter(|instruction| {
+        message
+            .account_keys
+            .get(instruction.program_id_index as usize)
+            .map_or(false, system_program::check_id)
+            && match bincode::deserialize(&instruction.data) {
+                Ok(SystemInstruction::AdvanceNonce) => true,
+                _ => false,
+            }
+    })
+}
+
+/// The nonce account an AdvanceNonce instruction operates on
+pub fn get_nonce_pubkey_from_instruction<'a>(
+    instruction: &CompiledInstruction,
+    tx: &'a Transaction,
+) -> Option<&'a Pubkey> {
+    instruction
+        .accounts
+        .get(0)
+        .and_then(|index| tx.message().account_keys.get(*index as usize))
+}
+
+/// True if `account` is an initialized nonce account whose stored nonce is `hash`
+pub fn verify_nonce(account: &Account, hash: &Hash) -> bool {
+    if !system_program::check_id(&account.owner) {
+        return false;
+    }
+    match NonceState::from(account) {
+        Some(NonceState::Initialized(_meta, ref nonce)) => nonce == hash,
+        _ => false,
+    }
+}
+
+/// Store `nonce` in `account` if it is an initialized nonce account, keeping its authority
+pub fn advance_nonce_account(account: &mut Account, nonce: &Hash) -> bool {
+    if !system_program::check_id(&account.owner) {
+        return false;
+    }
+    match NonceState::from(account) {
+        Some(NonceState::Initialized(meta, _)) => account
+            .serialize_data(&NonceState::Initialized(meta, *nonce))
+            .is_ok(),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::hash::hash;
+    use solana_sdk::nonce_state::{self, Meta};
+    use solana_sdk::system_instruction;
+
+    #[test]
+    fn test_transaction_uses_durable_nonce() {
+        let from_pubkey = Pubkey::new_rand();
+        let nonce_pubkey = Pubkey::new_rand();
+        let tx = Transaction::new_unsigned_instructions(vec![
+            system_instruction::advance_nonce(&nonce_pubkey, &from_pubkey),
+            system_instruction::transfer(&from_pubkey, &nonce_pubkey, 42),
+        ]);
+        let instruction = transaction_uses_durable_nonce(&tx).unwrap();
+        assert_eq!(
+            get_nonce_pubkey_from_instruction(instruction, &tx),
+            Some(&nonce_pubkey)
+        );
+
+        // the nonce must be advanced by the first instruction
+        let tx = Transaction::new_unsigned_instructions(vec![
+            system_instruction::transfer(&from_pubkey, &nonce_pubkey, 42),
+            system_instruction::advance_nonce(&nonce_pubkey, &from_pubkey),
+        ]);
+        assert!(transaction_uses_durable_nonce(&tx).is_none());
+
+        let tx = Transaction::new_unsigned_instructions(vec![system_instruction::transfer(
+            &from_pubkey,
+            &nonce_pubkey,
+            42,
+        )]);
+        assert!(transaction_uses_durable_nonce(&tx).is_none());
+    }
+
+    #[test]
+    fn test_verify_nonce() {
+        let stored_nonce = hash(&[1]);
+        let mut account = nonce_state::create_account(42);
+        assert!(!verify_nonce(&account, &stored_nonce));
+
+        let state = NonceState::Initialized(Meta::new(&Pubkey::new_rand()), stored_nonce);
+        account.serialize_data(&state).unwrap();
+        assert!(verify_nonce(&account, &stored_nonce));
+        assert!(!verify_nonce(&account, &hash(&[2])));
+
+        account.owner = Pubkey::new_rand();
+        assert!(!verify_nonce(&account, &stored_nonce));
+    }
+
+    #[test]
+    fn test_advance_nonce_account() {
+        let stored_nonce = hash(&[1]);
+        let new_nonce = hash(&[2]);
+        let mut account = nonce_state::create_account(42);
+        assert!(!advance_nonce_account(&mut account, &new_nonce));
+
+        let meta = Meta::new(&Pubkey::new_rand());
+        account
+            .serialize_data(&NonceState::Initialized(meta, stored_nonce))
+            .unwrap();
+        assert!(advance_nonce_account(&mut account, &new_nonce));
+        assert_eq!(
+            NonceState::from(&account),
+            Some(NonceState::Initialized(meta, new_nonce))
+        );
+
+        account.owner = Pubkey::new_rand();
+        assert!(!advance_nonce_account(&mut account, &stored_nonce));
+    }
+}
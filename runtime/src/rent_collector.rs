@@ -32,11 +32,16 @@ impl RentCollector {
         }
     }
     // updates this account's lamports and status and returns
-    //  the account rent collected, if any
+    //  the account rent collected, if any.  An account that can't
+    //  pay the rent due forfeits all its lamports.  It keeps its owner
+    //  and data, so that for the rest of the transaction it can't pass
+    //  for an unused system account, but storing it drained leaves a
+    //  zero-lamport version that hides it from loads until cleaning
+    //  removes it from the index
     //
-    pub fn update(&self, mut account: Account) -> Option<(Account, u64)> {
+    pub fn update(&self, mut account: Account) -> (Account, u64) {
         if account.data.is_empty() || account.rent_epoch > self.epoch {
-            Some((account, 0))
+            (account, 0)
         } else {
             let slots_elapsed: u64 = (account.rent_epoch..=self.epoch)
                 .map(|epoch| self.epoch_schedule.get_slots_in_epoch(epoch + 1))
@@ -52,14 +57,57 @@ impl RentCollector {
                 if account.lamports > rent_due {
                     account.rent_epoch = self.epoch + 1;
                     account.lamports -= rent_due;
-                    Some((account, rent_due))
+                    (account, rent_due)
                 } else {
-                    None
+                    let rent_collected = account.lamports;
+                    account.rent_epoch = self.epoch + 1;
+                    account.lamports = 0;
+                    (account, rent_collected)
                 }
             } else {
                 // maybe collect rent later, leave account alone
-                Some((account, 0))
+                (account, 0)
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::pubkey::Pubkey;
+
+    #[test]
+    fn test_update() {
+        let epoch_schedule = EpochSchedule::new(32, 32, false);
+        let rent = Rent {
+            lamports_per_byte_year: 1,
+            exemption_threshold: 3.0,
+            ..Rent::default()
+        };
+        let rent_collector = RentCollector::new(1, &epoch_schedule, 32.0, &rent);
+        let owner = Pubkey::new_rand();
+
+        // exempt accounts pay nothing
+        let account = Account::new(15, 5, &owner);
+        let (updated, rent_collected) = rent_collector.update(account);
+        assert_eq!(rent_collected, 0);
+        assert_eq!(updated.lamports, 15);
+
+        // rent is due for epochs 0 and 1, two years' worth
+        let account = Account::new(11, 4, &owner);
+        let (updated, rent_collected) = rent_collector.update(account);
+        assert_eq!(rent_collected, 8);
+        assert_eq!(updated.lamports, 3);
+        assert_eq!(updated.rent_epoch, 2);
+
+        // an account that can't pay forfeits its lamports, but is still its owner's
+        let account = Account::new(7, 4, &owner);
+        let (updated, rent_collected) = rent_collector.update(account);
+        assert_eq!(rent_collected, 7);
+        assert_eq!(updated.lamports, 0);
+        assert_eq!(updated.owner, owner);
+        assert_eq!(updated.data, vec![0; 4]);
+        assert_eq!(updated.rent_epoch, 2);
+    }
+}
@@ -1,10 +1,12 @@
 use log::*;
 use solana_sdk::account::KeyedAccount;
 use solana_sdk::instruction::InstructionError;
+use solana_sdk::nonce_state::NonceAccount;
 use solana_sdk::pubkey::Pubkey;
 use solana_sdk::system_instruction::{SystemError, SystemInstruction};
 use solana_sdk::system_program;
 use solana_sdk::sysvar;
+use std::collections::HashSet;
 
 const FROM_ACCOUNT_INDEX: usize = 0;
 const TO_ACCOUNT_INDEX: usize = 1;
@@ -64,6 +66,52 @@ fn create_system_account(
     Ok(())
 }
 
+fn verify_address_with_seed(
+    address: &Pubkey,
+    base: &Pubkey,
+    seed: &str,
+    program_id: &Pubkey,
+) -> Result<(), SystemError> {
+    let expected = Pubkey::create_with_seed(base, seed, program_id)
+        .map_err(|_| SystemError::MaxSeedLengthExceeded)?;
+    if *address != expected {
+        debug!(
+            "address {} does not match derived address {}",
+            address, expected
+        );
+        Err(SystemError::AddressWithSeedMismatch)?;
+    }
+    Ok(())
+}
+
+fn allocate_account(
+    keyed_accounts: &mut [KeyedAccount],
+    space: u64,
+    program_id: &Pubkey,
+) -> Result<(), SystemError> {
+    if !keyed_accounts[FROM_ACCOUNT_INDEX].account.data.is_empty()
+        || !system_program::check_id(&keyed_accounts[FROM_ACCOUNT_INDEX].account.owner)
+    {
+        debug!(
+            "Allocate: invalid argument; account {} already in use",
+            keyed_accounts[FROM_ACCOUNT_INDEX].unsigned_key()
+        );
+        Err(SystemError::AccountAlreadyInUse)?;
+    }
+
+    if sysvar::check_id(&program_id) {
+        debug!(
+            "Allocate: invalid argument; program id {} invalid",
+            program_id
+        );
+        Err(SystemError::InvalidProgramId)?;
+    }
+
+    keyed_accounts[FROM_ACCOUNT_INDEX].account.owner = *program_id;
+    keyed_accounts[FROM_ACCOUNT_INDEX].account.data = vec![0; space as usize];
+    Ok(())
+}
+
 fn assign_account_to_program(
     keyed_accounts: &mut [KeyedAccount],
     program_id: &Pubkey,
@@ -96,27 +144,130 @@ pub fn process_instruction(
         trace!("process_instruction: {:?}", instruction);
         trace!("keyed_accounts: {:?}", keyed_accounts);
 
-        // All system instructions require that accounts_keys[0] be a signer
-        if keyed_accounts[FROM_ACCOUNT_INDEX].signer_key().is_none() {
-            debug!("account[from] is unsigned");
-            Err(InstructionError::MissingRequiredSignature)?;
+        // All system instructions other than the nonce instructions require that
+        //  accounts_keys[0] be a signer, nonce accounts are signed for by their authority
+        match instruction {
+            SystemInstruction::CreateAccount { .. }
+            | SystemInstruction::CreateAccountWithSeed { .. }
+            | SystemInstruction::Assign { .. }
+            | SystemInstruction::Transfer { .. } => {
+                if keyed_accounts[FROM_ACCOUNT_INDEX].signer_key().is_none() {
+                    debug!("account[from] is unsigned");
+                    Err(InstructionError::MissingRequiredSignature)?;
+                }
+            }
+            _ => (),
         }
 
+        let signers: HashSet<Pubkey> = keyed_accounts
+            .iter()
+            .filter_map(|keyed_account| keyed_account.signer_key())
+            .cloned()
+            .collect();
+
         match instruction {
             SystemInstruction::CreateAccount {
                 lamports,
                 space,
                 program_id,
-            } => create_system_account(keyed_accounts, lamports, space, &program_id),
+            } => create_system_account(keyed_accounts, lamports, space, &program_id)
+                .map_err(|e| InstructionError::CustomError(e as u32)),
+            SystemInstruction::CreateAccountWithSeed {
+                base,
+                seed,
+                lamports,
+                space,
+                program_id,
+            } => {
+                if !signers.contains(&base) {
+                    debug!("base {} is unsigned", base);
+                    Err(InstructionError::MissingRequiredSignature)?;
+                }
+                verify_address_with_seed(
+                    keyed_accounts[TO_ACCOUNT_INDEX].unsigned_key(),
+                    &base,
+                    &seed,
+                    &program_id,
+                )
+                .and_then(|_| create_system_account(keyed_accounts, lamports, space, &program_id))
+                .map_err(|e| InstructionError::CustomError(e as u32))
+            }
+            SystemInstruction::AllocateWithSeed {
+                base,
+                seed,
+                space,
+                program_id,
+            } => {
+                if !signers.contains(&base) {
+                    debug!("base {} is unsigned", base);
+                    Err(InstructionError::MissingRequiredSignature)?;
+                }
+                verify_address_with_seed(
+                    keyed_accounts[FROM_ACCOUNT_INDEX].unsigned_key(),
+                    &base,
+                    &seed,
+                    &program_id,
+                )
+                .and_then(|_| allocate_account(keyed_accounts, space, &program_id))
+                .map_err(|e| InstructionError::CustomError(e as u32))
+            }
             SystemInstruction::Assign { program_id } => {
                 if !system_program::check_id(&keyed_accounts[FROM_ACCOUNT_INDEX].account.owner) {
                     Err(InstructionError::IncorrectProgramId)?;
                 }
+                if !keyed_accounts[FROM_ACCOUNT_INDEX].account.data.is_empty() {
+                    debug!("Assign: account[from] must not carry data");
+                    Err(InstructionError::InvalidArgument)?;
+                }
                 assign_account_to_program(keyed_accounts, &program_id)
+                    .map_err(|e| InstructionError::CustomError(e as u32))
+            }
+            SystemInstruction::Transfer { lamports } => {
+                // Accounts with data, such as nonce accounts, are debited by their own instructions
+                if !keyed_accounts[FROM_ACCOUNT_INDEX].account.data.is_empty() {
+                    debug!("Transfer: account[from] must not carry data");
+                    Err(InstructionError::InvalidArgument)?;
+                }
+                transfer_lamports(keyed_accounts, lamports)
+                    .map_err(|e| InstructionError::CustomError(e as u32))
+            }
+            SystemInstruction::AdvanceNonce => {
+                if keyed_accounts.len() < 2 {
+                    Err(InstructionError::InvalidInstructionData)?;
+                }
+                let (me, rest) = keyed_accounts.split_at_mut(1);
+                me[0].nonce_advance(
+                    &sysvar::recent_blockhashes::from_keyed_account(&rest[0])?,
+                    &signers,
+                )
+            }
+            SystemInstruction::WithdrawNonce { lamports } => {
+                if keyed_accounts.len() < 3 {
+                    Err(InstructionError::InvalidInstructionData)?;
+                }
+                let (me, rest) = keyed_accounts.split_at_mut(1);
+                let (to, rest) = rest.split_at_mut(1);
+                me[0].nonce_withdraw(
+                    lamports,
+                    &mut to[0],
+                    &sysvar::recent_blockhashes::from_keyed_account(&rest[0])?,
+                    &signers,
+                )
+            }
+            SystemInstruction::InitializeNonce { nonce_authority } => {
+                if keyed_accounts.len() < 2 {
+                    Err(InstructionError::InvalidInstructionData)?;
+                }
+                let (me, rest) = keyed_accounts.split_at_mut(1);
+                me[0].nonce_initialize(
+                    &nonce_authority,
+                    &sysvar::recent_blockhashes::from_keyed_account(&rest[0])?,
+                )
+            }
+            SystemInstruction::AuthorizeNonce { nonce_authority } => {
+                keyed_accounts[0].nonce_authorize(&nonce_authority, &signers)
             }
-            SystemInstruction::Transfer { lamports } => transfer_lamports(keyed_accounts, lamports),
         }
-        .map_err(|e| InstructionError::CustomError(e as u32))
     } else {
         debug!("Invalid instruction data: {:?}", data);
         Err(InstructionError::InvalidInstructionData)
@@ -132,8 +283,12 @@ mod tests {
     use solana_sdk::account::Account;
     use solana_sdk::client::SyncClient;
     use solana_sdk::genesis_block::create_genesis_block;
+    use solana_sdk::hash::hash;
     use solana_sdk::instruction::{AccountMeta, Instruction, InstructionError};
+    use solana_sdk::message::Message;
+    use solana_sdk::nonce_state::{self, Meta, NonceState};
     use solana_sdk::signature::{Keypair, KeypairUtil};
+    use solana_sdk::system_instruction;
     use solana_sdk::system_program;
     use solana_sdk::transaction::TransactionError;
 
@@ -278,6 +433,140 @@ mod tests {
         assert_eq!(result, Err(SystemError::SourceNotSystemAccount));
     }
 
+    #[test]
+    fn test_create_account_with_seed() {
+        let (genesis_block, mint_keypair) = create_genesis_block(100);
+        let mint_pubkey = mint_keypair.pubkey();
+        let base_keypair = Keypair::new();
+        let base = base_keypair.pubkey();
+        let program_id = Pubkey::new(&[9; 32]);
+        let address = Pubkey::create_with_seed(&base, "seed", &program_id).unwrap();
+
+        let bank_client = BankClient::new(Bank::new(&genesis_block));
+
+        // the base must sign for the derived address
+        let mut instruction = system_instruction::create_account_with_seed(
+            &mint_pubkey,
+            &address,
+            &base,
+            "seed",
+            50,
+            2,
+            &program_id,
+        );
+        instruction.accounts[2].is_signer = false;
+        assert_eq!(
+            bank_client
+                .send_instruction(&mint_keypair, instruction)
+                .unwrap_err()
+                .unwrap(),
+            TransactionError::InstructionError(0, InstructionError::MissingRequiredSignature)
+        );
+
+        // the created account must be the one derived from the seed
+        let instruction = system_instruction::create_account_with_seed(
+            &mint_pubkey,
+            &Pubkey::new_rand(),
+            &base,
+            "seed",
+            50,
+            2,
+            &program_id,
+        );
+        let message = Message::new(vec![instruction]);
+        assert_eq!(
+            bank_client
+                .send_message(&[&mint_keypair, &base_keypair], message)
+                .unwrap_err()
+                .unwrap(),
+            TransactionError::InstructionError(
+                0,
+                InstructionError::CustomError(SystemError::AddressWithSeedMismatch as u32)
+            )
+        );
+
+        let instruction = system_instruction::create_account_with_seed(
+            &mint_pubkey,
+            &address,
+            &base,
+            "seed",
+            50,
+            2,
+            &program_id,
+        );
+        let message = Message::new(vec![instruction]);
+        bank_client
+            .send_message(&[&mint_keypair, &base_keypair], message)
+            .unwrap();
+        assert_eq!(bank_client.get_balance(&address).unwrap(), 50);
+        let account = bank_client.get_account(&address).unwrap().unwrap();
+        assert_eq!(account.owner, program_id);
+        assert_eq!(account.data, vec![0, 0]);
+    }
+
+    #[test]
+    fn test_allocate_with_seed() {
+        let base = Pubkey::new_rand();
+        let program_id = Pubkey::new(&[9; 32]);
+        let address = Pubkey::create_with_seed(&base, "seed", &program_id).unwrap();
+        let mut account = Account::new(100, 0, &system_program::id());
+        let mut base_account = Account::default();
+
+        let data = serialize(&SystemInstruction::AllocateWithSeed {
+            base,
+            seed: "seed".to_string(),
+            space: 2,
+            program_id,
+        })
+        .unwrap();
+        assert_eq!(
+            process_instruction(
+                &system_program::id(),
+                &mut [
+                    KeyedAccount::new(&address, false, &mut account),
+                    KeyedAccount::new(&base, false, &mut base_account),
+                ],
+                &data,
+            ),
+            Err(InstructionError::MissingRequiredSignature)
+        );
+
+        let other_address = Pubkey::create_with_seed(&base, "other", &program_id).unwrap();
+        assert_eq!(
+            process_instruction(
+                &system_program::id(),
+                &mut [
+                    KeyedAccount::new(&other_address, false, &mut account),
+                    KeyedAccount::new(&base, true, &mut base_account),
+                ],
+                &data,
+            ),
+            Err(InstructionError::CustomError(
+                SystemError::AddressWithSeedMismatch as u32
+            ))
+        );
+
+        process_instruction(
+            &system_program::id(),
+            &mut [
+                KeyedAccount::new(&address, false, &mut account),
+                KeyedAccount::new(&base, true, &mut base_account),
+            ],
+            &data,
+        )
+        .unwrap();
+        assert_eq!(account.owner, program_id);
+        assert_eq!(account.data, vec![0, 0]);
+        assert_eq!(account.lamports, 100);
+
+        // can't allocate an account that's already in use
+        let mut keyed_accounts = [KeyedAccount::new(&address, false, &mut account)];
+        assert_eq!(
+            allocate_account(&mut keyed_accounts, 2, &program_id),
+            Err(SystemError::AccountAlreadyInUse)
+        );
+    }
+
     #[test]
     fn test_assign_account_to_program() {
         let new_program_owner = Pubkey::new(&[9; 32]);
@@ -299,6 +588,13 @@ mod tests {
         let result = process_instruction(&system_program::id(), &mut keyed_accounts, &data);
         assert_eq!(result, Err(InstructionError::IncorrectProgramId));
         assert_eq!(from_account.owner, new_program_owner);
+
+        // Attempt to assign a system account that carries data, such as a nonce account
+        let mut nonce_account = nonce_state::create_account(100);
+        let mut keyed_accounts = [KeyedAccount::new(&from, true, &mut nonce_account)];
+        let result = process_instruction(&system_program::id(), &mut keyed_accounts, &data);
+        assert_eq!(result, Err(InstructionError::InvalidArgument));
+        assert_eq!(nonce_account.owner, system_program::id());
     }
 
     #[test]
@@ -326,6 +622,104 @@ mod tests {
         assert_eq!(result, Err(SystemError::ResultWithNegativeLamports));
         assert_eq!(from_account.lamports, 50);
         assert_eq!(to_account.lamports, 51);
+
+        // Attempt to transfer out of an account that carries data, such as a nonce account
+        let mut nonce_account = nonce_state::create_account(100);
+        let mut keyed_accounts = [
+            KeyedAccount::new(&from, true, &mut nonce_account),
+            KeyedAccount::new_credit_only(&to, false, &mut to_account),
+        ];
+        let data = serialize(&SystemInstruction::Transfer { lamports: 50 }).unwrap();
+        let result = process_instruction(&system_program::id(), &mut keyed_accounts, &data);
+        assert_eq!(result, Err(InstructionError::InvalidArgument));
+        assert_eq!(nonce_account.lamports, 100);
+        assert_eq!(to_account.lamports, 51);
+    }
+
+    #[test]
+    fn test_process_nonce_instructions() {
+        let nonce = Pubkey::new_rand();
+        let mut nonce_account = nonce_state::create_account(100);
+        let authority = Pubkey::new_rand();
+        let mut authority_account = Account::default();
+        let recent_blockhashes_id = sysvar::recent_blockhashes::id();
+        let mut recent_blockhashes_account =
+            sysvar::recent_blockhashes::create_account(1, &[hash(&[0])]);
+
+        let data = serialize(&SystemInstruction::InitializeNonce {
+            nonce_authority: authority,
+        })
+        .unwrap();
+        // InitializeNonce needs the RecentBlockhashes sysvar
+        assert_eq!(
+            process_instruction(
+                &system_program::id(),
+                &mut [KeyedAccount::new(&nonce, false, &mut nonce_account)],
+                &data,
+            ),
+            Err(InstructionError::InvalidInstructionData)
+        );
+        assert_eq!(
+            process_instruction(
+                &system_program::id(),
+                &mut [
+                    KeyedAccount::new(&nonce, false, &mut nonce_account),
+                    KeyedAccount::new(&recent_blockhashes_id, false, &mut authority_account),
+                ],
+                &data,
+            ),
+            Err(InstructionError::InvalidArgument)
+        );
+        process_instruction(
+            &system_program::id(),
+            &mut [
+                KeyedAccount::new(&nonce, false, &mut nonce_account),
+                KeyedAccount::new(
+                    &recent_blockhashes_id,
+                    false,
+                    &mut recent_blockhashes_account,
+                ),
+            ],
+            &data,
+        )
+        .unwrap();
+
+        // AdvanceNonce is signed for by the nonce authority, not the nonce account
+        recent_blockhashes_account = sysvar::recent_blockhashes::create_account(1, &[hash(&[1])]);
+        let data = serialize(&SystemInstruction::AdvanceNonce).unwrap();
+        assert_eq!(
+            process_instruction(
+                &system_program::id(),
+                &mut [
+                    KeyedAccount::new(&nonce, true, &mut nonce_account),
+                    KeyedAccount::new(
+                        &recent_blockhashes_id,
+                        false,
+                        &mut recent_blockhashes_account
+                    ),
+                ],
+                &data,
+            ),
+            Err(InstructionError::MissingRequiredSignature)
+        );
+        process_instruction(
+            &system_program::id(),
+            &mut [
+                KeyedAccount::new(&nonce, false, &mut nonce_account),
+                KeyedAccount::new(
+                    &recent_blockhashes_id,
+                    false,
+                    &mut recent_blockhashes_account,
+                ),
+                KeyedAccount::new(&authority, true, &mut authority_account),
+            ],
+            &data,
+        )
+        .unwrap();
+        assert_eq!(
+            NonceState::from(&nonce_account),
+            Some(NonceState::Initialized(Meta::new(&authority), hash(&[1])))
+        );
     }
 
     #[test]
@@ -2,6 +2,7 @@
 
 use crate::pubkey::Pubkey;
 use crate::short_vec;
+#[cfg(feature = "kitchen_sink")]
 use crate::system_instruction::SystemError;
 use bincode::serialize;
 use serde::Serialize;
@@ -74,15 +75,26 @@ pub enum InstructionError {
     /// NOTE: u64 requires special serialization to avoid the loss of precision in JS clients and
     /// so is not used for now.
     CustomError(u32),
+
+    /// An instruction invoked by a program requested a signer or debitable privilege that
+    /// the program itself was not granted for the account
+    PrivilegeEscalation,
+
+    /// Programs invoked other programs more deeply than the runtime allows
+    CallDepth,
+
+    /// The transaction ran out of compute units
+    ComputationalBudgetExceeded,
 }
 
+#[cfg(feature = "kitchen_sink")]
 impl InstructionError {
     pub fn new_result_with_negative_lamports() -> Self {
         InstructionError::CustomError(SystemError::ResultWithNegativeLamports as u32)
     }
 }
 
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone)]
 pub struct Instruction {
     /// Pubkey of the instruction processor that executes this instruction
     pub program_id: Pubkey,
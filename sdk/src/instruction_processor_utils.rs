@@ -1,7 +1,9 @@
 use crate::account::KeyedAccount;
-use crate::instruction::InstructionError;
+use crate::instruction::{Instruction, InstructionError};
 use crate::pubkey::Pubkey;
 use num_traits::{FromPrimitive, ToPrimitive};
+use std::cell::RefCell;
+use std::rc::Rc;
 
 // All native programs export a symbol named process()
 pub const ENTRYPOINT: &str = "process";
@@ -11,10 +13,121 @@ pub type Entrypoint = unsafe extern "C" fn(
     program_id: &Pubkey,
     keyed_accounts: &mut [KeyedAccount],
     data: &[u8],
+    invoke_context: &dyn InvokeContext,
 ) -> Result<(), InstructionError>;
 
+/// Receives the messages a program logs while processing an instruction, such as those
+/// written by a BPF program via `sol_log`
+pub trait Logger {
+    fn log(&self, message: &str);
+}
+
+thread_local! {
+    /// Messages logged via `log` by the native program running on this thread, if any
+    static LOG_MESSAGES: RefCell<Option<Vec<String>>> = RefCell::new(None);
+}
+
+/// Log `message` from a native program.  While the program runs under `with_logger` the
+/// message is recorded with the transaction's log messages, otherwise it is printed
+pub fn log(message: &str) {
+    LOG_MESSAGES.with(|log_messages| match log_messages.borrow_mut().as_mut() {
+        Some(log_messages) => log_messages.push(message.to_string()),
+        None => println!("{}", message),
+    });
+}
+
+/// Run `f`, forwarding the messages it logs via `log` to `logger`
+pub fn with_logger<L: Logger + ?Sized, T>(logger: &L, f: impl FnOnce() -> T) -> T {
+    let outer = LOG_MESSAGES.with(|log_messages| log_messages.replace(Some(vec![])));
+    let result = f();
+    let log_messages = LOG_MESSAGES.with(|log_messages| log_messages.replace(outer));
+    for message in log_messages.unwrap_or_default() {
+        logger.log(&message);
+    }
+    result
+}
+
+/// The compute units a transaction may spend, and what each unit of program work costs
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ComputeBudget {
+    /// Units available to each transaction
+    pub max_units: u64,
+    /// Units charged per BPF instruction executed, must be non-zero
+    pub instruction_units: u64,
+    /// Units charged per call to `sol_log`
+    pub log_units: u64,
+    /// Units charged per call to `sol_log_64`
+    pub log_64_units: u64,
+    /// Units charged per call to `sol_alloc_free`
+    pub alloc_free_units: u64,
+    /// Units charged per call to `sol_invoke`, on top of what the invoked program spends
+    pub invoke_units: u64,
+}
+
+impl Default for ComputeBudget {
+    fn default() -> Self {
+        Self {
+            max_units: 200_000,
+            instruction_units: 1,
+            log_units: 100,
+            log_64_units: 100,
+            alloc_free_units: 10,
+            invoke_units: 1_000,
+        }
+    }
+}
+
+/// Tracks the compute units left to a transaction as its programs run
+#[derive(Debug)]
+pub struct ComputeMeter {
+    remaining: u64,
+}
+
+impl ComputeMeter {
+    pub fn new(units: u64) -> Self {
+        Self { remaining: units }
+    }
+
+    /// Charge `units`, failing once the budget is exhausted, after which nothing remains
+    pub fn consume(&mut self, units: u64) -> Result<(), InstructionError> {
+        if units > self.remaining {
+            self.remaining = 0;
+            return Err(InstructionError::ComputationalBudgetExceeded);
+        }
+        self.remaining -= units;
+        Ok(())
+    }
+
+    pub fn get_remaining(&self) -> u64 {
+        self.remaining
+    }
+}
+
+/// Everything the runtime offers a loader while it runs a program: somewhere to send the
+/// program's log messages, a way to invoke other programs on the program's behalf, and the
+/// compute budget the program's work is charged against
+pub trait InvokeContext: Logger {
+    /// Process `instruction` for the program `caller_program_id`.  The accounts the
+    /// instruction references must be among the caller's `keyed_accounts`, and are passed to
+    /// the invoked program with at most the signer and debitable privileges the caller holds
+    fn invoke(
+        &self,
+        caller_program_id: &Pubkey,
+        instruction: &Instruction,
+        keyed_accounts: &mut [KeyedAccount],
+    ) -> Result<(), InstructionError>;
+
+    /// What program work costs
+    fn get_compute_budget(&self) -> ComputeBudget;
+
+    /// The compute units left to the transaction, shared by every program it invokes
+    fn get_compute_meter(&self) -> Rc<RefCell<ComputeMeter>>;
+}
+
 // Convenience macro to define the native program entrypoint.  Supply a fn to this macro that
-// conforms to the `Entrypoint` type signature.
+// conforms to the `Entrypoint` type signature, less the invoke context.  Messages the program
+// logs via `log` are forwarded to the invoke context.  Loaders that run programs which invoke
+// other programs should use `solana_loader_entrypoint!` instead.
 #[macro_export]
 macro_rules! solana_entrypoint(
     ($entrypoint:ident) => (
@@ -23,8 +136,28 @@ macro_rules! solana_entrypoint(
             program_id: &$crate::pubkey::Pubkey,
             keyed_accounts: &mut [$crate::account::KeyedAccount],
             data: &[u8],
+            invoke_context: &dyn $crate::instruction_processor_utils::InvokeContext,
+        ) -> Result<(), $crate::instruction::InstructionError> {
+            $crate::instruction_processor_utils::with_logger(invoke_context, || {
+                $entrypoint(program_id, keyed_accounts, data)
+            })
+        }
+    )
+);
+
+// Convenience macro to define the entrypoint of a native loader.  Supply a fn to this macro that
+// conforms to the `Entrypoint` type signature.
+#[macro_export]
+macro_rules! solana_loader_entrypoint(
+    ($entrypoint:ident) => (
+        #[no_mangle]
+        pub extern "C" fn process(
+            program_id: &$crate::pubkey::Pubkey,
+            keyed_accounts: &mut [$crate::account::KeyedAccount],
+            data: &[u8],
+            invoke_context: &dyn $crate::instruction_processor_utils::InvokeContext,
         ) -> Result<(), $crate::instruction::InstructionError> {
-            $entrypoint(program_id, keyed_accounts, data)
+            $entrypoint(program_id, keyed_accounts, data, invoke_context)
         }
     )
 );
@@ -72,4 +205,50 @@ mod tests {
         let option: Option<TestEnum> = TestEnum::decode_custom_error_to_enum(3);
         assert_eq!(option, None);
     }
+
+    #[derive(Default)]
+    struct TestLogger {
+        messages: RefCell<Vec<String>>,
+    }
+
+    impl Logger for TestLogger {
+        fn log(&self, message: &str) {
+            self.messages.borrow_mut().push(message.to_string());
+        }
+    }
+
+    #[test]
+    fn test_with_logger() {
+        let logger = TestLogger::default();
+        let nested_logger = TestLogger::default();
+        let result = with_logger(&logger, || {
+            log("first");
+            with_logger(&nested_logger, || log("nested"));
+            log("second");
+            42
+        });
+        assert_eq!(result, 42);
+        assert_eq!(*logger.messages.borrow(), vec!["first", "second"]);
+        assert_eq!(*nested_logger.messages.borrow(), vec!["nested"]);
+
+        // Outside of `with_logger` messages are only printed
+        log("printed");
+        assert_eq!(logger.messages.borrow().len(), 2);
+    }
+
+    #[test]
+    fn test_compute_meter() {
+        let mut compute_meter = ComputeMeter::new(10);
+        assert_eq!(compute_meter.consume(4), Ok(()));
+        assert_eq!(compute_meter.consume(6), Ok(()));
+        assert_eq!(compute_meter.get_remaining(), 0);
+        assert_eq!(compute_meter.consume(0), Ok(()));
+
+        let mut compute_meter = ComputeMeter::new(10);
+        assert_eq!(
+            compute_meter.consume(11),
+            Err(InstructionError::ComputationalBudgetExceeded)
+        );
+        assert_eq!(compute_meter.get_remaining(), 0);
+    }
 }
@@ -1,5 +1,7 @@
 pub mod clock;
+pub mod instruction;
 pub mod pubkey;
+pub mod short_vec;
 
 // On-chain program modules
 #[cfg(feature = "program")]
@@ -9,6 +11,8 @@ pub mod entrypoint;
 #[cfg(feature = "program")]
 pub mod log;
 #[cfg(feature = "program")]
+pub mod program;
+#[cfg(feature = "program")]
 pub mod program_test;
 
 // Kitchen sink modules
@@ -29,8 +33,6 @@ pub mod hash;
 #[cfg(feature = "kitchen_sink")]
 pub mod inflation;
 #[cfg(feature = "kitchen_sink")]
-pub mod instruction;
-#[cfg(feature = "kitchen_sink")]
 pub mod instruction_processor_utils;
 #[cfg(feature = "kitchen_sink")]
 pub mod loader_instruction;
@@ -39,6 +41,8 @@ pub mod message;
 #[cfg(feature = "kitchen_sink")]
 pub mod native_loader;
 #[cfg(feature = "kitchen_sink")]
+pub mod nonce_state;
+#[cfg(feature = "kitchen_sink")]
 pub mod packet;
 #[cfg(feature = "kitchen_sink")]
 pub mod poh_config;
@@ -47,8 +51,6 @@ pub mod rent;
 #[cfg(feature = "kitchen_sink")]
 pub mod rpc_port;
 #[cfg(feature = "kitchen_sink")]
-pub mod short_vec;
-#[cfg(feature = "kitchen_sink")]
 pub mod signature;
 #[cfg(feature = "kitchen_sink")]
 pub mod system_instruction;
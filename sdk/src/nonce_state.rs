@@ -0,0 +1,342 @@
+//! Durable transaction nonces
+//! * a nonce account is a system account whose data holds a `NonceState`
+//! * the stored nonce stands in for a recent blockhash, so transactions signed
+//!   against it do not expire until the nonce is advanced
+
+use crate::{
+    account::{Account, KeyedAccount},
+    account_utils::State,
+    hash::Hash,
+    instruction::InstructionError,
+    pubkey::Pubkey,
+    system_instruction::SystemError,
+    sysvar::recent_blockhashes::RecentBlockhashes,
+};
+use bincode::serialized_size;
+use std::collections::HashSet;
+
+#[derive(Debug, Default, Serialize, Deserialize, PartialEq, Clone, Copy)]
+pub struct Meta {
+    pub nonce_authority: Pubkey,
+}
+
+impl Meta {
+    pub fn new(nonce_authority: &Pubkey) -> Self {
+        Self {
+            nonce_authority: *nonce_authority,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Copy)]
+pub enum NonceState {
+    Uninitialized,
+    Initialized(Meta, Hash),
+}
+
+impl Default for NonceState {
+    fn default() -> Self {
+        NonceState::Uninitialized
+    }
+}
+
+impl NonceState {
+    pub fn size() -> usize {
+        serialized_size(&NonceState::Initialized(Meta::default(), Hash::default())).unwrap()
+            as usize
+    }
+
+    // utility function, used by Bank, tests
+    pub fn from(account: &Account) -> Option<NonceState> {
+        account.state().ok()
+    }
+}
+
+pub trait NonceAccount {
+    fn nonce_advance(
+        &mut self,
+        recent_blockhashes: &RecentBlockhashes,
+        signers: &HashSet<Pubkey>,
+    ) -> Result<(), InstructionError>;
+    fn nonce_withdraw(
+        &mut self,
+        lamports: u64,
+        to: &mut KeyedAccount,
+        recent_blockhashes: &RecentBlockhashes,
+        signers: &HashSet<Pubkey>,
+    ) -> Result<(), InstructionError>;
+    fn nonce_initialize(
+        &mut self,
+        nonce_authority: &Pubkey,
+        recent_blockhashes: &RecentBlockhashes,
+    ) -> Result<(), InstructionError>;
+    fn nonce_authorize(
+        &mut self,
+        nonce_authority: &Pubkey,
+        signers: &HashSet<Pubkey>,
+    ) -> Result<(), InstructionError>;
+}
+
+impl<'a> NonceAccount for KeyedAccount<'a> {
+    fn nonce_advance(
+        &mut self,
+        recent_blockhashes: &RecentBlockhashes,
+        signers: &HashSet<Pubkey>,
+    ) -> Result<(), InstructionError> {
+        if recent_blockhashes.is_empty() {
+            return Err(SystemError::NonceNoRecentBlockhashes.into());
+        }
+
+        if let NonceState::Initialized(meta, hash) = self.state()? {
+            if !signers.contains(&meta.nonce_authority) {
+                return Err(InstructionError::MissingRequiredSignature);
+            }
+            if hash == recent_blockhashes[0] {
+                return Err(SystemError::NonceBlockhashNotExpired.into());
+            }
+            self.set_state(&NonceState::Initialized(meta, recent_blockhashes[0]))
+        } else {
+            Err(InstructionError::InvalidAccountData)
+        }
+    }
+
+    fn nonce_withdraw(
+        &mut self,
+        lamports: u64,
+        to: &mut KeyedAccount,
+        recent_blockhashes: &RecentBlockhashes,
+        signers: &HashSet<Pubkey>,
+    ) -> Result<(), InstructionError> {
+        if lamports > self.account.lamports {
+            return Err(InstructionError::InsufficientFunds);
+        }
+
+        match self.state()? {
+            NonceState::Uninitialized => {
+                if !signers.contains(self.unsigned_key()) {
+                    return Err(InstructionError::MissingRequiredSignature);
+                }
+            }
+            NonceState::Initialized(meta, hash) => {
+                if !signers.contains(&meta.nonce_authority) {
+                    return Err(InstructionError::MissingRequiredSignature);
+                }
+                // closing the account must not let a transaction signed against the
+                //  current nonce land after the account is recreated
+                if lamports == self.account.lamports && recent_blockhashes.get(0) == Some(&hash) {
+                    return Err(SystemError::NonceBlockhashNotExpired.into());
+                }
+            }
+        }
+
+        self.account.lamports -= lamports;
+        to.account.lamports += lamports;
+        Ok(())
+    }
+
+    fn nonce_initialize(
+        &mut self,
+        nonce_authority: &Pubkey,
+        recent_blockhashes: &RecentBlockhashes,
+    ) -> Result<(), InstructionError> {
+        if recent_blockhashes.is_empty() {
+            return Err(SystemError::NonceNoRecentBlockhashes.into());
+        }
+
+        if let NonceState::Uninitialized = self.state()? {
+            self.set_state(&NonceState::Initialized(
+                Meta::new(nonce_authority),
+                recent_blockhashes[0],
+            ))
+        } else {
+            Err(InstructionError::InvalidAccountData)
+        }
+    }
+
+    fn nonce_authorize(
+        &mut self,
+        nonce_authority: &Pubkey,
+        signers: &HashSet<Pubkey>,
+    ) -> Result<(), InstructionError> {
+        if let NonceState::Initialized(meta, hash) = self.state()? {
+            if !signers.contains(&meta.nonce_authority) {
+                return Err(InstructionError::MissingRequiredSignature);
+            }
+            self.set_state(&NonceState::Initialized(Meta::new(nonce_authority), hash))
+        } else {
+            Err(InstructionError::InvalidAccountData)
+        }
+    }
+}
+
+pub fn create_account(lamports: u64) -> Account {
+    Account::new(lamports, NonceState::size(), &crate::system_program::id())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::hash::hash;
+
+    fn with_test_keyed_account<F>(lamports: u64, signer: bool, mut f: F)
+    where
+        F: FnMut(&mut KeyedAccount),
+    {
+        let pubkey = Pubkey::new_rand();
+        let mut account = create_account(lamports);
+        let mut keyed_account = KeyedAccount::new(&pubkey, signer, &mut account);
+        f(&mut keyed_account)
+    }
+
+    fn signers_of(keyed_accounts: &[&KeyedAccount]) -> HashSet<Pubkey> {
+        keyed_accounts
+            .iter()
+            .filter_map(|keyed_account| keyed_account.signer_key())
+            .cloned()
+            .collect()
+    }
+
+    fn recent_blockhashes(seed: u8) -> RecentBlockhashes {
+        RecentBlockhashes::new(&[hash(&[seed])])
+    }
+
+    #[test]
+    fn test_nonce_state_default() {
+        assert_eq!(NonceState::default(), NonceState::Uninitialized);
+        let account = create_account(42);
+        assert_eq!(NonceState::from(&account), Some(NonceState::Uninitialized));
+    }
+
+    #[test]
+    fn test_nonce_initialize_and_advance() {
+        with_test_keyed_account(42, true, |nonce_account| {
+            let authority = *nonce_account.unsigned_key();
+            let signers = signers_of(&[&*nonce_account]);
+
+            // can't advance an uninitialized nonce
+            assert_eq!(
+                nonce_account.nonce_advance(&recent_blockhashes(0), &signers),
+                Err(InstructionError::InvalidAccountData)
+            );
+
+            nonce_account
+                .nonce_initialize(&authority, &recent_blockhashes(0))
+                .unwrap();
+            assert_eq!(
+                NonceState::from(nonce_account.account),
+                Some(NonceState::Initialized(
+                    Meta::new(&authority),
+                    recent_blockhashes(0)[0]
+                ))
+            );
+
+            // can't initialize twice
+            assert_eq!(
+                nonce_account.nonce_initialize(&authority, &recent_blockhashes(1)),
+                Err(InstructionError::InvalidAccountData)
+            );
+
+            // the nonce must move past the stored value before it can be advanced
+            assert_eq!(
+                nonce_account.nonce_advance(&recent_blockhashes(0), &signers),
+                Err(SystemError::NonceBlockhashNotExpired.into())
+            );
+            assert_eq!(
+                nonce_account.nonce_advance(&RecentBlockhashes::default(), &signers),
+                Err(SystemError::NonceNoRecentBlockhashes.into())
+            );
+            nonce_account
+                .nonce_advance(&recent_blockhashes(1), &signers)
+                .unwrap();
+            assert_eq!(
+                NonceState::from(nonce_account.account),
+                Some(NonceState::Initialized(
+                    Meta::new(&authority),
+                    recent_blockhashes(1)[0]
+                ))
+            );
+
+            // only the authority may advance
+            assert_eq!(
+                nonce_account.nonce_advance(&recent_blockhashes(2), &HashSet::new()),
+                Err(InstructionError::MissingRequiredSignature)
+            );
+        });
+    }
+
+    #[test]
+    fn test_nonce_authorize() {
+        with_test_keyed_account(42, true, |nonce_account| {
+            let authority = *nonce_account.unsigned_key();
+            let signers = signers_of(&[&*nonce_account]);
+            let new_authority = Pubkey::new_rand();
+
+            assert_eq!(
+                nonce_account.nonce_authorize(&new_authority, &signers),
+                Err(InstructionError::InvalidAccountData)
+            );
+            nonce_account
+                .nonce_initialize(&authority, &recent_blockhashes(0))
+                .unwrap();
+            nonce_account
+                .nonce_authorize(&new_authority, &signers)
+                .unwrap();
+
+            // the old authority no longer controls the nonce
+            assert_eq!(
+                nonce_account.nonce_advance(&recent_blockhashes(1), &signers),
+                Err(InstructionError::MissingRequiredSignature)
+            );
+            let new_signers = [new_authority].iter().cloned().collect();
+            nonce_account
+                .nonce_advance(&recent_blockhashes(1), &new_signers)
+                .unwrap();
+        });
+    }
+
+    #[test]
+    fn test_nonce_withdraw() {
+        with_test_keyed_account(42, true, |nonce_account| {
+            let authority = *nonce_account.unsigned_key();
+            let signers = signers_of(&[&*nonce_account]);
+            let to = Pubkey::new_rand();
+            let mut to_account = Account::new(0, 0, &crate::system_program::id());
+            let mut to_keyed = KeyedAccount::new(&to, false, &mut to_account);
+
+            // uninitialized nonce accounts withdraw with the account's own signature
+            assert_eq!(
+                nonce_account.nonce_withdraw(
+                    1,
+                    &mut to_keyed,
+                    &recent_blockhashes(0),
+                    &HashSet::new()
+                ),
+                Err(InstructionError::MissingRequiredSignature)
+            );
+            nonce_account
+                .nonce_withdraw(1, &mut to_keyed, &recent_blockhashes(0), &signers)
+                .unwrap();
+            assert_eq!(nonce_account.account.lamports, 41);
+            assert_eq!(to_keyed.account.lamports, 1);
+
+            assert_eq!(
+                nonce_account.nonce_withdraw(42, &mut to_keyed, &recent_blockhashes(0), &signers),
+                Err(InstructionError::InsufficientFunds)
+            );
+
+            nonce_account
+                .nonce_initialize(&authority, &recent_blockhashes(0))
+                .unwrap();
+            // can't close the account while its nonce is the most recent blockhash
+            assert_eq!(
+                nonce_account.nonce_withdraw(41, &mut to_keyed, &recent_blockhashes(0), &signers),
+                Err(SystemError::NonceBlockhashNotExpired.into())
+            );
+            nonce_account
+                .nonce_withdraw(41, &mut to_keyed, &recent_blockhashes(1), &signers)
+                .unwrap();
+            assert_eq!(nonce_account.account.lamports, 0);
+            assert_eq!(to_keyed.account.lamports, 42);
+        });
+    }
+}
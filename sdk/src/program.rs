@@ -0,0 +1,33 @@
+//! @brief Solana Rust-based BPF program cross-program invocation
+
+use crate::{account_info::AccountInfo, instruction::Instruction, pubkey::Pubkey};
+
+/// Invoke another program
+///
+/// The accounts `instruction` references must be among `account_infos`, and may only be
+/// marked as signers or as debitable if they are so for the calling program.  Once the
+/// invoked program returns, its changes to the accounts are visible through `account_infos`.
+/// If the invoked program fails, the calling program is aborted with its error.
+pub fn invoke(instruction: &Instruction, account_infos: &[AccountInfo]) {
+    let instruction = bincode::serialize(instruction).unwrap();
+    let account_keys: Vec<Pubkey> = account_infos
+        .iter()
+        .map(|account_info| *account_info.key)
+        .collect();
+    unsafe {
+        sol_invoke_(
+            instruction.as_ptr(),
+            instruction.len() as u64,
+            account_keys.as_ptr(),
+            account_keys.len() as u64,
+        );
+    }
+}
+extern "C" {
+    fn sol_invoke_(
+        instruction_addr: *const u8,
+        instruction_len: u64,
+        account_keys_addr: *const Pubkey,
+        account_keys_len: u64,
+    ) -> u64;
+}
@@ -4,10 +4,38 @@
 pub unsafe fn sol_log_(message: *const u8, length: u64) {
     let slice = std::slice::from_raw_parts(message, length as usize);
     let string = std::str::from_utf8(&slice).unwrap();
-    std::println!("{}", string);
+    log(string);
 }
 
 #[no_mangle]
 pub fn sol_log_64_(arg1: u64, arg2: u64, arg3: u64, arg4: u64, arg5: u64) {
-    std::println!("{} {} {} {} {}", arg1, arg2, arg3, arg4, arg5);
+    log(&std::format!(
+        "{:#x}, {:#x}, {:#x}, {:#x}, {:#x}",
+        arg1,
+        arg2,
+        arg3,
+        arg4,
+        arg5
+    ));
+}
+
+// A program built natively and run by the runtime logs like any other native program
+#[cfg(feature = "kitchen_sink")]
+fn log(message: &str) {
+    crate::instruction_processor_utils::log(message);
+}
+
+#[cfg(not(feature = "kitchen_sink"))]
+fn log(message: &str) {
+    std::println!("{}", message);
+}
+
+#[no_mangle]
+pub fn sol_invoke_(
+    _instruction_addr: *const u8,
+    _instruction_len: u64,
+    _account_keys_addr: *const crate::pubkey::Pubkey,
+    _account_keys_len: u64,
+) -> u64 {
+    panic!("sol_invoke_ is only supported by the BPF loader");
 }
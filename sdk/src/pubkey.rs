@@ -6,6 +6,9 @@ use std::str::FromStr;
 
 pub use bs58;
 
+/// maximum length of the seed used to derive an address with `Pubkey::create_with_seed`
+pub const MAX_SEED_LEN: usize = 32;
+
 #[repr(transparent)]
 #[derive(Serialize, Deserialize, Clone, Copy, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
 pub struct Pubkey([u8; 32]);
@@ -24,6 +27,19 @@ impl fmt::Display for ParsePubkeyError {
 
 impl error::Error for ParsePubkeyError {}
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PubkeyError {
+    MaxSeedLengthExceeded,
+}
+
+impl fmt::Display for PubkeyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "PubkeyError: {:?}", self)
+    }
+}
+
+impl error::Error for PubkeyError {}
+
 impl FromStr for Pubkey {
     type Err = ParsePubkeyError;
 
@@ -52,6 +68,23 @@ impl Pubkey {
         Self::new(&rand::random::<[u8; 32]>())
     }
 
+    /// Derive an address from `base`, `seed` and the `program_id` that will own it.
+    /// Only `base` has to sign to create or allocate the derived account.
+    #[cfg(not(feature = "program"))]
+    pub fn create_with_seed(
+        base: &Pubkey,
+        seed: &str,
+        program_id: &Pubkey,
+    ) -> Result<Pubkey, PubkeyError> {
+        if seed.len() > MAX_SEED_LEN {
+            return Err(PubkeyError::MaxSeedLengthExceeded);
+        }
+
+        Ok(Pubkey::new(
+            crate::hash::hashv(&[base.as_ref(), seed.as_ref(), program_id.as_ref()]).as_ref(),
+        ))
+    }
+
     #[cfg(feature = "program")]
     pub fn log(&self) {
         use crate::log::sol_log_64;
@@ -177,6 +210,37 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_create_with_seed() {
+        let base = Pubkey::new_rand();
+        let program_id = Pubkey::new_rand();
+
+        let address = Pubkey::create_with_seed(&base, "seed", &program_id).unwrap();
+        assert_eq!(
+            Pubkey::create_with_seed(&base, "seed", &program_id),
+            Ok(address)
+        );
+        assert_ne!(
+            Pubkey::create_with_seed(&base, "seed2", &program_id),
+            Ok(address)
+        );
+        assert_ne!(
+            Pubkey::create_with_seed(&base, "seed", &Pubkey::new_rand()),
+            Ok(address)
+        );
+        assert_ne!(
+            Pubkey::create_with_seed(&Pubkey::new_rand(), "seed", &program_id),
+            Ok(address)
+        );
+
+        assert!(Pubkey::create_with_seed(&base, "", &program_id).is_ok());
+        assert!(Pubkey::create_with_seed(&base, &"x".repeat(MAX_SEED_LEN), &program_id).is_ok());
+        assert_eq!(
+            Pubkey::create_with_seed(&base, &"x".repeat(MAX_SEED_LEN + 1), &program_id),
+            Err(PubkeyError::MaxSeedLengthExceeded)
+        );
+    }
+
     #[test]
     fn test_read_write_pubkey() -> Result<(), Box<dyn error::Error>> {
         let filename = "test_pubkey.json";
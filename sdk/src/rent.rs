@@ -47,6 +47,13 @@ impl Rent {
         balance >= self.minimum_balance(data_len)
     }
 
+    /// split collected rent into the portion to distribute and the portion to burn
+    pub fn calculate_burn(&self, rent_collected: u64) -> (u64, u64) {
+        let unburned =
+            rent_collected * u64::from(std::u8::MAX - self.burn_percent) / u64::from(std::u8::MAX);
+        (unburned, rent_collected - unburned)
+    }
+
     /// rent due on account's data_len with balance
     pub fn due(&self, balance: u64, data_len: usize, years_elapsed: f64) -> (u64, bool) {
         if self.is_exempt(balance, data_len) {
@@ -85,6 +92,19 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_calculate_burn() {
+        let mut rent = Rent::default();
+        rent.burn_percent = 0;
+        assert_eq!(rent.calculate_burn(10), (10, 0));
+        rent.burn_percent = std::u8::MAX;
+        assert_eq!(rent.calculate_burn(10), (0, 10));
+        rent.burn_percent = DEFAULT_BURN_PERCENT;
+        let (unburned, burned) = rent.calculate_burn(1000);
+        assert_eq!(unburned + burned, 1000);
+        assert!(burned > 0 && unburned > 0);
+    }
+
     // uncomment me and make my eprintlns macros
     //    #[test]
     //    fn test_rent_model() {
@@ -1,16 +1,22 @@
 use crate::instruction::{AccountMeta, Instruction};
 use crate::instruction_processor_utils::DecodeError;
+use crate::nonce_state::NonceState;
 use crate::pubkey::Pubkey;
 use crate::system_program;
-use num_derive::FromPrimitive;
+use crate::sysvar::recent_blockhashes;
+use num_derive::{FromPrimitive, ToPrimitive};
 
-#[derive(Serialize, Debug, Clone, PartialEq, FromPrimitive)]
+#[derive(Serialize, Debug, Clone, PartialEq, FromPrimitive, ToPrimitive)]
 pub enum SystemError {
     AccountAlreadyInUse,
     ResultWithNegativeLamports,
     SourceNotSystemAccount,
     InvalidProgramId,
     InvalidAccountId,
+    NonceNoRecentBlockhashes,
+    NonceBlockhashNotExpired,
+    AddressWithSeedMismatch,
+    MaxSeedLengthExceeded,
 }
 
 impl<T> DecodeError<T> for SystemError {
@@ -46,6 +52,56 @@ pub enum SystemInstruction {
     /// * Transaction::keys[0] - source
     /// * Transaction::keys[1] - destination
     Transfer { lamports: u64 },
+    /// Consume a stored nonce, replacing it with the most recent blockhash
+    /// * Transaction::keys[0] - nonce account
+    /// * Transaction::keys[1] - RecentBlockhashes sysvar
+    /// * Transaction::keys[2] - nonce authority, if not the nonce account itself
+    AdvanceNonce,
+    /// Withdraw lamports from a nonce account
+    /// * Transaction::keys[0] - nonce account
+    /// * Transaction::keys[1] - destination
+    /// * Transaction::keys[2] - RecentBlockhashes sysvar
+    /// * Transaction::keys[3] - nonce authority, if not the nonce account itself
+    /// * lamports - number of lamports to withdraw, the account is closed if
+    ///   this is its entire balance
+    WithdrawNonce { lamports: u64 },
+    /// Drive the state of an Uninitialized nonce account to Initialized,
+    /// storing the most recent blockhash as its nonce
+    /// * Transaction::keys[0] - nonce account
+    /// * Transaction::keys[1] - RecentBlockhashes sysvar
+    /// * nonce_authority - the entity authorized to advance, withdraw from and
+    ///   re-authorize the nonce
+    InitializeNonce { nonce_authority: Pubkey },
+    /// Change the entity authorized to operate a nonce account
+    /// * Transaction::keys[0] - nonce account
+    /// * Transaction::keys[1] - current nonce authority, if not the nonce account itself
+    AuthorizeNonce { nonce_authority: Pubkey },
+    /// Create a new account at an address derived from a base pubkey and a seed
+    /// * Transaction::keys[0] - source
+    /// * Transaction::keys[1] - new account key, `Pubkey::create_with_seed(base, seed, program_id)`
+    /// * Transaction::keys[2] - base, if not the source itself
+    /// * lamports - number of lamports to transfer to the new account
+    /// * space - memory to allocate if greater then zero
+    /// * program_id - the program id of the new account
+    CreateAccountWithSeed {
+        base: Pubkey,
+        seed: String,
+        lamports: u64,
+        space: u64,
+        program_id: Pubkey,
+    },
+    /// Allocate space in and assign an account at an address derived from a
+    /// base pubkey and a seed
+    /// * Transaction::keys[0] - account to allocate, `Pubkey::create_with_seed(base, seed, program_id)`
+    /// * Transaction::keys[1] - base
+    /// * space - memory to allocate
+    /// * program_id - the program id of the account
+    AllocateWithSeed {
+        base: Pubkey,
+        seed: String,
+        space: u64,
+        program_id: Pubkey,
+    },
 }
 
 pub fn create_account(
@@ -70,6 +126,58 @@ pub fn create_account(
     )
 }
 
+pub fn create_account_with_seed(
+    from_pubkey: &Pubkey,
+    to_pubkey: &Pubkey, // must match create_with_seed(base, seed, program_id)
+    base: &Pubkey,
+    seed: &str,
+    lamports: u64,
+    space: u64,
+    program_id: &Pubkey,
+) -> Instruction {
+    let mut account_metas = vec![
+        AccountMeta::new(*from_pubkey, true),
+        AccountMeta::new(*to_pubkey, false),
+    ];
+    if base != from_pubkey {
+        account_metas.push(AccountMeta::new_credit_only(*base, true));
+    }
+    Instruction::new(
+        system_program::id(),
+        &SystemInstruction::CreateAccountWithSeed {
+            base: *base,
+            seed: seed.to_string(),
+            lamports,
+            space,
+            program_id: *program_id,
+        },
+        account_metas,
+    )
+}
+
+pub fn allocate_with_seed(
+    address: &Pubkey, // must match create_with_seed(base, seed, program_id)
+    base: &Pubkey,
+    seed: &str,
+    space: u64,
+    program_id: &Pubkey,
+) -> Instruction {
+    let account_metas = vec![
+        AccountMeta::new(*address, false),
+        AccountMeta::new_credit_only(*base, true),
+    ];
+    Instruction::new(
+        system_program::id(),
+        &SystemInstruction::AllocateWithSeed {
+            base: *base,
+            seed: seed.to_string(),
+            space,
+            program_id: *program_id,
+        },
+        account_metas,
+    )
+}
+
 /// Create and sign a transaction to create a system account
 pub fn create_user_account(from_pubkey: &Pubkey, to_pubkey: &Pubkey, lamports: u64) -> Instruction {
     let program_id = system_program::id();
@@ -107,6 +215,92 @@ pub fn transfer_many(from_pubkey: &Pubkey, to_lamports: &[(Pubkey, u64)]) -> Vec
         .collect()
 }
 
+/// Account metas for the nonce account and its authority, which signs in place of
+/// the nonce account when they differ
+fn nonce_account_metas(nonce_pubkey: &Pubkey, nonce_authority_pubkey: &Pubkey) -> Vec<AccountMeta> {
+    if nonce_pubkey == nonce_authority_pubkey {
+        vec![AccountMeta::new(*nonce_pubkey, true)]
+    } else {
+        vec![
+            AccountMeta::new(*nonce_pubkey, false),
+            AccountMeta::new(*nonce_authority_pubkey, true),
+        ]
+    }
+}
+
+pub fn create_nonce_account(
+    from_pubkey: &Pubkey,
+    nonce_pubkey: &Pubkey,
+    nonce_authority_pubkey: &Pubkey,
+    lamports: u64,
+) -> Vec<Instruction> {
+    vec![
+        create_account(
+            from_pubkey,
+            nonce_pubkey,
+            lamports,
+            NonceState::size() as u64,
+            &system_program::id(),
+        ),
+        Instruction::new(
+            system_program::id(),
+            &SystemInstruction::InitializeNonce {
+                nonce_authority: *nonce_authority_pubkey,
+            },
+            vec![
+                AccountMeta::new(*nonce_pubkey, false),
+                AccountMeta::new_credit_only(recent_blockhashes::id(), false),
+            ],
+        ),
+    ]
+}
+
+pub fn advance_nonce(nonce_pubkey: &Pubkey, nonce_authority_pubkey: &Pubkey) -> Instruction {
+    let mut account_metas = nonce_account_metas(nonce_pubkey, nonce_authority_pubkey);
+    account_metas.insert(
+        1,
+        AccountMeta::new_credit_only(recent_blockhashes::id(), false),
+    );
+    Instruction::new(
+        system_program::id(),
+        &SystemInstruction::AdvanceNonce,
+        account_metas,
+    )
+}
+
+pub fn withdraw_nonce(
+    nonce_pubkey: &Pubkey,
+    nonce_authority_pubkey: &Pubkey,
+    to_pubkey: &Pubkey,
+    lamports: u64,
+) -> Instruction {
+    let mut account_metas = nonce_account_metas(nonce_pubkey, nonce_authority_pubkey);
+    account_metas.insert(1, AccountMeta::new(*to_pubkey, false));
+    account_metas.insert(
+        2,
+        AccountMeta::new_credit_only(recent_blockhashes::id(), false),
+    );
+    Instruction::new(
+        system_program::id(),
+        &SystemInstruction::WithdrawNonce { lamports },
+        account_metas,
+    )
+}
+
+pub fn authorize_nonce(
+    nonce_pubkey: &Pubkey,
+    nonce_authority_pubkey: &Pubkey,
+    new_nonce_authority_pubkey: &Pubkey,
+) -> Instruction {
+    Instruction::new(
+        system_program::id(),
+        &SystemInstruction::AuthorizeNonce {
+            nonce_authority: *new_nonce_authority_pubkey,
+        },
+        nonce_account_metas(nonce_pubkey, nonce_authority_pubkey),
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -127,4 +321,79 @@ mod tests {
         assert_eq!(get_keys(&instructions[0]), vec![alice_pubkey, bob_pubkey]);
         assert_eq!(get_keys(&instructions[1]), vec![alice_pubkey, carol_pubkey]);
     }
+
+    #[test]
+    fn test_with_seed_instruction_keys() {
+        let from_pubkey = Pubkey::new_rand();
+        let base = Pubkey::new_rand();
+        let program_id = Pubkey::new_rand();
+        let address = Pubkey::create_with_seed(&base, "seed", &program_id).unwrap();
+
+        assert_eq!(
+            get_keys(&create_account_with_seed(
+                &from_pubkey,
+                &address,
+                &from_pubkey,
+                "seed",
+                1,
+                0,
+                &program_id
+            )),
+            vec![from_pubkey, address]
+        );
+        assert_eq!(
+            get_keys(&create_account_with_seed(
+                &from_pubkey,
+                &address,
+                &base,
+                "seed",
+                1,
+                0,
+                &program_id
+            )),
+            vec![from_pubkey, address, base]
+        );
+        assert_eq!(
+            get_keys(&allocate_with_seed(&address, &base, "seed", 1, &program_id)),
+            vec![address, base]
+        );
+    }
+
+    #[test]
+    fn test_nonce_instruction_keys() {
+        let nonce_pubkey = Pubkey::new_rand();
+        let authority_pubkey = Pubkey::new_rand();
+        let to_pubkey = Pubkey::new_rand();
+
+        assert_eq!(
+            get_keys(&advance_nonce(&nonce_pubkey, &nonce_pubkey)),
+            vec![nonce_pubkey, recent_blockhashes::id()]
+        );
+        assert_eq!(
+            get_keys(&advance_nonce(&nonce_pubkey, &authority_pubkey)),
+            vec![nonce_pubkey, recent_blockhashes::id(), authority_pubkey]
+        );
+        assert_eq!(
+            get_keys(&withdraw_nonce(
+                &nonce_pubkey,
+                &authority_pubkey,
+                &to_pubkey,
+                1
+            )),
+            vec![
+                nonce_pubkey,
+                to_pubkey,
+                recent_blockhashes::id(),
+                authority_pubkey
+            ]
+        );
+        assert_eq!(
+            get_keys(&authorize_nonce(
+                &nonce_pubkey,
+                &nonce_pubkey,
+                &authority_pubkey
+            )),
+            vec![nonce_pubkey]
+        );
+    }
 }
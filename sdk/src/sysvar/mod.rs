@@ -4,12 +4,17 @@ use crate::pubkey::Pubkey;
 
 pub mod clock;
 pub mod fees;
+pub mod recent_blockhashes;
 pub mod rewards;
 pub mod slot_hashes;
 pub mod stake_history;
 
 pub fn is_sysvar_id(id: &Pubkey) -> bool {
-    clock::check_id(id) || fees::check_id(id) || rewards::check_id(id) || slot_hashes::check_id(id)
+    clock::check_id(id)
+        || fees::check_id(id)
+        || recent_blockhashes::check_id(id)
+        || rewards::check_id(id)
+        || slot_hashes::check_id(id)
 }
 
 /// "Sysvar1111111111111111111111111111111111111"
@@ -10,6 +10,7 @@ use solana_core::contact_info::ContactInfo;
 use solana_core::gossip_service::discover;
 use solana_core::ledger_cleanup_service::DEFAULT_MAX_LEDGER_SLOTS;
 use solana_core::service::Service;
+use solana_core::snapshot_utils::{self, ArchiveFormat};
 use solana_core::socketaddr;
 use solana_core::validator::{Validator, ValidatorConfig};
 use solana_sdk::clock::Slot;
@@ -190,18 +191,39 @@ fn initialize_ledger_path(
     download_tar_bz2(&rpc_addr, "genesis.tar.bz2", ledger_path, true)?;
 
     if !no_snapshot_fetch {
-        let snapshot_package = solana_core::snapshot_utils::get_snapshot_tar_path(ledger_path);
-        if snapshot_package.exists() {
-            fs::remove_file(&snapshot_package)
-                .unwrap_or_else(|err| warn!("error removing {:?}: {}", snapshot_package, err));
+        let snapshot_packages: Vec<_> = ArchiveFormat::all()
+            .iter()
+            .map(|format| snapshot_utils::get_snapshot_tar_path(ledger_path, *format))
+            .collect();
+        download_snapshot_archive(&rpc_addr, &snapshot_packages)
+            .unwrap_or_else(|err| eprintln!("Warning: Unable to fetch snapshot: {:?}", err));
+
+        let incremental_snapshot_packages: Vec<_> = ArchiveFormat::all()
+            .iter()
+            .map(|format| snapshot_utils::get_incremental_snapshot_tar_path(ledger_path, *format))
+            .collect();
+        download_snapshot_archive(&rpc_addr, &incremental_snapshot_packages)
+            .unwrap_or_else(|err| info!("No incremental snapshot fetched: {:?}", err));
+
+        // The entrypoint may have packaged a new full snapshot in between the two downloads, an
+        // incremental snapshot relative to another one is no use
+        let is_matching_incremental = snapshot_utils::find_snapshot_tar_path(ledger_path)
+            .and_then(|snapshot_tar| {
+                snapshot_utils::find_matching_incremental_snapshot_tar_path(
+                    ledger_path,
+                    snapshot_tar,
+                )
+            })
+            .is_some();
+        if !is_matching_incremental {
+            for incremental_snapshot_package in &incremental_snapshot_packages {
+                if incremental_snapshot_package.exists() {
+                    fs::remove_file(&incremental_snapshot_package).unwrap_or_else(|err| {
+                        warn!("error removing {:?}: {}", incremental_snapshot_package, err)
+                    });
+                }
+            }
         }
-        download_tar_bz2(
-            &rpc_addr,
-            snapshot_package.file_name().unwrap().to_str().unwrap(),
-            snapshot_package.parent().unwrap(),
-            false,
-        )
-        .unwrap_or_else(|err| eprintln!("Warning: Unable to fetch snapshot: {:?}", err));
     }
 
     match client.get_slot() {
@@ -212,6 +234,33 @@ fn initialize_ledger_path(
     Ok(genesis_blockhash)
 }
 
+// Each of `snapshot_packages` names the same archive in a different compression format.  Remove
+// any stale copies, then download the first one the entrypoint serves
+fn download_snapshot_archive(
+    rpc_addr: &SocketAddr,
+    snapshot_packages: &[PathBuf],
+) -> Result<(), String> {
+    for snapshot_package in snapshot_packages {
+        if snapshot_package.exists() {
+            fs::remove_file(&snapshot_package)
+                .unwrap_or_else(|err| warn!("error removing {:?}: {}", snapshot_package, err));
+        }
+    }
+    let mut result = Err("No snapshot archive to fetch".to_string());
+    for snapshot_package in snapshot_packages {
+        result = download_tar_bz2(
+            rpc_addr,
+            snapshot_package.file_name().unwrap().to_str().unwrap(),
+            snapshot_package.parent().unwrap(),
+            false,
+        );
+        if result.is_ok() {
+            break;
+        }
+    }
+    result
+}
+
 // Return an error if a keypair file cannot be parsed.
 fn is_keypair(string: String) -> Result<(), String> {
     read_keypair(&string)
@@ -300,6 +349,13 @@ fn main() {
                 .requires("entrypoint")
                 .help("Do not attempt to fetch a new snapshot from the cluster entrypoint, start from a local snapshot if present"),
         )
+        .arg(
+            Arg::with_name("expected_accounts_hash")
+                .long("expected-accounts-hash")
+                .value_name("HASH")
+                .takes_value(true)
+                .help("Refuse to start from a snapshot whose accounts hash isn't this one, as reported by a trusted validator"),
+        )
         .arg(
             Arg::with_name("no_voting")
                 .long("no-voting")
@@ -380,6 +436,26 @@ fn main() {
                 .default_value("100")
                 .help("Number of slots between generating snapshots, 0 to disable snapshots"),
         )
+        .arg(
+            clap::Arg::with_name("incremental_snapshot_interval_slots")
+                .long("incremental-snapshot-interval-slots")
+                .value_name("INCREMENTAL_SNAPSHOT_INTERVAL_SLOTS")
+                .takes_value(true)
+                .default_value("0")
+                .help(
+                    "Number of slots between generating incremental snapshots relative to the \
+                     last full snapshot, 0 to disable incremental snapshots",
+                ),
+        )
+        .arg(
+            clap::Arg::with_name("snapshot_compression")
+                .long("snapshot-compression")
+                .value_name("COMPRESSION_TYPE")
+                .takes_value(true)
+                .possible_values(&["bz2", "gzip", "zstd", "none"])
+                .default_value("bz2")
+                .help("Type of compression to use for snapshot archives"),
+        )
         .arg(
             clap::Arg::with_name("limit_ledger_size")
                 .long("limit-ledger-size")
@@ -462,6 +538,14 @@ fn main() {
     }
 
     let snapshot_interval_slots = value_t_or_exit!(matches, "snapshot_interval_slots", usize);
+    let incremental_snapshot_interval_slots =
+        value_t_or_exit!(matches, "incremental_snapshot_interval_slots", usize);
+    let archive_format = value_t_or_exit!(matches, "snapshot_compression", ArchiveFormat);
+    let expected_accounts_hash = if matches.is_present("expected_accounts_hash") {
+        Some(value_t_or_exit!(matches, "expected_accounts_hash", Hash))
+    } else {
+        None
+    };
     let snapshot_path = ledger_path.clone().join("snapshot");
     fs::create_dir_all(&snapshot_path).unwrap_or_else(|err| {
         eprintln!(
@@ -477,8 +561,15 @@ fn main() {
         } else {
             std::usize::MAX
         },
+        incremental_snapshot_interval_slots: if incremental_snapshot_interval_slots > 0 {
+            incremental_snapshot_interval_slots
+        } else {
+            std::usize::MAX
+        },
         snapshot_path,
         snapshot_package_output_path: ledger_path.clone(),
+        archive_format,
+        expected_accounts_hash,
     });
 
     if matches.is_present("limit_ledger_size") {